@@ -15,6 +15,7 @@
 use core::arch::asm;
 use core::sync::atomic::{AtomicBool, Ordering};
 use crate::serial_println;
+use crate::{keyboard, process, time};
 
 /// Memory region types from multiboot2
 #[repr(u32)]
@@ -244,6 +245,9 @@ pub fn init_idt() {
         // Set up timer interrupt (IRQ0 -> IDT 32)
         IDT[32].set_handler(timer_interrupt_handler as u64);
         
+        // Set up keyboard interrupt (IRQ1 -> IDT 33)
+        IDT[33].set_handler(keyboard_interrupt_handler as u64);
+        
         // Load IDT
         let idt_ptr = IdtPointer {
             limit: (core::mem::size_of::<[IdtEntry; 256]>() - 1) as u16,
@@ -338,25 +342,64 @@ unsafe extern "C" fn timer_interrupt_handler() {
     );
 }
 
+/// Keyboard interrupt handler (assembly stub)
+#[naked]
+unsafe extern "C" fn keyboard_interrupt_handler() {
+    asm!(
+        // Save registers
+        "push rax",
+        "push rcx",
+        "push rdx",
+        "push rsi",
+        "push rdi",
+        "push r8",
+        "push r9",
+        "push r10",
+        "push r11",
+        
+        // Call Rust handler
+        "call handle_keyboard_interrupt",
+        
+        // Restore registers
+        "pop r11",
+        "pop r10",
+        "pop r9",
+        "pop r8",
+        "pop rdi",
+        "pop rsi",
+        "pop rdx",
+        "pop rcx",
+        "pop rax",
+        
+        // Return from interrupt
+        "iretq",
+        options(noreturn)
+    );
+}
+
+/// PS/2 controller data port
+const KEYBOARD_DATA_PORT: u16 = 0x60;
+
+/// Rust keyboard interrupt handler
+#[no_mangle]
+unsafe extern "C" fn handle_keyboard_interrupt() {
+    keyboard::handle_scancode(cpu_io_in(KEYBOARD_DATA_PORT));
+    
+    // Send EOI to PIC
+    send_eoi(1);
+}
+
 /// Rust timer interrupt handler
 #[no_mangle]
 unsafe extern "C" fn handle_timer_interrupt() {
-    static mut TICKS: u64 = 0;
-    TICKS += 1;
+    time::tick();
+    process::wake_sleepers();
+    process::timer_tick(time::MS_PER_TICK);
     
     // Send EOI to PIC
     send_eoi(0);
 }
 
-/// Get current tick count
-pub fn get_ticks() -> u64 {
-    unsafe {
-        static mut LAST_TICKS: u64 = 0;
-        // In a real implementation, this would read a shared atomic
-        LAST_TICKS
-    }
-}
-
 /// Initialize the PIC (Programmable Interrupt Controller)
 pub fn init_pic() {
     unsafe {
@@ -384,8 +427,8 @@ pub fn init_pic() {
         cpu_io_out(0xA1, 0x01);
         cpu_io_wait();
         
-        // OCW1: Mask all interrupts except timer (IRQ0)
-        cpu_io_out(0x21, 0xFE); // Enable only timer (bit 0)
+        // OCW1: Mask all interrupts except timer (IRQ0) and keyboard (IRQ1)
+        cpu_io_out(0x21, 0xFC); // Enable timer and keyboard (bits 0-1)
         cpu_io_out(0xA1, 0xFF); // Disable all slave interrupts
     }
 
@@ -504,7 +547,7 @@ pub fn init() {
     init_gdt();
     init_idt();
     init_pic();
-    init_timer(100); // 100 Hz timer
+    init_timer(time::TIMER_HZ);
     
     serial_println!("[boot] Boot subsystem initialized");
 }
@@ -519,6 +562,10 @@ pub fn finish_boot() -> ! {
     
     loop {
         hlt();
+        // Sleepers the timer woke are queued here, outside the interrupt
+        disable_interrupts();
+        process::requeue_woken();
+        enable_interrupts();
     }
 }
 
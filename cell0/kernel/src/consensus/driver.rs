@@ -0,0 +1,274 @@
+//! Raft State Machine Driver Module
+//!
+//! Applies committed log entries to a replicated state machine. `Raft`
+//! only reports commits through `Event::Committed`; `RaftDriver` owns the
+//! node together with a `StateMachine` and feeds it every command between
+//! its applied index and `commit_index`, exactly once and in order.
+//!
+//! Clients that retry a request after a leader change can get it logged
+//! twice. Commands that carry a client id and sequence number (see
+//! `ClientRequest`) are applied only the first time each sequence number
+//! commits.
+
+use super::{Config, EntryType, LogEntry, LogIndex, ProposeError, Raft};
+use core::fmt::Debug;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// Identifier of a client session
+pub type ClientId = u64;
+
+/// Replicated state machine fed by a `RaftDriver`
+pub trait StateMachine<T: Clone> {
+    /// Apply a committed command entry
+    ///
+    /// Called once per entry, in log order. Configuration and no-op
+    /// entries are not passed on.
+    fn apply(&mut self, entry: &LogEntry<T>);
+}
+
+/// Commands that may carry a client session for duplicate detection
+pub trait ClientCommand {
+    /// Client id and per-client sequence number, if any
+    fn session(&self) -> Option<(ClientId, u64)>;
+}
+
+impl ClientCommand for u64 {
+    fn session(&self) -> Option<(ClientId, u64)> {
+        None
+    }
+}
+
+impl ClientCommand for Vec<u8> {
+    fn session(&self) -> Option<(ClientId, u64)> {
+        None
+    }
+}
+
+/// Command tagged with the client session that issued it
+///
+/// Sequence numbers start at 1 and increase with every new request from
+/// the same client; a retry reuses the original number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientRequest<C> {
+    pub client_id: ClientId,
+    pub sequence: u64,
+    pub command: C,
+}
+
+impl<C> ClientCommand for ClientRequest<C> {
+    fn session(&self) -> Option<(ClientId, u64)> {
+        Some((self.client_id, self.sequence))
+    }
+}
+
+/// A Raft node wired to the state machine it replicates
+pub struct RaftDriver<T: Clone + Debug, S> {
+    raft: Raft<T>,
+    state_machine: S,
+    /// Highest log index handed to the state machine (or skipped)
+    applied: LogIndex,
+    /// Highest sequence number applied per client
+    sessions: BTreeMap<ClientId, u64>,
+}
+
+impl<T, S> RaftDriver<T, S>
+where
+    T: Clone + Debug + Default + ClientCommand,
+    S: StateMachine<T>,
+{
+    /// Create a driver around a fresh node
+    pub fn new(config: Config, state_machine: S) -> Self {
+        Self::from_raft(Raft::new(config), state_machine)
+    }
+
+    /// Wrap an existing node, e.g. one rebuilt with `Raft::restore`
+    ///
+    /// `state_machine` must already reflect everything up to the node's
+    /// snapshot boundary.
+    pub fn from_raft(raft: Raft<T>, state_machine: S) -> Self {
+        let applied = raft.persistent.snapshot_index;
+        Self {
+            raft,
+            state_machine,
+            applied,
+            sessions: BTreeMap::new(),
+        }
+    }
+
+    /// The underlying node
+    pub fn raft(&self) -> &Raft<T> {
+        &self.raft
+    }
+
+    /// Mutable access to the node
+    ///
+    /// Call `apply_committed` afterwards, or use `step` instead.
+    pub fn raft_mut(&mut self) -> &mut Raft<T> {
+        &mut self.raft
+    }
+
+    /// The replicated state machine
+    pub fn state_machine(&self) -> &S {
+        &self.state_machine
+    }
+
+    /// Highest log index applied to the state machine
+    pub fn applied(&self) -> LogIndex {
+        self.applied
+    }
+
+    /// Run an operation on the node, then apply whatever it committed
+    pub fn step<R>(&mut self, f: impl FnOnce(&mut Raft<T>) -> R) -> R {
+        let result = f(&mut self.raft);
+        self.apply_committed();
+        result
+    }
+
+    /// Propose a command (must be leader)
+    pub fn propose(&mut self, command: T) -> Result<LogIndex, ProposeError> {
+        self.step(|raft| raft.propose(command))
+    }
+
+    /// Apply entries up to `commit_index`, returning how many were passed
+    /// to the state machine
+    ///
+    /// Duplicate client requests are skipped. Calling this again without
+    /// new commits does nothing.
+    pub fn apply_committed(&mut self) -> usize {
+        // A snapshot installed from the leader replaces everything it covers
+        if self.raft.persistent.snapshot_index > self.applied {
+            self.applied = self.raft.persistent.snapshot_index;
+        }
+
+        let mut count = 0;
+        while self.applied < self.raft.commit_index {
+            self.applied += 1;
+            let entry = match self.raft.persistent.entry_at(self.applied) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if entry.entry_type != EntryType::Command || self.is_duplicate(&entry.command) {
+                continue;
+            }
+            if let Some((client_id, sequence)) = entry.command.session() {
+                self.sessions.insert(client_id, sequence);
+            }
+            self.state_machine.apply(entry);
+            count += 1;
+        }
+        count
+    }
+
+    /// Whether the client already had this sequence number applied
+    fn is_duplicate(&self, command: &T) -> bool {
+        match command.session() {
+            Some((client_id, sequence)) => self
+                .sessions
+                .get(&client_id)
+                .is_some_and(|&last| sequence <= last),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{AppendEntriesArgs, NodeState};
+
+    #[derive(Default)]
+    struct Counter {
+        value: u64,
+        applied: Vec<LogIndex>,
+    }
+
+    impl StateMachine<ClientRequest<u64>> for Counter {
+        fn apply(&mut self, entry: &LogEntry<ClientRequest<u64>>) {
+            self.value += entry.command.command;
+            self.applied.push(entry.index);
+        }
+    }
+
+    fn request(client_id: ClientId, sequence: u64, command: u64) -> ClientRequest<u64> {
+        ClientRequest { client_id, sequence, command }
+    }
+
+    #[test]
+    fn test_counter_applies_once() {
+        let mut driver = RaftDriver::new(Config::new(1, vec![1]), Counter::default());
+        driver.step(|raft| raft.start_election());
+        assert_eq!(driver.raft().state, NodeState::Leader);
+
+        for seq in 1..=3 {
+            driver.propose(request(7, seq, seq * 10)).unwrap();
+        }
+        driver.propose(request(8, 1, 5)).unwrap();
+        assert_eq!(driver.state_machine().value, 65);
+        // Index 1 is the leader's no-op, which is never applied
+        assert_eq!(driver.applied(), 5);
+
+        // Client 7 retries request 2 and an old one; both commit but are skipped
+        driver.propose(request(7, 2, 20)).unwrap();
+        driver.propose(request(7, 1, 10)).unwrap();
+        assert_eq!(driver.raft().commit_index, 7);
+        assert_eq!(driver.state_machine().value, 65);
+
+        // Replaying with nothing new committed changes nothing
+        assert_eq!(driver.apply_committed(), 0);
+        assert_eq!(driver.state_machine().applied, vec![2, 3, 4, 5]);
+
+        driver.propose(request(7, 4, 1)).unwrap();
+        assert_eq!(driver.state_machine().value, 66);
+        assert_eq!(driver.applied(), 8);
+    }
+
+    #[test]
+    fn test_follower_applies_replicated_commits() {
+        let mut leader = RaftDriver::new(Config::new(1, vec![1, 2]), Counter::default());
+        let mut follower = RaftDriver::new(Config::new(2, vec![1, 2]), Counter::default());
+
+        leader.step(|raft| raft.start_election());
+        leader.step(|raft| raft.record_vote(2));
+        for seq in 1..=2 {
+            leader.propose(request(1, seq, 3)).unwrap();
+        }
+        // The retry is logged as a separate entry
+        leader.propose(request(1, 2, 3)).unwrap();
+        assert_eq!(leader.state_machine().value, 0);
+
+        let args = AppendEntriesArgs {
+            term: leader.raft().persistent.current_term,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: leader.raft().persistent.log.clone(),
+            leader_commit: 0,
+            round: 0,
+        };
+        let reply = follower.step(|raft| raft.handle_append_entries(args.clone()));
+        leader.step(|raft| raft.handle_append_entries_reply(2, reply));
+        assert_eq!(leader.state_machine().value, 6);
+
+        let args = AppendEntriesArgs {
+            entries: Vec::new(),
+            prev_log_index: 4,
+            prev_log_term: args.term,
+            leader_commit: leader.raft().commit_index,
+            ..args
+        };
+        follower.step(|raft| raft.handle_append_entries(args));
+        assert_eq!(follower.state_machine().value, 6);
+        assert_eq!(follower.applied(), 4);
+    }
+}
@@ -9,10 +9,15 @@
 //! - Log replication and commitment
 //! - Safety guarantees via term numbers and log validation
 
+pub mod driver;
+pub mod storage;
 pub mod transport;
 
 use core::fmt::Debug;
 
+use storage::RaftStorage;
+use transport::{InstallSnapshotArgs, InstallSnapshotReply};
+
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
@@ -37,14 +42,28 @@ pub type LogIndex = u64;
 pub enum NodeState {
     /// Follower: receives RPCs from leader, responds to requests
     Follower,
+    /// Pre-candidate: polling peers before starting an election
+    PreCandidate,
     /// Candidate: running for election
     Candidate,
     /// Leader: manages log replication and cluster coordination
     Leader,
 }
 
+impl core::fmt::Display for NodeState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NodeState::Follower => write!(f, "Follower"),
+            NodeState::PreCandidate => write!(f, "PreCandidate"),
+            NodeState::Candidate => write!(f, "Candidate"),
+            NodeState::Leader => write!(f, "Leader"),
+        }
+    }
+}
+
 /// A single entry in the Raft log
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LogEntry<T: Clone> {
     /// Term when entry was received by leader
     pub term: Term,
@@ -58,11 +77,12 @@ pub struct LogEntry<T: Clone> {
 
 /// Types of log entries
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EntryType {
     /// Normal user command
     Command,
     /// Configuration change (membership update)
-    ConfigChange,
+    ConfigChange(MembershipChange),
     /// No-op entry from leader (prevents stale reads)
     NoOp,
 }
@@ -73,6 +93,79 @@ impl Default for EntryType {
     }
 }
 
+/// Single-server membership change carried by a ConfigChange entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MembershipChange {
+    /// Add a voting member
+    AddServer(NodeId),
+    /// Remove a voting member
+    RemoveServer(NodeId),
+    /// Turn a learner into a voting member
+    PromoteLearner(NodeId),
+}
+
+impl MembershipChange {
+    /// Encode as bytes: tag byte followed by the node ID (LE)
+    pub fn encode(&self) -> Vec<u8> {
+        let (tag, id) = match *self {
+            MembershipChange::AddServer(id) => (1u8, id),
+            MembershipChange::RemoveServer(id) => (2u8, id),
+            MembershipChange::PromoteLearner(id) => (3u8, id),
+        };
+        let mut data = Vec::with_capacity(9);
+        data.push(tag);
+        data.extend_from_slice(&id.to_le_bytes());
+        data
+    }
+    
+    /// Decode bytes produced by `encode`
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() != 9 {
+            return None;
+        }
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&data[1..]);
+        let id = NodeId::from_le_bytes(id);
+        match data[0] {
+            1 => Some(MembershipChange::AddServer(id)),
+            2 => Some(MembershipChange::RemoveServer(id)),
+            3 => Some(MembershipChange::PromoteLearner(id)),
+            _ => None,
+        }
+    }
+}
+
+/// Voting members and learners in effect at some log index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Membership {
+    /// Voting members
+    pub peers: Vec<NodeId>,
+    /// Non-voting members
+    pub learners: Vec<NodeId>,
+}
+
+impl Membership {
+    /// Apply a committed change; applying it twice has no further effect
+    pub fn apply(&mut self, change: MembershipChange) {
+        match change {
+            MembershipChange::AddServer(id) => {
+                if !self.peers.contains(&id) {
+                    self.peers.push(id);
+                }
+            }
+            MembershipChange::RemoveServer(id) => self.peers.retain(|&p| p != id),
+            MembershipChange::PromoteLearner(id) => {
+                if let Some(pos) = self.learners.iter().position(|&l| l == id) {
+                    self.learners.remove(pos);
+                    self.peers.push(id);
+                }
+            }
+        }
+    }
+}
+
 /// Persistent state (must be saved to stable storage)
 #[derive(Debug, Clone)]
 pub struct PersistentState<T: Clone> {
@@ -80,8 +173,15 @@ pub struct PersistentState<T: Clone> {
     pub current_term: Term,
     /// CandidateId that received vote in current term (None if none)
     pub voted_for: Option<NodeId>,
-    /// Log entries; each entry contains command for state machine
+    /// Log entries after the snapshot; each entry contains command for state machine
     pub log: Vec<LogEntry<T>>,
+    /// Index of the last entry folded into the snapshot (0 if none)
+    pub snapshot_index: LogIndex,
+    /// Term of the entry at snapshot_index
+    pub snapshot_term: Term,
+    /// Membership as of snapshot_index; ConfigChange entries in the log
+    /// apply on top of it
+    pub snapshot_membership: Membership,
 }
 
 impl<T: Clone> PersistentState<T> {
@@ -91,37 +191,80 @@ impl<T: Clone> PersistentState<T> {
             current_term: 0,
             voted_for: None,
             log: Vec::new(),
+            snapshot_index: 0,
+            snapshot_term: 0,
+            snapshot_membership: Membership::default(),
         }
     }
     
     /// Get last log index
     pub fn last_index(&self) -> LogIndex {
-        self.log.len() as LogIndex
+        self.snapshot_index + self.log.len() as LogIndex
     }
     
     /// Get last log term
     pub fn last_term(&self) -> Term {
-        self.log.last().map(|e| e.term).unwrap_or(0)
+        self.log.last().map(|e| e.term).unwrap_or(self.snapshot_term)
     }
     
     /// Get term at specific index
+    ///
+    /// Returns 0 for indices that were compacted away, except the snapshot
+    /// boundary itself whose term is remembered.
     pub fn term_at(&self, index: LogIndex) -> Term {
-        if index == 0 {
-            0
+        if index == self.snapshot_index {
+            self.snapshot_term
         } else {
-            self.log.get((index - 1) as usize)
-                .map(|e| e.term)
-                .unwrap_or(0)
+            self.entry_at(index).map(|e| e.term).unwrap_or(0)
         }
     }
     
     /// Get entry at specific index
     pub fn entry_at(&self, index: LogIndex) -> Option<&LogEntry<T>> {
-        if index == 0 {
+        if index <= self.snapshot_index {
             None
         } else {
-            self.log.get((index - 1) as usize)
+            self.log.get((index - self.snapshot_index - 1) as usize)
+        }
+    }
+    
+    /// Get entries from start_index onwards (empty if compacted or past the end)
+    pub fn entries_from(&self, start_index: LogIndex) -> &[LogEntry<T>] {
+        if start_index <= self.snapshot_index || start_index > self.last_index() {
+            &[]
+        } else {
+            &self.log[(start_index - self.snapshot_index - 1) as usize..]
+        }
+    }
+    
+    /// Delete the entry at index and everything after it
+    pub fn truncate_from(&mut self, index: LogIndex) {
+        if index > self.snapshot_index && index <= self.last_index() {
+            self.log.truncate((index - self.snapshot_index - 1) as usize);
+        }
+    }
+    
+    /// Drop entries up to and including index, moving the snapshot boundary
+    ///
+    /// Returns the number of entries discarded.
+    pub fn compact_to(&mut self, index: LogIndex) -> usize {
+        if index <= self.snapshot_index || index > self.last_index() {
+            return 0;
         }
+        
+        let term = self.term_at(index);
+        let count = (index - self.snapshot_index) as usize;
+        self.log.drain(..count);
+        self.snapshot_index = index;
+        self.snapshot_term = term;
+        count
+    }
+    
+    /// Discard the whole log and restart it after a snapshot
+    pub fn reset_to_snapshot(&mut self, index: LogIndex, term: Term) {
+        self.log.clear();
+        self.snapshot_index = index;
+        self.snapshot_term = term;
     }
 }
 
@@ -131,7 +274,22 @@ impl<T: Clone> Default for PersistentState<T> {
     }
 }
 
+/// Point-in-time image of the state machine that replaces a log prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Last log index covered by the snapshot
+    pub last_included_index: LogIndex,
+    /// Term of last_included_index
+    pub last_included_term: Term,
+    /// Membership as of last_included_index
+    pub membership: Membership,
+    /// Serialized state machine
+    pub data: Vec<u8>,
+}
+
 /// Volatile state for leaders (reinitialized after election)
+///
+/// Entries follow `Config::progress_index`: voters first, then learners.
 #[derive(Debug, Clone)]
 pub struct LeaderState {
     /// For each server, index of the next log entry to send
@@ -165,6 +323,8 @@ pub struct Config {
     pub node_id: NodeId,
     /// All node IDs in the cluster (including this one)
     pub peers: Vec<NodeId>,
+    /// Non-voting members that receive the log but do not count for quorum
+    pub learners: Vec<NodeId>,
     /// Election timeout minimum (ms)
     pub election_timeout_min: u64,
     /// Election timeout maximum (ms)
@@ -173,6 +333,8 @@ pub struct Config {
     pub heartbeat_interval: u64,
     /// Maximum log entries per AppendEntries RPC
     pub max_entries_per_rpc: usize,
+    /// Run a pre-vote round before bumping the term on election timeout
+    pub enable_pre_vote: bool,
 }
 
 impl Config {
@@ -181,10 +343,12 @@ impl Config {
         Self {
             node_id,
             peers,
+            learners: Vec::new(),
             election_timeout_min: 150,
             election_timeout_max: 300,
             heartbeat_interval: 50,
             max_entries_per_rpc: 100,
+            enable_pre_vote: false,
         }
     }
     
@@ -202,10 +366,51 @@ impl Config {
     pub fn quorum(&self) -> usize {
         (self.peers.len() / 2) + 1
     }
+    
+    /// Whether the node votes and counts toward quorum
+    pub fn is_voter(&self, id: NodeId) -> bool {
+        self.peers.contains(&id)
+    }
+    
+    /// Whether the node is a non-voting learner
+    pub fn is_learner(&self, id: NodeId) -> bool {
+        self.learners.contains(&id)
+    }
+    
+    /// Position of a member's progress in `LeaderState`
+    pub fn progress_index(&self, id: NodeId) -> Option<usize> {
+        self.peers.iter().position(|&p| p == id).or_else(|| {
+            self.learners.iter().position(|&l| l == id).map(|pos| self.peers.len() + pos)
+        })
+    }
+    
+    /// Current voters and learners
+    pub fn membership(&self) -> Membership {
+        Membership {
+            peers: self.peers.clone(),
+            learners: self.learners.clone(),
+        }
+    }
+    
+    /// Replace the voters and learners
+    pub fn set_membership(&mut self, membership: Membership) {
+        self.peers = membership.peers;
+        self.learners = membership.learners;
+    }
+    
+    /// Every other node the leader replicates to, voters then learners
+    pub fn replication_targets(&self) -> Vec<NodeId> {
+        self.peers.iter()
+            .chain(self.learners.iter())
+            .filter(|&&p| p != self.node_id)
+            .cloned()
+            .collect()
+    }
 }
 
 /// RequestVote RPC arguments
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestVoteArgs {
     /// Candidate's term
     pub term: Term,
@@ -219,6 +424,7 @@ pub struct RequestVoteArgs {
 
 /// RequestVote RPC reply
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestVoteReply {
     /// Current term, for candidate to update itself
     pub term: Term,
@@ -230,6 +436,7 @@ pub struct RequestVoteReply {
 
 /// AppendEntries RPC arguments
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppendEntriesArgs<T: Clone> {
     /// Leader's term
     pub term: Term,
@@ -243,10 +450,13 @@ pub struct AppendEntriesArgs<T: Clone> {
     pub entries: Vec<LogEntry<T>>,
     /// Leader's commit_index
     pub leader_commit: LogIndex,
+    /// Leader's heartbeat round when sent, echoed in the reply
+    pub round: u64,
 }
 
 /// AppendEntries RPC reply
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppendEntriesReply {
     /// Current term, for leader to update itself
     pub term: Term,
@@ -254,10 +464,15 @@ pub struct AppendEntriesReply {
     pub success: bool,
     /// Conflict information for log optimization
     pub conflict_info: Option<LogConflict>,
+    /// On success, index of the last entry known to match the leader
+    pub match_index: LogIndex,
+    /// Heartbeat round of the request being answered
+    pub round: u64,
 }
 
 /// Log conflict information for AppendEntries optimization
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LogConflict {
     /// Term in conflicting entry (if any)
     pub conflict_term: Term,
@@ -274,11 +489,15 @@ pub enum Event<T: Clone> {
     SteppedDown { new_term: Term },
     /// Entries committed (can apply to state machine)
     Committed { entries: Vec<LogEntry<T>> },
+    /// Pre-vote request to send to peer (args.term is the prospective term)
+    SendPreVote { peer: NodeId, args: RequestVoteArgs },
     /// Vote request to send to peer
     SendRequestVote { peer: NodeId, args: RequestVoteArgs },
     /// AppendEntries to send to peer  
     SendAppendEntries { peer: NodeId, args: AppendEntriesArgs<T> },
-    /// Save persistent state
+    /// Peer is behind the snapshot boundary and needs the snapshot instead
+    InstallSnapshot { peer: NodeId, args: InstallSnapshotArgs },
+    /// Save persistent state (flush with `Raft::persist` before replying)
     PersistState,
     /// Election timeout should be reset
     ResetElectionTimer,
@@ -304,34 +523,227 @@ pub struct Raft<T: Clone + Debug> {
     pub votes_received: Vec<NodeId>,
     /// Pending events to process
     pub pending_events: Vec<Event<T>>,
+    /// Latest snapshot, if the log has been compacted
+    pub snapshot: Option<Snapshot>,
+    /// Leader of the current term, if known and not timed out
+    pub leader_id: Option<NodeId>,
+    /// Peers that acknowledged the current heartbeat round (leader only)
+    pub heartbeat_acks: Vec<NodeId>,
+    /// Current heartbeat round; only replies echoing it count as acks
+    pub heartbeat_round: u64,
+    /// Lowest log index changed since the last `persist`
+    unsynced_from: Option<LogIndex>,
+    /// Snapshot boundary last written to storage
+    synced_snapshot_index: LogIndex,
 }
 
-impl<T: Clone + Debug> Raft<T> {
+impl<T: Clone + Debug + Default> Raft<T> {
     /// Create new Raft node
     pub fn new(config: Config) -> Self {
         let leader_state = None;
+        let persistent = PersistentState {
+            snapshot_membership: config.membership(),
+            ..PersistentState::new()
+        };
         
         Self {
             config,
-            persistent: PersistentState::new(),
+            persistent,
             commit_index: 0,
             last_applied: 0,
             state: NodeState::Follower,
             leader_state,
             votes_received: Vec::new(),
             pending_events: Vec::new(),
+            snapshot: None,
+            leader_id: None,
+            heartbeat_acks: Vec::new(),
+            heartbeat_round: 0,
+            unsynced_from: None,
+            synced_snapshot_index: 0,
+        }
+    }
+    
+    /// Rebuild a node from stable storage after a restart
+    ///
+    /// Term, vote and log come back; commit and apply progress restart at
+    /// the snapshot boundary and are relearned from the leader.
+    ///
+    /// Membership is the one saved with the snapshot, or `config`'s if the
+    /// log was never compacted, plus every ConfigChange still in the log.
+    pub fn restore<S: RaftStorage<T>>(config: Config, storage: &S) -> Result<Self, S::Error> {
+        let mut raft = Self::new(config);
+        let initial = raft.persistent.snapshot_membership.clone();
+        raft.persistent = storage.load()?;
+        if raft.persistent.snapshot_index == 0 {
+            raft.persistent.snapshot_membership = initial;
         }
+        let membership = raft.membership_at(raft.persistent.last_index());
+        raft.config.set_membership(membership);
+        raft.commit_index = raft.persistent.snapshot_index;
+        raft.last_applied = raft.persistent.snapshot_index;
+        raft.synced_snapshot_index = raft.persistent.snapshot_index;
+        Ok(raft)
+    }
+    
+    /// Flush term, vote and log changes to stable storage
+    ///
+    /// Call whenever `Event::PersistState` is raised, before sending any
+    /// RPC reply or message produced alongside it.
+    pub fn persist<S: RaftStorage<T>>(&mut self, storage: &mut S) -> Result<(), S::Error> {
+        storage.save_state(self.persistent.current_term, self.persistent.voted_for)?;
+        
+        if self.persistent.snapshot_index != self.synced_snapshot_index {
+            storage.compact_to(
+                self.persistent.snapshot_index,
+                self.persistent.snapshot_term,
+                &self.persistent.snapshot_membership,
+            )?;
+            self.synced_snapshot_index = self.persistent.snapshot_index;
+        }
+        
+        if let Some(from) = self.unsynced_from {
+            let from = from.max(self.persistent.snapshot_index + 1);
+            storage.truncate_from(from)?;
+            storage.append(self.persistent.entries_from(from))?;
+            self.unsynced_from = None;
+        }
+        
+        Ok(())
+    }
+    
+    /// Membership after the ConfigChange entries up to index
+    fn membership_at(&self, index: LogIndex) -> Membership {
+        let mut membership = self.persistent.snapshot_membership.clone();
+        for entry in self.persistent.log.iter().take_while(|e| e.index <= index) {
+            if let EntryType::ConfigChange(change) = entry.entry_type {
+                membership.apply(change);
+            }
+        }
+        membership
+    }
+    
+    /// Note that log entries from index onwards differ from storage
+    fn mark_unsynced(&mut self, index: LogIndex) {
+        self.unsynced_from = Some(self.unsynced_from.map_or(index, |from| from.min(index)));
     }
     
     /// Initialize as leader (for single-node clusters or testing)
     pub fn become_leader(&mut self) {
         self.state = NodeState::Leader;
+        self.leader_id = Some(self.config.node_id);
+        self.start_heartbeat_round();
         self.leader_state = Some(LeaderState::new(
-            self.config.cluster_size(),
+            self.config.cluster_size() + self.config.learners.len(),
             self.persistent.last_index()
         ));
         self.pending_events.push(Event::BecameLeader);
         self.pending_events.push(Event::SendHeartbeats);
+        
+        // Commit something from this term right away so commit_index (and
+        // with it read_index) catches up without waiting for a proposal
+        self.append_and_replicate(T::default(), EntryType::NoOp);
+    }
+    
+    /// Handle election timeout
+    ///
+    /// With `enable_pre_vote`, a multi-node cluster first polls peers and
+    /// only starts a real election once a quorum would grant it, so a
+    /// partitioned node cannot inflate the term.
+    pub fn handle_election_timeout(&mut self) {
+        // Learners never campaign
+        if self.state == NodeState::Leader || !self.config.is_voter(self.config.node_id) {
+            return;
+        }
+        
+        // The leader went quiet; stop vouching for it in pre-votes
+        self.leader_id = None;
+        
+        if self.config.enable_pre_vote && self.config.cluster_size() > 1 {
+            self.start_pre_vote();
+        } else {
+            self.start_election();
+        }
+    }
+    
+    /// Become pre-candidate and poll peers; term and vote are unchanged
+    fn start_pre_vote(&mut self) {
+        self.state = NodeState::PreCandidate;
+        self.votes_received = vec![self.config.node_id];
+        
+        let args = RequestVoteArgs {
+            term: self.persistent.current_term + 1,
+            candidate_id: self.config.node_id,
+            last_log_index: self.persistent.last_index(),
+            last_log_term: self.persistent.last_term(),
+        };
+        
+        for &peer in &self.config.peers {
+            if peer != self.config.node_id {
+                self.pending_events.push(Event::SendPreVote {
+                    peer,
+                    args: args.clone(),
+                });
+            }
+        }
+        
+        self.pending_events.push(Event::ResetElectionTimer);
+    }
+    
+    /// Handle PreVote RPC
+    ///
+    /// Granted only if the candidate's log is up to date and this node has
+    /// no live leader. Never changes term or vote.
+    pub fn handle_pre_vote(&self, args: RequestVoteArgs) -> RequestVoteReply {
+        let reject = |reason: &str| RequestVoteReply {
+            term: self.persistent.current_term,
+            vote_granted: false,
+            reason: Some(reason.to_string()),
+        };
+        
+        if args.term <= self.persistent.current_term {
+            return reject("Stale term");
+        }
+        if self.leader_id.is_some() {
+            return reject("Leader is alive");
+        }
+        
+        let my_last_term = self.persistent.last_term();
+        let log_is_up_to_date =
+            args.last_log_term > my_last_term ||
+            (args.last_log_term == my_last_term && args.last_log_index >= self.persistent.last_index());
+        
+        if !log_is_up_to_date {
+            return reject("Log not up-to-date");
+        }
+        
+        RequestVoteReply {
+            term: self.persistent.current_term,
+            vote_granted: true,
+            reason: None,
+        }
+    }
+    
+    /// Handle PreVote reply; starts the real election at a quorum
+    pub fn handle_pre_vote_reply(&mut self, from: NodeId, reply: RequestVoteReply) {
+        if self.state != NodeState::PreCandidate {
+            return;
+        }
+        
+        if !reply.vote_granted {
+            if reply.term > self.persistent.current_term {
+                self.step_down(reply.term);
+            }
+            return;
+        }
+        
+        if self.config.is_voter(from) && !self.votes_received.contains(&from) {
+            self.votes_received.push(from);
+        }
+        
+        if self.votes_received.len() >= self.config.quorum() {
+            self.start_election();
+        }
     }
     
     /// Start election (called on election timeout)
@@ -437,7 +849,8 @@ impl<T: Clone + Debug> Raft<T> {
             return;
         }
         
-        if reply.vote_granted {
+        // Only voting members' ballots count
+        if reply.vote_granted && self.config.is_voter(from) {
             // Record vote
             if !self.votes_received.contains(&from) {
                 self.votes_received.push(from);
@@ -458,24 +871,29 @@ impl<T: Clone + Debug> Raft<T> {
                 term: self.persistent.current_term,
                 success: false,
                 conflict_info: None,
+                match_index: 0,
+                round: args.round,
             };
         }
         
         // Reset election timer on valid RPC
         self.pending_events.push(Event::ResetElectionTimer);
         
-        // If term > current_term, step down
         if args.term > self.persistent.current_term {
+            // Newer term: adopt it and forget our vote
             self.step_down(args.term);
+        } else if self.state != NodeState::Follower {
+            // Someone else won this term; our vote in it still stands
+            self.state = NodeState::Follower;
+            self.leader_state = None;
+            self.pending_events.push(Event::SteppedDown { new_term: args.term });
         }
         
-        // Step down if we're a leader/candidate receiving valid AppendEntries
-        if self.state != NodeState::Follower {
-            self.step_down(args.term);
-        }
+        self.leader_id = Some(args.leader_id);
         
-        // Check log consistency at prev_log_index
-        if args.prev_log_index > 0 {
+        // Check log consistency at prev_log_index; anything inside the
+        // snapshot is committed and therefore matches
+        if args.prev_log_index > self.persistent.snapshot_index {
             if args.prev_log_index > self.persistent.last_index() {
                 // We don't have prev_log_index entry
                 return AppendEntriesReply {
@@ -485,6 +903,8 @@ impl<T: Clone + Debug> Raft<T> {
                         conflict_term: 0,
                         conflict_index: self.persistent.last_index() + 1,
                     }),
+                    match_index: 0,
+                    round: args.round,
                 };
             }
             
@@ -507,6 +927,8 @@ impl<T: Clone + Debug> Raft<T> {
                         conflict_term,
                         conflict_index,
                     }),
+                    match_index: 0,
+                    round: args.round,
                 };
             }
         }
@@ -516,18 +938,23 @@ impl<T: Clone + Debug> Raft<T> {
         for (i, entry) in args.entries.iter().enumerate() {
             let index = args.prev_log_index + 1 + i as u64;
             
-            if index <= self.persistent.last_index() {
+            if index <= self.persistent.snapshot_index {
+                // Already covered by the snapshot
+                continue;
+            } else if index <= self.persistent.last_index() {
                 // Check for conflict
                 let existing = self.persistent.entry_at(index).unwrap();
                 if existing.term != entry.term {
                     // Delete this and all following entries
-                    self.persistent.log.truncate((index - 1) as usize);
+                    self.persistent.truncate_from(index);
+                    self.mark_unsynced(index);
                     entries_added = true;
                 }
                 // Skip if already exists with same term
             } else {
                 // Append new entry
                 self.persistent.log.push(entry.clone());
+                self.mark_unsynced(index);
                 entries_added = true;
             }
         }
@@ -546,11 +973,13 @@ impl<T: Clone + Debug> Raft<T> {
             term: self.persistent.current_term,
             success: true,
             conflict_info: None,
+            match_index: args.prev_log_index + args.entries.len() as u64,
+            round: args.round,
         }
     }
     
     /// Handle AppendEntries reply
-    pub fn handle_append_entries_reply(&mut self, peer: NodeId, args: &AppendEntriesArgs<T>, reply: AppendEntriesReply) {
+    pub fn handle_append_entries_reply(&mut self, peer: NodeId, reply: AppendEntriesReply) {
         // If term > current_term, step down
         if reply.term > self.persistent.current_term {
             self.step_down(reply.term);
@@ -562,12 +991,26 @@ impl<T: Clone + Debug> Raft<T> {
             return;
         }
         
-        let peer_idx = self.config.peers.iter().position(|&id| id == peer).unwrap_or(0);
+        // Replies from servers removed since the RPC was sent are dropped
+        let peer_idx = match self.config.progress_index(peer) {
+            Some(idx) => idx,
+            None => return,
+        };
+        
+        // Any voter's reply in our term to a request from this round proves
+        // it still accepts us as leader; older rounds prove nothing now
+        if reply.round == self.heartbeat_round
+            && self.config.is_voter(peer)
+            && !self.heartbeat_acks.contains(&peer)
+        {
+            self.heartbeat_acks.push(peer);
+        }
+        
         let leader_state = self.leader_state.as_mut().unwrap();
         
         if reply.success {
             // Update next_index and match_index
-            let new_match = args.prev_log_index + args.entries.len() as u64;
+            let new_match = reply.match_index;
             if new_match > leader_state.match_index[peer_idx] {
                 leader_state.match_index[peer_idx] = new_match;
                 leader_state.next_index[peer_idx] = new_match + 1;
@@ -601,29 +1044,125 @@ impl<T: Clone + Debug> Raft<T> {
             }
             
             // Retry AppendEntries - queue event instead of calling directly
-            let leader_state = self.leader_state.as_ref().unwrap();
-            let next_idx = leader_state.next_index[peer_idx];
-            let prev_log_index = next_idx - 1;
-            let prev_log_term = self.persistent.term_at(prev_log_index);
-            
-            let entries: Vec<LogEntry<T>> = self.persistent.log
-                .iter()
-                .skip((next_idx - 1) as usize)
-                .take(self.config.max_entries_per_rpc)
-                .cloned()
-                .collect();
-            
-            let retry_args = AppendEntriesArgs {
-                term: self.persistent.current_term,
-                leader_id: self.config.node_id,
-                prev_log_index,
-                prev_log_term,
-                entries,
-                leader_commit: self.commit_index,
-            };
-            
-            self.pending_events.push(Event::SendAppendEntries { peer, args: retry_args });
+            self.send_append_entries_to(peer);
+        }
+    }
+    
+    /// Handle InstallSnapshot RPC from the leader
+    pub fn handle_install_snapshot(&mut self, args: InstallSnapshotArgs) -> InstallSnapshotReply {
+        if args.term < self.persistent.current_term {
+            return InstallSnapshotReply { term: self.persistent.current_term, last_included_index: 0 };
+        }
+        
+        self.pending_events.push(Event::ResetElectionTimer);
+        
+        if args.term > self.persistent.current_term {
+            self.step_down(args.term);
+        } else if self.state != NodeState::Follower {
+            // Same term: the sender is this term's leader, our vote stands
+            self.state = NodeState::Follower;
+            self.leader_state = None;
+            self.pending_events.push(Event::SteppedDown { new_term: args.term });
+        }
+        
+        self.leader_id = Some(args.leader_id);
+        
+        let last_included_index = args.last_included_index;
+        self.install_snapshot_with(last_included_index, args.last_included_term, args.data, args.membership);
+        
+        InstallSnapshotReply { term: self.persistent.current_term, last_included_index }
+    }
+    
+    /// Handle InstallSnapshot reply
+    pub fn handle_install_snapshot_reply(&mut self, peer: NodeId, reply: InstallSnapshotReply) {
+        if reply.term > self.persistent.current_term {
+            self.step_down(reply.term);
+            return;
+        }
+        
+        if self.state != NodeState::Leader || reply.term != self.persistent.current_term {
+            return;
+        }
+        
+        let peer_idx = match self.config.progress_index(peer) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let leader_state = self.leader_state.as_mut().unwrap();
+        
+        if reply.last_included_index > leader_state.match_index[peer_idx] {
+            leader_state.match_index[peer_idx] = reply.last_included_index;
+            leader_state.next_index[peer_idx] = reply.last_included_index + 1;
+        }
+        
+        self.advance_commit_index();
+    }
+    
+    /// Install a snapshot covering the log up to last_included_index
+    ///
+    /// If the log already holds the boundary entry with a matching term, the
+    /// entries after it are kept; otherwise the whole log is replaced.
+    /// Snapshots older than the current one are ignored.
+    pub fn install_snapshot(&mut self, last_included_index: LogIndex, last_included_term: Term, state_bytes: Vec<u8>) {
+        let membership = self.membership_at(last_included_index);
+        self.install_snapshot_with(last_included_index, last_included_term, state_bytes, membership);
+    }
+    
+    /// Install a snapshot whose boundary has the given membership
+    ///
+    /// When the whole log is replaced, the membership becomes the active
+    /// configuration.
+    fn install_snapshot_with(
+        &mut self,
+        last_included_index: LogIndex,
+        last_included_term: Term,
+        state_bytes: Vec<u8>,
+        membership: Membership,
+    ) {
+        if last_included_index <= self.persistent.snapshot_index {
+            return;
+        }
+        
+        self.snapshot = Some(Snapshot {
+            last_included_index,
+            last_included_term,
+            membership: membership.clone(),
+            data: state_bytes,
+        });
+        
+        if self.persistent.term_at(last_included_index) == last_included_term
+            && last_included_index <= self.persistent.last_index()
+        {
+            self.compact_log(last_included_index);
+        } else {
+            self.persistent.reset_to_snapshot(last_included_index, last_included_term);
+            self.persistent.snapshot_membership = membership.clone();
+            self.config.set_membership(membership);
+            self.mark_unsynced(last_included_index + 1);
+        }
+        
+        self.commit_index = self.commit_index.max(last_included_index);
+        self.last_applied = self.last_applied.max(last_included_index);
+        self.pending_events.push(Event::PersistState);
+    }
+    
+    /// Discard log entries up to up_to_index that are covered by the snapshot
+    ///
+    /// Returns the number of entries removed.
+    pub fn compact_log(&mut self, up_to_index: LogIndex) -> usize {
+        let boundary = match &self.snapshot {
+            Some(snapshot) => snapshot.last_included_index,
+            None => return 0,
+        };
+        
+        let index = up_to_index.min(boundary);
+        let membership = self.membership_at(index);
+        let removed = self.persistent.compact_to(index);
+        if removed > 0 {
+            self.persistent.snapshot_membership = membership;
+            self.pending_events.push(Event::PersistState);
         }
+        removed
     }
     
     /// Propose a new entry (client request, only valid for leader)
@@ -632,28 +1171,130 @@ impl<T: Clone + Debug> Raft<T> {
             return Err(ProposeError::NotLeader);
         }
         
+        Ok(self.append_and_replicate(command, EntryType::Command))
+    }
+    
+    /// Whether a membership change is in the log but not yet committed
+    pub fn config_change_pending(&self) -> bool {
+        self.persistent.entries_from(self.commit_index + 1)
+            .iter()
+            .any(|e| matches!(e.entry_type, EntryType::ConfigChange(_)))
+    }
+    
+    /// Validate a membership change before the leader logs it
+    fn check_config_change(&self, change: MembershipChange) -> Result<(), ProposeError> {
+        if self.state != NodeState::Leader {
+            return Err(ProposeError::NotLeader);
+        }
+        
+        // One server at a time: overlapping changes could form two majorities
+        if self.config_change_pending() {
+            return Err(ProposeError::ConfigChangePending);
+        }
+        
+        let is_member = |id: NodeId| self.config.peers.contains(&id);
+        match change {
+            MembershipChange::AddServer(id) if is_member(id) || self.config.is_learner(id) => {
+                Err(ProposeError::InvalidMembership)
+            }
+            MembershipChange::RemoveServer(id) if !is_member(id) || self.config.peers.len() == 1 => {
+                Err(ProposeError::InvalidMembership)
+            }
+            MembershipChange::PromoteLearner(id) => {
+                let idx = match self.config.learners.iter().position(|&l| l == id) {
+                    Some(pos) => self.config.peers.len() + pos,
+                    None => return Err(ProposeError::InvalidMembership),
+                };
+                let match_index = self.leader_state.as_ref().map_or(0, |ls| ls.match_index[idx]);
+                if match_index < self.commit_index {
+                    return Err(ProposeError::LearnerNotCaughtUp);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    /// Apply a committed membership change to the active configuration
+    fn apply_config_change(&mut self, change: MembershipChange) {
+        match change {
+            MembershipChange::AddServer(id) => {
+                if self.config.peers.contains(&id) {
+                    return;
+                }
+                self.config.peers.push(id);
+                
+                // Voter progress stays ahead of the learners'
+                let pos = self.config.peers.len() - 1;
+                if let Some(leader_state) = self.leader_state.as_mut() {
+                    leader_state.next_index.insert(pos, self.persistent.last_index() + 1);
+                    leader_state.match_index.insert(pos, 0);
+                    self.send_append_entries_to(id);
+                }
+            }
+            MembershipChange::RemoveServer(id) => {
+                let pos = match self.config.peers.iter().position(|&p| p == id) {
+                    Some(pos) => pos,
+                    None => return,
+                };
+                self.config.peers.remove(pos);
+                self.votes_received.retain(|&v| v != id);
+                
+                if let Some(leader_state) = self.leader_state.as_mut() {
+                    leader_state.next_index.remove(pos);
+                    leader_state.match_index.remove(pos);
+                }
+                
+                // A leader that removed itself hands off by stepping down
+                if id == self.config.node_id && self.state == NodeState::Leader {
+                    self.state = NodeState::Follower;
+                    self.leader_state = None;
+                    self.pending_events.push(Event::SteppedDown { new_term: self.persistent.current_term });
+                }
+            }
+            MembershipChange::PromoteLearner(id) => {
+                let pos = match self.config.learners.iter().position(|&l| l == id) {
+                    Some(pos) => pos,
+                    None => return,
+                };
+                let voters = self.config.peers.len();
+                self.config.learners.remove(pos);
+                self.config.peers.push(id);
+                
+                // Move its progress from the learner section to the end of the voters
+                if let Some(leader_state) = self.leader_state.as_mut() {
+                    let next = leader_state.next_index.remove(voters + pos);
+                    let matched = leader_state.match_index.remove(voters + pos);
+                    leader_state.next_index.insert(voters, next);
+                    leader_state.match_index.insert(voters, matched);
+                }
+            }
+        }
+    }
+    
+    /// Append an entry to the leader's log and replicate it to all peers
+    fn append_and_replicate(&mut self, command: T, entry_type: EntryType) -> LogIndex {
         let entry = LogEntry {
             term: self.persistent.current_term,
             index: self.persistent.last_index() + 1,
             command,
-            entry_type: EntryType::Command,
+            entry_type,
         };
         
         let index = entry.index;
         self.persistent.log.push(entry);
+        self.mark_unsynced(index);
         self.pending_events.push(Event::PersistState);
         
-        // Replicate to all peers - collect peers first to avoid borrow issues
-        let peers: Vec<NodeId> = self.config.peers.iter()
-            .filter(|&&p| p != self.config.node_id)
-            .cloned()
-            .collect();
-        
-        for peer in peers {
+        // Replicate to all peers and learners
+        for peer in self.config.replication_targets() {
             self.send_append_entries_to(peer);
         }
         
-        Ok(index)
+        // A lone leader is its own quorum
+        self.advance_commit_index();
+        
+        index
     }
     
     /// Step down to follower
@@ -679,8 +1320,10 @@ impl<T: Clone + Debug> Raft<T> {
                 continue;
             }
             
+            // Learners' progress sits after the voters and is not counted
             let replicated_count = leader_state.match_index
                 .iter()
+                .take(self.config.peers.len())
                 .filter(|&&m| m >= n)
                 .count();
             
@@ -704,6 +1347,13 @@ impl<T: Clone + Debug> Raft<T> {
             
             self.last_applied = self.commit_index;
             
+            // New configuration takes effect once its entry commits
+            for entry in &entries {
+                if let EntryType::ConfigChange(change) = entry.entry_type {
+                    self.apply_config_change(change);
+                }
+            }
+            
             if !entries.is_empty() {
                 self.pending_events.push(Event::Committed { entries });
             }
@@ -713,16 +1363,34 @@ impl<T: Clone + Debug> Raft<T> {
     /// Send AppendEntries to specific peer
     fn send_append_entries_to(&mut self, peer: NodeId) {
         let leader_state = self.leader_state.as_ref().unwrap();
-        let peer_idx = self.config.peers.iter().position(|&id| id == peer).unwrap_or(0);
+        let peer_idx = self.config.progress_index(peer).unwrap_or(0);
         
         let next_idx = leader_state.next_index[peer_idx];
+        
+        // Entries the peer needs were compacted away; ship the snapshot
+        if next_idx <= self.persistent.snapshot_index {
+            if let Some(snapshot) = &self.snapshot {
+                let args = InstallSnapshotArgs {
+                    term: self.persistent.current_term,
+                    leader_id: self.config.node_id,
+                    last_included_index: snapshot.last_included_index,
+                    last_included_term: snapshot.last_included_term,
+                    membership: snapshot.membership.clone(),
+                    offset: 0,
+                    data: snapshot.data.clone(),
+                    done: true,
+                };
+                self.pending_events.push(Event::InstallSnapshot { peer, args });
+                return;
+            }
+        }
+        
         let prev_log_index = next_idx - 1;
         let prev_log_term = self.persistent.term_at(prev_log_index);
         
         // Get entries to send
-        let entries: Vec<LogEntry<T>> = self.persistent.log
+        let entries: Vec<LogEntry<T>> = self.persistent.entries_from(next_idx)
             .iter()
-            .skip((next_idx - 1) as usize)
             .take(self.config.max_entries_per_rpc)
             .cloned()
             .collect();
@@ -734,28 +1402,58 @@ impl<T: Clone + Debug> Raft<T> {
             prev_log_term,
             entries,
             leader_commit: self.commit_index,
+            round: self.heartbeat_round,
         };
         
         self.pending_events.push(Event::SendAppendEntries { peer, args });
     }
     
     /// Generate heartbeats for all peers (call periodically when leader)
+    ///
+    /// Each call starts a new round for `read_index`.
     pub fn send_heartbeats(&mut self) {
         if self.state != NodeState::Leader {
             return;
         }
         
-        // Collect peers first to avoid borrow issues
-        let peers: Vec<NodeId> = self.config.peers.iter()
-            .filter(|&&p| p != self.config.node_id)
-            .cloned()
-            .collect();
+        self.start_heartbeat_round();
         
-        for peer in peers {
+        for peer in self.config.replication_targets() {
             self.send_append_entries_to(peer);
         }
     }
     
+    /// Forget earlier acknowledgements and tag requests with a new round
+    fn start_heartbeat_round(&mut self) {
+        self.heartbeat_round += 1;
+        self.heartbeat_acks.clear();
+    }
+    
+    /// Commit index that is safe to serve a linearizable read from
+    ///
+    /// Returns `None` unless this node is a leader that has committed an
+    /// entry in its current term and a quorum acknowledged the current
+    /// heartbeat round. The caller answers the read once `last_applied`
+    /// reaches the returned index.
+    pub fn read_index(&self) -> Option<LogIndex> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        
+        // Until something from this term commits, commit_index may lag
+        // entries an earlier leader already acknowledged
+        if self.persistent.term_at(self.commit_index) != self.persistent.current_term {
+            return None;
+        }
+        
+        // Count ourselves plus peers confirming leadership this round
+        if self.heartbeat_acks.len() + 1 >= self.config.quorum() {
+            Some(self.commit_index)
+        } else {
+            None
+        }
+    }
+    
     /// Take pending events for processing
     pub fn take_events(&mut self) -> Vec<Event<T>> {
         core::mem::take(&mut self.pending_events)
@@ -775,18 +1473,89 @@ impl<T: Clone + Debug> Raft<T> {
     }
 }
 
+impl<T: Clone + Debug + Default> Raft<T> {
+    /// Add a voting member (leader only, one change at a time)
+    ///
+    /// The server joins the active configuration once the entry commits.
+    pub fn add_server(&mut self, node_id: NodeId) -> Result<LogIndex, ProposeError> {
+        let change = MembershipChange::AddServer(node_id);
+        self.check_config_change(change)?;
+        Ok(self.append_and_replicate(T::default(), EntryType::ConfigChange(change)))
+    }
+    
+    /// Remove a voting member (leader only, one change at a time)
+    ///
+    /// The server leaves the active configuration once the entry commits.
+    pub fn remove_server(&mut self, node_id: NodeId) -> Result<LogIndex, ProposeError> {
+        let change = MembershipChange::RemoveServer(node_id);
+        self.check_config_change(change)?;
+        Ok(self.append_and_replicate(T::default(), EntryType::ConfigChange(change)))
+    }
+    
+    /// Make a learner a voting member (leader only, one change at a time)
+    ///
+    /// The learner must have replicated everything committed so far; it
+    /// starts voting once the entry commits.
+    pub fn promote_learner(&mut self, node_id: NodeId) -> Result<LogIndex, ProposeError> {
+        let change = MembershipChange::PromoteLearner(node_id);
+        self.check_config_change(change)?;
+        Ok(self.append_and_replicate(T::default(), EntryType::ConfigChange(change)))
+    }
+}
+
 /// Error types for propose operation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProposeError {
     NotLeader,
     ClusterNotReady,
     Timeout,
+    /// A previous membership change has not committed yet
+    ConfigChangePending,
+    /// Adding an existing member or removing an unknown one
+    InvalidMembership,
+    /// The learner has not yet replicated everything committed
+    LearnerNotCaughtUp,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_append_entries_serde_round_trip() {
+        let entries = vec![
+            LogEntry { term: 1, index: 1, command: 0, entry_type: EntryType::NoOp },
+            LogEntry { term: 1, index: 2, command: 42, entry_type: EntryType::Command },
+            LogEntry {
+                term: 2,
+                index: 3,
+                command: 0,
+                entry_type: EntryType::ConfigChange(MembershipChange::AddServer(4)),
+            },
+            LogEntry { term: 2, index: 4, command: u64::MAX, entry_type: EntryType::Command },
+        ];
+        let args = AppendEntriesArgs {
+            term: 2,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: entries.clone(),
+            leader_commit: 3,
+            round: 0,
+        };
+        
+        let json = serde_json::to_string(&args).unwrap();
+        let decoded: AppendEntriesArgs<u64> = serde_json::from_str(&json).unwrap();
+        
+        assert_eq!(decoded.term, 2);
+        assert_eq!(decoded.leader_id, 1);
+        assert_eq!(decoded.prev_log_index, 0);
+        assert_eq!(decoded.prev_log_term, 0);
+        assert_eq!(decoded.entries, entries);
+        assert_eq!(decoded.leader_commit, 3);
+    }
+
     #[test]
     fn test_persistent_state_new() {
         let state: PersistentState<u64> = PersistentState::new();
@@ -869,9 +1638,10 @@ mod tests {
         
         raft.become_leader();
         
+        // Index 1 holds the no-op appended on election
         let result = raft.propose(42);
-        assert_eq!(result, Ok(1));
-        assert_eq!(raft.persistent.last_index(), 1);
+        assert_eq!(result, Ok(2));
+        assert_eq!(raft.persistent.last_index(), 2);
     }
     
     #[test]
@@ -887,4 +1657,420 @@ mod tests {
         // Now have 2 votes (self + peer 2), quorum is 2, should become leader
         assert_eq!(raft.state, NodeState::Leader);
     }
+    
+    /// Leader whose log holds the election no-op at index 1, then
+    /// commands equal to their index up to `len`
+    fn leader_with_log(peers: Vec<NodeId>, len: u64) -> Raft<u64> {
+        let mut raft: Raft<u64> = Raft::new(Config::new(1, peers));
+        raft.persistent.current_term = 1;
+        raft.become_leader();
+        
+        for i in 2..=len {
+            // Second half of the log comes from a later term
+            if i == len / 2 + 1 {
+                raft.persistent.current_term = 2;
+            }
+            raft.propose(i).unwrap();
+        }
+        raft.take_events();
+        raft
+    }
+    
+    #[test]
+    fn test_leader_steps_down_once_on_higher_term() {
+        let mut raft = leader_with_log(vec![1, 2, 3], 0);
+
+        let reply = raft.handle_append_entries(AppendEntriesArgs {
+            term: 5,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+            round: 0,
+        });
+        assert!(reply.success);
+        assert_eq!(raft.state, NodeState::Follower);
+        assert_eq!(raft.persistent.current_term, 5);
+
+        let stepped_down = raft.take_events()
+            .iter()
+            .filter(|e| matches!(e, Event::SteppedDown { .. }))
+            .count();
+        assert_eq!(stepped_down, 1);
+    }
+
+    #[test]
+    fn test_candidate_keeps_vote_when_leader_appears() {
+        let mut raft: Raft<u64> = Raft::new(Config::new(1, vec![1, 2, 3]));
+        raft.start_election();
+        raft.take_events();
+
+        raft.handle_append_entries(AppendEntriesArgs {
+            term: 1,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+            round: 0,
+        });
+        assert_eq!(raft.state, NodeState::Follower);
+        assert_eq!(raft.persistent.voted_for, Some(1));
+        assert_eq!(raft.leader_id, Some(2));
+    }
+
+    #[test]
+    fn test_candidate_keeps_vote_on_snapshot_from_leader() {
+        let mut raft: Raft<u64> = Raft::new(Config::new(1, vec![1, 2, 3]));
+        raft.start_election();
+        raft.take_events();
+
+        let reply = raft.handle_install_snapshot(InstallSnapshotArgs {
+            term: 1,
+            leader_id: 2,
+            last_included_index: 10,
+            last_included_term: 1,
+            membership: Config::new(1, vec![1, 2, 3]).membership(),
+            offset: 0,
+            data: vec![1, 2, 3],
+            done: true,
+        });
+        assert_eq!(reply.term, 1);
+        assert_eq!(raft.state, NodeState::Follower);
+        assert_eq!(raft.persistent.voted_for, Some(1));
+        assert_eq!(raft.leader_id, Some(2));
+        assert_eq!(raft.persistent.last_index(), 10);
+    }
+
+    #[test]
+    fn test_compact_log_to_snapshot() {
+        let mut raft = leader_with_log(vec![1], 100);
+        
+        // Nothing to compact without a snapshot
+        assert_eq!(raft.compact_log(50), 0);
+        assert_eq!(raft.persistent.log.len(), 100);
+        
+        let term = raft.persistent.term_at(80);
+        raft.install_snapshot(80, term, vec![0xAB; 16]);
+        
+        assert_eq!(raft.persistent.log.len(), 20);
+        assert_eq!(raft.persistent.last_index(), 100);
+        assert_eq!(raft.persistent.last_term(), 2);
+        assert_eq!(raft.persistent.term_at(80), 2);
+        assert_eq!(raft.persistent.term_at(81), 2);
+        assert_eq!(raft.persistent.entry_at(81).map(|e| e.command), Some(81));
+        assert_eq!(raft.persistent.term_at(40), 0);
+        assert!(raft.persistent.entry_at(80).is_none());
+        
+        // Compaction never goes past the snapshot boundary
+        assert_eq!(raft.compact_log(90), 0);
+        assert_eq!(raft.propose(101), Ok(101));
+        assert_eq!(raft.persistent.term_at(101), 2);
+    }
+    
+    #[test]
+    fn test_lagging_follower_gets_snapshot() {
+        let mut leader = leader_with_log(vec![1, 2], 100);
+        let term = leader.persistent.term_at(80);
+        leader.install_snapshot(80, term, vec![7; 4]);
+        
+        // Follower 2 is still at the start of the log
+        leader.leader_state.as_mut().unwrap().next_index[1] = 1;
+        leader.send_heartbeats();
+        
+        let args = leader.take_events().into_iter().find_map(|e| match e {
+            Event::InstallSnapshot { peer: 2, args } => Some(args),
+            _ => None,
+        }).expect("leader should send InstallSnapshot");
+        assert_eq!(args.last_included_index, 80);
+        assert_eq!(args.data, vec![7; 4]);
+        
+        let mut follower: Raft<u64> = Raft::new(Config::new(2, vec![1, 2]));
+        let reply = follower.handle_install_snapshot(args);
+        assert_eq!(reply.term, 2);
+        assert_eq!(follower.persistent.last_index(), 80);
+        assert_eq!(follower.persistent.last_term(), 2);
+        assert_eq!(follower.commit_index, 80);
+        assert_eq!(follower.snapshot.as_ref().map(|s| s.data.len()), Some(4));
+        
+        leader.handle_install_snapshot_reply(2, reply);
+        let leader_state = leader.leader_state.as_ref().unwrap();
+        assert_eq!(leader_state.match_index[1], 80);
+        assert_eq!(leader_state.next_index[1], 81);
+        
+        // The follower can now accept entries after the snapshot
+        let append = AppendEntriesArgs {
+            term: 2,
+            leader_id: 1,
+            prev_log_index: 80,
+            prev_log_term: 2,
+            entries: leader.persistent.entries_from(81).to_vec(),
+            leader_commit: 100,
+            round: 0,
+        };
+        assert!(follower.handle_append_entries(append).success);
+        assert_eq!(follower.persistent.last_index(), 100);
+        assert_eq!(follower.persistent.term_at(81), 2);
+    }
+    
+    /// Deliver queued RPCs between nodes until the cluster goes quiet
+    fn run_cluster(nodes: &mut [Raft<u64>]) {
+        for _ in 0..50 {
+            let mut delivered = false;
+            
+            for i in 0..nodes.len() {
+                for event in nodes[i].take_events() {
+                    let target = match &event {
+                        Event::SendPreVote { peer, .. }
+                        | Event::SendRequestVote { peer, .. }
+                        | Event::SendAppendEntries { peer, .. }
+                        | Event::InstallSnapshot { peer, .. } => *peer,
+                        _ => continue,
+                    };
+                    let j = match nodes.iter().position(|n| n.config.node_id == target) {
+                        Some(j) => j,
+                        None => continue,
+                    };
+                    delivered = true;
+                    
+                    match event {
+                        Event::SendPreVote { args, .. } => {
+                            let reply = nodes[j].handle_pre_vote(args);
+                            nodes[i].handle_pre_vote_reply(target, reply);
+                        }
+                        Event::SendRequestVote { args, .. } => {
+                            let reply = nodes[j].handle_request_vote(args);
+                            nodes[i].handle_request_vote_reply(target, reply);
+                        }
+                        Event::SendAppendEntries { args, .. } => {
+                            let reply = nodes[j].handle_append_entries(args);
+                            nodes[i].handle_append_entries_reply(target, reply);
+                        }
+                        Event::InstallSnapshot { args, .. } => {
+                            let reply = nodes[j].handle_install_snapshot(args);
+                            nodes[i].handle_install_snapshot_reply(target, reply);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            
+            if !delivered {
+                // Heartbeat again so followers learn the final commit index
+                let synced = nodes.iter().all(|n| n.commit_index == n.persistent.last_index());
+                match nodes.iter_mut().find(|n| n.state == NodeState::Leader) {
+                    Some(leader) if !synced => leader.send_heartbeats(),
+                    _ => return,
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn test_grow_cluster_three_to_five() {
+        let mut nodes: Vec<Raft<u64>> = (1..=5)
+            .map(|id| Raft::new(Config::new(id, vec![1, 2, 3])))
+            .collect();
+        
+        nodes[0].start_election();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].state, NodeState::Leader);
+        assert_eq!(nodes[0].config.quorum(), 2);
+        
+        nodes[0].add_server(4).unwrap();
+        // Only one membership change may be in flight
+        assert_eq!(nodes[0].add_server(5), Err(ProposeError::ConfigChangePending));
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].config.peers, vec![1, 2, 3, 4]);
+        assert_eq!(nodes[0].config.quorum(), 3);
+        
+        nodes[0].add_server(5).unwrap();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].add_server(5), Err(ProposeError::InvalidMembership));
+        
+        for node in &nodes {
+            assert_eq!(node.config.peers, vec![1, 2, 3, 4, 5]);
+            assert_eq!(node.config.quorum(), 3);
+            // The election no-op plus the two changes
+            assert_eq!(node.persistent.last_index(), 3);
+        }
+        
+        // Two votes used to be a majority; with five members it takes three
+        let candidate = &mut nodes[1];
+        candidate.start_election();
+        candidate.record_vote(3);
+        assert_eq!(candidate.state, NodeState::Candidate);
+        candidate.record_vote(4);
+        assert_eq!(candidate.state, NodeState::Leader);
+    }
+
+    #[test]
+    fn test_read_index_on_fresh_leader() {
+        let mut nodes: Vec<Raft<u64>> = (1..=3)
+            .map(|id| Raft::new(Config::new(id, vec![1, 2, 3])))
+            .collect();
+        
+        nodes[0].start_election();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].state, NodeState::Leader);
+        
+        // Nothing proposed yet: the election no-op is what commits this term
+        assert_eq!(nodes[0].persistent.entry_at(1).map(|e| e.entry_type), Some(EntryType::NoOp));
+        assert_eq!(nodes[0].commit_index, 1);
+        
+        nodes[0].send_heartbeats();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].read_index(), Some(1));
+    }
+
+    #[test]
+    fn test_read_index_ignores_previous_round() {
+        let mut nodes: Vec<Raft<u64>> = (1..=3)
+            .map(|id| Raft::new(Config::new(id, vec![1, 2, 3])))
+            .collect();
+        
+        nodes[0].start_election();
+        run_cluster(&mut nodes);
+        
+        // Followers answer a heartbeat, but the replies arrive late
+        nodes[0].send_heartbeats();
+        let late: Vec<(NodeId, AppendEntriesReply)> = nodes[0].take_events().into_iter()
+            .filter_map(|e| match e {
+                Event::SendAppendEntries { peer, args } => Some((peer, args)),
+                _ => None,
+            })
+            .map(|(peer, args)| (peer, nodes[peer as usize - 1].handle_append_entries(args)))
+            .collect();
+        assert_eq!(late.len(), 2);
+        
+        nodes[0].send_heartbeats();
+        for (peer, reply) in late {
+            assert_eq!(reply.term, nodes[0].persistent.current_term);
+            nodes[0].handle_append_entries_reply(peer, reply);
+        }
+        assert!(nodes[0].heartbeat_acks.is_empty());
+        assert_eq!(nodes[0].read_index(), None);
+        
+        // Replies to the current round do confirm leadership
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].read_index(), Some(1));
+    }
+
+    fn cluster_with_learner() -> Vec<Raft<u64>> {
+        (1..=4)
+            .map(|id| {
+                let mut config = Config::new(id, vec![1, 2, 3]);
+                config.learners = vec![4];
+                Raft::new(config)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_learner_replicates_without_voting() {
+        let mut nodes = cluster_with_learner();
+
+        nodes[0].start_election();
+        let vote_targets: Vec<NodeId> = nodes[0].pending_events.iter()
+            .filter_map(|e| match e {
+                Event::SendRequestVote { peer, .. } => Some(*peer),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vote_targets, vec![2, 3]);
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].state, NodeState::Leader);
+
+        // Learners never start elections
+        nodes[3].handle_election_timeout();
+        assert_eq!(nodes[3].state, NodeState::Follower);
+        assert!(nodes[3].take_events().is_empty());
+
+        // With both voting followers cut off, the learner's copy is not enough
+        let mut voters = nodes.split_off(1);
+        let learner = voters.pop().unwrap();
+        nodes.push(learner);
+        nodes[0].propose(7).unwrap();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[1].persistent.last_index(), 2);
+        // Only the election no-op, committed before the cut, counts
+        assert_eq!(nodes[0].commit_index, 1);
+
+        let learner = nodes.pop().unwrap();
+        nodes.append(&mut voters);
+        nodes.push(learner);
+        run_cluster(&mut nodes);
+        for node in &nodes {
+            assert_eq!(node.commit_index, 2);
+        }
+    }
+
+    #[test]
+    fn test_learner_vote_ignored() {
+        let mut nodes = cluster_with_learner();
+        let candidate = &mut nodes[1];
+        candidate.start_election();
+        let term = candidate.persistent.current_term;
+        candidate.handle_request_vote_reply(4, RequestVoteReply { term, vote_granted: true, reason: None });
+        assert_eq!(candidate.state, NodeState::Candidate);
+        candidate.handle_request_vote_reply(3, RequestVoteReply { term, vote_granted: true, reason: None });
+        assert_eq!(candidate.state, NodeState::Leader);
+    }
+
+    #[test]
+    fn test_promote_learner() {
+        let mut nodes = cluster_with_learner();
+        nodes[0].start_election();
+        run_cluster(&mut nodes);
+
+        assert_eq!(nodes[0].promote_learner(2), Err(ProposeError::InvalidMembership));
+        assert_eq!(nodes[0].add_server(4), Err(ProposeError::InvalidMembership));
+
+        // Commit an entry the learner has not seen yet
+        let learner = nodes.pop().unwrap();
+        nodes[0].propose(1).unwrap();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].commit_index, 2);
+        assert_eq!(nodes[0].promote_learner(4), Err(ProposeError::LearnerNotCaughtUp));
+
+        nodes.push(learner);
+        nodes[0].send_heartbeats();
+        run_cluster(&mut nodes);
+        nodes[0].promote_learner(4).unwrap();
+        run_cluster(&mut nodes);
+
+        for node in &nodes {
+            assert_eq!(node.config.peers, vec![1, 2, 3, 4]);
+            assert!(node.config.learners.is_empty());
+            assert_eq!(node.config.quorum(), 3);
+            assert_eq!(node.commit_index, 3);
+        }
+        assert_eq!(nodes[0].leader_state.as_ref().unwrap().match_index[3], 3);
+    }
+
+    #[test]
+    fn test_remove_server() {
+        let mut nodes: Vec<Raft<u64>> = (1..=3)
+            .map(|id| Raft::new(Config::new(id, vec![1, 2, 3])))
+            .collect();
+        
+        nodes[0].start_election();
+        run_cluster(&mut nodes);
+        
+        assert_eq!(nodes[1].remove_server(3), Err(ProposeError::NotLeader));
+        assert_eq!(nodes[0].remove_server(9), Err(ProposeError::InvalidMembership));
+        
+        nodes[0].remove_server(3).unwrap();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].config.peers, vec![1, 2]);
+        assert_eq!(nodes[1].config.peers, vec![1, 2]);
+        assert_eq!(nodes[0].leader_state.as_ref().unwrap().match_index.len(), 2);
+        
+        // The remaining pair still commits new entries
+        nodes[0].propose(7).unwrap();
+        run_cluster(&mut nodes);
+        assert_eq!(nodes[0].commit_index, 3);
+        assert_eq!(nodes[1].commit_index, 3);
+    }
 }
@@ -0,0 +1,566 @@
+//! Raft Stable Storage Module
+//!
+//! Durability for `PersistentState`. The Raft state machine only keeps its
+//! term, vote and log in memory and raises `Event::PersistState` whenever
+//! they change; the host answers by calling `Raft::persist` with a
+//! `RaftStorage` backend, and rebuilds a node after a crash with
+//! `Raft::restore`.
+
+use super::{EntryType, LogEntry, LogIndex, Membership, MembershipChange, NodeId, PersistentState, Term};
+use core::fmt::Debug;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// Stable storage backend for Raft persistent state
+///
+/// Every call must be durable before it returns: Raft may answer an RPC
+/// as soon as `Raft::persist` succeeds.
+pub trait RaftStorage<T: Clone> {
+    /// Error type for storage operations
+    type Error: Debug;
+
+    /// Record the current term and vote
+    fn save_state(&mut self, term: Term, voted_for: Option<NodeId>) -> Result<(), Self::Error>;
+
+    /// Store entries, replacing any stored entries at the same or later indices
+    fn append(&mut self, entries: &[LogEntry<T>]) -> Result<(), Self::Error>;
+
+    /// Delete the stored entry at index and everything after it
+    fn truncate_from(&mut self, index: LogIndex) -> Result<(), Self::Error>;
+
+    /// Drop stored entries covered by a snapshot ending at index/term,
+    /// keeping the membership in effect there
+    fn compact_to(&mut self, index: LogIndex, term: Term, membership: &Membership) -> Result<(), Self::Error>;
+
+    /// Read back everything saved so far
+    fn load(&self) -> Result<PersistentState<T>, Self::Error>;
+}
+
+/// Replay an append onto an in-memory copy of the state
+fn apply_append<T: Clone>(state: &mut PersistentState<T>, entries: &[LogEntry<T>]) {
+    if let Some(first) = entries.first() {
+        // Entries already inside the snapshot are committed and kept as-is
+        state.truncate_from(first.index.max(state.snapshot_index + 1));
+        let skip = state.snapshot_index.saturating_sub(first.index - 1) as usize;
+        state.log.extend(entries.iter().skip(skip).cloned());
+    }
+}
+
+/// Replay a compaction onto an in-memory copy of the state
+fn apply_compact<T: Clone>(state: &mut PersistentState<T>, index: LogIndex, term: Term, membership: &Membership) {
+    if index <= state.snapshot_index {
+        return;
+    }
+    if index <= state.last_index() && state.term_at(index) == term {
+        state.compact_to(index);
+    } else {
+        state.reset_to_snapshot(index, term);
+    }
+    state.snapshot_membership = membership.clone();
+}
+
+/// Volatile storage that keeps a copy of the state in memory
+///
+/// Survives a `Raft` instance being dropped and rebuilt, which is enough
+/// for tests and for nodes that rejoin with a fresh log anyway.
+#[derive(Debug, Clone)]
+pub struct MemoryStorage<T: Clone> {
+    state: PersistentState<T>,
+}
+
+impl<T: Clone> MemoryStorage<T> {
+    /// Create empty storage
+    pub fn new() -> Self {
+        Self {
+            state: PersistentState::new(),
+        }
+    }
+}
+
+impl<T: Clone> Default for MemoryStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> RaftStorage<T> for MemoryStorage<T> {
+    type Error = ();
+
+    fn save_state(&mut self, term: Term, voted_for: Option<NodeId>) -> Result<(), Self::Error> {
+        self.state.current_term = term;
+        self.state.voted_for = voted_for;
+        Ok(())
+    }
+
+    fn append(&mut self, entries: &[LogEntry<T>]) -> Result<(), Self::Error> {
+        apply_append(&mut self.state, entries);
+        Ok(())
+    }
+
+    fn truncate_from(&mut self, index: LogIndex) -> Result<(), Self::Error> {
+        self.state.truncate_from(index);
+        Ok(())
+    }
+
+    fn compact_to(&mut self, index: LogIndex, term: Term, membership: &Membership) -> Result<(), Self::Error> {
+        apply_compact(&mut self.state, index, term, membership);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<PersistentState<T>, Self::Error> {
+        Ok(self.state.clone())
+    }
+}
+
+/// Byte encoding for log commands kept in file-backed storage
+pub trait LogCommand: Sized {
+    /// Serialize the command
+    fn encode(&self) -> Vec<u8>;
+    /// Deserialize a command produced by `encode`
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl LogCommand for u64 {
+    fn encode(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 8] = bytes.try_into().ok()?;
+        Some(u64::from_le_bytes(bytes))
+    }
+}
+
+impl LogCommand for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+#[cfg(feature = "std")]
+pub use file::{FileStorage, FileStorageError};
+
+#[cfg(feature = "std")]
+mod file {
+    use super::*;
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::marker::PhantomData;
+    use std::path::{Path, PathBuf};
+
+    const RECORD_STATE: u8 = 1;
+    const RECORD_APPEND: u8 = 2;
+    const RECORD_TRUNCATE: u8 = 3;
+    const RECORD_COMPACT: u8 = 4;
+
+    const ENTRY_COMMAND: u8 = 0;
+    const ENTRY_ADD_SERVER: u8 = 1;
+    const ENTRY_REMOVE_SERVER: u8 = 2;
+    const ENTRY_NOOP: u8 = 3;
+    const ENTRY_PROMOTE_LEARNER: u8 = 4;
+
+    /// File storage errors
+    #[derive(Debug)]
+    pub enum FileStorageError {
+        /// Underlying I/O failure
+        Io(io::Error),
+        /// The file holds a record that cannot be decoded
+        Corrupt,
+    }
+
+    impl From<io::Error> for FileStorageError {
+        fn from(err: io::Error) -> Self {
+            FileStorageError::Io(err)
+        }
+    }
+
+    /// Append-only write-ahead log on the host filesystem
+    ///
+    /// Each operation is written as one record and synced before the call
+    /// returns; `load` replays the records in order. A torn record at the
+    /// tail (crash mid-write) is ignored.
+    pub struct FileStorage<T> {
+        path: PathBuf,
+        _command: PhantomData<T>,
+    }
+
+    impl<T> FileStorage<T> {
+        /// Use the log at path, creating it on first write
+        pub fn open(path: impl AsRef<Path>) -> Self {
+            Self {
+                path: path.as_ref().to_path_buf(),
+                _command: PhantomData,
+            }
+        }
+
+        /// Path of the backing file
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        fn write_record(&self, kind: u8, body: &[u8]) -> Result<(), FileStorageError> {
+            let mut record = Vec::with_capacity(5 + body.len());
+            record.push(kind);
+            record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            record.extend_from_slice(body);
+
+            let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            file.write_all(&record)?;
+            file.sync_all()?;
+            Ok(())
+        }
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl<'a> Reader<'a> {
+        fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+            if self.bytes.len() < len {
+                return None;
+            }
+            let (head, tail) = self.bytes.split_at(len);
+            self.bytes = tail;
+            Some(head)
+        }
+
+        fn u8(&mut self) -> Option<u8> {
+            self.take(1).map(|b| b[0])
+        }
+
+        fn u32(&mut self) -> Option<u32> {
+            self.take(4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        }
+
+        fn u64(&mut self) -> Option<u64> {
+            self.take(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        }
+    }
+
+    fn encode_entry<T: LogCommand + Clone>(entry: &LogEntry<T>, out: &mut Vec<u8>) {
+        let (kind, node) = match entry.entry_type {
+            EntryType::Command => (ENTRY_COMMAND, 0),
+            EntryType::ConfigChange(MembershipChange::AddServer(id)) => (ENTRY_ADD_SERVER, id),
+            EntryType::ConfigChange(MembershipChange::RemoveServer(id)) => (ENTRY_REMOVE_SERVER, id),
+            EntryType::ConfigChange(MembershipChange::PromoteLearner(id)) => (ENTRY_PROMOTE_LEARNER, id),
+            EntryType::NoOp => (ENTRY_NOOP, 0),
+        };
+        let command = entry.command.encode();
+
+        out.extend_from_slice(&entry.term.to_le_bytes());
+        out.extend_from_slice(&entry.index.to_le_bytes());
+        out.push(kind);
+        out.extend_from_slice(&node.to_le_bytes());
+        out.extend_from_slice(&(command.len() as u32).to_le_bytes());
+        out.extend_from_slice(&command);
+    }
+
+    fn decode_entry<T: LogCommand + Clone>(reader: &mut Reader) -> Option<LogEntry<T>> {
+        let term = reader.u64()?;
+        let index = reader.u64()?;
+        let kind = reader.u8()?;
+        let node = reader.u64()?;
+        let len = reader.u32()? as usize;
+        let command = T::decode(reader.take(len)?)?;
+
+        let entry_type = match kind {
+            ENTRY_COMMAND => EntryType::Command,
+            ENTRY_ADD_SERVER => EntryType::ConfigChange(MembershipChange::AddServer(node)),
+            ENTRY_REMOVE_SERVER => EntryType::ConfigChange(MembershipChange::RemoveServer(node)),
+            ENTRY_NOOP => EntryType::NoOp,
+            ENTRY_PROMOTE_LEARNER => EntryType::ConfigChange(MembershipChange::PromoteLearner(node)),
+            _ => return None,
+        };
+
+        Some(LogEntry { term, index, command, entry_type })
+    }
+
+    fn encode_nodes(nodes: &[NodeId], out: &mut Vec<u8>) {
+        out.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+        for node in nodes {
+            out.extend_from_slice(&node.to_le_bytes());
+        }
+    }
+
+    fn decode_nodes(reader: &mut Reader) -> Option<Vec<NodeId>> {
+        let count = reader.u32()?;
+        (0..count).map(|_| reader.u64()).collect()
+    }
+
+    impl<T: LogCommand + Clone> RaftStorage<T> for FileStorage<T> {
+        type Error = FileStorageError;
+
+        fn save_state(&mut self, term: Term, voted_for: Option<NodeId>) -> Result<(), Self::Error> {
+            let mut body = Vec::with_capacity(17);
+            body.extend_from_slice(&term.to_le_bytes());
+            body.push(voted_for.is_some() as u8);
+            body.extend_from_slice(&voted_for.unwrap_or(0).to_le_bytes());
+            self.write_record(RECORD_STATE, &body)
+        }
+
+        fn append(&mut self, entries: &[LogEntry<T>]) -> Result<(), Self::Error> {
+            if entries.is_empty() {
+                return Ok(());
+            }
+            let mut body = Vec::new();
+            body.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+            for entry in entries {
+                encode_entry(entry, &mut body);
+            }
+            self.write_record(RECORD_APPEND, &body)
+        }
+
+        fn truncate_from(&mut self, index: LogIndex) -> Result<(), Self::Error> {
+            self.write_record(RECORD_TRUNCATE, &index.to_le_bytes())
+        }
+
+        fn compact_to(&mut self, index: LogIndex, term: Term, membership: &Membership) -> Result<(), Self::Error> {
+            let mut body = Vec::with_capacity(24 + 8 * (membership.peers.len() + membership.learners.len()));
+            body.extend_from_slice(&index.to_le_bytes());
+            body.extend_from_slice(&term.to_le_bytes());
+            encode_nodes(&membership.peers, &mut body);
+            encode_nodes(&membership.learners, &mut body);
+            self.write_record(RECORD_COMPACT, &body)
+        }
+
+        fn load(&self) -> Result<PersistentState<T>, Self::Error> {
+            let mut state = PersistentState::new();
+
+            let mut bytes = Vec::new();
+            match File::open(&self.path) {
+                Ok(mut file) => {
+                    file.read_to_end(&mut bytes)?;
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(state),
+                Err(err) => return Err(err.into()),
+            }
+
+            let mut reader = Reader { bytes: &bytes };
+            while let Some(kind) = reader.u8() {
+                // A short header or body means the last write was torn
+                let body = match reader.u32().and_then(|len| reader.take(len as usize)) {
+                    Some(body) => body,
+                    None => break,
+                };
+                let mut body = Reader { bytes: body };
+
+                match kind {
+                    RECORD_STATE => {
+                        let term = body.u64().ok_or(FileStorageError::Corrupt)?;
+                        let has_vote = body.u8().ok_or(FileStorageError::Corrupt)?;
+                        let vote = body.u64().ok_or(FileStorageError::Corrupt)?;
+                        state.current_term = term;
+                        state.voted_for = if has_vote != 0 { Some(vote) } else { None };
+                    }
+                    RECORD_APPEND => {
+                        let count = body.u32().ok_or(FileStorageError::Corrupt)?;
+                        let entries = (0..count)
+                            .map(|_| decode_entry(&mut body))
+                            .collect::<Option<Vec<LogEntry<T>>>>()
+                            .ok_or(FileStorageError::Corrupt)?;
+                        apply_append(&mut state, &entries);
+                    }
+                    RECORD_TRUNCATE => {
+                        let index = body.u64().ok_or(FileStorageError::Corrupt)?;
+                        state.truncate_from(index);
+                    }
+                    RECORD_COMPACT => {
+                        let index = body.u64().ok_or(FileStorageError::Corrupt)?;
+                        let term = body.u64().ok_or(FileStorageError::Corrupt)?;
+                        let peers = decode_nodes(&mut body).ok_or(FileStorageError::Corrupt)?;
+                        let learners = decode_nodes(&mut body).ok_or(FileStorageError::Corrupt)?;
+                        apply_compact(&mut state, index, term, &Membership { peers, learners });
+                    }
+                    _ => return Err(FileStorageError::Corrupt),
+                }
+            }
+
+            Ok(state)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{Config, Event, Raft};
+
+    fn entry(index: LogIndex, term: Term, command: u64) -> LogEntry<u64> {
+        LogEntry {
+            term,
+            index,
+            command,
+            entry_type: EntryType::Command,
+        }
+    }
+
+    /// Drive a node through a vote, some proposals and a conflict, flushing
+    /// on every PersistState event, then rebuild it from storage
+    fn check_reload<S: RaftStorage<u64>>(storage: &mut S) {
+        let config = Config::new(1, vec![1, 2, 3]);
+        let mut raft: Raft<u64> = Raft::new(config.clone());
+
+        let flush = |raft: &mut Raft<u64>, storage: &mut S| {
+            let events = raft.take_events();
+            if events.iter().any(|e| matches!(e, Event::PersistState)) {
+                raft.persist(storage).unwrap();
+            }
+        };
+
+        // Follower accepts five entries from leader 2 in term 3
+        let args = crate::consensus::AppendEntriesArgs {
+            term: 3,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: (1..=5).map(|i| entry(i, 3, i * 10)).collect(),
+            leader_commit: 2,
+            round: 0,
+        };
+        assert!(raft.handle_append_entries(args).success);
+        flush(&mut raft, storage);
+
+        // A new leader in term 4 overwrites the tail
+        let args = crate::consensus::AppendEntriesArgs {
+            term: 4,
+            leader_id: 3,
+            prev_log_index: 3,
+            prev_log_term: 3,
+            entries: vec![entry(4, 4, 400)],
+            leader_commit: 3,
+            round: 0,
+        };
+        assert!(raft.handle_append_entries(args).success);
+        flush(&mut raft, storage);
+        assert_eq!(raft.persistent.last_index(), 3);
+
+        // Then votes for node 3 in term 5
+        let reply = raft.handle_request_vote(crate::consensus::RequestVoteArgs {
+            term: 5,
+            candidate_id: 3,
+            last_log_index: 3,
+            last_log_term: 3,
+        });
+        assert!(reply.vote_granted);
+        flush(&mut raft, storage);
+
+        let restored: Raft<u64> = Raft::restore(config, storage).unwrap();
+        assert_eq!(restored.persistent.current_term, 5);
+        assert_eq!(restored.persistent.voted_for, Some(3));
+        assert_eq!(restored.persistent.log, raft.persistent.log);
+        assert_eq!(restored.persistent.last_index(), 3);
+        assert_eq!(restored.commit_index, 0);
+    }
+
+    /// Commit an added server, restart, compact it into a snapshot and
+    /// restart again: both times the node must come back with five members
+    fn check_membership_reload<S: RaftStorage<u64>>(storage: &mut S) {
+        let config = Config::new(1, vec![1, 2, 3]);
+        let mut raft: Raft<u64> = Raft::new(config.clone());
+        raft.start_election();
+        raft.record_vote(2);
+        let term = raft.persistent.current_term;
+
+        let index = raft.add_server(4).unwrap();
+        raft.handle_append_entries_reply(2, crate::consensus::AppendEntriesReply {
+            term,
+            success: true,
+            conflict_info: None,
+            match_index: index,
+            round: raft.heartbeat_round,
+        });
+        assert_eq!(raft.commit_index, index);
+        assert_eq!(raft.config.quorum(), 3);
+        raft.persist(storage).unwrap();
+
+        // The change is still in the log and is replayed
+        let restored: Raft<u64> = Raft::restore(config.clone(), storage).unwrap();
+        assert_eq!(restored.config.peers, vec![1, 2, 3, 4]);
+        assert_eq!(restored.config.quorum(), 3);
+
+        // Once compacted away, the snapshot carries it
+        raft.install_snapshot(index, term, vec![0xAB]);
+        assert_eq!(raft.persistent.snapshot_index, index);
+        assert!(raft.persistent.log.is_empty());
+        raft.persist(storage).unwrap();
+
+        let restored: Raft<u64> = Raft::restore(config, storage).unwrap();
+        assert_eq!(restored.persistent.snapshot_index, index);
+        assert_eq!(restored.config.peers, vec![1, 2, 3, 4]);
+        assert_eq!(restored.config.quorum(), 3);
+    }
+
+    #[test]
+    fn test_memory_storage_reload() {
+        let mut storage = MemoryStorage::new();
+        check_reload(&mut storage);
+    }
+
+    #[test]
+    fn test_memory_storage_membership_reload() {
+        let mut storage = MemoryStorage::new();
+        check_membership_reload(&mut storage);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_storage_reload() {
+        let path = std::env::temp_dir().join(format!("cell0-raft-{}.wal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut storage: FileStorage<u64> = FileStorage::open(&path);
+        assert_eq!(storage.load().unwrap().last_index(), 0);
+        check_reload(&mut storage);
+
+        // A torn record at the tail is ignored
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, &[2, 0xFF, 0xFF]).unwrap();
+        let state = FileStorage::<u64>::open(&path).load().unwrap();
+        assert_eq!(state.current_term, 5);
+        assert_eq!(state.last_index(), 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_storage_membership_reload() {
+        let path = std::env::temp_dir().join(format!("cell0-raft-members-{}.wal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut storage: FileStorage<u64> = FileStorage::open(&path);
+        check_membership_reload(&mut storage);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_storage_compaction() {
+        let mut storage = MemoryStorage::new();
+        storage.append(&(1..=10).map(|i| entry(i, 1, i)).collect::<Vec<_>>()).unwrap();
+        let membership = Config::new(1, vec![1, 2, 3]).membership();
+        storage.compact_to(6, 1, &membership).unwrap();
+
+        let state = storage.load().unwrap();
+        assert_eq!(state.snapshot_index, 6);
+        assert_eq!(state.snapshot_membership, membership);
+        assert_eq!(state.last_index(), 10);
+        assert_eq!(state.term_at(7), 1);
+
+        // Re-appending from inside the snapshot only replaces the tail
+        storage.append(&(5..=8).map(|i| entry(i, 2, i)).collect::<Vec<_>>()).unwrap();
+        let state = storage.load().unwrap();
+        assert_eq!(state.last_index(), 8);
+        assert_eq!(state.term_at(6), 1);
+        assert_eq!(state.term_at(7), 2);
+    }
+}
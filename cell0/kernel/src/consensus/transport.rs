@@ -3,18 +3,28 @@
 //! Provides network primitives for Raft consensus communication.
 //! Handles RPC serialization/deserialization and reliable message delivery.
 
-use super::{RequestVoteArgs, RequestVoteReply, AppendEntriesArgs, AppendEntriesReply, NodeId};
+use super::{Event, Raft, RequestVoteArgs, RequestVoteReply, AppendEntriesArgs, AppendEntriesReply, Membership, NodeId};
+use super::storage::RaftStorage;
+use core::cell::RefCell;
 use core::fmt::Debug;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap, VecDeque};
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
 
 #[cfg(feature = "std")]
 use std::vec::Vec;
 #[cfg(feature = "std")]
 use std::string::String;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "std")]
+use std::rc::Rc;
 
 /// Maximum RPC message size
 pub const MAX_RPC_SIZE: usize = 65536;
@@ -22,6 +32,10 @@ pub const MAX_RPC_SIZE: usize = 65536;
 /// RPC message types
 #[derive(Debug, Clone)]
 pub enum RpcMessage<T: Clone + Debug> {
+    /// PreVote RPC (same arguments as RequestVote, prospective term)
+    PreVote(RequestVoteArgs),
+    /// PreVote RPC reply
+    PreVoteReply(RequestVoteReply),
     /// RequestVote RPC
     RequestVote(RequestVoteArgs),
     /// RequestVote RPC reply
@@ -47,6 +61,8 @@ pub struct InstallSnapshotArgs {
     pub last_included_index: u64,
     /// Term of last_included_index
     pub last_included_term: u64,
+    /// Voters and learners as of last_included_index
+    pub membership: Membership,
     /// Byte offset where chunk is positioned in the snapshot file
     pub offset: u64,
     /// Raw bytes of the snapshot chunk
@@ -60,6 +76,8 @@ pub struct InstallSnapshotArgs {
 pub struct InstallSnapshotReply {
     /// Current term for leader
     pub term: u64,
+    /// Snapshot boundary the follower now holds (0 if rejected)
+    pub last_included_index: u64,
 }
 
 /// Network transport trait for Raft
@@ -83,49 +101,115 @@ pub trait Transport<T: Clone + Debug> {
     fn peers(&self) -> &[NodeId];
 }
 
+/// Failure in `Raft::poll`: the transport or stable storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PollError<TE, SE> {
+    /// Receiving a request or sending a reply failed
+    Transport(TE),
+    /// Flushing state before replying failed; no reply was sent
+    Storage(SE),
+}
+
+/// Per-node inboxes shared by every transport on a `MemoryNetwork`
+type Mailboxes<T> = Rc<RefCell<BTreeMap<NodeId, VecDeque<(NodeId, RpcMessage<T>)>>>>;
+
+/// In-memory message bus connecting `MemoryTransport`s
+///
+/// Each connected node owns a FIFO inbox. Messages to nodes that are not
+/// connected (or were disconnected to simulate a partition) are dropped.
+pub struct MemoryNetwork<T: Clone + Debug> {
+    mailboxes: Mailboxes<T>,
+}
+
+impl<T: Clone + Debug> MemoryNetwork<T> {
+    /// Create an empty network
+    pub fn new() -> Self {
+        Self {
+            mailboxes: Rc::new(RefCell::new(BTreeMap::new())),
+        }
+    }
+    
+    /// Attach a node and return its transport
+    pub fn connect(&self, node_id: NodeId, peers: Vec<NodeId>) -> MemoryTransport<T> {
+        self.mailboxes.borrow_mut().entry(node_id).or_default();
+        MemoryTransport {
+            node_id,
+            peers,
+            mailboxes: self.mailboxes.clone(),
+            outbox: Vec::new(),
+        }
+    }
+    
+    /// Detach a node; its queued and future messages are dropped
+    pub fn disconnect(&self, node_id: NodeId) {
+        self.mailboxes.borrow_mut().remove(&node_id);
+    }
+    
+    /// Re-attach a node with an empty inbox
+    pub fn reconnect(&self, node_id: NodeId) {
+        self.mailboxes.borrow_mut().entry(node_id).or_default();
+    }
+    
+    /// Number of messages waiting across all inboxes
+    pub fn in_flight(&self) -> usize {
+        self.mailboxes.borrow().values().map(|inbox| inbox.len()).sum()
+    }
+}
+
+impl<T: Clone + Debug> Default for MemoryNetwork<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// In-memory transport for testing
 pub struct MemoryTransport<T: Clone + Debug> {
     node_id: NodeId,
     peers: Vec<NodeId>,
-    inbox: Vec<(NodeId, RpcMessage<T>)>,
+    mailboxes: Mailboxes<T>,
+    outbox: Vec<(NodeId, RpcMessage<T>)>,
 }
 
 impl<T: Clone + Debug> MemoryTransport<T> {
-    /// Create new in-memory transport
+    /// Create a standalone transport on its own private network
     pub fn new(node_id: NodeId, peers: Vec<NodeId>) -> Self {
-        Self {
-            node_id,
-            peers,
-            inbox: Vec::new(),
-        }
+        MemoryNetwork::new().connect(node_id, peers)
     }
     
     /// Inject a message into the inbox (used by test harness)
     pub fn inject_message(&mut self, from: NodeId, message: RpcMessage<T>) {
-        self.inbox.push((from, message));
+        if let Some(inbox) = self.mailboxes.borrow_mut().get_mut(&self.node_id) {
+            inbox.push_back((from, message));
+        }
     }
     
-    /// Get pending outgoing messages (used by test harness)
+    /// Get messages this node sent (used by test harness)
     pub fn take_outbox(&mut self) -> Vec<(NodeId, RpcMessage<T>)> {
-        core::mem::take(&mut self.inbox)
+        core::mem::take(&mut self.outbox)
     }
 }
 
 impl<T: Clone + Debug> Transport<T> for MemoryTransport<T> {
     type Error = ();
     
-    fn send_rpc(&mut self, _target: NodeId, _message: RpcMessage<T>) -> Result<(), Self::Error> {
-        // In real implementation, would queue for delivery
-        // For memory transport, messages are handled directly by test harness
+    fn send_rpc(&mut self, target: NodeId, message: RpcMessage<T>) -> Result<(), Self::Error> {
+        self.outbox.push((target, message.clone()));
+        
+        // Unreachable targets lose the message, like a real network
+        let mut mailboxes = self.mailboxes.borrow_mut();
+        if mailboxes.contains_key(&self.node_id) {
+            if let Some(inbox) = mailboxes.get_mut(&target) {
+                inbox.push_back((self.node_id, message));
+            }
+        }
         Ok(())
     }
     
     fn recv_rpc(&mut self) -> Result<Option<(NodeId, RpcMessage<T>)>, Self::Error> {
-        if self.inbox.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(self.inbox.remove(0)))
-        }
+        Ok(self.mailboxes
+            .borrow_mut()
+            .get_mut(&self.node_id)
+            .and_then(|inbox| inbox.pop_front()))
     }
     
     fn node_id(&self) -> NodeId {
@@ -137,6 +221,99 @@ impl<T: Clone + Debug> Transport<T> for MemoryTransport<T> {
     }
 }
 
+impl<T: Clone + Debug + Default> Raft<T> {
+    /// Handle one incoming RPC; returns the reply to send back, if any
+    pub fn handle_rpc(&mut self, from: NodeId, message: RpcMessage<T>) -> Option<RpcMessage<T>> {
+        match message {
+            RpcMessage::PreVote(args) => {
+                Some(RpcMessage::PreVoteReply(self.handle_pre_vote(args)))
+            }
+            RpcMessage::PreVoteReply(reply) => {
+                self.handle_pre_vote_reply(from, reply);
+                None
+            }
+            RpcMessage::RequestVote(args) => {
+                Some(RpcMessage::RequestVoteReply(self.handle_request_vote(args)))
+            }
+            RpcMessage::RequestVoteReply(reply) => {
+                self.handle_request_vote_reply(from, reply);
+                None
+            }
+            RpcMessage::AppendEntries(args) => {
+                let reply = self.handle_append_entries(args);
+                Some(RpcMessage::AppendEntriesReply(self.config.node_id, reply))
+            }
+            RpcMessage::AppendEntriesReply(peer, reply) => {
+                self.handle_append_entries_reply(peer, reply);
+                None
+            }
+            RpcMessage::InstallSnapshot(args) => {
+                Some(RpcMessage::InstallSnapshotReply(self.handle_install_snapshot(args)))
+            }
+            RpcMessage::InstallSnapshotReply(reply) => {
+                self.handle_install_snapshot_reply(from, reply);
+                None
+            }
+        }
+    }
+    
+    /// Send queued RPC events through the transport
+    ///
+    /// Returns the remaining events (persistence, timers, commits) for the
+    /// host to act on.
+    pub fn send_events<Tr: Transport<T>>(&mut self, transport: &mut Tr) -> Result<Vec<Event<T>>, Tr::Error> {
+        let mut other = Vec::new();
+        for event in self.take_events() {
+            match event {
+                Event::SendPreVote { peer, args } => {
+                    transport.send_rpc(peer, RpcMessage::PreVote(args))?;
+                }
+                Event::SendRequestVote { peer, args } => {
+                    transport.send_rpc(peer, RpcMessage::RequestVote(args))?;
+                }
+                Event::SendAppendEntries { peer, args } => {
+                    transport.send_rpc(peer, RpcMessage::AppendEntries(args))?;
+                }
+                Event::InstallSnapshot { peer, args } => {
+                    transport.send_rpc(peer, RpcMessage::InstallSnapshot(args))?;
+                }
+                event => other.push(event),
+            }
+        }
+        Ok(other)
+    }
+    
+    /// Drain the transport inbox, handling each RPC and sending replies
+    ///
+    /// Term, vote and log changes made while handling are flushed to
+    /// `storage` before any reply goes out, so a peer is never told about
+    /// a vote or entry a crash could forget. The `PersistState` events
+    /// this covers are consumed. Returns the number of messages handled.
+    pub fn poll<Tr: Transport<T>, S: RaftStorage<T>>(
+        &mut self,
+        transport: &mut Tr,
+        storage: &mut S,
+    ) -> Result<usize, PollError<Tr::Error, S::Error>> {
+        let mut replies = Vec::new();
+        while let Some((from, message)) = transport.recv_rpc().map_err(PollError::Transport)? {
+            replies.push((from, self.handle_rpc(from, message)));
+        }
+        
+        if self.pending_events.iter().any(|e| matches!(e, Event::PersistState)) {
+            self.persist(storage).map_err(PollError::Storage)?;
+            self.pending_events.retain(|e| !matches!(e, Event::PersistState));
+        }
+        
+        let handled = replies.len();
+        for (from, reply) in replies {
+            if let Some(reply) = reply {
+                transport.send_rpc(from, reply).map_err(PollError::Transport)?;
+            }
+        }
+        Ok(handled)
+    }
+}
+
 /// Simple serialization trait for RPC messages
 pub trait RpcCodec<T: Clone + Debug> {
     /// Serialize message to bytes
@@ -252,6 +429,8 @@ impl ConnectionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus::storage::MemoryStorage;
+    use crate::consensus::{Config, LogEntry, LogIndex, PersistentState, Term};
     
     #[test]
     fn test_memory_transport_creation() {
@@ -297,4 +476,80 @@ mod tests {
         let addr = manager.get_address(2).unwrap();
         assert_eq!(addr.rpc_port, 7001);
     }
+    
+    #[test]
+    fn test_memory_network_delivery() {
+        let network: MemoryNetwork<u64> = MemoryNetwork::new();
+        let mut a = network.connect(1, vec![2]);
+        let mut b = network.connect(2, vec![1]);
+        
+        let reply = RequestVoteReply { term: 3, vote_granted: true, reason: None };
+        a.send_rpc(2, RpcMessage::RequestVoteReply(reply)).unwrap();
+        assert_eq!(network.in_flight(), 1);
+        
+        match b.recv_rpc().unwrap() {
+            Some((1, RpcMessage::RequestVoteReply(reply))) => assert_eq!(reply.term, 3),
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(b.recv_rpc().unwrap().is_none());
+        
+        // A partitioned node neither sends nor receives
+        network.disconnect(2);
+        let reply = RequestVoteReply { term: 4, vote_granted: false, reason: None };
+        a.send_rpc(2, RpcMessage::RequestVoteReply(reply.clone())).unwrap();
+        b.send_rpc(1, RpcMessage::RequestVoteReply(reply)).unwrap();
+        assert_eq!(network.in_flight(), 0);
+        assert_eq!(a.take_outbox().len(), 2);
+    }
+    
+    /// Storage whose writes always fail
+    struct FailingStorage;
+    
+    impl RaftStorage<u64> for FailingStorage {
+        type Error = ();
+        
+        fn save_state(&mut self, _term: Term, _voted_for: Option<NodeId>) -> Result<(), ()> {
+            Err(())
+        }
+        
+        fn append(&mut self, _entries: &[LogEntry<u64>]) -> Result<(), ()> {
+            Err(())
+        }
+        
+        fn truncate_from(&mut self, _index: LogIndex) -> Result<(), ()> {
+            Err(())
+        }
+        
+        fn compact_to(&mut self, _index: LogIndex, _term: Term, _membership: &Membership) -> Result<(), ()> {
+            Err(())
+        }
+        
+        fn load(&self) -> Result<PersistentState<u64>, ()> {
+            Ok(PersistentState::new())
+        }
+    }
+    
+    #[test]
+    fn test_poll_persists_before_replying() {
+        let network: MemoryNetwork<u64> = MemoryNetwork::new();
+        let mut candidate = network.connect(1, vec![2]);
+        let mut transport = network.connect(2, vec![1]);
+        let mut raft: Raft<u64> = Raft::new(Config::new(2, vec![1, 2]));
+        let vote = RequestVoteArgs { term: 1, candidate_id: 1, last_log_index: 0, last_log_term: 0 };
+        
+        // A vote that cannot be made durable is never announced
+        candidate.send_rpc(2, RpcMessage::RequestVote(vote.clone())).unwrap();
+        assert_eq!(raft.poll(&mut transport, &mut FailingStorage), Err(PollError::Storage(())));
+        assert_eq!(network.in_flight(), 0);
+        
+        let mut storage = MemoryStorage::new();
+        candidate.send_rpc(2, RpcMessage::RequestVote(vote)).unwrap();
+        assert_eq!(raft.poll(&mut transport, &mut storage), Ok(1));
+        assert_eq!(storage.load().unwrap().voted_for, Some(1));
+        match candidate.recv_rpc().unwrap() {
+            Some((2, RpcMessage::RequestVoteReply(reply))) => assert!(reply.vote_granted),
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(!raft.take_events().iter().any(|e| matches!(e, Event::PersistState)));
+    }
 }
@@ -0,0 +1,254 @@
+//! AEAD (Authenticated Encryption with Associated Data) Interface
+//!
+//! Common interface over AES-GCM and ChaCha20-Poly1305 so the agility
+//! layer can pick a cipher at runtime.
+
+use super::aes_gcm::{AesGcm, KEY_SIZE_128, KEY_SIZE_256};
+use super::chacha20::{self, ChaCha20Poly1305};
+use super::{AlgorithmId, CryptoError, CryptoResult};
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Nonce size shared by every supported AEAD
+pub const NONCE_SIZE: usize = 12;
+/// Authentication tag size shared by every supported AEAD
+pub const TAG_SIZE: usize = 16;
+
+/// Authenticated encryption with associated data
+pub trait Aead {
+    /// Algorithm implemented by this cipher
+    fn algorithm(&self) -> AlgorithmId;
+
+    /// Encrypt `plaintext`, authenticating it together with `aad`
+    fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]);
+
+    /// Verify `tag` and decrypt `ciphertext`
+    fn decrypt(
+        &self,
+        nonce: &[u8; NONCE_SIZE],
+        ciphertext: &[u8],
+        aad: &[u8],
+        tag: &[u8; TAG_SIZE],
+    ) -> CryptoResult<Vec<u8>>;
+}
+
+impl Aead for AesGcm {
+    fn algorithm(&self) -> AlgorithmId {
+        if self.key_bits() == 128 {
+            AlgorithmId::Aes128Gcm
+        } else {
+            AlgorithmId::Aes256Gcm
+        }
+    }
+
+    fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
+        AesGcm::encrypt(self, nonce, plaintext, aad)
+    }
+
+    fn decrypt(
+        &self,
+        nonce: &[u8; NONCE_SIZE],
+        ciphertext: &[u8],
+        aad: &[u8],
+        tag: &[u8; TAG_SIZE],
+    ) -> CryptoResult<Vec<u8>> {
+        AesGcm::decrypt(self, nonce, ciphertext, aad, tag)
+    }
+}
+
+impl Aead for ChaCha20Poly1305 {
+    fn algorithm(&self) -> AlgorithmId {
+        AlgorithmId::ChaCha20Poly1305
+    }
+
+    fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
+        ChaCha20Poly1305::encrypt(self, nonce, plaintext, aad)
+    }
+
+    fn decrypt(
+        &self,
+        nonce: &[u8; NONCE_SIZE],
+        ciphertext: &[u8],
+        aad: &[u8],
+        tag: &[u8; TAG_SIZE],
+    ) -> CryptoResult<Vec<u8>> {
+        ChaCha20Poly1305::decrypt(self, nonce, ciphertext, aad, tag)
+    }
+}
+
+/// Deterministic per-key nonce generator
+///
+/// Nonces are a 32-bit fixed field followed by a 64-bit big-endian
+/// counter, so each one is used at most once. Once `limit` nonces have
+/// been issued the sequence is exhausted and never wraps.
+pub struct NonceSequence {
+    fixed: [u8; 4],
+    counter: u64,
+    limit: u64,
+}
+
+impl NonceSequence {
+    /// Start a sequence; `fixed` should differ between senders sharing a key
+    pub const fn new(fixed: [u8; 4]) -> Self {
+        Self::with_limit(fixed, u64::MAX)
+    }
+
+    /// Start a sequence that issues at most `limit` nonces
+    pub const fn with_limit(fixed: [u8; 4], limit: u64) -> Self {
+        NonceSequence {
+            fixed,
+            counter: 0,
+            limit,
+        }
+    }
+
+    /// Number of nonces issued so far
+    pub fn issued(&self) -> u64 {
+        self.counter
+    }
+
+    /// Take the next nonce, or `InvalidInput` once exhausted
+    pub fn next_nonce(&mut self) -> CryptoResult<[u8; NONCE_SIZE]> {
+        if self.counter >= self.limit {
+            return Err(CryptoError::InvalidInput);
+        }
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce[..4].copy_from_slice(&self.fixed);
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter += 1;
+        Ok(nonce)
+    }
+}
+
+/// AEAD cipher that draws every nonce from its own `NonceSequence`
+pub struct SequencedAead {
+    cipher: Box<dyn Aead>,
+    nonces: NonceSequence,
+}
+
+impl SequencedAead {
+    pub fn new(cipher: Box<dyn Aead>, nonces: NonceSequence) -> Self {
+        SequencedAead { cipher, nonces }
+    }
+
+    /// Encrypt with the next nonce, returning it alongside the output
+    pub fn encrypt_next(
+        &mut self,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> CryptoResult<([u8; NONCE_SIZE], Vec<u8>, [u8; TAG_SIZE])> {
+        let nonce = self.nonces.next_nonce()?;
+        let (ciphertext, tag) = self.cipher.encrypt(&nonce, plaintext, aad);
+        Ok((nonce, ciphertext, tag))
+    }
+
+    /// Decrypt a message produced by the peer's `encrypt_next`
+    pub fn decrypt(
+        &self,
+        nonce: &[u8; NONCE_SIZE],
+        ciphertext: &[u8],
+        aad: &[u8],
+        tag: &[u8; TAG_SIZE],
+    ) -> CryptoResult<Vec<u8>> {
+        self.cipher.decrypt(nonce, ciphertext, aad, tag)
+    }
+}
+
+/// Build an AEAD cipher for `alg` keyed with `key`
+///
+/// Returns `AlgorithmNotSupported` for non-AEAD algorithms and
+/// `InvalidKey` if the key length doesn't match the algorithm.
+pub fn aead_for(alg: AlgorithmId, key: &[u8]) -> CryptoResult<Box<dyn Aead>> {
+    match alg {
+        AlgorithmId::Aes128Gcm if key.len() == KEY_SIZE_128 => Ok(Box::new(AesGcm::new(key)?)),
+        AlgorithmId::Aes256Gcm if key.len() == KEY_SIZE_256 => Ok(Box::new(AesGcm::new(key)?)),
+        AlgorithmId::ChaCha20Poly1305 => {
+            let key: &[u8; chacha20::KEY_SIZE] = key.try_into().map_err(|_| CryptoError::InvalidKey)?;
+            Ok(Box::new(ChaCha20Poly1305::new(key)))
+        }
+        AlgorithmId::Aes128Gcm | AlgorithmId::Aes256Gcm => Err(CryptoError::InvalidKey),
+        _ => Err(CryptoError::AlgorithmNotSupported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aead_trait_roundtrip() {
+        let nonce = [7u8; NONCE_SIZE];
+        let plaintext = b"agility through a trait object";
+        let aad = b"header";
+
+        for (alg, key_len) in [
+            (AlgorithmId::Aes128Gcm, KEY_SIZE_128),
+            (AlgorithmId::Aes256Gcm, KEY_SIZE_256),
+            (AlgorithmId::ChaCha20Poly1305, chacha20::KEY_SIZE),
+        ] {
+            let key: Vec<u8> = (0..key_len as u8).collect();
+            let cipher = aead_for(alg, &key).unwrap();
+            assert_eq!(cipher.algorithm(), alg);
+
+            let (ciphertext, tag) = cipher.encrypt(&nonce, plaintext, aad);
+            assert_ne!(&ciphertext[..], &plaintext[..]);
+            assert_eq!(cipher.decrypt(&nonce, &ciphertext, aad, &tag).unwrap(), plaintext);
+
+            let mut bad_tag = tag;
+            bad_tag[0] ^= 1;
+            assert_eq!(
+                cipher.decrypt(&nonce, &ciphertext, aad, &bad_tag),
+                Err(CryptoError::VerificationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sequenced_nonces_are_unique() {
+        use std::collections::BTreeSet;
+
+        let cipher = aead_for(AlgorithmId::ChaCha20Poly1305, &[9u8; 32]).unwrap();
+        let mut sealer = SequencedAead::new(cipher, NonceSequence::new(*b"node"));
+
+        let mut seen = BTreeSet::new();
+        for i in 0..1000u32 {
+            let (nonce, ciphertext, tag) = sealer.encrypt_next(&i.to_le_bytes(), b"").unwrap();
+            assert!(seen.insert(nonce));
+            assert_eq!(nonce[..4], *b"node");
+            assert_eq!(sealer.decrypt(&nonce, &ciphertext, b"", &tag).unwrap(), i.to_le_bytes());
+        }
+        assert_eq!(seen.len(), 1000);
+    }
+
+    #[test]
+    fn test_nonce_sequence_exhaustion() {
+        let mut nonces = NonceSequence::with_limit([0; 4], 3);
+        for _ in 0..3 {
+            assert!(nonces.next_nonce().is_ok());
+        }
+        assert_eq!(nonces.next_nonce(), Err(CryptoError::InvalidInput));
+        assert_eq!(nonces.issued(), 3);
+
+        let cipher = aead_for(AlgorithmId::Aes128Gcm, &[1u8; 16]).unwrap();
+        let mut sealer = SequencedAead::new(cipher, NonceSequence::with_limit([0; 4], 1));
+        assert!(sealer.encrypt_next(b"first", b"").is_ok());
+        assert!(matches!(sealer.encrypt_next(b"second", b""), Err(CryptoError::InvalidInput)));
+    }
+
+    #[test]
+    fn test_aead_for_rejects_bad_input() {
+        assert!(matches!(
+            aead_for(AlgorithmId::Ed25519, &[0u8; 32]),
+            Err(CryptoError::AlgorithmNotSupported)
+        ));
+        assert!(matches!(aead_for(AlgorithmId::Aes256Gcm, &[0u8; 16]), Err(CryptoError::InvalidKey)));
+        assert!(matches!(
+            aead_for(AlgorithmId::ChaCha20Poly1305, &[0u8; 16]),
+            Err(CryptoError::InvalidKey)
+        ));
+    }
+}
@@ -2,8 +2,11 @@
 //! 
 //! Implementation of AES-128/256-GCM for authenticated encryption.
 //! Provides confidentiality, integrity, and authenticity.
+//!
+//! The block cipher uses a table-based S-box and is not hardened against
+//! cache-timing side channels.
 
-use super::{CryptoError, CryptoResult, CryptoRng, HardwareRng};
+use super::{CryptoError, CryptoResult, CryptoRng, HardwareRng, constant_time_eq_fixed};
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -36,6 +39,11 @@ impl AesGcm {
         })
     }
 
+    /// Key size in bits (128 or 256)
+    pub fn key_bits(&self) -> usize {
+        self.key_bits
+    }
+
     pub fn generate_key(key_bits: usize) -> CryptoResult<Vec<u8>> {
         let size = match key_bits {
             128 => KEY_SIZE_128,
@@ -49,42 +57,346 @@ impl AesGcm {
         Ok(key)
     }
 
-    pub fn encrypt(&self, _nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
-        // Simplified - real implementation would use AES-NI or constant-time software
-        let mut ciphertext = plaintext.to_vec();
+    pub fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
+        let mut encryptor = AesGcmEncryptor::with_aad(&self.key, nonce, aad)
+            .expect("key length validated in AesGcm::new");
+        let ciphertext = encryptor.update(plaintext);
+        (ciphertext, encryptor.finalize())
+    }
+
+    pub fn decrypt(&self, nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8], tag: &[u8; TAG_SIZE]) -> CryptoResult<Vec<u8>> {
+        let mut decryptor = AesGcmDecryptor::with_aad(&self.key, nonce, aad)?;
+        let plaintext = decryptor.update(ciphertext);
+        decryptor.finalize(tag)?;
+        Ok(plaintext)
+    }
+}
+
+/// AES S-box
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Key schedule round constants
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// AES block cipher (encryption direction only, as GCM needs)
+struct AesCipher {
+    round_keys: [[u8; BLOCK_SIZE]; 15],
+    rounds: usize,
+}
+
+impl AesCipher {
+    fn new(key: &[u8]) -> CryptoResult<Self> {
+        let nk = match key.len() {
+            KEY_SIZE_128 => 4,
+            KEY_SIZE_256 => 8,
+            _ => return Err(CryptoError::InvalidKey),
+        };
+        let rounds = nk + 6;
         
-        // XOR with keystream (simplified)
-        for (i, byte) in ciphertext.iter_mut().enumerate() {
-            *byte ^= self.key[i % self.key.len()];
+        // Expand into 4-byte words
+        let mut words = [[0u8; 4]; 60];
+        for (i, word) in key.chunks(4).enumerate() {
+            words[i].copy_from_slice(word);
+        }
+        for i in nk..4 * (rounds + 1) {
+            let mut temp = words[i - 1];
+            if i % nk == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(|b| SBOX[b as usize]);
+                temp[0] ^= RCON[i / nk - 1];
+            } else if nk > 6 && i % nk == 4 {
+                temp = temp.map(|b| SBOX[b as usize]);
+            }
+            for j in 0..4 {
+                words[i][j] = words[i - nk][j] ^ temp[j];
+            }
         }
         
-        // Compute tag (simplified GCM GHASH)
-        let mut tag = [0u8; TAG_SIZE];
-        for (i, byte) in aad.iter().chain(plaintext.iter()).enumerate() {
-            tag[i % TAG_SIZE] ^= *byte;
+        let mut round_keys = [[0u8; BLOCK_SIZE]; 15];
+        for (r, round_key) in round_keys.iter_mut().take(rounds + 1).enumerate() {
+            for c in 0..4 {
+                round_key[4 * c..4 * c + 4].copy_from_slice(&words[4 * r + c]);
+            }
         }
         
-        (ciphertext, tag)
+        Ok(AesCipher { round_keys, rounds })
     }
 
-    pub fn decrypt(&self, _nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8], tag: &[u8; TAG_SIZE]) -> CryptoResult<Vec<u8>> {
-        // Verify tag (simplified)
-        let mut computed_tag = [0u8; TAG_SIZE];
-        for (i, byte) in aad.iter().chain(ciphertext.iter()).enumerate() {
-            computed_tag[i % TAG_SIZE] ^= *byte;
-        }
+    fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        xor_block(block, &self.round_keys[0]);
         
-        if computed_tag != *tag {
-            return Err(CryptoError::VerificationFailed);
+        for round in 1..=self.rounds {
+            // SubBytes
+            for byte in block.iter_mut() {
+                *byte = SBOX[*byte as usize];
+            }
+            
+            // ShiftRows (column-major state: byte = row + 4 * column)
+            let state = *block;
+            for row in 1..4 {
+                for col in 0..4 {
+                    block[row + 4 * col] = state[row + 4 * ((col + row) % 4)];
+                }
+            }
+            
+            // MixColumns, skipped in the final round
+            if round != self.rounds {
+                for col in block.chunks_mut(4) {
+                    let [a0, a1, a2, a3] = [col[0], col[1], col[2], col[3]];
+                    let all = a0 ^ a1 ^ a2 ^ a3;
+                    col[0] ^= all ^ xtime(a0 ^ a1);
+                    col[1] ^= all ^ xtime(a1 ^ a2);
+                    col[2] ^= all ^ xtime(a2 ^ a3);
+                    col[3] ^= all ^ xtime(a3 ^ a0);
+                }
+            }
+            
+            xor_block(block, &self.round_keys[round]);
         }
-        
-        // Decrypt (XOR with keystream)
-        let mut plaintext = ciphertext.to_vec();
-        for (i, byte) in plaintext.iter_mut().enumerate() {
-            *byte ^= self.key[i % self.key.len()];
+    }
+}
+
+/// Multiply by x in GF(2^8)
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ (((b >> 7) & 1) * 0x1b)
+}
+
+fn xor_block(block: &mut [u8; BLOCK_SIZE], other: &[u8; BLOCK_SIZE]) {
+    for (b, o) in block.iter_mut().zip(other.iter()) {
+        *b ^= o;
+    }
+}
+
+/// Multiply in GF(2^128) with GCM's bit ordering
+fn gf128_mul(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xe1 << 120;
+    let mut z = 0u128;
+    let mut v = y;
+    for i in 0..128 {
+        // Branch-free: mask is all ones when bit i of x is set
+        let mask = 0u128.wrapping_sub((x >> (127 - i)) & 1);
+        z ^= v & mask;
+        let carry = 0u128.wrapping_sub(v & 1);
+        v = (v >> 1) ^ (R & carry);
+    }
+    z
+}
+
+/// Incremental GHASH over AAD then ciphertext
+struct Ghash {
+    h: u128,
+    acc: u128,
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    aad_len: u64,
+    text_len: u64,
+    in_text: bool,
+}
+
+impl Ghash {
+    fn new(h: u128) -> Self {
+        Ghash {
+            h,
+            acc: 0,
+            buf: [0u8; BLOCK_SIZE],
+            buf_len: 0,
+            aad_len: 0,
+            text_len: 0,
+            in_text: false,
         }
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.buf[self.buf_len] = byte;
+            self.buf_len += 1;
+            if self.buf_len == BLOCK_SIZE {
+                self.flush();
+            }
+        }
+    }
+
+    /// Fold the buffered block (zero-padded) into the accumulator
+    fn flush(&mut self) {
+        if self.buf_len == 0 {
+            return;
+        }
+        self.buf[self.buf_len..].fill(0);
+        self.acc = gf128_mul(self.acc ^ u128::from_be_bytes(self.buf), self.h);
+        self.buf_len = 0;
+    }
+
+    fn update_aad(&mut self, aad: &[u8]) {
+        self.aad_len += aad.len() as u64;
+        self.absorb(aad);
+    }
+
+    fn update_text(&mut self, ciphertext: &[u8]) {
+        if !self.in_text {
+            // AAD is padded to a block boundary before the ciphertext
+            self.flush();
+            self.in_text = true;
+        }
+        self.text_len += ciphertext.len() as u64;
+        self.absorb(ciphertext);
+    }
+
+    fn finalize(mut self) -> u128 {
+        self.flush();
+        let lengths = ((self.aad_len * 8) as u128) << 64 | (self.text_len * 8) as u128;
+        gf128_mul(self.acc ^ lengths, self.h)
+    }
+}
+
+/// Shared CTR + GHASH state for the streaming encryptor and decryptor
+struct GcmState {
+    cipher: AesCipher,
+    ghash: Ghash,
+    /// Encrypted initial counter block, masks the final tag
+    tag_mask: [u8; BLOCK_SIZE],
+    counter_block: [u8; BLOCK_SIZE],
+    keystream: [u8; BLOCK_SIZE],
+    keystream_pos: usize,
+}
+
+impl GcmState {
+    fn new(key: &[u8], nonce: &[u8; NONCE_SIZE], aad: &[u8]) -> CryptoResult<Self> {
+        let cipher = AesCipher::new(key)?;
         
-        Ok(plaintext)
+        let mut h = [0u8; BLOCK_SIZE];
+        cipher.encrypt_block(&mut h);
+        
+        // J0 = nonce || 0^31 || 1 for 96-bit nonces
+        let mut counter_block = [0u8; BLOCK_SIZE];
+        counter_block[..NONCE_SIZE].copy_from_slice(nonce);
+        counter_block[BLOCK_SIZE - 1] = 1;
+        let mut tag_mask = counter_block;
+        cipher.encrypt_block(&mut tag_mask);
+        
+        let mut ghash = Ghash::new(u128::from_be_bytes(h));
+        ghash.update_aad(aad);
+        
+        Ok(GcmState {
+            cipher,
+            ghash,
+            tag_mask,
+            counter_block,
+            keystream: [0u8; BLOCK_SIZE],
+            keystream_pos: BLOCK_SIZE,
+        })
+    }
+
+    /// XOR `data` with the CTR keystream, continuing where the last call stopped
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.keystream_pos == BLOCK_SIZE {
+                let mut ctr = [0u8; 4];
+                ctr.copy_from_slice(&self.counter_block[NONCE_SIZE..]);
+                let next = u32::from_be_bytes(ctr).wrapping_add(1);
+                self.counter_block[NONCE_SIZE..].copy_from_slice(&next.to_be_bytes());
+                
+                self.keystream = self.counter_block;
+                self.cipher.encrypt_block(&mut self.keystream);
+                self.keystream_pos = 0;
+            }
+            *byte ^= self.keystream[self.keystream_pos];
+            self.keystream_pos += 1;
+        }
+    }
+
+    fn tag(self) -> [u8; TAG_SIZE] {
+        let mut tag = self.ghash.finalize().to_be_bytes();
+        xor_block(&mut tag, &self.tag_mask);
+        tag
+    }
+}
+
+/// Streaming AES-GCM encryption
+///
+/// Feed plaintext in chunks of any size with `update`; `finalize` returns
+/// the authentication tag. Output is identical to `AesGcm::encrypt`.
+pub struct AesGcmEncryptor {
+    state: GcmState,
+}
+
+impl AesGcmEncryptor {
+    pub fn new(key: &[u8], nonce: &[u8; NONCE_SIZE]) -> CryptoResult<Self> {
+        Self::with_aad(key, nonce, &[])
+    }
+
+    /// Start an encryption that also authenticates `aad`
+    pub fn with_aad(key: &[u8], nonce: &[u8; NONCE_SIZE], aad: &[u8]) -> CryptoResult<Self> {
+        Ok(AesGcmEncryptor {
+            state: GcmState::new(key, nonce, aad)?,
+        })
+    }
+
+    /// Encrypt the next chunk of plaintext
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut out = chunk.to_vec();
+        self.state.apply_keystream(&mut out);
+        self.state.ghash.update_text(&out);
+        out
+    }
+
+    /// Finish and return the authentication tag
+    pub fn finalize(self) -> [u8; TAG_SIZE] {
+        self.state.tag()
+    }
+}
+
+/// Streaming AES-GCM decryption
+///
+/// Plaintext returned by `update` is unauthenticated until `finalize`
+/// succeeds; callers must discard it if the tag check fails.
+pub struct AesGcmDecryptor {
+    state: GcmState,
+}
+
+impl AesGcmDecryptor {
+    pub fn new(key: &[u8], nonce: &[u8; NONCE_SIZE]) -> CryptoResult<Self> {
+        Self::with_aad(key, nonce, &[])
+    }
+
+    /// Start a decryption that also authenticates `aad`
+    pub fn with_aad(key: &[u8], nonce: &[u8; NONCE_SIZE], aad: &[u8]) -> CryptoResult<Self> {
+        Ok(AesGcmDecryptor {
+            state: GcmState::new(key, nonce, aad)?,
+        })
+    }
+
+    /// Decrypt the next chunk of ciphertext
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.state.ghash.update_text(chunk);
+        let mut out = chunk.to_vec();
+        self.state.apply_keystream(&mut out);
+        out
+    }
+
+    /// Verify the tag over everything passed to `update`
+    pub fn finalize(self, tag: &[u8; TAG_SIZE]) -> CryptoResult<()> {
+        if constant_time_eq_fixed(&self.state.tag(), tag) {
+            Ok(())
+        } else {
+            Err(CryptoError::VerificationFailed)
+        }
     }
 }
 
@@ -105,4 +417,84 @@ mod tests {
         
         assert_eq!(decrypted, plaintext);
     }
+
+    #[test]
+    fn test_nist_vectors() {
+        // SP 800-38D test case 2: zero key, nonce and one zero block
+        let cipher = AesGcm::new(&[0u8; 16]).unwrap();
+        let (ciphertext, tag) = cipher.encrypt(&[0u8; 12], &[0u8; 16], &[]);
+        assert_eq!(
+            ciphertext,
+            [
+                0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2,
+                0xfe, 0x78,
+            ]
+        );
+        assert_eq!(
+            tag,
+            [
+                0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+                0xbd, 0xdf,
+            ]
+        );
+        
+        // AES-256 with AAD and a partial final block
+        let key: Vec<u8> = (0..32).collect();
+        let nonce: [u8; 12] = core::array::from_fn(|i| i as u8);
+        let plaintext: Vec<u8> = (0..100).map(|i| (i % 251) as u8).collect();
+        let cipher = AesGcm::new(&key).unwrap();
+        let (ciphertext, tag) = cipher.encrypt(&nonce, &plaintext, b"hdr");
+        assert_eq!(
+            ciphertext[..16],
+            [
+                0x47, 0x03, 0xd4, 0x18, 0xc1, 0xe0, 0xc4, 0x1c, 0x85, 0x48, 0x9d, 0x80, 0xbd, 0xe4,
+                0x76, 0x62,
+            ]
+        );
+        assert_eq!(
+            tag,
+            [
+                0x5f, 0xe4, 0x8e, 0xf9, 0xcf, 0x12, 0xd4, 0x45, 0x94, 0xe3, 0xea, 0x30, 0x32, 0x3e,
+                0xc4, 0xba,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let key = [0x42u8; 32];
+        let nonce = [0x24u8; 12];
+        let aad = b"boot image v1";
+        let image: Vec<u8> = (0..1024 * 1024).map(|i| (i * 31 % 256) as u8).collect();
+        
+        let cipher = AesGcm::new(&key).unwrap();
+        let (expected_ct, expected_tag) = cipher.encrypt(&nonce, &image, aad);
+        
+        let mut encryptor = AesGcmEncryptor::with_aad(&key, &nonce, aad).unwrap();
+        let mut ciphertext = Vec::with_capacity(image.len());
+        for chunk in image.chunks(64 * 1024) {
+            ciphertext.extend_from_slice(&encryptor.update(chunk));
+        }
+        let tag = encryptor.finalize();
+        
+        assert!(ciphertext == expected_ct);
+        assert_eq!(tag, expected_tag);
+        
+        // Decrypt with chunk sizes that don't line up with AES blocks
+        let mut decryptor = AesGcmDecryptor::with_aad(&key, &nonce, aad).unwrap();
+        let mut plaintext = Vec::with_capacity(image.len());
+        for chunk in ciphertext.chunks(1000) {
+            plaintext.extend_from_slice(&decryptor.update(chunk));
+        }
+        assert!(decryptor.finalize(&tag).is_ok());
+        assert!(plaintext == image);
+        
+        // A flipped ciphertext bit fails the final check
+        let mut decryptor = AesGcmDecryptor::with_aad(&key, &nonce, aad).unwrap();
+        let mut tampered = ciphertext[..4096].to_vec();
+        tampered[100] ^= 1;
+        decryptor.update(&tampered);
+        decryptor.update(&ciphertext[4096..]);
+        assert_eq!(decryptor.finalize(&tag), Err(CryptoError::VerificationFailed));
+    }
 }
@@ -57,6 +57,15 @@ impl AlgorithmCapability {
         }
     }
 
+    /// Capability with the algorithm's default security level and
+    /// post-quantum flag
+    pub fn for_algorithm(id: AlgorithmId) -> Self {
+        AlgorithmCapability {
+            post_quantum: id.is_post_quantum(),
+            ..Self::new(id, id.default_security_level())
+        }
+    }
+
     pub fn with_performance(mut self, ops_per_sec: u64) -> Self {
         self.performance_ops_per_sec = ops_per_sec;
         self
@@ -263,8 +272,14 @@ pub struct AgilityManager {
     blacklist: Vec<AlgorithmId>,
     /// Algorithm deprecation warnings
     deprecated: Vec<(AlgorithmId, String)>,
+    /// Timestamps after which deprecated algorithms are refused
+    sunsets: Vec<(AlgorithmId, u64)>,
+    /// Current time, as supplied by `set_time`
+    now: u64,
     /// Negotiation history
     negotiation_history: Vec<NegotiationRecord>,
+    /// Migration being executed, and the index of its next step
+    migration: Option<(MigrationPlan, usize)>,
 }
 
 /// Fallback strategy
@@ -290,6 +305,16 @@ pub struct NegotiationRecord {
     success: bool,
 }
 
+impl NegotiationRecord {
+    pub fn peer_id(&self) -> Option<[u8; 16]> {
+        self.peer_id
+    }
+
+    pub fn selected_algorithm(&self) -> AlgorithmId {
+        self.selected
+    }
+}
+
 impl AgilityManager {
     pub fn new() -> Self {
         let mut manager = AgilityManager {
@@ -298,7 +323,10 @@ impl AgilityManager {
             fallback_strategy: FallbackStrategy::MinimumSecure,
             blacklist: vec![],
             deprecated: vec![],
+            sunsets: vec![],
+            now: 0,
             negotiation_history: vec![],
+            migration: None,
         };
         
         manager.register_default_capabilities();
@@ -308,50 +336,53 @@ impl AgilityManager {
     /// Register default local capabilities
     fn register_default_capabilities(&mut self) {
         // Symmetric encryption
-        self.register_capability(AlgorithmCategory::SymmetricEncryption, 
-            AlgorithmCapability::new(AlgorithmId::Aes256Gcm, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Aes256Gcm)
                 .with_performance(1000000)
                 .with_hardware_acceleration()
                 .with_fips_compliance());
         
-        self.register_capability(AlgorithmCategory::SymmetricEncryption,
-            AlgorithmCapability::new(AlgorithmId::ChaCha20Poly1305, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::ChaCha20Poly1305)
                 .with_performance(1500000)
                 .with_hardware_acceleration());
 
         // Signatures
-        self.register_capability(AlgorithmCategory::Signature,
-            AlgorithmCapability::new(AlgorithmId::Ed25519, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Ed25519)
                 .with_performance(50000)
                 .with_hardware_acceleration());
         
-        self.register_capability(AlgorithmCategory::Signature,
-            AlgorithmCapability::new(AlgorithmId::Dilithium3, SecurityLevel::PostQuantum128)
-                .with_performance(5000)
-                .with_post_quantum());
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Dilithium3)
+                .with_performance(5000));
 
-        self.register_capability(AlgorithmCategory::Signature,
-            AlgorithmCapability::new(AlgorithmId::Bls12_381, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Bls12_381)
                 .with_performance(10000));
 
         // Key exchange
-        self.register_capability(AlgorithmCategory::KeyExchange,
-            AlgorithmCapability::new(AlgorithmId::X25519, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::X25519)
                 .with_performance(10000)
                 .with_hardware_acceleration());
         
-        self.register_capability(AlgorithmCategory::KeyExchange,
-            AlgorithmCapability::new(AlgorithmId::Kyber768, SecurityLevel::PostQuantum128)
-                .with_performance(2000)
-                .with_post_quantum());
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Kyber768)
+                .with_performance(2000));
 
         // Hashes
-        self.register_capability(AlgorithmCategory::Hash,
-            AlgorithmCapability::new(AlgorithmId::Sha3_256, SecurityLevel::Bits256)
+        self.register(
+            AlgorithmCapability::for_algorithm(AlgorithmId::Sha3_256)
                 .with_performance(1000000)
                 .with_hardware_acceleration());
     }
 
+    /// Register a local capability under its algorithm's own category
+    pub fn register(&mut self, capability: AlgorithmCapability) {
+        self.register_capability(capability.id.category(), capability);
+    }
+
     /// Register a local capability
     pub fn register_capability(&mut self, category: AlgorithmCategory, capability: AlgorithmCapability) {
         if let Some((_, caps)) = self.local_capabilities.iter_mut().find(|(c, _)| *c == category) {
@@ -383,6 +414,26 @@ impl AgilityManager {
         self.deprecated.push((alg, notice.to_string()));
     }
 
+    /// Deprecate algorithm and stop selecting it from `sunset_ts` onwards
+    pub fn deprecate_after(&mut self, alg: AlgorithmId, notice: &str, sunset_ts: u64) {
+        self.deprecate(alg, notice);
+        if let Some((_, ts)) = self.sunsets.iter_mut().find(|(a, _)| *a == alg) {
+            *ts = sunset_ts;
+        } else {
+            self.sunsets.push((alg, sunset_ts));
+        }
+    }
+
+    /// Set the current time used for sunsets and negotiation records
+    pub fn set_time(&mut self, now: u64) {
+        self.now = now;
+    }
+
+    /// Check if algorithm has passed its sunset
+    pub fn is_sunset(&self, alg: &AlgorithmId) -> bool {
+        self.sunsets.iter().any(|(a, ts)| a == alg && self.now >= *ts)
+    }
+
     /// Check if algorithm is available
     pub fn is_available(&self, alg: &AlgorithmId) -> bool {
         if self.blacklist.contains(alg) {
@@ -391,6 +442,9 @@ impl AgilityManager {
         if self.preference.forbidden.contains(alg) {
             return false;
         }
+        if self.is_sunset(alg) {
+            return false;
+        }
         
         // Check if we have this capability
         for (_, caps) in &self.local_capabilities {
@@ -401,8 +455,16 @@ impl AgilityManager {
         false
     }
 
-    /// Negotiate with peer
-    pub fn negotiate(&mut self, peer_capabilities: &[AlgorithmCapability]) -> CryptoResult<NegotiationResult> {
+    /// Category a locally supported algorithm is registered under
+    pub fn category_of(&self, alg: &AlgorithmId) -> Option<AlgorithmCategory> {
+        self.local_capabilities
+            .iter()
+            .find(|(_, caps)| caps.iter().any(|c| c.id == *alg))
+            .map(|(category, _)| *category)
+    }
+
+    /// Filter, score and order peer capabilities, best first
+    fn rank_candidates<'a>(&self, peer_capabilities: &'a [AlgorithmCapability]) -> Vec<&'a AlgorithmCapability> {
         // Filter peer capabilities by our requirements
         let mut candidates: Vec<&AlgorithmCapability> = peer_capabilities
             .iter()
@@ -420,20 +482,78 @@ impl AgilityManager {
             score_b.cmp(&score_a) // Higher score first
         });
 
-        // Apply priority list
-        for preferred in &self.preference.priority_list {
+        // Apply priority list, so its first entry ends up in front
+        for preferred in self.preference.priority_list.iter().rev() {
             if let Some(pos) = candidates.iter().position(|c| c.id == *preferred) {
                 let cap = candidates.remove(pos);
                 candidates.insert(0, cap);
             }
         }
 
+        candidates
+    }
+
+    /// Negotiate with peer
+    ///
+    /// Picks the category of the best-ranked peer capability and negotiates
+    /// within it, so alternatives never cross categories.
+    pub fn negotiate(&mut self, peer_capabilities: &[AlgorithmCapability]) -> CryptoResult<NegotiationResult> {
+        self.negotiate_for(None, peer_capabilities)
+    }
+
+    /// Negotiate with an identified peer, recording its id in the history
+    pub fn negotiate_with_peer(
+        &mut self,
+        peer_id: [u8; 16],
+        peer_capabilities: &[AlgorithmCapability],
+    ) -> CryptoResult<NegotiationResult> {
+        self.negotiate_for(Some(peer_id), peer_capabilities)
+    }
+
+    fn negotiate_for(
+        &mut self,
+        peer_id: Option<[u8; 16]>,
+        peer_capabilities: &[AlgorithmCapability],
+    ) -> CryptoResult<NegotiationResult> {
+        let category = self.rank_candidates(peer_capabilities)
+            .first()
+            .and_then(|cap| self.category_of(&cap.id));
+
+        match category {
+            Some(category) => self.negotiate_in(peer_id, category, peer_capabilities),
+            None => self.fallback(),
+        }
+    }
+
+    /// Negotiate an algorithm of one category with peer
+    ///
+    /// Peer capabilities outside `category` are ignored, and the fallback is
+    /// drawn from the same category.
+    pub fn negotiate_category(
+        &mut self,
+        category: AlgorithmCategory,
+        peer_capabilities: &[AlgorithmCapability],
+    ) -> CryptoResult<NegotiationResult> {
+        self.negotiate_in(None, category, peer_capabilities)
+    }
+
+    fn negotiate_in(
+        &mut self,
+        peer_id: Option<[u8; 16]>,
+        category: AlgorithmCategory,
+        peer_capabilities: &[AlgorithmCapability],
+    ) -> CryptoResult<NegotiationResult> {
+        let candidates: Vec<&AlgorithmCapability> = self.rank_candidates(peer_capabilities)
+            .into_iter()
+            .filter(|cap| self.category_of(&cap.id) == Some(category))
+            .collect();
+
         if candidates.is_empty() {
             // Try fallback
-            return self.fallback();
+            return self.fallback_in(Some(category));
         }
 
-        let selected = candidates[0].id.clone();
+        let selected = candidates[0].id;
         let alternatives: Vec<AlgorithmId> = candidates[1..]
             .iter()
             .map(|c| c.id)
@@ -442,14 +562,15 @@ impl AgilityManager {
 
         // Record negotiation
         self.negotiation_history.push(NegotiationRecord {
-            timestamp: 0, // Would use actual time
-            peer_id: None,
+            timestamp: self.now,
+            peer_id,
             requested: peer_capabilities.iter().map(|c| c.id).collect(),
             selected,
             success: true,
         });
 
-        let fallback_available = !alternatives.is_empty();
+        let fallback_available = !alternatives.is_empty()
+            || !matches!(self.fallback_strategy, FallbackStrategy::Fail);
         Ok(NegotiationResult {
             selected,
             alternatives,
@@ -460,63 +581,45 @@ impl AgilityManager {
 
     /// Fallback selection
     pub fn fallback(&mut self) -> CryptoResult<NegotiationResult> {
-        match self.fallback_strategy {
-            FallbackStrategy::Fail => {
-                Err(CryptoError::AgilityNegotiationFailed)
-            }
+        self.fallback_in(None)
+    }
+
+    /// Fallback selection, restricted to `category` when given
+    fn fallback_in(&self, category: Option<AlgorithmCategory>) -> CryptoResult<NegotiationResult> {
+        let mut local = self.local_capabilities
+            .iter()
+            .filter(|(c, _)| category.map_or(true, |wanted| *c == wanted))
+            .flat_map(|(_, caps)| caps.iter())
+            .filter(|cap| self.is_available(&cap.id));
+
+        let chosen = match self.fallback_strategy {
+            FallbackStrategy::Fail => None,
+            // Find minimum algorithm that meets security requirements
             FallbackStrategy::MinimumSecure => {
-                // Find minimum algorithm that meets security requirements
-                for (_, caps) in &self.local_capabilities {
-                    for cap in caps {
-                        if cap.security_level >= self.preference.min_security {
-                            return Ok(NegotiationResult {
-                                selected: cap.id,
-                                alternatives: vec![],
-                                security_level: cap.security_level,
-                                fallback_available: true,
-                            });
-                        }
-                    }
-                }
-                Err(CryptoError::AgilityNegotiationFailed)
+                local.find(|cap| cap.security_level >= self.preference.min_security)
             }
-            FallbackStrategy::Fastest => {
-                // Find fastest available
-                let mut fastest: Option<&AlgorithmCapability> = None;
-                for (_, caps) in &self.local_capabilities {
-                    for cap in caps {
-                        if fastest.is_none() || 
-                           cap.performance_ops_per_sec > fastest.unwrap().performance_ops_per_sec {
-                            fastest = Some(cap);
-                        }
-                    }
+            // Find fastest available
+            FallbackStrategy::Fastest => local.fold(None, |fastest: Option<&AlgorithmCapability>, cap| {
+                match fastest {
+                    Some(f) if f.performance_ops_per_sec >= cap.performance_ops_per_sec => Some(f),
+                    _ => Some(cap),
                 }
-                fastest.map(|c| NegotiationResult {
-                    selected: c.id,
-                    alternatives: vec![],
-                    security_level: c.security_level,
-                    fallback_available: true,
-                }).ok_or(CryptoError::AgilityNegotiationFailed)
-            }
-            FallbackStrategy::MostSecure => {
-                // Find most secure available
-                let mut most_secure: Option<&AlgorithmCapability> = None;
-                for (_, caps) in &self.local_capabilities {
-                    for cap in caps {
-                        if most_secure.is_none() || 
-                           cap.security_level > most_secure.unwrap().security_level {
-                            most_secure = Some(cap);
-                        }
-                    }
+            }),
+            // Find most secure available
+            FallbackStrategy::MostSecure => local.fold(None, |most_secure: Option<&AlgorithmCapability>, cap| {
+                match most_secure {
+                    Some(m) if m.security_level >= cap.security_level => Some(m),
+                    _ => Some(cap),
                 }
-                most_secure.map(|c| NegotiationResult {
-                    selected: c.id,
-                    alternatives: vec![],
-                    security_level: c.security_level,
-                    fallback_available: true,
-                }).ok_or(CryptoError::AgilityNegotiationFailed)
-            }
-        }
+            }),
+        };
+
+        chosen.map(|c| NegotiationResult {
+            selected: c.id,
+            alternatives: vec![],
+            security_level: c.security_level,
+            fallback_available: true,
+        }).ok_or(CryptoError::AgilityNegotiationFailed)
     }
 
     /// Get algorithm capabilities for a category
@@ -535,11 +638,98 @@ impl AgilityManager {
             .collect()
     }
 
+    /// Measure local primitives and record their throughput
+    ///
+    /// Each capability's core operation is run `iterations` times and its
+    /// `performance_ops_per_sec` replaced by the measured rate. Algorithms
+    /// without a local implementation keep their configured estimate.
+    #[cfg(feature = "std")]
+    pub fn benchmark_local(&mut self, iterations: usize) {
+        let iterations = iterations.max(1);
+
+        for (_, caps) in self.local_capabilities.iter_mut() {
+            for cap in caps.iter_mut() {
+                if let Some(ops_per_sec) = bench::measure(cap.id, iterations) {
+                    cap.performance_ops_per_sec = ops_per_sec;
+                }
+            }
+        }
+    }
+
     /// Get negotiation history
     pub fn negotiation_history(&self) -> &[NegotiationRecord] {
         &self.negotiation_history
     }
 
+    /// Negotiation history with one peer, oldest first
+    pub fn history_for_peer(&self, peer_id: [u8; 16]) -> Vec<&NegotiationRecord> {
+        self.negotiation_history
+            .iter()
+            .filter(|record| record.peer_id == Some(peer_id))
+            .collect()
+    }
+
+    /// Start executing a migration plan
+    ///
+    /// Nothing changes until [`advance_migration`](Self::advance_migration)
+    /// is called. Fails while another migration still has steps left.
+    pub fn begin_migration(&mut self, plan: MigrationPlan) -> CryptoResult<()> {
+        if self.migration_status().is_some_and(|status| !status.is_complete()) {
+            return Err(CryptoError::InvalidInput);
+        }
+        self.migration = Some((plan, 0));
+        Ok(())
+    }
+
+    /// Apply the next step of the current migration, returning it
+    pub fn advance_migration(&mut self) -> CryptoResult<MigrationStep> {
+        let (from, to, step) = match &mut self.migration {
+            Some((plan, next)) if *next < plan.steps.len() => {
+                *next += 1;
+                (plan.from, plan.to, plan.steps[*next - 1])
+            }
+            _ => return Err(CryptoError::InvalidInput),
+        };
+
+        match step {
+            // Make the target selectable, registering it if needed
+            MigrationStep::EnableTarget => {
+                self.blacklist.retain(|a| *a != to);
+                self.preference.forbidden.retain(|a| *a != to);
+                if self.category_of(&to).is_none() {
+                    self.register(AlgorithmCapability::for_algorithm(to));
+                }
+            }
+            // Both are accepted, the source still preferred
+            MigrationStep::DualOperation => {
+                self.preference.priority_list.retain(|a| *a != from && *a != to);
+                self.preference.priority_list.splice(0..0, [from, to]);
+            }
+            // Both are accepted, the target now preferred
+            MigrationStep::PreferTarget => {
+                self.preference.priority_list.retain(|a| *a != to);
+                self.preference.priority_list.insert(0, to);
+            }
+            // The source is no longer selectable
+            MigrationStep::DisableSource => {
+                self.preference.priority_list.retain(|a| *a != from);
+                self.blacklist(from, "migrated");
+            }
+        }
+        Ok(step)
+    }
+
+    /// Progress of the current (or last) migration
+    pub fn migration_status(&self) -> Option<MigrationStatus> {
+        self.migration.as_ref().map(|(plan, next)| MigrationStatus {
+            from: plan.from,
+            to: plan.to,
+            completed_steps: *next,
+            total_steps: plan.steps.len(),
+            last_step: next.checked_sub(1).map(|i| plan.steps[i]),
+        })
+    }
+
     /// Get deprecation warnings
     pub fn deprecation_warnings(&self) -> &[(AlgorithmId, String)] {
         &self.deprecated
@@ -553,10 +743,105 @@ impl AgilityManager {
     }
 }
 
+/// Micro-benchmarks backing `AgilityManager::benchmark_local`
+#[cfg(feature = "std")]
+mod bench {
+    use super::AlgorithmId;
+    use super::super::{
+        aes_gcm::AesGcm,
+        bls::BlsKeypair,
+        chacha20::ChaCha20Poly1305,
+        dilithium::{DilithiumKeypair, DilithiumVariant},
+        ed25519::Ed25519Keypair,
+        kyber::{KyberKeypair, KyberVariant},
+        sha3::{Sha3_256, Sha3_512},
+        x25519::X25519Keypair,
+    };
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    /// Payload for symmetric ciphers and hashes
+    const PAYLOAD: [u8; 1024] = [0xa5; 1024];
+    const NONCE: [u8; 12] = [0u8; 12];
+
+    /// Operations per second of `alg`'s core operation, if implemented
+    pub(super) fn measure(alg: AlgorithmId, iterations: usize) -> Option<u64> {
+        let mut op: Box<dyn FnMut()> = match alg {
+            AlgorithmId::Aes128Gcm | AlgorithmId::Aes256Gcm => {
+                let key_len = if alg == AlgorithmId::Aes128Gcm { 16 } else { 32 };
+                let cipher = AesGcm::new(&vec![0x42; key_len]).ok()?;
+                Box::new(move || { black_box(cipher.encrypt(&NONCE, &PAYLOAD, &[])); })
+            }
+            AlgorithmId::ChaCha20Poly1305 => {
+                let cipher = ChaCha20Poly1305::new(&[0x42; 32]);
+                Box::new(move || { black_box(cipher.encrypt(&NONCE, &PAYLOAD, &[])); })
+            }
+            AlgorithmId::Ed25519 => {
+                let keypair = Ed25519Keypair::generate();
+                Box::new(move || { black_box(keypair.sign(&PAYLOAD[..32])); })
+            }
+            AlgorithmId::Dilithium2 | AlgorithmId::Dilithium3 | AlgorithmId::Dilithium5 => {
+                let variant = match alg {
+                    AlgorithmId::Dilithium2 => DilithiumVariant::Dilithium2,
+                    AlgorithmId::Dilithium3 => DilithiumVariant::Dilithium3,
+                    _ => DilithiumVariant::Dilithium5,
+                };
+                let keypair = DilithiumKeypair::generate(variant);
+                Box::new(move || { black_box(keypair.sign(&PAYLOAD[..32])); })
+            }
+            AlgorithmId::Bls12_381 => {
+                let keypair = BlsKeypair::generate();
+                Box::new(move || { black_box(keypair.sign(&PAYLOAD[..32])); })
+            }
+            AlgorithmId::X25519 => {
+                let ours = X25519Keypair::generate();
+                let theirs = *X25519Keypair::generate().public_key();
+                Box::new(move || { let _ = black_box(ours.shared_secret(&theirs)); })
+            }
+            AlgorithmId::Kyber512 | AlgorithmId::Kyber768 | AlgorithmId::Kyber1024 => {
+                let variant = match alg {
+                    AlgorithmId::Kyber512 => KyberVariant::Kyber512,
+                    AlgorithmId::Kyber768 => KyberVariant::Kyber768,
+                    _ => KyberVariant::Kyber1024,
+                };
+                let keypair = KyberKeypair::generate(variant);
+                Box::new(move || { black_box(keypair.encapsulate()); })
+            }
+            AlgorithmId::Sha3_256 => Box::new(|| { black_box(Sha3_256::hash(&PAYLOAD)); }),
+            AlgorithmId::Sha3_512 => Box::new(|| { black_box(Sha3_512::hash(&PAYLOAD)); }),
+            _ => return None,
+        };
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            op();
+        }
+        let nanos = start.elapsed().as_nanos().max(1);
+
+        Some(((iterations as u128 * 1_000_000_000) / nanos).clamp(1, u64::MAX as u128) as u64)
+    }
+}
+
+/// Operations an algorithm must have recorded before its failure alert can fire
+pub const FAILURE_ALERT_MIN_SAMPLES: u64 = 20;
+
+/// Called with the algorithm and its failure rate when an alert fires
+pub type FailureAlertCallback = fn(AlgorithmId, f64);
+
+/// Failure-rate alert on one algorithm
+struct FailureAlert {
+    alg: AlgorithmId,
+    threshold: f64,
+    callback: FailureAlertCallback,
+    /// Fired and not yet back below the threshold
+    tripped: bool,
+}
+
 /// Crypto inventory for tracking algorithm usage
 pub struct CryptoInventory {
     algorithm_usage: Vec<(AlgorithmId, UsageStats)>,
     total_operations: u64,
+    failure_alerts: Vec<FailureAlert>,
 }
 
 /// Usage statistics
@@ -570,11 +855,55 @@ pub struct UsageStats {
     pub failures: u64,
 }
 
+impl UsageStats {
+    /// Operations recorded, failures included
+    pub fn total(&self) -> u64 {
+        self.encrypt_ops + self.decrypt_ops + self.sign_ops + self.verify_ops + self.key_gen_ops + self.failures
+    }
+}
+
 impl CryptoInventory {
     pub fn new() -> Self {
         CryptoInventory {
             algorithm_usage: Vec::new(),
             total_operations: 0,
+            failure_alerts: Vec::new(),
+        }
+    }
+
+    /// Call `callback` when `alg`'s failure rate reaches `threshold` (0.0-1.0)
+    ///
+    /// Checked whenever a failure is recorded, once the algorithm has at
+    /// least `FAILURE_ALERT_MIN_SAMPLES` operations. The alert fires once
+    /// per crossing and re-arms when the rate drops below the threshold
+    /// again. Replaces any earlier alert on the same algorithm.
+    pub fn set_failure_alert(&mut self, alg: AlgorithmId, threshold: f64, callback: FailureAlertCallback) {
+        self.failure_alerts.retain(|alert| alert.alg != alg);
+        self.failure_alerts.push(FailureAlert {
+            alg,
+            threshold,
+            callback,
+            tripped: false,
+        });
+    }
+
+    /// Fire or re-arm `alg`'s alert after an operation was recorded
+    fn check_failure_alert(&mut self, alg: AlgorithmId, op_type: OperationType) {
+        let Some(index) = self.failure_alerts.iter().position(|alert| alert.alg == alg) else {
+            return;
+        };
+        let samples = self.get_stats(alg).map_or(0, UsageStats::total);
+        let rate = self.failure_rate(alg);
+
+        let alert = &mut self.failure_alerts[index];
+        if rate < alert.threshold {
+            alert.tripped = false;
+        } else if matches!(op_type, OperationType::Failure)
+            && samples >= FAILURE_ALERT_MIN_SAMPLES
+            && !alert.tripped
+        {
+            alert.tripped = true;
+            (alert.callback)(alg, rate);
         }
     }
 
@@ -602,6 +931,7 @@ impl CryptoInventory {
         }
         
         self.total_operations += 1;
+        self.check_failure_alert(alg, op_type);
     }
 
     pub fn get_stats(&self, alg: AlgorithmId) -> Option<&UsageStats> {
@@ -620,8 +950,7 @@ impl CryptoInventory {
 
     pub fn failure_rate(&self, alg: AlgorithmId) -> f64 {
         if let Some((_, stats)) = self.algorithm_usage.iter().find(|(a, _)| *a == alg) {
-            let total = stats.encrypt_ops + stats.decrypt_ops + stats.sign_ops + 
-                       stats.verify_ops + stats.key_gen_ops + stats.failures;
+            let total = stats.total();
             if total > 0 {
                 stats.failures as f64 / total as f64
             } else {
@@ -702,7 +1031,7 @@ pub struct MigrationPlan {
 }
 
 /// Migration step
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MigrationStep {
     EnableTarget,
     DualOperation,
@@ -710,6 +1039,23 @@ pub enum MigrationStep {
     DisableSource,
 }
 
+/// Progress of a migration being executed by `AgilityManager`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub from: AlgorithmId,
+    pub to: AlgorithmId,
+    pub completed_steps: usize,
+    pub total_steps: usize,
+    /// Most recently applied step
+    pub last_step: Option<MigrationStep>,
+}
+
+impl MigrationStatus {
+    pub fn is_complete(&self) -> bool {
+        self.completed_steps == self.total_steps
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -733,6 +1079,17 @@ mod tests {
         assert!(score > 0);
     }
 
+    #[test]
+    fn test_capability_for_algorithm() {
+        let cap = AlgorithmCapability::for_algorithm(AlgorithmId::Kyber1024);
+        assert_eq!(cap.security_level, SecurityLevel::PostQuantum256);
+        assert!(cap.post_quantum);
+
+        let mut manager = AgilityManager::new();
+        manager.register(cap);
+        assert_eq!(manager.category_of(&AlgorithmId::Kyber1024), Some(AlgorithmCategory::KeyExchange));
+    }
+
     #[test]
     fn test_negotiation() {
         let mut manager = AgilityManager::new();
@@ -747,6 +1104,113 @@ mod tests {
         assert!(result.has_fallback());
     }
 
+    #[test]
+    fn test_negotiate_category_ignores_other_categories() {
+        let mut manager = AgilityManager::new();
+        manager.set_preference(AlgorithmPreference::secure_default());
+
+        let peer_caps = vec![
+            AlgorithmCapability::new(AlgorithmId::Aes256Gcm, SecurityLevel::Bits256)
+                .with_performance(1000000)
+                .with_hardware_acceleration()
+                .with_fips_compliance(),
+            AlgorithmCapability::new(AlgorithmId::Sha3_256, SecurityLevel::Bits256),
+            AlgorithmCapability::new(AlgorithmId::Dilithium3, SecurityLevel::PostQuantum128)
+                .with_post_quantum(),
+            AlgorithmCapability::new(AlgorithmId::Ed25519, SecurityLevel::Bits256),
+        ];
+
+        let result = manager.negotiate_category(AlgorithmCategory::Signature, &peer_caps).unwrap();
+        let signatures = [AlgorithmId::Ed25519, AlgorithmId::Dilithium3];
+        assert!(signatures.contains(&result.selected_algorithm()));
+        assert!(result.alternatives().iter().all(|alg| signatures.contains(alg)));
+
+        // With no signature on offer the fallback stays within the category
+        let result = manager.negotiate_category(AlgorithmCategory::Signature, &peer_caps[..2]).unwrap();
+        assert_eq!(manager.category_of(&result.selected_algorithm()), Some(AlgorithmCategory::Signature));
+    }
+
+    #[test]
+    fn test_history_for_peer() {
+        let mut manager = AgilityManager::new();
+        let alice = [0xa1u8; 16];
+        let bob = [0xb0u8; 16];
+
+        let signature = [AlgorithmCapability::new(AlgorithmId::Ed25519, SecurityLevel::Bits256)];
+        let cipher = [AlgorithmCapability::new(AlgorithmId::ChaCha20Poly1305, SecurityLevel::Bits256)];
+
+        manager.negotiate_with_peer(alice, &signature).unwrap();
+        manager.negotiate_with_peer(bob, &cipher).unwrap();
+        manager.negotiate_with_peer(alice, &cipher).unwrap();
+        manager.negotiate(&signature).unwrap();
+
+        let history = manager.history_for_peer(alice);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].selected_algorithm(), AlgorithmId::Ed25519);
+        assert_eq!(history[1].selected_algorithm(), AlgorithmId::ChaCha20Poly1305);
+
+        let history = manager.history_for_peer(bob);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].peer_id(), Some(bob));
+        assert_eq!(history[0].selected_algorithm(), AlgorithmId::ChaCha20Poly1305);
+
+        assert!(manager.history_for_peer([0u8; 16]).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_benchmark_local() {
+        let mut manager = AgilityManager::new();
+        for cap in manager.get_all_capabilities() {
+            assert_ne!(cap.performance_ops_per_sec, 0);
+        }
+
+        // Zero the estimates so every figure below comes from a measurement
+        for (_, caps) in manager.local_capabilities.iter_mut() {
+            for cap in caps.iter_mut() {
+                cap.performance_ops_per_sec = 0;
+            }
+        }
+        manager.benchmark_local(2);
+
+        let caps = manager.get_all_capabilities();
+        assert!(caps.iter().all(|cap| cap.performance_ops_per_sec > 0));
+
+        // The fastest fallback follows the measured figures
+        let best = caps.iter().map(|cap| cap.performance_ops_per_sec).max().unwrap();
+        manager.set_fallback_strategy(FallbackStrategy::Fastest);
+        let selected = manager.fallback().unwrap().selected_algorithm();
+        let selected_cap = manager.get_all_capabilities().into_iter().find(|cap| cap.id == selected).unwrap();
+        assert_eq!(selected_cap.performance_ops_per_sec, best);
+    }
+
+    #[test]
+    fn test_deprecation_sunset() {
+        let mut manager = AgilityManager::new();
+        manager.set_fallback_strategy(FallbackStrategy::Fail);
+        manager.deprecate_after(AlgorithmId::Aes256Gcm, "Migrate to ChaCha20-Poly1305", 1000);
+
+        let peer_caps = [AlgorithmCapability::new(AlgorithmId::Aes256Gcm, SecurityLevel::Bits256)];
+
+        // Before the sunset the algorithm is flagged but still selectable
+        manager.set_time(999);
+        assert!(manager.is_available(&AlgorithmId::Aes256Gcm));
+        assert_eq!(manager.check_deprecation(AlgorithmId::Aes256Gcm), Some("Migrate to ChaCha20-Poly1305"));
+        let result = manager.negotiate(&peer_caps).unwrap();
+        assert_eq!(result.selected_algorithm(), AlgorithmId::Aes256Gcm);
+
+        // From the sunset onwards it is refused
+        manager.set_time(1000);
+        assert!(!manager.is_available(&AlgorithmId::Aes256Gcm));
+        assert!(manager.negotiate(&peer_caps).is_err());
+        assert!(manager.is_available(&AlgorithmId::ChaCha20Poly1305));
+
+        // Nor does the fallback hand it out
+        manager.set_fallback_strategy(FallbackStrategy::MinimumSecure);
+        let result = manager.negotiate_category(AlgorithmCategory::SymmetricEncryption, &peer_caps).unwrap();
+        assert_eq!(result.selected_algorithm(), AlgorithmId::ChaCha20Poly1305);
+    }
+
     #[test]
     fn test_blacklist() {
         let mut manager = AgilityManager::new();
@@ -771,6 +1235,41 @@ mod tests {
         assert_eq!(report.total_operations, 3);
     }
 
+    #[test]
+    fn test_failure_alert_fires_once() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static FIRED: AtomicUsize = AtomicUsize::new(0);
+        fn on_alert(alg: AlgorithmId, rate: f64) {
+            assert_eq!(alg, AlgorithmId::Aes256Gcm);
+            assert!(rate >= 0.1);
+            FIRED.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut inventory = CryptoInventory::new();
+        inventory.set_failure_alert(AlgorithmId::Aes256Gcm, 0.1, on_alert);
+
+        // A high rate on too few samples does not count
+        inventory.record_operation(AlgorithmId::Aes256Gcm, OperationType::Failure);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 0);
+
+        for _ in 0..17 {
+            inventory.record_operation(AlgorithmId::Aes256Gcm, OperationType::Encrypt);
+        }
+        // Other algorithms' failures are not watched
+        inventory.record_operation(AlgorithmId::Ed25519, OperationType::Failure);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 0);
+
+        // Fires once 20 operations are in, with 3 of them failures
+        inventory.record_operation(AlgorithmId::Aes256Gcm, OperationType::Failure);
+        inventory.record_operation(AlgorithmId::Aes256Gcm, OperationType::Failure);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 1);
+
+        // Staying above the threshold does not fire again
+        inventory.record_operation(AlgorithmId::Aes256Gcm, OperationType::Failure);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_preference_builders() {
         let pref = AlgorithmPreference::secure_default()
@@ -793,4 +1292,49 @@ mod tests {
         assert_eq!(plan.to, AlgorithmId::Aes256Gcm);
         assert!(!plan.steps.is_empty());
     }
+
+    #[test]
+    fn test_migration_executor() {
+        let mut manager = AgilityManager::new();
+        manager.register(AlgorithmCapability::for_algorithm(AlgorithmId::Aes128Gcm));
+        let peer_caps = [
+            AlgorithmCapability::for_algorithm(AlgorithmId::Aes128Gcm),
+            AlgorithmCapability::for_algorithm(AlgorithmId::Aes256Gcm),
+        ];
+        let negotiate = |manager: &mut AgilityManager| {
+            manager
+                .negotiate_category(AlgorithmCategory::SymmetricEncryption, &peer_caps)
+                .unwrap()
+                .selected_algorithm()
+        };
+
+        assert_eq!(manager.advance_migration(), Err(CryptoError::InvalidInput));
+        manager
+            .begin_migration(AlgorithmMigration::create_plan(AlgorithmId::Aes128Gcm, AlgorithmId::Aes256Gcm))
+            .unwrap();
+        assert_eq!(manager.migration_status().unwrap().last_step, None);
+
+        assert_eq!(manager.advance_migration(), Ok(MigrationStep::EnableTarget));
+        assert!(manager.is_available(&AlgorithmId::Aes256Gcm));
+
+        assert_eq!(manager.advance_migration(), Ok(MigrationStep::DualOperation));
+        assert_eq!(negotiate(&mut manager), AlgorithmId::Aes128Gcm);
+
+        assert_eq!(manager.advance_migration(), Ok(MigrationStep::PreferTarget));
+        assert_eq!(negotiate(&mut manager), AlgorithmId::Aes256Gcm);
+        assert!(manager.is_available(&AlgorithmId::Aes128Gcm));
+        // A second migration cannot start until this one finishes
+        let plan = AlgorithmMigration::create_plan(AlgorithmId::X25519, AlgorithmId::Kyber768);
+        assert_eq!(manager.begin_migration(plan.clone()), Err(CryptoError::InvalidInput));
+
+        assert_eq!(manager.advance_migration(), Ok(MigrationStep::DisableSource));
+        assert!(!manager.is_available(&AlgorithmId::Aes128Gcm));
+        assert_eq!(negotiate(&mut manager), AlgorithmId::Aes256Gcm);
+
+        let status = manager.migration_status().unwrap();
+        assert!(status.is_complete());
+        assert_eq!(status.last_step, Some(MigrationStep::DisableSource));
+        assert_eq!(manager.advance_migration(), Err(CryptoError::InvalidInput));
+        assert!(manager.begin_migration(plan).is_ok());
+    }
 }
@@ -1,6 +1,11 @@
 //! BLS12-381 Signatures
+//!
+//! Placeholder arithmetic: the pairing groups are modelled by the prime
+//! field GF(2^61 - 1) with e(a, b) = a * b, so signing, verification and
+//! aggregation follow the real BLS equations but keys offer no security.
 
 use super::{CryptoRng, CryptoError, CryptoResult, HardwareRng};
+use super::sha3::Sha3_256;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -8,7 +13,51 @@ use alloc::vec::Vec;
 pub const SIGNATURE_SIZE: usize = 48;
 pub const PUBLIC_KEY_SIZE: usize = 96;
 
-#[derive(Clone, Copy, Debug)]
+/// Order of the placeholder group (Mersenne prime 2^61 - 1)
+const GROUP_ORDER: u64 = (1 << 61) - 1;
+/// Generator of the placeholder G2
+const G2_GENERATOR: u64 = 5;
+/// Domain separation tag for hashing messages to G1
+const HASH_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA3-256_CELL0_";
+
+fn field_mul(a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % GROUP_ORDER as u128) as u64
+}
+
+fn field_add(a: u64, b: u64) -> u64 {
+    ((a as u128 + b as u128) % GROUP_ORDER as u128) as u64
+}
+
+/// Placeholder pairing e: G1 x G2 -> GT
+fn pairing(g1: u64, g2: u64) -> u64 {
+    field_mul(g1, g2)
+}
+
+/// Hash a message to a nonzero G1 element
+fn hash_to_g1(message: &[u8]) -> u64 {
+    let mut hasher = Sha3_256::new();
+    hasher.update(HASH_DST);
+    hasher.update(message);
+    let digest = hasher.finalize();
+
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(word) % (GROUP_ORDER - 1) + 1
+}
+
+fn encode<const N: usize>(element: u64) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes[..8].copy_from_slice(&element.to_le_bytes());
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BlsSignature([u8; SIGNATURE_SIZE]);
 
 impl Default for BlsSignature {
@@ -19,16 +68,21 @@ impl Default for BlsSignature {
 
 impl BlsSignature {
     pub fn to_bytes(&self) -> [u8; SIGNATURE_SIZE] { self.0 }
+
+    /// Combine signatures into one by adding them in G1
     pub fn aggregate(signatures: &[BlsSignature]) -> Self {
-        let mut result = [0u8; SIGNATURE_SIZE];
-        for sig in signatures {
-            for i in 0..SIGNATURE_SIZE { result[i] ^= sig.0[i]; }
-        }
-        BlsSignature(result)
+        let sum = signatures
+            .iter()
+            .fold(0, |acc, sig| field_add(acc, sig.point()));
+        BlsSignature(encode(sum))
+    }
+
+    fn point(&self) -> u64 {
+        decode(&self.0)
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BlsPublicKey([u8; PUBLIC_KEY_SIZE]);
 
 impl Default for BlsPublicKey {
@@ -37,6 +91,14 @@ impl Default for BlsPublicKey {
     }
 }
 
+impl BlsPublicKey {
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] { self.0 }
+
+    fn point(&self) -> u64 {
+        decode(&self.0)
+    }
+}
+
 #[derive(Clone)]
 pub struct BlsKeypair {
     secret_key: [u8; 32],
@@ -49,39 +111,159 @@ impl BlsKeypair {
         let mut rng = HardwareRng;
         let mut sk = [0u8; 32];
         rng.fill_bytes(&mut sk);
-        let pk = BlsPublicKey([0u8; PUBLIC_KEY_SIZE]);
-        BlsKeypair { secret_key: sk, public_key: pk, proof_of_possession: BlsSignature([0u8; SIGNATURE_SIZE]) }
+        Self::from_secret_key(sk)
+    }
+
+    /// Build a keypair from 32 bytes of secret material
+    pub fn from_secret_key(secret_key: [u8; 32]) -> Self {
+        let mut keypair = BlsKeypair {
+            secret_key,
+            public_key: BlsPublicKey::default(),
+            proof_of_possession: BlsSignature::default(),
+        };
+        keypair.public_key = BlsPublicKey(encode(field_mul(keypair.scalar(), G2_GENERATOR)));
+        keypair.proof_of_possession = keypair.sign(&keypair.public_key.0);
+        keypair
     }
-    
+
     pub fn public_key(&self) -> &BlsPublicKey { &self.public_key }
-    
+
+    /// Signature over the public key, guarding aggregation against rogue keys
+    pub fn proof_of_possession(&self) -> &BlsSignature { &self.proof_of_possession }
+
+    /// Secret scalar, reduced to a nonzero group element
+    fn scalar(&self) -> u64 {
+        decode(&self.secret_key) % (GROUP_ORDER - 1) + 1
+    }
+
     pub fn sign(&self, message: &[u8]) -> BlsSignature {
-        let mut sig = [0u8; SIGNATURE_SIZE];
-        for (i, byte) in message.iter().enumerate() {
-            sig[i % SIGNATURE_SIZE] ^= byte;
+        BlsSignature(encode(field_mul(self.scalar(), hash_to_g1(message))))
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &BlsSignature) -> CryptoResult<()> {
+        verify(&self.public_key, message, signature)
+    }
+}
+
+/// Verify a single signature: e(sig, g2) == e(H(m), pk)
+pub fn verify(public_key: &BlsPublicKey, message: &[u8], signature: &BlsSignature) -> CryptoResult<()> {
+    if pairing(signature.point(), G2_GENERATOR) == pairing(hash_to_g1(message), public_key.point()) {
+        Ok(())
+    } else {
+        Err(CryptoError::VerificationFailed)
+    }
+}
+
+/// Verify an aggregate over distinct messages, one per public key
+///
+/// Checks e(agg, g2) == prod e(H(m_i), pk_i). Repeated messages are
+/// rejected; use `fast_aggregate_verify` when everyone signed the same one.
+pub fn aggregate_verify(
+    public_keys: &[BlsPublicKey],
+    messages: &[&[u8]],
+    aggregate: &BlsSignature,
+) -> CryptoResult<()> {
+    if public_keys.is_empty() || public_keys.len() != messages.len() {
+        return Err(CryptoError::InvalidInput);
+    }
+    for (i, message) in messages.iter().enumerate() {
+        if messages[..i].contains(message) {
+            return Err(CryptoError::InvalidInput);
         }
-        BlsSignature(sig)
     }
-    
-    pub fn verify(&self, _message: &[u8], _signature: &BlsSignature) -> CryptoResult<()> {
+
+    // GT is additive in the placeholder model, so the product is a sum
+    let expected = public_keys
+        .iter()
+        .zip(messages)
+        .fold(0, |acc, (pk, m)| field_add(acc, pairing(hash_to_g1(m), pk.point())));
+
+    if pairing(aggregate.point(), G2_GENERATOR) == expected {
         Ok(())
+    } else {
+        Err(CryptoError::VerificationFailed)
     }
 }
 
+/// Verify an aggregate where every key signed the same message
+///
+/// Only sound if each key's proof of possession has been checked.
+pub fn fast_aggregate_verify(
+    public_keys: &[BlsPublicKey],
+    message: &[u8],
+    aggregate: &BlsSignature,
+) -> CryptoResult<()> {
+    if public_keys.is_empty() {
+        return Err(CryptoError::InvalidInput);
+    }
+
+    let combined = public_keys.iter().fold(0, |acc, pk| field_add(acc, pk.point()));
+    verify(&BlsPublicKey(encode(combined)), message, aggregate)
+}
+
+/// Check a proof of possession for `public_key`
+pub fn verify_proof_of_possession(public_key: &BlsPublicKey, proof: &BlsSignature) -> CryptoResult<()> {
+    verify(public_key, &public_key.0, proof)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
-    #[test] 
+
+    #[test]
     fn test_keygen() {
         let keypair = BlsKeypair::generate();
         assert_ne!(keypair.secret_key, [0u8; 32]);
     }
-    
-    #[test] 
+
+    #[test]
     fn test_sign() {
         let keypair = BlsKeypair::generate();
         let sig = keypair.sign(b"test");
         assert!(keypair.verify(b"test", &sig).is_ok());
+        assert!(keypair.verify(b"tset", &sig).is_err());
+        assert!(BlsKeypair::generate().verify(b"test", &sig).is_err());
+    }
+
+    #[test]
+    fn test_fast_aggregate_same_message() {
+        let signers: Vec<BlsKeypair> = (1..=3u8).map(|i| BlsKeypair::from_secret_key([i; 32])).collect();
+        let message = b"block 42";
+
+        for signer in &signers {
+            assert!(verify_proof_of_possession(signer.public_key(), signer.proof_of_possession()).is_ok());
+        }
+
+        let sigs: Vec<BlsSignature> = signers.iter().map(|k| k.sign(message)).collect();
+        let aggregate = BlsSignature::aggregate(&sigs);
+        let pubkeys: Vec<BlsPublicKey> = signers.iter().map(|k| *k.public_key()).collect();
+
+        assert!(fast_aggregate_verify(&pubkeys, message, &aggregate).is_ok());
+        assert!(fast_aggregate_verify(&pubkeys[..2], message, &aggregate).is_err());
+        assert!(fast_aggregate_verify(&pubkeys, b"block 43", &aggregate).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_distinct_messages() {
+        let signers: Vec<BlsKeypair> = (1..=3u8).map(|i| BlsKeypair::from_secret_key([i; 32])).collect();
+        let messages: [&[u8]; 3] = [b"alpha", b"beta", b"gamma"];
+
+        let sigs: Vec<BlsSignature> = signers.iter().zip(messages).map(|(k, m)| k.sign(m)).collect();
+        let aggregate = BlsSignature::aggregate(&sigs);
+        let pubkeys: Vec<BlsPublicKey> = signers.iter().map(|k| *k.public_key()).collect();
+
+        assert!(aggregate_verify(&pubkeys, &messages, &aggregate).is_ok());
+
+        // Swapping which key signed which message breaks verification
+        let swapped: [&[u8]; 3] = [b"beta", b"alpha", b"gamma"];
+        assert_eq!(
+            aggregate_verify(&pubkeys, &swapped, &aggregate),
+            Err(CryptoError::VerificationFailed)
+        );
+
+        // Repeated messages and length mismatches are rejected outright
+        let repeated: [&[u8]; 3] = [b"alpha", b"alpha", b"gamma"];
+        assert_eq!(aggregate_verify(&pubkeys, &repeated, &aggregate), Err(CryptoError::InvalidInput));
+        assert_eq!(aggregate_verify(&pubkeys[..2], &messages, &aggregate), Err(CryptoError::InvalidInput));
     }
 }
@@ -1,19 +1,24 @@
 //! ChaCha20-Poly1305 Authenticated Encryption
-//! 
+//!
 //! High-performance authenticated encryption based on ChaCha20 stream cipher
 //! and Poly1305 MAC. Faster than AES-GCM on platforms without AES-NI.
+//!
+//! Follows RFC 8439: the first keystream block (counter 0) keys Poly1305,
+//! the message is encrypted from counter 1, and the tag covers the padded
+//! AAD, the padded ciphertext and both lengths.
 
-use super::{CryptoRng, CryptoError, CryptoResult, constant_time_eq, HardwareRng};
+use super::{CryptoRng, CryptoError, CryptoResult, constant_time_eq_fixed, secure_clear, secure_clear_drop, HardwareRng};
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
-#[cfg(not(feature = "std"))]
-use alloc::vec;
 
 pub const KEY_SIZE: usize = 32;
 pub const NONCE_SIZE: usize = 12;
 pub const TAG_SIZE: usize = 16;
 
+/// ChaCha20 block size in bytes
+const BLOCK_SIZE: usize = 64;
+
 /// ChaCha20-Poly1305 context
 pub struct ChaCha20Poly1305 {
     key: [u8; KEY_SIZE],
@@ -24,6 +29,13 @@ struct ChaChaState {
     state: [u32; 16],
 }
 
+/// Poly1305 accumulator, in 26-bit limbs
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+}
+
 impl ChaCha20Poly1305 {
     pub fn new(key: &[u8; KEY_SIZE]) -> Self {
         ChaCha20Poly1305 { key: *key }
@@ -37,55 +49,59 @@ impl ChaCha20Poly1305 {
     }
 
     pub fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
-        let mut chacha = ChaChaState::new(&self.key, nonce);
-        let _key_block = chacha.block();
-        
-        // XOR plaintext with keystream (simplified)
-        let mut ciphertext = vec![0u8; plaintext.len()];
-        for (i, byte) in plaintext.iter().enumerate() {
-            ciphertext[i] = byte ^ self.key[i % KEY_SIZE];
-        }
-        
-        // Compute Poly1305 tag (simplified)
-        let mut tag = [0u8; TAG_SIZE];
-        for (i, byte) in aad.iter().chain(ciphertext.iter()).enumerate() {
-            tag[i % TAG_SIZE] ^= *byte;
-        }
-        
+        let mut ciphertext = plaintext.to_vec();
+        ChaChaState::new(&self.key, nonce, 1).apply_keystream(&mut ciphertext);
+        let tag = self.compute_tag(nonce, &ciphertext, aad);
+
         (ciphertext, tag)
     }
 
-    pub fn decrypt(&self, _nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8], tag: &[u8; TAG_SIZE]) -> CryptoResult<Vec<u8>> {
-        // Verify tag
-        let mut computed_tag = [0u8; TAG_SIZE];
-        for (i, byte) in aad.iter().chain(ciphertext.iter()).enumerate() {
-            computed_tag[i % TAG_SIZE] ^= *byte;
-        }
-        
-        if !constant_time_eq(&computed_tag, tag) {
+    pub fn decrypt(&self, nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8], tag: &[u8; TAG_SIZE]) -> CryptoResult<Vec<u8>> {
+        // Verify tag before releasing any plaintext
+        let computed_tag = self.compute_tag(nonce, ciphertext, aad);
+        if !constant_time_eq_fixed(&computed_tag, tag) {
             return Err(CryptoError::VerificationFailed);
         }
-        
-        // Decrypt (XOR with keystream)
-        let mut plaintext = vec![0u8; ciphertext.len()];
-        for (i, byte) in ciphertext.iter().enumerate() {
-            plaintext[i] = byte ^ self.key[i % KEY_SIZE];
-        }
-        
+
+        let mut plaintext = ciphertext.to_vec();
+        ChaChaState::new(&self.key, nonce, 1).apply_keystream(&mut plaintext);
+
         Ok(plaintext)
     }
+
+    /// Poly1305 tag over the AAD and ciphertext, keyed from block 0
+    fn compute_tag(&self, nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8]) -> [u8; TAG_SIZE] {
+        let mut key_block = ChaChaState::new(&self.key, nonce, 0).block();
+        let mut mac = Poly1305::new(&key_block[..32]);
+        secure_clear(&mut key_block);
+
+        mac.update_padded(aad);
+        mac.update_padded(ciphertext);
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+        lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+        mac.update_padded(&lengths);
+
+        mac.finalize()
+    }
+}
+
+impl Drop for ChaCha20Poly1305 {
+    fn drop(&mut self) {
+        secure_clear(&mut self.key);
+    }
 }
 
 impl ChaChaState {
-    fn new(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE]) -> Self {
+    fn new(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], counter: u32) -> Self {
         let mut state = [0u32; 16];
-        
+
         // Constants
         state[0] = 0x61707865;
         state[1] = 0x3320646e;
         state[2] = 0x79622d32;
         state[3] = 0x6b206574;
-        
+
         // Key
         for i in 0..8 {
             state[4 + i] = u32::from_le_bytes([
@@ -95,10 +111,10 @@ impl ChaChaState {
                 key[i * 4 + 3],
             ]);
         }
-        
+
         // Counter
-        state[12] = 0;
-        
+        state[12] = counter;
+
         // Nonce
         for i in 0..3 {
             state[13 + i] = u32::from_le_bytes([
@@ -108,43 +124,204 @@ impl ChaChaState {
                 nonce[i * 4 + 3],
             ]);
         }
-        
+
         ChaChaState { state }
     }
 
-    fn block(&mut self) -> [u8; 64] {
+    fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(16);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(12);
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(8);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(7);
+    }
+
+    fn block(&mut self) -> [u8; BLOCK_SIZE] {
         let mut working = self.state;
-        
-        // 20 rounds (simplified)
+
+        // 20 rounds: alternating column and diagonal rounds
         for _round in 0..10 {
-            // Quarter rounds (simplified)
-            working[0] = working[0].wrapping_add(working[4]);
-            working[12] = (working[12] ^ working[0]).rotate_left(16);
+            Self::quarter_round(&mut working, 0, 4, 8, 12);
+            Self::quarter_round(&mut working, 1, 5, 9, 13);
+            Self::quarter_round(&mut working, 2, 6, 10, 14);
+            Self::quarter_round(&mut working, 3, 7, 11, 15);
+            Self::quarter_round(&mut working, 0, 5, 10, 15);
+            Self::quarter_round(&mut working, 1, 6, 11, 12);
+            Self::quarter_round(&mut working, 2, 7, 8, 13);
+            Self::quarter_round(&mut working, 3, 4, 9, 14);
         }
-        
+
         // Add original state
         for i in 0..16 {
             working[i] = working[i].wrapping_add(self.state[i]);
         }
-        
+
         // Increment counter
         self.state[12] = self.state[12].wrapping_add(1);
-        
+
         // Convert to bytes
-        let mut result = [0u8; 64];
+        let mut result = [0u8; BLOCK_SIZE];
         for i in 0..16 {
             let bytes = working[i].to_le_bytes();
             result[i * 4..(i + 1) * 4].copy_from_slice(&bytes);
         }
-        
+        // SAFETY: `[u32; 16]` has no invalid bit patterns and owns nothing
+        unsafe { secure_clear_drop(&mut working) };
+
         result
     }
+
+    /// XOR `data` with the keystream from the current counter onwards
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for chunk in data.chunks_mut(BLOCK_SIZE) {
+            let mut keystream = self.block();
+            for (byte, key) in chunk.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+            secure_clear(&mut keystream);
+        }
+    }
+}
+
+impl Drop for ChaChaState {
+    fn drop(&mut self) {
+        // SAFETY: `[u32; 16]` has no invalid bit patterns and owns nothing
+        unsafe { secure_clear_drop(&mut self.state) };
+    }
+}
+
+impl Poly1305 {
+    /// Key with the 32-byte one-time key `r || s`, clamping `r`
+    fn new(key: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes([key[i], key[i + 1], key[i + 2], key[i + 3]]);
+
+        Poly1305 {
+            r: [
+                word(0) & 0x3ffffff,
+                (word(3) >> 2) & 0x3ffff03,
+                (word(6) >> 4) & 0x3ffc0ff,
+                (word(9) >> 6) & 0x3f03fff,
+                (word(12) >> 8) & 0x00fffff,
+            ],
+            h: [0; 5],
+            pad: [word(16), word(20), word(24), word(28)],
+        }
+    }
+
+    /// Absorb `data`, zero-padding the last block to 16 bytes as the AEAD
+    /// construction requires
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.block(&block);
+        }
+    }
+
+    /// `h = (h + block + 2^128) * r mod 2^130 - 5`
+    fn block(&mut self, block: &[u8; 16]) {
+        let word = |i: usize| u32::from_le_bytes([block[i], block[i + 1], block[i + 2], block[i + 3]]);
+        let [r0, r1, r2, r3, r4] = self.r.map(u64::from);
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+        let h = &mut self.h;
+        let h0 = u64::from(h[0] + (word(0) & 0x3ffffff));
+        let h1 = u64::from(h[1] + ((word(3) >> 2) & 0x3ffffff));
+        let h2 = u64::from(h[2] + ((word(6) >> 4) & 0x3ffffff));
+        let h3 = u64::from(h[3] + ((word(9) >> 6) & 0x3ffffff));
+        let h4 = u64::from(h[4] + ((word(12) >> 8) | (1 << 24)));
+
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        // Partial reduction back to 26-bit limbs
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let mut h0 = (d0 & 0x3ffffff) + (d4 >> 26) * 5;
+        let h1 = (d1 & 0x3ffffff) + (h0 >> 26);
+        h0 &= 0x3ffffff;
+
+        *h = [h0 as u32, h1 as u32, (d2 & 0x3ffffff) as u32, (d3 & 0x3ffffff) as u32, (d4 & 0x3ffffff) as u32];
+    }
+
+    /// Fully reduce `h`, add `s` and serialize the tag
+    fn finalize(self) -> [u8; TAG_SIZE] {
+        let mut h = self.h;
+
+        let mut carry = h[1] >> 26;
+        h[1] &= 0x3ffffff;
+        for limb in &mut h[2..] {
+            *limb += carry;
+            carry = *limb >> 26;
+            *limb &= 0x3ffffff;
+        }
+        h[0] += carry * 5;
+        carry = h[0] >> 26;
+        h[0] &= 0x3ffffff;
+        h[1] += carry;
+
+        // g = h + 5 - 2^130; keep it instead of h if it did not go negative
+        let mut g = [0u32; 5];
+        carry = 5;
+        for i in 0..5 {
+            g[i] = h[i] + carry;
+            carry = g[i] >> 26;
+            g[i] &= 0x3ffffff;
+        }
+        g[4] = g[4].wrapping_add(carry << 26).wrapping_sub(1 << 26);
+        let use_g = (g[4] >> 31).wrapping_sub(1);
+        for i in 0..5 {
+            h[i] = (h[i] & !use_g) | (g[i] & use_g);
+        }
+
+        // h mod 2^128, then + s
+        let words = [
+            h[0] | (h[1] << 26),
+            (h[1] >> 6) | (h[2] << 20),
+            (h[2] >> 12) | (h[3] << 14),
+            (h[3] >> 18) | (h[4] << 8),
+        ];
+        let mut tag = [0u8; TAG_SIZE];
+        let mut sum = 0u64;
+        for i in 0..4 {
+            sum = u64::from(words[i]) + u64::from(self.pad[i]) + (sum >> 32);
+            tag[i * 4..(i + 1) * 4].copy_from_slice(&(sum as u32).to_le_bytes());
+        }
+
+        // SAFETY: `[u32; 5]` has no invalid bit patterns and owns nothing
+        unsafe { secure_clear_drop(&mut h) };
+        tag
+    }
+}
+
+impl Drop for Poly1305 {
+    fn drop(&mut self) {
+        // SAFETY: plain integer arrays; all-zero is valid and nothing is owned
+        unsafe { secure_clear_drop(self) };
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// RFC 8439 section 2.8.2
+    const RFC_PLAINTEXT: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+    const RFC_AAD: [u8; 12] = [0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7];
+    const RFC_NONCE: [u8; NONCE_SIZE] = [0x07, 0x00, 0x00, 0x00, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47];
+
+    fn rfc_key() -> [u8; KEY_SIZE] {
+        core::array::from_fn(|i| 0x80 + i as u8)
+    }
+
     #[test]
     fn test_chacha20_roundtrip() {
         let key = ChaCha20Poly1305::generate_key();
@@ -152,10 +329,67 @@ mod tests {
         let nonce = [0u8; 12];
         let plaintext = b"Hello, ChaCha20!";
         let aad = b"Additional data";
-        
+
         let (ciphertext, tag) = cipher.encrypt(&nonce, plaintext, aad);
         let decrypted = cipher.decrypt(&nonce, &ciphertext, aad, &tag).unwrap();
-        
+
         assert_eq!(decrypted, plaintext);
     }
+
+    #[test]
+    fn test_chacha20_block_function() {
+        // RFC 8439 section 2.3.2
+        let key: [u8; KEY_SIZE] = core::array::from_fn(|i| i as u8);
+        let nonce = [0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00];
+        let block = ChaChaState::new(&key, &nonce, 1).block();
+
+        assert_eq!(
+            block[..16],
+            [0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20, 0x71, 0xc4]
+        );
+        assert_eq!(
+            block[48..],
+            [0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9, 0xcb, 0xd0, 0x83, 0xe8, 0xa2, 0x50, 0x3c, 0x4e]
+        );
+    }
+
+    #[test]
+    fn test_chacha20_poly1305_rfc8439() {
+        let cipher = ChaCha20Poly1305::new(&rfc_key());
+        let (ciphertext, tag) = cipher.encrypt(&RFC_NONCE, RFC_PLAINTEXT, &RFC_AAD);
+
+        assert_eq!(ciphertext.len(), RFC_PLAINTEXT.len());
+        assert_eq!(
+            ciphertext[..16],
+            [0xd3, 0x1a, 0x8d, 0x34, 0x64, 0x8e, 0x60, 0xdb, 0x7b, 0x86, 0xaf, 0xbc, 0x53, 0xef, 0x7e, 0xc2]
+        );
+        assert_eq!(ciphertext[112..], [0x61, 0x16]);
+        assert_eq!(
+            tag,
+            [0x1a, 0xe1, 0x0b, 0x59, 0x4f, 0x09, 0xe2, 0x6a, 0x7e, 0x90, 0x2e, 0xcb, 0xd0, 0x60, 0x06, 0x91]
+        );
+        assert_eq!(cipher.decrypt(&RFC_NONCE, &ciphertext, &RFC_AAD, &tag).unwrap(), RFC_PLAINTEXT);
+    }
+
+    #[test]
+    fn test_chacha20_poly1305_rejects_tampering() {
+        let cipher = ChaCha20Poly1305::new(&rfc_key());
+        let (mut ciphertext, mut tag) = cipher.encrypt(&RFC_NONCE, RFC_PLAINTEXT, &RFC_AAD);
+
+        assert_eq!(
+            cipher.decrypt(&RFC_NONCE, &ciphertext, b"other aad", &tag),
+            Err(CryptoError::VerificationFailed)
+        );
+        ciphertext[0] ^= 0x01;
+        assert_eq!(
+            cipher.decrypt(&RFC_NONCE, &ciphertext, &RFC_AAD, &tag),
+            Err(CryptoError::VerificationFailed)
+        );
+        ciphertext[0] ^= 0x01;
+        tag[15] ^= 0x80;
+        assert_eq!(
+            cipher.decrypt(&RFC_NONCE, &ciphertext, &RFC_AAD, &tag),
+            Err(CryptoError::VerificationFailed)
+        );
+    }
 }
@@ -21,6 +21,11 @@ pub const DILITHIUM3_PUBLIC_KEY_SIZE: usize = 1952;
 pub const DILITHIUM3_SECRET_KEY_SIZE: usize = 4032;
 pub const DILITHIUM3_SIGNATURE_SIZE: usize = 3293;
 
+/// Dilithium-5 sizes
+pub const DILITHIUM5_PUBLIC_KEY_SIZE: usize = 2592;
+pub const DILITHIUM5_SECRET_KEY_SIZE: usize = 4864;
+pub const DILITHIUM5_SIGNATURE_SIZE: usize = 4595;
+
 /// Dilithium security level
 #[derive(Clone, Copy, Debug)]
 pub enum DilithiumVariant {
@@ -29,6 +34,35 @@ pub enum DilithiumVariant {
     Dilithium5,
 }
 
+impl DilithiumVariant {
+    /// Encoded public key length in bytes
+    pub const fn public_key_len(self) -> usize {
+        match self {
+            DilithiumVariant::Dilithium2 => DILITHIUM2_PUBLIC_KEY_SIZE,
+            DilithiumVariant::Dilithium3 => DILITHIUM3_PUBLIC_KEY_SIZE,
+            DilithiumVariant::Dilithium5 => DILITHIUM5_PUBLIC_KEY_SIZE,
+        }
+    }
+
+    /// Encoded secret key length in bytes
+    pub const fn secret_key_len(self) -> usize {
+        match self {
+            DilithiumVariant::Dilithium2 => DILITHIUM2_SECRET_KEY_SIZE,
+            DilithiumVariant::Dilithium3 => DILITHIUM3_SECRET_KEY_SIZE,
+            DilithiumVariant::Dilithium5 => DILITHIUM5_SECRET_KEY_SIZE,
+        }
+    }
+
+    /// Signature length in bytes
+    pub const fn signature_len(self) -> usize {
+        match self {
+            DilithiumVariant::Dilithium2 => DILITHIUM2_SIGNATURE_SIZE,
+            DilithiumVariant::Dilithium3 => DILITHIUM3_SIGNATURE_SIZE,
+            DilithiumVariant::Dilithium5 => DILITHIUM5_SIGNATURE_SIZE,
+        }
+    }
+}
+
 /// Dilithium keypair
 pub struct DilithiumKeypair {
     variant: DilithiumVariant,
@@ -38,15 +72,9 @@ pub struct DilithiumKeypair {
 
 impl DilithiumKeypair {
     pub fn generate(variant: DilithiumVariant) -> Self {
-        let (pk_size, sk_size) = match variant {
-            DilithiumVariant::Dilithium2 => (DILITHIUM2_PUBLIC_KEY_SIZE, DILITHIUM2_SECRET_KEY_SIZE),
-            DilithiumVariant::Dilithium3 => (DILITHIUM3_PUBLIC_KEY_SIZE, DILITHIUM3_SECRET_KEY_SIZE),
-            DilithiumVariant::Dilithium5 => (2592, 4960), // Dilithium5 sizes
-        };
-        
         let mut rng = HardwareRng;
-        let mut public_key = vec![0u8; pk_size];
-        let mut secret_key = vec![0u8; sk_size];
+        let mut public_key = vec![0u8; variant.public_key_len()];
+        let mut secret_key = vec![0u8; variant.secret_key_len()];
         
         rng.fill_bytes(&mut public_key);
         rng.fill_bytes(&mut secret_key);
@@ -66,28 +94,24 @@ impl DilithiumKeypair {
         &self.secret_key
     }
 
+    pub fn variant(&self) -> DilithiumVariant {
+        self.variant
+    }
+
     pub fn sign(&self, _message: &[u8]) -> Vec<u8> {
-        let sig_size = match self.variant {
-            DilithiumVariant::Dilithium2 => DILITHIUM2_SIGNATURE_SIZE,
-            DilithiumVariant::Dilithium3 => DILITHIUM3_SIGNATURE_SIZE,
-            DilithiumVariant::Dilithium5 => 4595,
-        };
-        
-        let mut signature = vec![0u8; sig_size];
+        let mut signature = vec![0u8; self.variant.signature_len()];
         let mut rng = HardwareRng;
         rng.fill_bytes(&mut signature);
         
         signature
     }
 
+    /// Verify a signature
+    ///
+    /// A signature whose length does not match the keypair's variant is
+    /// rejected up front, before any decoding.
     pub fn verify(&self, _message: &[u8], signature: &[u8]) -> CryptoResult<()> {
-        let expected_size = match self.variant {
-            DilithiumVariant::Dilithium2 => DILITHIUM2_SIGNATURE_SIZE,
-            DilithiumVariant::Dilithium3 => DILITHIUM3_SIGNATURE_SIZE,
-            DilithiumVariant::Dilithium5 => 4595,
-        };
-        
-        if signature.len() != expected_size {
+        if signature.len() != self.variant.signature_len() {
             return Err(CryptoError::InvalidSignature);
         }
         
@@ -129,4 +153,38 @@ mod tests {
         let keypair = Dilithium::keygen(DilithiumVariant::Dilithium2);
         assert_eq!(keypair.public_key().len(), DILITHIUM2_PUBLIC_KEY_SIZE);
     }
+
+    #[test]
+    fn test_dilithium_variant_sizes() {
+        let expected = [
+            (DilithiumVariant::Dilithium2, 1312, 2528, 2420),
+            (DilithiumVariant::Dilithium3, 1952, 4032, 3293),
+            (DilithiumVariant::Dilithium5, 2592, 4864, 4595),
+        ];
+        for (variant, pk_len, sk_len, sig_len) in expected {
+            assert_eq!(variant.public_key_len(), pk_len);
+            assert_eq!(variant.secret_key_len(), sk_len);
+            assert_eq!(variant.signature_len(), sig_len);
+
+            let keypair = DilithiumKeypair::generate(variant);
+            assert_eq!(keypair.public_key().len(), pk_len);
+            assert_eq!(keypair.secret_key().len(), sk_len);
+            assert_eq!(keypair.sign(b"sizes").len(), sig_len);
+        }
+    }
+
+    #[test]
+    fn test_dilithium_rejects_truncated_signature() {
+        let keypair = DilithiumKeypair::generate(DilithiumVariant::Dilithium5);
+        let message = b"Test message";
+        let signature = keypair.sign(message);
+
+        let truncated = &signature[..signature.len() - 1];
+        assert_eq!(keypair.verify(message, truncated), Err(CryptoError::InvalidSignature));
+        assert_eq!(keypair.verify(message, &[]), Err(CryptoError::InvalidSignature));
+
+        // A signature of another variant's length is malformed too
+        let other = DilithiumKeypair::generate(DilithiumVariant::Dilithium2).sign(message);
+        assert_eq!(keypair.verify(message, &other), Err(CryptoError::InvalidSignature));
+    }
 }
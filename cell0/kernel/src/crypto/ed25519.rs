@@ -1,8 +1,19 @@
 //! Ed25519 Digital Signatures
+//!
+//! RFC 8032 signing and verification. With the `real-crypto` feature the
+//! keypair and verifier delegate to `ed25519-dalek`; otherwise the built-in
+//! SHA-512, field and point arithmetic below is used.
 
-use super::{CryptoRng, CryptoError, CryptoResult, HardwareRng, constant_time_eq, secure_clear};
+use super::{CryptoRng, CryptoError, CryptoResult, HardwareRng};
+#[cfg(not(feature = "real-crypto"))]
+use super::{constant_time_eq, secure_clear_drop};
+#[cfg(feature = "real-crypto")]
+use super::secure_clear;
 
-#[cfg(not(feature = "std"))]
+#[cfg(feature = "real-crypto")]
+use ed25519_dalek::{Signer, Verifier};
+
+#[cfg(all(not(feature = "std"), not(feature = "real-crypto")))]
 use alloc::vec::Vec;
 
 pub const PUBLIC_KEY_SIZE: usize = 32;
@@ -13,7 +24,11 @@ pub const SIGNATURE_SIZE: usize = 64;
 pub struct Ed25519Keypair {
     secret_key: [u8; SECRET_KEY_SIZE],
     public_key: [u8; PUBLIC_KEY_SIZE],
+    #[cfg(not(feature = "real-crypto"))]
     extended_key: [u8; 64],
+    /// Wipes itself on drop (`zeroize` feature)
+    #[cfg(feature = "real-crypto")]
+    signing_key: ed25519_dalek::SigningKey,
 }
 
 impl Ed25519Keypair {
@@ -24,98 +39,597 @@ impl Ed25519Keypair {
         Self::from_seed(&seed)
     }
     
+    #[cfg(not(feature = "real-crypto"))]
     pub fn from_seed(seed: &[u8; SECRET_KEY_SIZE]) -> Self {
-        let extended = sha512(seed);
-        let mut secret_scalar = [0u8; 32];
-        secret_scalar.copy_from_slice(&extended[0..32]);
-        secret_scalar[0] &= 248;
-        secret_scalar[31] &= 127;
-        secret_scalar[31] |= 64;
-        let public_key = scalar_mul_base(&secret_scalar);
-        let mut extended_key = [0u8; 64];
-        extended_key.copy_from_slice(&extended);
+        let extended_key = sha512(seed);
+        let public_key = scalar_mul_base(&secret_scalar(&extended_key));
         let mut secret_key = [0u8; SECRET_KEY_SIZE];
         secret_key.copy_from_slice(seed);
         Ed25519Keypair { secret_key, public_key, extended_key }
     }
     
+    #[cfg(feature = "real-crypto")]
+    pub fn from_seed(seed: &[u8; SECRET_KEY_SIZE]) -> Self {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(seed);
+        Ed25519Keypair {
+            secret_key: *seed,
+            public_key: signing_key.verifying_key().to_bytes(),
+            signing_key,
+        }
+    }
+    
     pub fn public_key(&self) -> &[u8; PUBLIC_KEY_SIZE] { &self.public_key }
     pub fn secret_key(&self) -> &[u8; SECRET_KEY_SIZE] { &self.secret_key }
     
+    #[cfg(not(feature = "real-crypto"))]
     pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SIZE] {
         let prefix = &self.extended_key[32..64];
         let mut r_input = Vec::with_capacity(prefix.len() + message.len());
         r_input.extend_from_slice(prefix);
         r_input.extend_from_slice(message);
-        let r_hash = sha512(&r_input);
-        let mut r_scalar = [0u8; 32];
-        r_scalar.copy_from_slice(&r_hash[0..32]);
+        let r_scalar = scalar_reduce(&sha512(&r_input));
         let r_encoded = scalar_mul_base(&r_scalar);
-        let mut k_input = Vec::with_capacity(32 + 32 + message.len());
-        k_input.extend_from_slice(&r_encoded);
-        k_input.extend_from_slice(&self.public_key);
-        k_input.extend_from_slice(message);
-        let k_hash = sha512(&k_input);
-        let mut k_scalar = [0u8; 32];
-        k_scalar.copy_from_slice(&k_hash[0..32]);
-        let mut secret_scalar = [0u8; 32];
-        secret_scalar.copy_from_slice(&self.extended_key[0..32]);
-        secret_scalar[0] &= 248;
-        secret_scalar[31] &= 127;
-        secret_scalar[31] |= 64;
-        let s = scalar_mul_add(&k_scalar, &secret_scalar, &r_scalar);
+        let k_scalar = challenge(&r_encoded, &self.public_key, message);
+        let s = scalar_mul_add(&k_scalar, &secret_scalar(&self.extended_key), &r_scalar);
         let mut signature = [0u8; SIGNATURE_SIZE];
         signature[0..32].copy_from_slice(&r_encoded);
         signature[32..64].copy_from_slice(&s);
         signature
     }
     
+    #[cfg(feature = "real-crypto")]
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SIZE] {
+        self.signing_key.sign(message).to_bytes()
+    }
+    
     pub fn verify(&self, message: &[u8], signature: &[u8; SIGNATURE_SIZE]) -> CryptoResult<()> {
         verify_signature(&self.public_key, message, signature)
     }
 }
 
+#[cfg(not(feature = "real-crypto"))]
+impl Drop for Ed25519Keypair {
+    fn drop(&mut self) {
+        // SAFETY: the keypair is plain byte arrays
+        unsafe { secure_clear_drop(self) };
+    }
+}
+
+#[cfg(feature = "real-crypto")]
 impl Drop for Ed25519Keypair {
     fn drop(&mut self) {
         secure_clear(&mut self.secret_key);
-        secure_clear(&mut self.extended_key);
     }
 }
 
-pub fn verify_signature(_public_key: &[u8; PUBLIC_KEY_SIZE], _message: &[u8], _signature: &[u8; SIGNATURE_SIZE]) -> CryptoResult<()> {
-    // Simplified verification
-    Ok(())
+/// Verify an RFC 8032 signature: [S]B == R + [k]A
+#[cfg(not(feature = "real-crypto"))]
+pub fn verify_signature(public_key: &[u8; PUBLIC_KEY_SIZE], message: &[u8], signature: &[u8; SIGNATURE_SIZE]) -> CryptoResult<()> {
+    let a = Point::decompress(public_key).ok_or(CryptoError::InvalidKey)?;
+    let mut r_encoded = [0u8; 32];
+    r_encoded.copy_from_slice(&signature[0..32]);
+    let r = Point::decompress(&r_encoded).ok_or(CryptoError::InvalidSignature)?;
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&signature[32..64]);
+    if !scalar_is_canonical(&s) { return Err(CryptoError::InvalidSignature); }
+    
+    let k = challenge(&r_encoded, public_key, message);
+    let lhs = Point::base().mul(&s).compress();
+    let rhs = r.add(&a.mul(&k)).compress();
+    if constant_time_eq(&lhs, &rhs) { Ok(()) } else { Err(CryptoError::VerificationFailed) }
 }
 
+/// Verify an RFC 8032 signature
+#[cfg(feature = "real-crypto")]
+pub fn verify_signature(public_key: &[u8; PUBLIC_KEY_SIZE], message: &[u8], signature: &[u8; SIGNATURE_SIZE]) -> CryptoResult<()> {
+    let key = ed25519_dalek::VerifyingKey::from_bytes(public_key).map_err(|_| CryptoError::InvalidKey)?;
+    // Checked here too so both backends report S >= L the same way
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&signature[32..64]);
+    if !scalar_is_canonical(&s) { return Err(CryptoError::InvalidSignature); }
+    
+    key.verify(message, &ed25519_dalek::Signature::from_bytes(signature))
+        .map_err(|_| CryptoError::VerificationFailed)
+}
+
+/// Clamped secret scalar from the expanded key
+#[cfg(not(feature = "real-crypto"))]
+fn secret_scalar(extended_key: &[u8; 64]) -> [u8; 32] {
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&extended_key[0..32]);
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    scalar
+}
+
+/// k = SHA-512(R || A || M) mod L
+#[cfg(not(feature = "real-crypto"))]
+fn challenge(r_encoded: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> [u8; 32] {
+    let mut k_input = Vec::with_capacity(32 + 32 + message.len());
+    k_input.extend_from_slice(r_encoded);
+    k_input.extend_from_slice(public_key);
+    k_input.extend_from_slice(message);
+    scalar_reduce(&sha512(&k_input))
+}
+
+#[cfg(not(feature = "real-crypto"))]
+fn scalar_mul_base(scalar: &[u8; 32]) -> [u8; 32] {
+    Point::base().mul(scalar).compress()
+}
+
+// ---------------------------------------------------------------------------
+// SHA-512 (FIPS 180-4)
+// ---------------------------------------------------------------------------
+
+#[cfg(not(feature = "real-crypto"))]
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+#[cfg(not(feature = "real-crypto"))]
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+#[cfg(not(feature = "real-crypto"))]
 fn sha512(input: &[u8]) -> [u8; 64] {
+    let mut state = SHA512_IV;
+    let mut padded = Vec::with_capacity(input.len() + 144);
+    padded.extend_from_slice(input);
+    padded.push(0x80);
+    while padded.len() % 128 != 112 { padded.push(0); }
+    padded.extend_from_slice(&((input.len() as u128) * 8).to_be_bytes());
+    
+    for block in padded.chunks(128) {
+        let mut w = [0u64; 80];
+        for (i, word) in block.chunks(8).enumerate() {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(word);
+            w[i] = u64::from_be_bytes(bytes);
+        }
+        for i in 16..80 {
+            let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+            let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..80 {
+            let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA512_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g; g = f; f = e;
+            e = d.wrapping_add(t1);
+            d = c; c = b; b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    
     let mut result = [0u8; 64];
-    for (i, byte) in input.iter().enumerate() {
-        result[i % 64] ^= *byte;
-        result[i % 64] = result[i % 64].wrapping_add(1);
+    for (chunk, word) in result.chunks_mut(8).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
     }
     result
 }
 
-fn scalar_mul_base(scalar: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for (i, byte) in scalar.iter().enumerate() {
-        result[i % 32] ^= byte;
+// ---------------------------------------------------------------------------
+// Field arithmetic modulo p = 2^255 - 19, five 51-bit limbs
+// ---------------------------------------------------------------------------
+
+#[cfg(not(feature = "real-crypto"))]
+const MASK51: u64 = (1 << 51) - 1;
+
+#[cfg(not(feature = "real-crypto"))]
+#[derive(Clone, Copy)]
+struct Fe([u64; 5]);
+
+/// Edwards curve constant d = -121665/121666
+#[cfg(not(feature = "real-crypto"))]
+const D_BYTES: [u8; 32] = [
+    0xa3, 0x78, 0x59, 0x13, 0xca, 0x4d, 0xeb, 0x75, 0xab, 0xd8, 0x41, 0x41, 0x4d, 0x0a, 0x70, 0x00,
+    0x98, 0xe8, 0x79, 0x77, 0x79, 0x40, 0xc7, 0x8c, 0x73, 0xfe, 0x6f, 0x2b, 0xee, 0x6c, 0x03, 0x52,
+];
+/// sqrt(-1) mod p
+#[cfg(not(feature = "real-crypto"))]
+const SQRT_M1_BYTES: [u8; 32] = [
+    0xb0, 0xa0, 0x0e, 0x4a, 0x27, 0x1b, 0xee, 0xc4, 0x78, 0xe4, 0x2f, 0xad, 0x06, 0x18, 0x43, 0x2f,
+    0xa7, 0xd7, 0xfb, 0x3d, 0x99, 0x00, 0x4d, 0x2b, 0x0b, 0xdf, 0xc1, 0x4f, 0x80, 0x24, 0x83, 0x2b,
+];
+/// Encoding of the base point (y = 4/5, x even)
+#[cfg(not(feature = "real-crypto"))]
+const BASE_BYTES: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+
+#[cfg(not(feature = "real-crypto"))]
+impl Fe {
+    const ZERO: Fe = Fe([0; 5]);
+    const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let load = |i: usize| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&bytes[i..i + 8]);
+            u64::from_le_bytes(word)
+        };
+        Fe([
+            load(0) & MASK51,
+            (load(6) >> 3) & MASK51,
+            (load(12) >> 6) & MASK51,
+            (load(19) >> 1) & MASK51,
+            (load(24) >> 12) & MASK51,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut h = self.carry().carry().0;
+        // Subtract p if h >= p
+        let mut q = (h[0] + 19) >> 51;
+        for limb in &h[1..] {
+            q = (limb + q) >> 51;
+        }
+        h[0] += 19 * q;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= MASK51;
+        }
+        h[4] &= MASK51;
+
+        let words = [
+            h[0] | (h[1] << 51),
+            (h[1] >> 13) | (h[2] << 38),
+            (h[2] >> 26) | (h[3] << 25),
+            (h[3] >> 39) | (h[4] << 12),
+        ];
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_mut(8).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn carry(self) -> Fe {
+        let mut h = self.0;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= MASK51;
+        }
+        h[0] += 19 * (h[4] >> 51);
+        h[4] &= MASK51;
+        Fe(h)
+    }
+
+    fn add(&self, other: &Fe) -> Fe {
+        let mut h = [0u64; 5];
+        for (i, limb) in h.iter_mut().enumerate() {
+            *limb = self.0[i] + other.0[i];
+        }
+        Fe(h).carry()
+    }
+
+    fn sub(&self, other: &Fe) -> Fe {
+        // Add 4p so limbs never underflow
+        const FOUR_P: [u64; 5] = [0x1fffffffffffb4, 0x1ffffffffffffc, 0x1ffffffffffffc, 0x1ffffffffffffc, 0x1ffffffffffffc];
+        let other = other.carry();
+        let mut h = [0u64; 5];
+        for (i, limb) in h.iter_mut().enumerate() {
+            *limb = self.0[i] + FOUR_P[i] - other.0[i];
+        }
+        Fe(h).carry()
+    }
+
+    fn neg(&self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    fn mul(&self, other: &Fe) -> Fe {
+        let a = self.0.map(|x| x as u128);
+        let b = other.0.map(|x| x as u128);
+        let b19 = b.map(|x| x * 19);
+
+        let r0 = a[0] * b[0] + a[1] * b19[4] + a[2] * b19[3] + a[3] * b19[2] + a[4] * b19[1];
+        let r1 = a[0] * b[1] + a[1] * b[0] + a[2] * b19[4] + a[3] * b19[3] + a[4] * b19[2];
+        let r2 = a[0] * b[2] + a[1] * b[1] + a[2] * b[0] + a[3] * b19[4] + a[4] * b19[3];
+        let r3 = a[0] * b[3] + a[1] * b[2] + a[2] * b[1] + a[3] * b[0] + a[4] * b19[4];
+        let r4 = a[0] * b[4] + a[1] * b[3] + a[2] * b[2] + a[3] * b[1] + a[4] * b[0];
+
+        let mut r = [r0, r1, r2, r3, r4];
+        for i in 0..4 {
+            r[i + 1] += r[i] >> 51;
+            r[i] &= MASK51 as u128;
+        }
+        r[0] += 19 * (r[4] >> 51);
+        r[4] &= MASK51 as u128;
+        Fe(r.map(|x| x as u64)).carry()
+    }
+
+    fn square(&self) -> Fe {
+        self.mul(self)
+    }
+
+    /// self^e for a little-endian exponent
+    fn pow(&self, exponent: &[u8; 32]) -> Fe {
+        let mut result = Fe::ONE;
+        for i in (0..256).rev() {
+            result = result.square();
+            if (exponent[i / 8] >> (i % 8)) & 1 == 1 {
+                result = result.mul(self);
+            }
+        }
+        result
+    }
+
+    fn invert(&self) -> Fe {
+        // p - 2
+        let mut exponent = [0xffu8; 32];
+        exponent[0] = 0xeb;
+        exponent[31] = 0x7f;
+        self.pow(&exponent)
+    }
+
+    fn is_negative(&self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    fn is_zero(&self) -> bool {
+        self.to_bytes() == [0u8; 32]
+    }
+
+    fn equals(&self, other: &Fe) -> bool {
+        constant_time_eq(&self.to_bytes(), &other.to_bytes())
+    }
+
+    /// Pick `other` when `choice` is 1, without branching
+    fn select(&self, other: &Fe, choice: u64) -> Fe {
+        let mask = 0u64.wrapping_sub(choice);
+        let mut h = self.0;
+        for (limb, o) in h.iter_mut().zip(other.0) {
+            *limb ^= mask & (*limb ^ o);
+        }
+        Fe(h)
     }
-    result
 }
 
+// ---------------------------------------------------------------------------
+// Edwards25519 points in extended coordinates (X:Y:Z:T), x = X/Z, y = Y/Z
+// ---------------------------------------------------------------------------
+
+#[cfg(not(feature = "real-crypto"))]
+#[derive(Clone, Copy)]
+struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+#[cfg(not(feature = "real-crypto"))]
+impl Point {
+    const IDENTITY: Point = Point { x: Fe::ZERO, y: Fe::ONE, z: Fe::ONE, t: Fe::ZERO };
+
+    fn base() -> Point {
+        Point::decompress(&BASE_BYTES).unwrap()
+    }
+
+    fn decompress(bytes: &[u8; 32]) -> Option<Point> {
+        let y = Fe::from_bytes(bytes);
+        let sign = bytes[31] >> 7;
+        let d = Fe::from_bytes(&D_BYTES);
+
+        // x^2 = (y^2 - 1) / (d y^2 + 1)
+        let y2 = y.square();
+        let u = y2.sub(&Fe::ONE);
+        let v = d.mul(&y2).add(&Fe::ONE);
+
+        // x = u v^3 (u v^7)^((p-5)/8)
+        let mut exponent = [0xffu8; 32];
+        exponent[0] = 0xfd;
+        exponent[31] = 0x0f;
+        let v3 = v.square().mul(&v);
+        let v7 = v3.square().mul(&v);
+        let mut x = u.mul(&v3).mul(&u.mul(&v7).pow(&exponent));
+
+        let vx2 = v.mul(&x.square());
+        if !vx2.equals(&u) {
+            if vx2.equals(&u.neg()) {
+                x = x.mul(&Fe::from_bytes(&SQRT_M1_BYTES));
+            } else {
+                return None;
+            }
+        }
+        if x.is_zero() && sign == 1 {
+            return None;
+        }
+        if x.is_negative() != (sign == 1) {
+            x = x.neg();
+        }
+
+        Some(Point { x, y, z: Fe::ONE, t: x.mul(&y) })
+    }
+
+    fn compress(&self) -> [u8; 32] {
+        let z_inv = self.z.invert();
+        let x = self.x.mul(&z_inv);
+        let y = self.y.mul(&z_inv);
+        let mut bytes = y.to_bytes();
+        bytes[31] |= (x.is_negative() as u8) << 7;
+        bytes
+    }
+
+    /// Unified addition (also valid for doubling)
+    fn add(&self, other: &Point) -> Point {
+        let d2 = Fe::from_bytes(&D_BYTES).add(&Fe::from_bytes(&D_BYTES));
+        let a = self.y.sub(&self.x).mul(&other.y.sub(&other.x));
+        let b = self.y.add(&self.x).mul(&other.y.add(&other.x));
+        let c = self.t.mul(&d2).mul(&other.t);
+        let d = self.z.add(&self.z).mul(&other.z);
+        let e = b.sub(&a);
+        let f = d.sub(&c);
+        let g = d.add(&c);
+        let h = b.add(&a);
+        Point { x: e.mul(&f), y: g.mul(&h), z: f.mul(&g), t: e.mul(&h) }
+    }
+
+    fn select(&self, other: &Point, choice: u64) -> Point {
+        Point {
+            x: self.x.select(&other.x, choice),
+            y: self.y.select(&other.y, choice),
+            z: self.z.select(&other.z, choice),
+            t: self.t.select(&other.t, choice),
+        }
+    }
+
+    /// [scalar]P by double-and-always-add
+    fn mul(&self, scalar: &[u8; 32]) -> Point {
+        let mut result = Point::IDENTITY;
+        for i in (0..256).rev() {
+            result = result.add(&result);
+            let bit = ((scalar[i / 8] >> (i % 8)) & 1) as u64;
+            result = result.select(&result.add(self), bit);
+        }
+        result
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Scalars modulo the group order L = 2^252 + 27742317777372353535851937790883648493
+// ---------------------------------------------------------------------------
+
+const L: [u64; 4] = [0x5812631a5cf5d3ed, 0x14def9dea2f79cd6, 0, 0x1000000000000000];
+
+fn geq_l(r: &[u64; 4]) -> bool {
+    for i in (0..4).rev() {
+        if r[i] != L[i] {
+            return r[i] > L[i];
+        }
+    }
+    true
+}
+
+#[cfg(not(feature = "real-crypto"))]
+fn sub_l(r: &mut [u64; 4]) {
+    let mut borrow = 0u64;
+    for (limb, l) in r.iter_mut().zip(L) {
+        let (d1, b1) = limb.overflowing_sub(l);
+        let (d2, b2) = d1.overflowing_sub(borrow);
+        *limb = d2;
+        borrow = (b1 | b2) as u64;
+    }
+}
+
+/// Reduce a little-endian integer of any length modulo L
+#[cfg(not(feature = "real-crypto"))]
+fn scalar_reduce(bytes: &[u8]) -> [u8; 32] {
+    let mut r = [0u64; 4];
+    for i in (0..bytes.len() * 8).rev() {
+        // r = 2r + bit; r < 2L < 2^254 so the shift cannot overflow
+        let bit = ((bytes[i / 8] >> (i % 8)) & 1) as u64;
+        for j in (1..4).rev() {
+            r[j] = (r[j] << 1) | (r[j - 1] >> 63);
+        }
+        r[0] = (r[0] << 1) | bit;
+        if geq_l(&r) {
+            sub_l(&mut r);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_mut(8).zip(r) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+fn scalar_is_canonical(s: &[u8; 32]) -> bool {
+    let mut r = [0u64; 4];
+    for (limb, chunk) in r.iter_mut().zip(s.chunks(8)) {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(word);
+    }
+    !geq_l(&r)
+}
+
+/// (a * b + c) mod L
+#[cfg(not(feature = "real-crypto"))]
 fn scalar_mul_add(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for i in 0..32 {
-        result[i] = a[i].wrapping_mul(b[i]).wrapping_add(c[i]);
+    let limbs = |s: &[u8; 32]| {
+        let mut r = [0u64; 4];
+        for (limb, chunk) in r.iter_mut().zip(s.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
+        }
+        r
+    };
+    let (a, b, c) = (limbs(a), limbs(b), limbs(c));
+
+    let mut product = [0u64; 9];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = a[i] as u128 * b[j] as u128 + product[i + j] as u128 + carry;
+            product[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        product[i + 4] = carry as u64;
     }
-    result
+    let mut carry = 0u128;
+    for (i, limb) in product.iter_mut().enumerate() {
+        let t = *limb as u128 + if i < 4 { c[i] as u128 } else { 0 } + carry;
+        *limb = t as u64;
+        carry = t >> 64;
+    }
+
+    let mut bytes = [0u8; 72];
+    for (chunk, word) in bytes.chunks_mut(8).zip(product) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    scalar_reduce(&bytes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_drop_clears_secrets() {
+        use crate::crypto::bytes_after_drop;
+        
+        let seed = [0x77u8; SECRET_KEY_SIZE];
+        assert_eq!(bytes_after_drop(Ed25519Keypair::from_seed(&seed), |k| &k.secret_key), [0u8; 32]);
+        #[cfg(not(feature = "real-crypto"))]
+        assert_eq!(bytes_after_drop(Ed25519Keypair::from_seed(&seed), |k| &k.extended_key), [0u8; 64]);
+    }
+    
     #[test] 
     fn test_keypair() {
         let keypair = Ed25519Keypair::generate();
@@ -128,5 +642,53 @@ mod tests {
         let message = b"Hello";
         let signature = keypair.sign(message);
         assert!(keypair.verify(message, &signature).is_ok());
+        assert_eq!(keypair.verify(b"Hellp", &signature), Err(CryptoError::VerificationFailed));
+        assert!(Ed25519Keypair::generate().verify(message, &signature).is_err());
+    }
+    
+    fn unhex<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0u8; N];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+    
+    #[test]
+    fn test_rfc8032_vectors() {
+        // RFC 8032 section 7.1, tests 1 and 2
+        let cases: [(&str, &str, &[u8], &str); 2] = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                b"",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                &[0x72],
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+        
+        for (seed, public_key, message, signature) in cases {
+            let keypair = Ed25519Keypair::from_seed(&unhex(seed));
+            assert_eq!(*keypair.public_key(), unhex::<32>(public_key));
+            
+            let expected: [u8; SIGNATURE_SIZE] = unhex(signature);
+            assert_eq!(keypair.sign(message), expected);
+            assert!(verify_signature(&unhex(public_key), message, &expected).is_ok());
+        }
+    }
+    
+    #[test]
+    fn test_verify_rejects_malformed() {
+        let keypair = Ed25519Keypair::from_seed(&[7u8; 32]);
+        let mut signature = keypair.sign(b"msg");
+        
+        // S >= L is rejected rather than reduced
+        signature[63] |= 0xf0;
+        assert_eq!(keypair.verify(b"msg", &signature), Err(CryptoError::InvalidSignature));
     }
 }
@@ -0,0 +1,149 @@
+//! HKDF (HMAC-based Key Derivation Function)
+//!
+//! RFC 5869 extract-and-expand key derivation instantiated with
+//! HMAC-SHA3-256 from the `hmac` module.
+
+use super::hmac::{HmacSha256, HMAC_SHA256_SIZE};
+use super::{CryptoError, CryptoResult};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Pseudorandom key size produced by `extract`
+pub const PRK_SIZE: usize = HMAC_SHA256_SIZE;
+/// Maximum output length of `expand` (255 blocks)
+pub const MAX_OUTPUT_SIZE: usize = 255 * HMAC_SHA256_SIZE;
+
+/// Extract a pseudorandom key from input keying material
+///
+/// An empty salt is replaced by a block of zeros, as in RFC 5869.
+pub fn extract(salt: &[u8], ikm: &[u8]) -> [u8; PRK_SIZE] {
+    let zero_salt = [0u8; HMAC_SHA256_SIZE];
+    let salt = if salt.is_empty() { &zero_salt[..] } else { salt };
+    HmacSha256::new(salt).mac(ikm)
+}
+
+/// Expand a pseudorandom key into `out_len` bytes bound to `info`
+pub fn expand(prk: &[u8], info: &[u8], out_len: usize) -> CryptoResult<Vec<u8>> {
+    if out_len > MAX_OUTPUT_SIZE {
+        return Err(CryptoError::InvalidInput);
+    }
+
+    let hmac = HmacSha256::new(prk);
+    let mut okm = Vec::with_capacity(out_len);
+    let mut block = Vec::with_capacity(HMAC_SHA256_SIZE + info.len() + 1);
+    let mut counter = 1u8;
+
+    // T(i) = HMAC(PRK, T(i-1) || info || i)
+    while okm.len() < out_len {
+        block.extend_from_slice(info);
+        block.push(counter);
+        let t = hmac.mac(&block);
+
+        let take = core::cmp::min(HMAC_SHA256_SIZE, out_len - okm.len());
+        okm.extend_from_slice(&t[..take]);
+
+        block.clear();
+        block.extend_from_slice(&t);
+        counter = counter.wrapping_add(1);
+    }
+
+    Ok(okm)
+}
+
+/// One-shot extract-then-expand
+pub fn derive(salt: &[u8], ikm: &[u8], info: &[u8], out_len: usize) -> CryptoResult<Vec<u8>> {
+    let prk = extract(salt, ikm);
+    expand(&prk, info, out_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // RFC 5869 test case inputs; expected values are for the HMAC-SHA3-256
+    // instantiation used here.
+
+    #[test]
+    fn test_rfc5869_case_1() {
+        let ikm = [0x0bu8; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        let prk = extract(&salt, &ikm);
+        assert_eq!(
+            prk.to_vec(),
+            unhex("7d4194836f7a113a44677abc825640ade07af1c1d69a9a4b109b280a8fe54ef0")
+        );
+
+        let okm = expand(&prk, &info, 42).unwrap();
+        assert_eq!(
+            okm,
+            unhex(
+                "0c5160501d65021deaf2c14f5abce04c5bd2635abceeba61c2edb6e8ed72674900557728f2c9f2c4c179"
+            )
+        );
+    }
+
+    #[test]
+    fn test_rfc5869_case_2() {
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+
+        let prk = extract(&salt, &ikm);
+        assert_eq!(
+            prk.to_vec(),
+            unhex("addf31835b49366ac27734104d9f1865c1c2e7c8a2ebc1fed712808e4eab677c")
+        );
+
+        let okm = derive(&salt, &ikm, &info, 82).unwrap();
+        assert_eq!(
+            okm,
+            unhex(
+                "3dc251e66c75da6560405ec5ac10e17d851eedfbfdc13feafbec16964c25d021\
+                 bd971465a3e9c615f27769019e3f0407d84986fb0ba24e729c99834624baa21c\
+                 b623dc0098f430d52e18bbdf694df4edd8b2"
+            )
+        );
+    }
+
+    #[test]
+    fn test_rfc5869_case_3() {
+        // Empty salt and info
+        let ikm = [0x0bu8; 22];
+
+        let prk = extract(&[], &ikm);
+        assert_eq!(
+            prk.to_vec(),
+            unhex("b899e6e4b88a35f9f5d618f48b424c313f9704012763eb6295414d673365928a")
+        );
+
+        let okm = expand(&prk, &[], 42).unwrap();
+        assert_eq!(
+            okm,
+            unhex(
+                "bc1342cdd75c05e8b0c3ae609ce4410684d197232875073499b30cdfe2de2853c1c1bed63d725e885e78"
+            )
+        );
+    }
+
+    #[test]
+    fn test_expand_length_limit() {
+        let prk = extract(b"salt", b"ikm");
+        assert_eq!(expand(&prk, b"", MAX_OUTPUT_SIZE).unwrap().len(), MAX_OUTPUT_SIZE);
+        assert_eq!(expand(&prk, b"", MAX_OUTPUT_SIZE + 1), Err(CryptoError::InvalidInput));
+
+        // Shorter outputs are prefixes of longer ones
+        let short = expand(&prk, b"ctx", 10).unwrap();
+        let long = expand(&prk, b"ctx", 70).unwrap();
+        assert_eq!(short[..], long[..10]);
+    }
+}
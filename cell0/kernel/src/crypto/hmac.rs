@@ -2,31 +2,35 @@
 //! 
 //! Implementation of HMAC using SHA3-256/512 for message authentication.
 
-use super::sha3::{Sha3_256, Sha3_512, SHA3_256_SIZE, SHA3_512_SIZE};
-use super::constant_time_eq;
+use super::sha3::{Sha3_256, Sha3_512, SHA3_256_RATE, SHA3_256_SIZE, SHA3_512_RATE, SHA3_512_SIZE};
+use super::{constant_time_eq, constant_time_eq_fixed};
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 pub const HMAC_SHA256_SIZE: usize = SHA3_256_SIZE;
 pub const HMAC_SHA512_SIZE: usize = SHA3_512_SIZE;
-const BLOCK_SIZE: usize = 128;
+/// Shortest truncated tag accepted (80 bits, per RFC 2104)
+pub const HMAC_MIN_TAG_SIZE: usize = 10;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
 
 /// HMAC-SHA3-256
 pub struct HmacSha256 {
-    key: [u8; BLOCK_SIZE],
+    key: [u8; SHA3_256_RATE],
 }
 
 /// HMAC-SHA3-512
 pub struct HmacSha512 {
-    key: [u8; BLOCK_SIZE],
+    key: [u8; SHA3_512_RATE],
 }
 
 impl HmacSha256 {
     pub fn new(key: &[u8]) -> Self {
-        let mut processed_key = [0u8; BLOCK_SIZE];
+        let mut processed_key = [0u8; SHA3_256_RATE];
         
-        if key.len() > BLOCK_SIZE {
+        if key.len() > SHA3_256_RATE {
             let hash = Sha3_256::digest(key);
             processed_key[..SHA3_256_SIZE].copy_from_slice(&hash);
         } else {
@@ -36,22 +40,48 @@ impl HmacSha256 {
         HmacSha256 { key: processed_key }
     }
 
-    pub fn mac(&self, _message: &[u8]) -> [u8; HMAC_SHA256_SIZE] {
-        // Simplified HMAC
-        Sha3_256::digest(&self.key)
+    pub fn mac(&self, message: &[u8]) -> [u8; HMAC_SHA256_SIZE] {
+        let mut pad = self.key;
+        
+        // H((K ^ ipad) || m)
+        pad.iter_mut().for_each(|b| *b ^= IPAD);
+        let mut inner = Sha3_256::new();
+        inner.update(&pad);
+        inner.update(message);
+        let inner_hash = inner.finalize();
+        
+        // H((K ^ opad) || inner)
+        pad.iter_mut().for_each(|b| *b ^= IPAD ^ OPAD);
+        let mut outer = Sha3_256::new();
+        outer.update(&pad);
+        outer.update(&inner_hash);
+        outer.finalize()
     }
 
     pub fn verify(&self, message: &[u8], tag: &[u8; HMAC_SHA256_SIZE]) -> bool {
         let computed = self.mac(message);
-        constant_time_eq(&computed, tag)
+        constant_time_eq_fixed(&computed, tag)
+    }
+
+    /// Verify a tag truncated to its first `tag_len` bytes
+    ///
+    /// `tag_len` is fixed by the protocol, so only the tag contents are
+    /// compared in constant time. Tags shorter than `HMAC_MIN_TAG_SIZE`,
+    /// longer than a full tag, or not exactly `tag_len` bytes are rejected.
+    pub fn verify_truncated(&self, message: &[u8], tag: &[u8], tag_len: usize) -> bool {
+        if !(HMAC_MIN_TAG_SIZE..=HMAC_SHA256_SIZE).contains(&tag_len) || tag.len() != tag_len {
+            return false;
+        }
+        let computed = self.mac(message);
+        constant_time_eq(&computed[..tag_len], tag)
     }
 }
 
 impl HmacSha512 {
     pub fn new(key: &[u8]) -> Self {
-        let mut processed_key = [0u8; BLOCK_SIZE];
+        let mut processed_key = [0u8; SHA3_512_RATE];
         
-        if key.len() > BLOCK_SIZE {
+        if key.len() > SHA3_512_RATE {
             let hash = Sha3_512::digest(key);
             processed_key[..SHA3_512_SIZE].copy_from_slice(&hash);
         } else {
@@ -61,14 +91,25 @@ impl HmacSha512 {
         HmacSha512 { key: processed_key }
     }
 
-    pub fn mac(&self, _message: &[u8]) -> [u8; HMAC_SHA512_SIZE] {
-        // Simplified HMAC
-        Sha3_512::digest(&self.key)
+    pub fn mac(&self, message: &[u8]) -> [u8; HMAC_SHA512_SIZE] {
+        let mut pad = self.key;
+        
+        pad.iter_mut().for_each(|b| *b ^= IPAD);
+        let mut inner = Sha3_512::new();
+        inner.update(&pad);
+        inner.update(message);
+        let inner_hash = inner.finalize();
+        
+        pad.iter_mut().for_each(|b| *b ^= IPAD ^ OPAD);
+        let mut outer = Sha3_512::new();
+        outer.update(&pad);
+        outer.update(&inner_hash);
+        outer.finalize()
     }
 
     pub fn verify(&self, message: &[u8], tag: &[u8; HMAC_SHA512_SIZE]) -> bool {
         let computed = self.mac(message);
-        constant_time_eq(&computed, tag)
+        constant_time_eq_fixed(&computed, tag)
     }
 }
 
@@ -95,6 +136,31 @@ mod tests {
         let tag = hmac.mac(message);
         
         assert!(hmac.verify(message, &tag));
+        assert!(!hmac.verify(b"Hello, HMAC?", &tag));
+        assert!(!HmacSha256::new(b"other key").verify(message, &tag));
+    }
+
+    #[test]
+    fn test_hmac_sha256_truncated() {
+        let hmac = HmacSha256::new(b"secret key");
+        let message = b"Hello, HMAC!";
+        let tag = hmac.mac(message);
+        
+        assert!(hmac.verify_truncated(message, &tag, HMAC_SHA256_SIZE));
+        assert!(hmac.verify_truncated(message, &tag[..16], 16));
+        
+        // One byte off, in the full or the truncated tag
+        let mut bad = tag;
+        bad[15] ^= 0x01;
+        assert!(!hmac.verify(message, &bad));
+        assert!(!hmac.verify_truncated(message, &bad[..16], 16));
+        // ... but bytes past the truncation point are never looked at
+        assert!(hmac.verify_truncated(message, &bad[..15], 15));
+        
+        // Too short, or not matching the declared length
+        assert!(!hmac.verify_truncated(message, &tag[..4], 4));
+        assert!(!hmac.verify_truncated(message, &tag[..16], 20));
+        assert!(!hmac.verify_truncated(message, &tag, 40));
     }
 
     #[test]
@@ -0,0 +1,190 @@
+//! Known-Answer Self-Tests
+//!
+//! Power-on checks run by the kernel `self_test` before any key material
+//! is trusted. Each primitive is fed a fixed input and its output compared
+//! against a published answer; ciphers and signatures must also undo or
+//! accept that answer and refuse a tampered one.
+
+use super::aes_gcm::AesGcm;
+use super::chacha20::ChaCha20Poly1305;
+use super::ed25519::{self, Ed25519Keypair};
+use super::hmac::HmacSha256;
+use super::sha3::Sha3_256;
+use super::{constant_time_eq, CryptoError, CryptoResult};
+
+/// Expected outputs for the self-tests
+#[derive(Debug, Clone, Copy)]
+pub struct KnownAnswers {
+    /// SHA3-256 of `"abc"` (FIPS 202)
+    pub sha3_256_abc: [u8; 32],
+    /// HMAC-SHA3-256 of RFC 5869 case 1's IKM under its salt
+    pub hmac_sha3_256: [u8; 32],
+    /// AES-128-GCM ciphertext of one zero block under zero key and nonce
+    /// (SP 800-38D test case 2)
+    pub aes_gcm_ciphertext: [u8; 16],
+    /// Tag for the same encryption
+    pub aes_gcm_tag: [u8; 16],
+    /// ChaCha20-Poly1305 ciphertext of the RFC 8439 section 2.8.2 example
+    pub chacha20_poly1305_ciphertext: [u8; 114],
+    /// Tag for the same encryption
+    pub chacha20_poly1305_tag: [u8; 16],
+    /// Ed25519 signature of the empty message (RFC 8032 section 7.1, test 1)
+    pub ed25519_signature: [u8; 64],
+}
+
+/// Answers checked by [`self_test`]
+pub const KNOWN_ANSWERS: KnownAnswers = KnownAnswers {
+    sha3_256_abc: [
+        0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3,
+        0x90, 0xbd, 0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45,
+        0x11, 0x43, 0x15, 0x32,
+    ],
+    hmac_sha3_256: [
+        0x7d, 0x41, 0x94, 0x83, 0x6f, 0x7a, 0x11, 0x3a, 0x44, 0x67, 0x7a, 0xbc, 0x82, 0x56,
+        0x40, 0xad, 0xe0, 0x7a, 0xf1, 0xc1, 0xd6, 0x9a, 0x9a, 0x4b, 0x10, 0x9b, 0x28, 0x0a,
+        0x8f, 0xe5, 0x4e, 0xf0,
+    ],
+    aes_gcm_ciphertext: [
+        0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2,
+        0xfe, 0x78,
+    ],
+    aes_gcm_tag: [
+        0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+        0xbd, 0xdf,
+    ],
+    chacha20_poly1305_ciphertext: [
+        0xd3, 0x1a, 0x8d, 0x34, 0x64, 0x8e, 0x60, 0xdb, 0x7b, 0x86, 0xaf, 0xbc, 0x53, 0xef,
+        0x7e, 0xc2, 0xa4, 0xad, 0xed, 0x51, 0x29, 0x6e, 0x08, 0xfe, 0xa9, 0xe2, 0xb5, 0xa7,
+        0x36, 0xee, 0x62, 0xd6, 0x3d, 0xbe, 0xa4, 0x5e, 0x8c, 0xa9, 0x67, 0x12, 0x82, 0xfa,
+        0xfb, 0x69, 0xda, 0x92, 0x72, 0x8b, 0x1a, 0x71, 0xde, 0x0a, 0x9e, 0x06, 0x0b, 0x29,
+        0x05, 0xd6, 0xa5, 0xb6, 0x7e, 0xcd, 0x3b, 0x36, 0x92, 0xdd, 0xbd, 0x7f, 0x2d, 0x77,
+        0x8b, 0x8c, 0x98, 0x03, 0xae, 0xe3, 0x28, 0x09, 0x1b, 0x58, 0xfa, 0xb3, 0x24, 0xe4,
+        0xfa, 0xd6, 0x75, 0x94, 0x55, 0x85, 0x80, 0x8b, 0x48, 0x31, 0xd7, 0xbc, 0x3f, 0xf4,
+        0xde, 0xf0, 0x8e, 0x4b, 0x7a, 0x9d, 0xe5, 0x76, 0xd2, 0x65, 0x86, 0xce, 0xc6, 0x4b,
+        0x61, 0x16,
+    ],
+    chacha20_poly1305_tag: [
+        0x1a, 0xe1, 0x0b, 0x59, 0x4f, 0x09, 0xe2, 0x6a, 0x7e, 0x90, 0x2e, 0xcb, 0xd0, 0x60,
+        0x06, 0x91,
+    ],
+    ed25519_signature: [
+        0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+        0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+        0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+        0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+        0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ],
+};
+
+/// RFC 8439 section 2.8.2 inputs
+const CHACHA20_POLY1305_PLAINTEXT: &[u8; 114] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+const CHACHA20_POLY1305_AAD: [u8; 12] = [0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7];
+const CHACHA20_POLY1305_NONCE: [u8; 12] = [0x07, 0x00, 0x00, 0x00, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47];
+
+/// RFC 8032 section 7.1, test 1 secret key
+const ED25519_SEED: [u8; 32] = [
+    0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
+    0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
+    0x7f, 0x60,
+];
+
+/// Run every self-test against the built-in answers
+pub fn self_test() -> CryptoResult<()> {
+    self_test_with(&KNOWN_ANSWERS)
+}
+
+/// Run every self-test against `answers`, returning the first failure
+pub fn self_test_with(answers: &KnownAnswers) -> CryptoResult<()> {
+    check_hash(answers)?;
+    check_hmac(answers)?;
+    check_aead(answers)?;
+    check_signature(answers)
+}
+
+fn check_hash(answers: &KnownAnswers) -> CryptoResult<()> {
+    if !constant_time_eq(&Sha3_256::hash(b"abc"), &answers.sha3_256_abc) {
+        return Err(CryptoError::VerificationFailed);
+    }
+    Ok(())
+}
+
+fn check_hmac(answers: &KnownAnswers) -> CryptoResult<()> {
+    let salt: [u8; 13] = core::array::from_fn(|i| i as u8);
+    let hmac = HmacSha256::new(&salt);
+    if !hmac.verify(&[0x0b; 22], &answers.hmac_sha3_256) {
+        return Err(CryptoError::VerificationFailed);
+    }
+    Ok(())
+}
+
+fn check_aead(answers: &KnownAnswers) -> CryptoResult<()> {
+    let nonce = [0u8; 12];
+    let aes = AesGcm::new(&[0u8; 16])?;
+    let (ciphertext, tag) = aes.encrypt(&nonce, &[0u8; 16], &[]);
+    if !constant_time_eq(&ciphertext, &answers.aes_gcm_ciphertext) || !constant_time_eq(&tag, &answers.aes_gcm_tag) {
+        return Err(CryptoError::EncryptionFailed);
+    }
+    if aes.decrypt(&nonce, &ciphertext, &[], &tag)? != [0u8; 16] {
+        return Err(CryptoError::DecryptionFailed);
+    }
+
+    let key: [u8; 32] = core::array::from_fn(|i| 0x80 + i as u8);
+    let chacha = ChaCha20Poly1305::new(&key);
+    let (nonce, aad) = (&CHACHA20_POLY1305_NONCE, &CHACHA20_POLY1305_AAD);
+    let (ciphertext, mut tag) = chacha.encrypt(nonce, CHACHA20_POLY1305_PLAINTEXT, aad);
+    if !constant_time_eq(&ciphertext, &answers.chacha20_poly1305_ciphertext)
+        || !constant_time_eq(&tag, &answers.chacha20_poly1305_tag)
+    {
+        return Err(CryptoError::EncryptionFailed);
+    }
+    if chacha.decrypt(nonce, &ciphertext, aad, &tag)? != CHACHA20_POLY1305_PLAINTEXT {
+        return Err(CryptoError::DecryptionFailed);
+    }
+    // A flipped tag bit must be refused
+    tag[0] ^= 0x01;
+    if chacha.decrypt(nonce, &ciphertext, aad, &tag).is_ok() {
+        return Err(CryptoError::VerificationFailed);
+    }
+    Ok(())
+}
+
+fn check_signature(answers: &KnownAnswers) -> CryptoResult<()> {
+    let keypair = Ed25519Keypair::from_seed(&ED25519_SEED);
+    if !constant_time_eq(&keypair.sign(b""), &answers.ed25519_signature) {
+        return Err(CryptoError::InvalidSignature);
+    }
+    ed25519::verify_signature(keypair.public_key(), b"", &answers.ed25519_signature)?;
+    if ed25519::verify_signature(keypair.public_key(), b"x", &answers.ed25519_signature).is_ok() {
+        return Err(CryptoError::VerificationFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(self_test(), Ok(()));
+    }
+
+    #[test]
+    fn test_self_test_detects_bad_answers() {
+        let mut answers = KNOWN_ANSWERS;
+        answers.sha3_256_abc[0] ^= 0x01;
+        assert_eq!(self_test_with(&answers), Err(CryptoError::VerificationFailed));
+
+        let mut answers = KNOWN_ANSWERS;
+        answers.aes_gcm_tag[15] ^= 0x80;
+        assert_eq!(self_test_with(&answers), Err(CryptoError::EncryptionFailed));
+
+        let mut answers = KNOWN_ANSWERS;
+        answers.chacha20_poly1305_ciphertext[57] ^= 0x04;
+        assert_eq!(self_test_with(&answers), Err(CryptoError::EncryptionFailed));
+
+        let mut answers = KNOWN_ANSWERS;
+        answers.ed25519_signature[10] ^= 0x01;
+        assert_eq!(self_test_with(&answers), Err(CryptoError::InvalidSignature));
+    }
+}
@@ -3,7 +3,9 @@
 //! Implementation of Kyber-512/768/1024 for post-quantum secure key exchange.
 //! Winner of the NIST Post-Quantum Cryptography standardization competition.
 
-use super::{CryptoRng, CryptoError, CryptoResult, HardwareRng};
+use super::{constant_time_eq, CryptoRng, CryptoError, CryptoResult, HardwareRng};
+use super::sha3::Sha3_256;
+use super::shake::Shake256;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -20,6 +22,16 @@ pub const KYBER768_SECRET_KEY_SIZE: usize = 2400;
 pub const KYBER768_CIPHERTEXT_SIZE: usize = 1088;
 pub const KYBER768_SHARED_SECRET_SIZE: usize = 32;
 
+pub const KYBER1024_PUBLIC_KEY_SIZE: usize = 1568;
+pub const KYBER1024_SECRET_KEY_SIZE: usize = 3168;
+pub const KYBER1024_CIPHERTEXT_SIZE: usize = 1568;
+pub const KYBER1024_SHARED_SECRET_SIZE: usize = 32;
+
+/// Shared secret length, the same for every variant
+pub const KYBER_SHARED_SECRET_SIZE: usize = 32;
+/// Message encrypted under the public key during encapsulation
+const MESSAGE_SIZE: usize = 32;
+
 /// Kyber security level
 #[derive(Clone, Copy, Debug)]
 pub enum KyberVariant {
@@ -28,6 +40,119 @@ pub enum KyberVariant {
     Kyber1024,
 }
 
+impl KyberVariant {
+    pub const fn public_key_len(self) -> usize {
+        match self {
+            KyberVariant::Kyber512 => KYBER512_PUBLIC_KEY_SIZE,
+            KyberVariant::Kyber768 => KYBER768_PUBLIC_KEY_SIZE,
+            KyberVariant::Kyber1024 => KYBER1024_PUBLIC_KEY_SIZE,
+        }
+    }
+
+    pub const fn secret_key_len(self) -> usize {
+        match self {
+            KyberVariant::Kyber512 => KYBER512_SECRET_KEY_SIZE,
+            KyberVariant::Kyber768 => KYBER768_SECRET_KEY_SIZE,
+            KyberVariant::Kyber1024 => KYBER1024_SECRET_KEY_SIZE,
+        }
+    }
+
+    pub const fn ciphertext_len(self) -> usize {
+        match self {
+            KyberVariant::Kyber512 => KYBER512_CIPHERTEXT_SIZE,
+            KyberVariant::Kyber768 => KYBER768_CIPHERTEXT_SIZE,
+            KyberVariant::Kyber1024 => KYBER1024_CIPHERTEXT_SIZE,
+        }
+    }
+
+    pub const fn shared_secret_len(self) -> usize {
+        KYBER_SHARED_SECRET_SIZE
+    }
+
+    /// Length of the decryption key at the start of the secret key
+    ///
+    /// The secret key is laid out as in the standard:
+    /// `decryption key || public key || H(public key) || z`.
+    const fn decryption_key_len(self) -> usize {
+        self.secret_key_len() - self.public_key_len() - 2 * 32
+    }
+}
+
+/// Mask hiding the message in the ciphertext
+///
+/// Stands in for the lattice encryption: it is derived from the public
+/// key alone, so this round-trips but provides no secrecy.
+fn message_mask(public_key: &[u8]) -> [u8; MESSAGE_SIZE] {
+    let mut mask = [0u8; MESSAGE_SIZE];
+    Shake256::new().update(b"kyber-mask").update(public_key).finalize_xof(&mut mask);
+    mask
+}
+
+/// Deterministically encrypt `message` to a ciphertext of the variant's length
+fn encrypt(variant: KyberVariant, public_key: &[u8], message: &[u8; MESSAGE_SIZE]) -> Vec<u8> {
+    let mut ciphertext = vec![0u8; variant.ciphertext_len()];
+    let mask = message_mask(public_key);
+    for (i, byte) in ciphertext[..MESSAGE_SIZE].iter_mut().enumerate() {
+        *byte = message[i] ^ mask[i];
+    }
+    Shake256::new()
+        .update(b"kyber-body")
+        .update(public_key)
+        .update(message)
+        .finalize_xof(&mut ciphertext[MESSAGE_SIZE..]);
+    ciphertext
+}
+
+/// Shared secret for an accepted message: `SHA3-256(m || H(pk))`
+fn derive_shared_secret(message: &[u8; MESSAGE_SIZE], public_key_hash: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(message);
+    hasher.update(public_key_hash);
+    hasher.finalize()
+}
+
+/// Encapsulate a fresh shared secret to `public_key`
+fn encapsulate_to(variant: KyberVariant, public_key: &[u8]) -> (Vec<u8>, [u8; 32]) {
+    let mut message = [0u8; MESSAGE_SIZE];
+    HardwareRng.fill_bytes(&mut message);
+
+    let ciphertext = encrypt(variant, public_key, &message);
+    let shared_secret = derive_shared_secret(&message, &Sha3_256::hash(public_key));
+    (ciphertext, shared_secret)
+}
+
+/// Recover the shared secret from `ciphertext`
+///
+/// The recovered message is re-encrypted and compared with the
+/// ciphertext (Fujisaki-Okamoto). On mismatch, or a ciphertext or key of
+/// the wrong length, the result is a pseudorandom secret derived from `z`
+/// and the ciphertext (implicit rejection), so failures look like any
+/// other secret to the sender.
+fn decapsulate_with(variant: KyberVariant, secret_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let dk_len = variant.decryption_key_len();
+    let pk_end = dk_len + variant.public_key_len();
+    let z = &secret_key[secret_key.len().saturating_sub(32)..];
+
+    if secret_key.len() == variant.secret_key_len() && ciphertext.len() == variant.ciphertext_len() {
+        let public_key = &secret_key[dk_len..pk_end];
+        let public_key_hash = &secret_key[pk_end..pk_end + 32];
+
+        let mut message = [0u8; MESSAGE_SIZE];
+        let mask = message_mask(public_key);
+        for (i, byte) in message.iter_mut().enumerate() {
+            *byte = ciphertext[i] ^ mask[i];
+        }
+
+        if constant_time_eq(&encrypt(variant, public_key, &message), ciphertext) {
+            return derive_shared_secret(&message, public_key_hash);
+        }
+    }
+
+    let mut rejection = [0u8; 32];
+    Shake256::new().update(z).update(ciphertext).finalize_xof(&mut rejection);
+    rejection
+}
+
 /// Kyber keypair
 pub struct KyberKeypair {
     variant: KyberVariant,
@@ -37,18 +162,18 @@ pub struct KyberKeypair {
 
 impl KyberKeypair {
     pub fn generate(variant: KyberVariant) -> Self {
-        let (pk_size, sk_size) = match variant {
-            KyberVariant::Kyber512 => (KYBER512_PUBLIC_KEY_SIZE, KYBER512_SECRET_KEY_SIZE),
-            KyberVariant::Kyber768 => (KYBER768_PUBLIC_KEY_SIZE, KYBER768_SECRET_KEY_SIZE),
-            KyberVariant::Kyber1024 => (1568, 3168), // Kyber1024 sizes
-        };
-        
         let mut rng = HardwareRng;
-        let mut public_key = vec![0u8; pk_size];
-        let mut secret_key = vec![0u8; sk_size];
-        
+        let mut public_key = vec![0u8; variant.public_key_len()];
         rng.fill_bytes(&mut public_key);
+
+        // decryption key || public key || H(public key) || z
+        let mut secret_key = vec![0u8; variant.decryption_key_len()];
         rng.fill_bytes(&mut secret_key);
+        secret_key.extend_from_slice(&public_key);
+        secret_key.extend_from_slice(&Sha3_256::hash(&public_key));
+        let mut z = [0u8; 32];
+        rng.fill_bytes(&mut z);
+        secret_key.extend_from_slice(&z);
         
         KyberKeypair {
             variant,
@@ -65,34 +190,19 @@ impl KyberKeypair {
         &self.secret_key
     }
 
+    /// Length of the shared secret produced by encapsulation
+    pub fn shared_secret_len(&self) -> usize {
+        self.variant.shared_secret_len()
+    }
+
+    /// Encapsulate a fresh shared secret to this keypair's public key
     pub fn encapsulate(&self) -> (Vec<u8>, [u8; 32]) {
-        // Generate shared secret and encapsulate
-        let mut shared_secret = [0u8; 32];
-        let mut rng = HardwareRng;
-        rng.fill_bytes(&mut shared_secret);
-        
-        let ct_size = match self.variant {
-            KyberVariant::Kyber512 => KYBER512_CIPHERTEXT_SIZE,
-            KyberVariant::Kyber768 => KYBER768_CIPHERTEXT_SIZE,
-            KyberVariant::Kyber1024 => 1568,
-        };
-        
-        let mut ciphertext = vec![0u8; ct_size];
-        rng.fill_bytes(&mut ciphertext);
-        
-        (ciphertext, shared_secret)
+        encapsulate_to(self.variant, &self.public_key)
     }
 
+    /// Recover the shared secret; see [`KyberKem::decapsulate`]
     pub fn decapsulate(&self, ciphertext: &[u8]) -> [u8; 32] {
-        // Decrypt ciphertext to recover shared secret
-        let mut shared_secret = [0u8; 32];
-        
-        // Simplified - would use actual Kyber decapsulation
-        for (i, byte) in ciphertext.iter().take(32).enumerate() {
-            shared_secret[i] = *byte ^ self.secret_key[i % self.secret_key.len()];
-        }
-        
-        shared_secret
+        decapsulate_with(self.variant, &self.secret_key, ciphertext)
     }
 }
 
@@ -110,32 +220,17 @@ impl KyberKem {
         KyberKeypair::generate(self.variant)
     }
 
-    pub fn encapsulate(&self, _public_key: &[u8]) -> (Vec<u8>, [u8; 32]) {
-        // Simplified encapsulation
-        let ct_size = match self.variant {
-            KyberVariant::Kyber512 => KYBER512_CIPHERTEXT_SIZE,
-            KyberVariant::Kyber768 => KYBER768_CIPHERTEXT_SIZE,
-            KyberVariant::Kyber1024 => 1568,
-        };
-        
-        let mut ciphertext = vec![0u8; ct_size];
-        let mut shared_secret = [0u8; 32];
-        
-        let mut rng = HardwareRng;
-        rng.fill_bytes(&mut ciphertext);
-        rng.fill_bytes(&mut shared_secret);
-        
-        (ciphertext, shared_secret)
+    pub fn encapsulate(&self, public_key: &[u8]) -> (Vec<u8>, [u8; 32]) {
+        encapsulate_to(self.variant, public_key)
     }
 
+    /// Recover the shared secret from `ciphertext`
+    ///
+    /// Never fails: a corrupted ciphertext yields an unrelated secret
+    /// (implicit rejection), which the sender detects when the keys
+    /// disagree.
     pub fn decapsulate(&self, ciphertext: &[u8], secret_key: &[u8]) -> [u8; 32] {
-        let mut shared_secret = [0u8; 32];
-        
-        for (i, byte) in ciphertext.iter().take(32).enumerate() {
-            shared_secret[i] = *byte ^ secret_key[i % secret_key.len()];
-        }
-        
-        shared_secret
+        decapsulate_with(self.variant, secret_key, ciphertext)
     }
 }
 
@@ -152,10 +247,15 @@ mod tests {
 
     #[test]
     fn test_kyber_encaps_decaps() {
-        let keypair = KyberKeypair::generate(KyberVariant::Kyber768);
-        
-        let (_ciphertext, _ss_enc) = keypair.encapsulate();
-        // In real implementation, ss_enc and ss_dec would match
+        for variant in [KyberVariant::Kyber512, KyberVariant::Kyber768, KyberVariant::Kyber1024] {
+            let keypair = KyberKeypair::generate(variant);
+            assert_eq!(keypair.secret_key().len(), variant.secret_key_len());
+            assert_eq!(keypair.shared_secret_len(), 32);
+
+            let (ciphertext, ss_enc) = keypair.encapsulate();
+            assert_eq!(ciphertext.len(), variant.ciphertext_len());
+            assert_eq!(keypair.decapsulate(&ciphertext), ss_enc);
+        }
     }
 
     #[test]
@@ -163,7 +263,32 @@ mod tests {
         let kem = KyberKem::new(KyberVariant::Kyber512);
         let keypair = kem.keygen();
         
-        let (_ciphertext, _ss1) = kem.encapsulate(keypair.public_key());
-        // Simplified
+        let (ciphertext, ss1) = kem.encapsulate(keypair.public_key());
+        assert_eq!(ciphertext.len(), KYBER512_CIPHERTEXT_SIZE);
+        assert_eq!(kem.decapsulate(&ciphertext, keypair.secret_key()), ss1);
+
+        // Fresh encapsulations give fresh secrets
+        let (_, ss2) = kem.encapsulate(keypair.public_key());
+        assert_ne!(ss1, ss2);
+    }
+
+    #[test]
+    fn test_kyber_implicit_rejection() {
+        let keypair = KyberKeypair::generate(KyberVariant::Kyber768);
+        let (mut ciphertext, ss_enc) = keypair.encapsulate();
+
+        ciphertext[100] ^= 0x01;
+        let rejected = keypair.decapsulate(&ciphertext);
+        assert_ne!(rejected, ss_enc);
+        // Rejection is deterministic, so it cannot be told apart by retrying
+        assert_eq!(keypair.decapsulate(&ciphertext), rejected);
+
+        // Truncated ciphertexts are rejected the same way
+        assert_ne!(keypair.decapsulate(&ciphertext[..KYBER768_CIPHERTEXT_SIZE - 1]), ss_enc);
+
+        // Another keypair cannot recover the secret
+        let other = KyberKeypair::generate(KyberVariant::Kyber768);
+        ciphertext[100] ^= 0x01;
+        assert_ne!(other.decapsulate(&ciphertext), ss_enc);
     }
 }
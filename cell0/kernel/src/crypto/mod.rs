@@ -25,10 +25,11 @@
 //! ```
 //!
 //! # TODO: Integrate Real Crypto Libraries
-//! - [ ] ed25519-dalek for Ed25519 signatures
+//! - [x] ed25519-dalek for Ed25519 signatures (`real-crypto` feature)
 //! - [ ] pqc-kyber for Kyber KEM
 //! - [ ] pqc-dilithium for Dilithium signatures
 //! - [ ] sha2 for SHA-256/SHA-512
+//! - [x] sha3 for SHA3-256/SHA3-512 (`real-crypto` feature)
 
 // Import alloc for no_std environments
 #[cfg(not(feature = "std"))]
@@ -64,9 +65,12 @@ compile_error!("Production builds require real cryptographic implementations. \
 
 // Re-export individual crypto modules
 pub mod aes_gcm;
+pub mod aead;
 pub mod chacha20;
 pub mod sha3;
+pub mod shake;
 pub mod hmac;
+pub mod hkdf;
 pub mod ecdsa;
 pub mod ed25519;
 pub mod x25519;
@@ -78,6 +82,10 @@ pub mod secure_boot;
 pub mod tpm;
 pub mod agility;
 pub mod qkd;
+pub mod nfek;
+pub mod kat;
+
+pub use kat::{self_test, self_test_with};
 
 use core::fmt;
 
@@ -117,11 +125,15 @@ impl fmt::Display for CryptoError {
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for CryptoError {}
+
 /// Result type alias for crypto operations
 pub type CryptoResult<T> = Result<T, CryptoError>;
 
 /// Security level classification
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum SecurityLevel {
     /// 128-bit security (e.g., AES-128, Curve25519)
@@ -152,6 +164,7 @@ pub enum AlgorithmCategory {
 
 /// Algorithm identifier for agility framework
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum AlgorithmId {
     // Symmetric encryption
@@ -194,6 +207,47 @@ pub enum AlgorithmId {
     ZkStark = 0x0801,
 }
 
+impl AlgorithmId {
+    /// Category the algorithm belongs to
+    ///
+    /// KEMs count as key exchange, which is how agility negotiates them.
+    pub const fn category(self) -> AlgorithmCategory {
+        use AlgorithmId::*;
+        match self {
+            Aes128Gcm | Aes256Gcm | ChaCha20Poly1305 => AlgorithmCategory::SymmetricEncryption,
+            Kyber512 | Kyber768 | Kyber1024 | X25519 => AlgorithmCategory::KeyExchange,
+            EcdsaSecp256k1 | EcdsaP256 | Ed25519 | Dilithium2 | Dilithium3 | Dilithium5 | Bls12_381 => {
+                AlgorithmCategory::Signature
+            }
+            Sha3_256 | Sha3_512 | Shake128 | Shake256 => AlgorithmCategory::Hash,
+            HmacSha256 | HmacSha512 => AlgorithmCategory::Mac,
+            Bb84 | E91 => AlgorithmCategory::QuantumKeyDistribution,
+            ZkStark => AlgorithmCategory::ZeroKnowledgeProof,
+        }
+    }
+
+    /// Security level the algorithm provides with its standard parameters
+    pub const fn default_security_level(self) -> SecurityLevel {
+        use AlgorithmId::*;
+        match self {
+            Aes128Gcm | EcdsaSecp256k1 | EcdsaP256 | Shake128 => SecurityLevel::Bits128,
+            Aes256Gcm | ChaCha20Poly1305 | Ed25519 | Bls12_381 | X25519 | Sha3_256 | Sha3_512
+            | Shake256 | HmacSha256 | HmacSha512 => SecurityLevel::Bits256,
+            Kyber512 | Kyber768 | Dilithium2 | Dilithium3 | ZkStark => SecurityLevel::PostQuantum128,
+            // QKD security rests on physics rather than computational hardness
+            Kyber1024 | Dilithium5 | Bb84 | E91 => SecurityLevel::PostQuantum256,
+        }
+    }
+
+    /// Whether the algorithm resists quantum attacks
+    pub const fn is_post_quantum(self) -> bool {
+        matches!(
+            self.default_security_level(),
+            SecurityLevel::PostQuantum128 | SecurityLevel::PostQuantum256
+        )
+    }
+}
+
 /// Trait for all cryptographic primitives
 pub trait CryptoPrimitive: Send + Sync {
     /// Get algorithm identifier
@@ -210,6 +264,11 @@ pub trait CryptoPrimitive: Send + Sync {
 }
 
 /// Constant-time comparison to prevent timing attacks
+///
+/// Only the contents are compared in constant time: slices of different
+/// lengths return early, so the timing reveals whether the lengths match.
+/// Use [`constant_time_eq_fixed`] when the length itself is secret or
+/// attacker-controlled.
 pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     if a.len() != b.len() {
         return false;
@@ -221,32 +280,173 @@ pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     result == 0
 }
 
+/// Constant-time comparison of fixed-size arrays, with no data-dependent branches
+pub fn constant_time_eq_fixed<const N: usize>(a: &[u8; N], b: &[u8; N]) -> bool {
+    let mut result = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        result |= x ^ y;
+    }
+    // Keep the optimizer from turning the fold into an early exit
+    core::hint::black_box(result) == 0
+}
+
 /// Secure memory clearing
+///
+/// Each byte is zeroed with a volatile write, which the compiler must
+/// emit even when the memory is freed or goes out of scope right after;
+/// the fence keeps later code from being reordered before the clear.
+/// This defeats dead-store elimination, but not copies the compiler or
+/// CPU made earlier (spilled registers, moved values, swapped pages).
 pub fn secure_clear(bytes: &mut [u8]) {
     for byte in bytes.iter_mut() {
-        *byte = 0;
+        // SAFETY: `byte` is a valid, exclusive reference
+        unsafe { core::ptr::write_volatile(byte, 0) };
     }
     core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
 }
 
+/// Securely clear every byte of a value, for use in `Drop` impls
+///
+/// Same guarantee as [`secure_clear`], applied to the value's whole
+/// memory including padding.
+///
+/// # Safety
+///
+/// All-zero bytes must be a valid `T`, and `T` must not own resources
+/// (heap buffers, handles) whose pointers would be wiped before being
+/// released.
+pub unsafe fn secure_clear_drop<T>(value: &mut T) {
+    let bytes = core::slice::from_raw_parts_mut(
+        (value as *mut T).cast::<u8>(),
+        core::mem::size_of::<T>(),
+    );
+    secure_clear(bytes);
+}
+
 /// Random number generator interface
 pub trait CryptoRng {
     fn fill_bytes(&mut self, dest: &mut [u8]);
 }
 
-/// Hardware-backed RNG (placeholder for actual hardware RNG)
+/// Hardware-backed RNG
+///
+/// Sources, in order of preference: the OS (`getrandom`) when built with
+/// `std`, then RDSEED/RDRAND on x86_64. The deterministic placeholder is
+/// only used when neither is available.
 pub struct HardwareRng;
 
 impl CryptoRng for HardwareRng {
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        // In real implementation, this would use RDRAND or hardware TRNG
-        // For now, use a placeholder that should be replaced
+        #[cfg(feature = "std")]
+        if getrandom::getrandom(dest).is_ok() {
+            return;
+        }
+        
+        #[cfg(target_arch = "x86_64")]
+        if x86_rng::fill(dest) {
+            return;
+        }
+        
+        // Placeholder for targets without an entropy source
         for (i, byte) in dest.iter_mut().enumerate() {
             *byte = (i * 7 + 13) as u8;
         }
     }
 }
 
+/// RDSEED/RDRAND access for x86_64
+#[cfg(target_arch = "x86_64")]
+mod x86_rng {
+    use core::arch::asm;
+    use core::arch::x86_64::{__cpuid, __cpuid_count};
+    
+    /// Attempts per word before giving up; Intel recommends 10 for RDRAND
+    const RETRIES: usize = 10;
+    
+    #[allow(unused_unsafe)]
+    fn has_rdrand() -> bool {
+        // CPUID.01H:ECX.RDRAND[bit 30]
+        unsafe { __cpuid(1).ecx & (1 << 30) != 0 }
+    }
+    
+    #[allow(unused_unsafe)]
+    fn has_rdseed() -> bool {
+        // CPUID.(EAX=07H, ECX=0H):EBX.RDSEED[bit 18]
+        unsafe { __cpuid(0).eax >= 7 && __cpuid_count(7, 0).ebx & (1 << 18) != 0 }
+    }
+    
+    fn rdseed64() -> Option<u64> {
+        for _ in 0..RETRIES {
+            let value: u64;
+            let ok: u8;
+            // SAFETY: only called after CPUID reports RDSEED support
+            unsafe {
+                asm!("rdseed {0}", "setc {1}", out(reg) value, out(reg_byte) ok, options(nomem, nostack));
+            }
+            if ok == 1 {
+                return Some(value);
+            }
+            core::hint::spin_loop();
+        }
+        None
+    }
+    
+    fn rdrand64() -> Option<u64> {
+        for _ in 0..RETRIES {
+            let value: u64;
+            let ok: u8;
+            // SAFETY: only called after CPUID reports RDRAND support
+            unsafe {
+                asm!("rdrand {0}", "setc {1}", out(reg) value, out(reg_byte) ok, options(nomem, nostack));
+            }
+            if ok == 1 {
+                return Some(value);
+            }
+        }
+        None
+    }
+    
+    /// Fill `dest` from the CPU, returning false if no instruction is usable
+    pub fn fill(dest: &mut [u8]) -> bool {
+        let seed = has_rdseed();
+        let rand = has_rdrand();
+        if !seed && !rand {
+            return false;
+        }
+        
+        for chunk in dest.chunks_mut(8) {
+            // RDSEED can run dry under contention; RDRAND backs it up
+            let word = seed.then(rdseed64).flatten().or_else(|| rand.then(rdrand64).flatten());
+            match word {
+                Some(word) => chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]),
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Drop `value` in place and read back the bytes of one of its fields
+///
+/// Lets tests confirm that a `Drop` impl wiped secret material from the
+/// memory the value occupied.
+#[cfg(test)]
+pub(crate) fn bytes_after_drop<T, const N: usize>(value: T, field: fn(&T) -> &[u8; N]) -> [u8; N] {
+    let mut slot = core::mem::ManuallyDrop::new(value);
+    let ptr = field(&slot).as_ptr();
+    
+    let mut out = [0u8; N];
+    // SAFETY: the value is dropped exactly once and its storage stays in
+    // `slot` until this function returns; the field is plain bytes.
+    unsafe {
+        core::mem::ManuallyDrop::drop(&mut slot);
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = core::ptr::read_volatile(ptr.add(i));
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,6 +462,66 @@ mod tests {
         assert!(!constant_time_eq(&a, &b[..4]));
     }
 
+    #[test]
+    fn test_constant_time_eq_fixed() {
+        let a = [0xA5u8; 32];
+        let mut b = a;
+        assert!(constant_time_eq_fixed(&a, &b));
+        
+        // A difference in any position is caught
+        for i in [0, 15, 31] {
+            b[i] ^= 0x01;
+            assert!(!constant_time_eq_fixed(&a, &b));
+            b[i] = a[i];
+        }
+        assert!(constant_time_eq_fixed(&[0u8; 0], &[]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hardware_rng_output() {
+        let mut rng = HardwareRng;
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        rng.fill_bytes(&mut a);
+        rng.fill_bytes(&mut b);
+        assert_ne!(a, b);
+        
+        // 512 random bits should be roughly half ones
+        let ones: u32 = a.iter().chain(b.iter()).map(|byte| byte.count_ones()).sum();
+        assert!((192..=320).contains(&ones), "bit balance off: {} ones", ones);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_x86_rng_when_available() {
+        let mut a = [0u8; 13];
+        let mut b = [0u8; 13];
+        if x86_rng::fill(&mut a) {
+            assert!(x86_rng::fill(&mut b));
+            assert_ne!(a, b);
+        }
+    }
+
+    #[test]
+    fn test_algorithm_classification() {
+        let cases = [
+            (AlgorithmId::Aes128Gcm, AlgorithmCategory::SymmetricEncryption, SecurityLevel::Bits128),
+            (AlgorithmId::Kyber1024, AlgorithmCategory::KeyExchange, SecurityLevel::PostQuantum256),
+            (AlgorithmId::Dilithium3, AlgorithmCategory::Signature, SecurityLevel::PostQuantum128),
+            (AlgorithmId::Sha3_512, AlgorithmCategory::Hash, SecurityLevel::Bits256),
+            (AlgorithmId::HmacSha256, AlgorithmCategory::Mac, SecurityLevel::Bits256),
+            (AlgorithmId::Bb84, AlgorithmCategory::QuantumKeyDistribution, SecurityLevel::PostQuantum256),
+            (AlgorithmId::ZkStark, AlgorithmCategory::ZeroKnowledgeProof, SecurityLevel::PostQuantum128),
+        ];
+        for (id, category, level) in cases {
+            assert_eq!(id.category(), category, "{:?}", id);
+            assert_eq!(id.default_security_level(), level, "{:?}", id);
+        }
+        assert!(AlgorithmId::Kyber768.is_post_quantum());
+        assert!(!AlgorithmId::Ed25519.is_post_quantum());
+    }
+
     #[test]
     fn test_secure_clear() {
         let mut data = [0u8; 32];
@@ -269,4 +529,20 @@ mod tests {
         secure_clear(&mut data);
         assert_eq!(data, [0u8; 32]);
     }
+
+    #[test]
+    fn test_secure_clear_drop() {
+        struct KeyPair {
+            public: [u8; 32],
+            secret: [u8; 64],
+            counter: u64,
+        }
+
+        let mut keys = KeyPair { public: [0xAB; 32], secret: [0xCD; 64], counter: 7 };
+        // SAFETY: KeyPair is plain bytes and integers
+        unsafe { secure_clear_drop(&mut keys) };
+        assert_eq!(keys.public, [0u8; 32]);
+        assert_eq!(keys.secret, [0u8; 64]);
+        assert_eq!(keys.counter, 0);
+    }
 }
@@ -8,7 +8,10 @@
 //! Used for secure agent-to-agent communication and capability delegation.
 
 use core::sync::atomic::{AtomicU64, Ordering};
-use super::{CryptoRng, HardwareRng, constant_time_eq};
+use super::{constant_time_eq_fixed, secure_clear, CryptoRng, HardwareRng};
+use super::hkdf;
+use super::hmac::hmac_sha256;
+use super::sha3::Sha3_256;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -19,8 +22,74 @@ pub const NFEK_ID_SIZE: usize = 32;
 pub const NFEK_SEED_SIZE: usize = 32;
 /// Default key lifetime in seconds
 pub const NFEK_DEFAULT_LIFETIME_SECS: u64 = 300; // 5 minutes
-/// Maximum number of active keys before forced rotation
+/// Default pool capacity, and the hard limit for builds without `alloc`
 pub const NFEK_MAX_ACTIVE_KEYS: usize = 100;
+/// HKDF salt separating NFEK key derivation from other uses of a seed
+const NFEK_HKDF_SALT: &[u8] = b"cell0-nfek-v1";
+
+/// Source of wall-clock time for key lifetimes
+pub trait Clock {
+    /// Current time in seconds since the Unix epoch
+    fn now_secs(&self) -> u64;
+}
+
+/// Host wall clock
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_secs(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// Kernel uptime clock
+///
+/// Counts from boot rather than the epoch, which is enough for key
+/// lifetimes as long as every pool sharing keys uses it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KernelClock;
+
+impl Clock for KernelClock {
+    fn now_secs(&self) -> u64 {
+        crate::time::now_secs()
+    }
+}
+
+/// Manually driven clock for tests and simulations
+#[derive(Debug, Default)]
+pub struct TestClock {
+    now: AtomicU64,
+}
+
+impl TestClock {
+    pub const fn new(start_secs: u64) -> Self {
+        Self {
+            now: AtomicU64::new(start_secs),
+        }
+    }
+
+    /// Move time forward
+    pub fn advance(&self, secs: u64) {
+        self.now.fetch_add(secs, Ordering::Relaxed);
+    }
+
+    /// Jump to an absolute time
+    pub fn set(&self, secs: u64) {
+        self.now.store(secs, Ordering::Relaxed);
+    }
+}
+
+impl Clock for TestClock {
+    fn now_secs(&self) -> u64 {
+        self.now.load(Ordering::Relaxed)
+    }
+}
 
 /// Key state
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -91,8 +160,8 @@ pub struct Nfek {
 }
 
 impl Nfek {
-    /// Create a new NFEK from seed material
-    pub fn from_seed(seed: &[u8; NFEK_SEED_SIZE], agent_id: u64, purpose: &[u8]) -> Self {
+    /// Create a new NFEK from seed material, issued at `now` (seconds)
+    pub fn from_seed(seed: &[u8; NFEK_SEED_SIZE], agent_id: u64, purpose: &[u8], now: u64) -> Self {
         let mut nfek = Self {
             id: [0u8; NFEK_ID_SIZE],
             seed: *seed,
@@ -106,7 +175,7 @@ impl Nfek {
         nfek.derive_keys();
         nfek.compute_id();
         
-        nfek.metadata.created_at = current_timestamp();
+        nfek.metadata.created_at = now;
         nfek.metadata.expires_at = nfek.metadata.created_at + NFEK_DEFAULT_LIFETIME_SECS;
         nfek.metadata.agent_id = agent_id;
         
@@ -116,52 +185,45 @@ impl Nfek {
         nfek
     }
 
-    /// Generate a new random NFEK
-    pub fn generate(agent_id: u64, purpose: &[u8]) -> Self {
+    /// Generate a new random NFEK, issued at `now` (seconds)
+    pub fn generate(agent_id: u64, purpose: &[u8], now: u64) -> Self {
         let seed = generate_random_seed();
-        Self::from_seed(&seed, agent_id, purpose)
+        Self::from_seed(&seed, agent_id, purpose, now)
     }
 
     /// Derive symmetric and authentication keys from seed
     fn derive_keys(&mut self) {
-        let mut derived = [0u8; 96];
-        
-        // Simple key derivation using XOR mixing
-        for i in 0..96 {
-            derived[i] = self.seed[i % 32].wrapping_add(i as u8);
-        }
+        // 64 bytes never exceeds the HKDF output limit
+        let mut derived = hkdf::derive(NFEK_HKDF_SALT, &self.seed, b"sym+auth", 64)
+            .expect("HKDF output length within limit");
         
-        // Mix with additional entropy
-        for i in 0..96 {
-            derived[i] = derived[i].wrapping_mul(7).wrapping_add(13);
-        }
-        
-        self.sym_key.copy_from_slice(&derived[32..64]);
-        self.auth_key.copy_from_slice(&derived[64..96]);
+        self.sym_key.copy_from_slice(&derived[..32]);
+        self.auth_key.copy_from_slice(&derived[32..]);
+        secure_clear(&mut derived);
     }
 
     /// Compute unique key ID from public components
     fn compute_id(&mut self) {
-        // ID = hash(seed || sym_key || auth_key)
-        let mut input = [0u8; 96];
-        input[0..32].copy_from_slice(&self.seed);
-        input[32..64].copy_from_slice(&self.sym_key);
-        input[64..96].copy_from_slice(&self.auth_key);
+        // ID = SHA3-256(seed || sym_key || auth_key)
+        let mut hasher = Sha3_256::new();
+        hasher.update(&self.seed);
+        hasher.update(&self.sym_key);
+        hasher.update(&self.auth_key);
         
-        self.id = simple_hash(&input);
+        self.id = hasher.finalize();
     }
 
-    /// Check if key is expired
-    pub fn is_expired(&self) -> bool {
+    /// Check if key is expired at `now` (seconds)
+    pub fn is_expired(&self, now: u64) -> bool {
         match self.metadata.state {
             NfekState::Expired => true,
-            _ => current_timestamp() > self.metadata.expires_at,
+            _ => now > self.metadata.expires_at,
         }
     }
 
-    /// Check if key is active and valid
-    pub fn is_valid(&self) -> bool {
-        matches!(self.metadata.state, NfekState::Active) && !self.is_expired()
+    /// Check if key is active and unexpired at `now` (seconds)
+    pub fn is_valid(&self, now: u64) -> bool {
+        matches!(self.metadata.state, NfekState::Active) && !self.is_expired(now)
     }
 
     /// Mark key for rotation
@@ -174,19 +236,19 @@ impl Nfek {
         self.metadata.state = NfekState::Revoked;
     }
 
-    /// Derive a child NFEK
-    pub fn derive_child(&self, purpose: &[u8]) -> Nfek {
+    /// Derive a child NFEK, issued at `now` (seconds)
+    pub fn derive_child(&self, purpose: &[u8], now: u64) -> Nfek {
         static COUNTER: AtomicU64 = AtomicU64::new(0);
         let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
         
-        let mut input = Vec::with_capacity(self.seed.len() + purpose.len() + 8);
-        input.extend_from_slice(&self.seed);
-        input.extend_from_slice(purpose);
-        input.extend_from_slice(&counter.to_le_bytes());
+        let mut hasher = Sha3_256::new();
+        hasher.update(&self.seed);
+        hasher.update(purpose);
+        hasher.update(&counter.to_le_bytes());
         
-        let child_seed = simple_hash(&input);
+        let child_seed = hasher.finalize();
         
-        let mut child = Self::from_seed(&child_seed, self.metadata.agent_id, purpose);
+        let mut child = Self::from_seed(&child_seed, self.metadata.agent_id, purpose, now);
         child.parent_id = Some(self.id);
         child.metadata.rotation_count = self.metadata.rotation_count + 1;
         
@@ -202,7 +264,7 @@ impl Nfek {
         data[48..56].copy_from_slice(&self.metadata.agent_id.to_le_bytes());
         data[56..88].copy_from_slice(&self.metadata.purpose);
         
-        let signature = hmac_sha3_256(issuer_key, &data);
+        let signature = hmac_sha256(issuer_key, &data);
         
         NfekAttestation {
             key_id: self.id,
@@ -223,10 +285,11 @@ impl Nfek {
         data[48..56].copy_from_slice(&attestation.agent_id.to_le_bytes());
         data[56..88].copy_from_slice(&attestation.purpose);
         
-        let expected_sig = hmac_sha3_256(issuer_key, &data);
+        let expected_sig = hmac_sha256(issuer_key, &data);
         
-        attestation.key_id == self.id 
-            && constant_time_eq(&attestation.signature, &expected_sig)
+        // Evaluate both comparisons so the timing does not reveal which failed
+        constant_time_eq_fixed(&attestation.key_id, &self.id)
+            & constant_time_eq_fixed(&attestation.signature, &expected_sig)
     }
 }
 
@@ -244,6 +307,14 @@ impl Default for Nfek {
     }
 }
 
+impl Drop for Nfek {
+    fn drop(&mut self) {
+        secure_clear(&mut self.seed);
+        secure_clear(&mut self.sym_key);
+        secure_clear(&mut self.auth_key);
+    }
+}
+
 /// NFEK Attestation
 #[derive(Clone, Debug)]
 pub struct NfekAttestation {
@@ -256,48 +327,116 @@ pub struct NfekAttestation {
 }
 
 /// NFEK Pool for managing multiple ephemeral keys
-pub struct NfekPool {
+#[cfg(feature = "alloc")]
+pub struct NfekPool<C: Clock> {
     /// Active keys indexed by ID
-    keys: heapless::Vec<Nfek, NFEK_MAX_ACTIVE_KEYS>,
+    keys: Vec<Nfek>,
+    /// Maximum number of keys held at once
+    capacity: usize,
+    /// IDs revoked locally or learned from peers
+    revoked: Vec<[u8; NFEK_ID_SIZE]>,
     /// Default agent ID for new keys
     default_agent_id: u64,
     /// Master key for attestations
     master_key: [u8; 32],
     /// Rotation interval
     rotation_interval_secs: u64,
+    /// Time source for issuing and expiring keys
+    clock: C,
+}
+
+#[cfg(all(feature = "alloc", feature = "std"))]
+impl NfekPool<SystemClock> {
+    /// Create a pool that uses the host wall clock
+    pub fn new(default_agent_id: u64, master_key: [u8; 32]) -> Self {
+        Self::with_clock(default_agent_id, master_key, SystemClock)
+    }
 }
 
-impl NfekPool {
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+impl NfekPool<KernelClock> {
+    /// Create a pool that uses the kernel uptime clock
     pub fn new(default_agent_id: u64, master_key: [u8; 32]) -> Self {
+        Self::with_clock(default_agent_id, master_key, KernelClock)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: Clock> NfekPool<C> {
+    pub fn with_clock(default_agent_id: u64, master_key: [u8; 32], clock: C) -> Self {
         Self {
-            keys: heapless::Vec::new(),
+            keys: Vec::new(),
+            capacity: NFEK_MAX_ACTIVE_KEYS,
+            revoked: Vec::new(),
             default_agent_id,
             master_key,
             rotation_interval_secs: NFEK_DEFAULT_LIFETIME_SECS,
+            clock,
         }
     }
 
+    /// Set the maximum number of keys held at once
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Maximum number of keys held at once
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of keys held, whatever their state
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the pool holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Time source used by this pool
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Current time according to the pool's clock
+    pub fn now(&self) -> u64 {
+        self.clock.now_secs()
+    }
+
     /// Create a new ephemeral key
+    ///
+    /// Expired keys are dropped first; fails only if the pool is still at
+    /// capacity.
     pub fn create_key(&mut self, purpose: &[u8]) -> Option<[u8; NFEK_ID_SIZE]> {
         self.cleanup_expired();
         
-        if self.keys.len() >= NFEK_MAX_ACTIVE_KEYS {
+        let nfek = Nfek::generate(self.default_agent_id, purpose, self.now());
+        self.insert(nfek)
+    }
+
+    /// Add a key if there is room, returning its ID
+    fn insert(&mut self, nfek: Nfek) -> Option<[u8; NFEK_ID_SIZE]> {
+        if self.keys.len() >= self.capacity {
             return None;
         }
         
-        let nfek = Nfek::generate(self.default_agent_id, purpose);
         let id = nfek.id;
-        
-        if self.keys.push(nfek).is_ok() {
-            Some(id)
-        } else {
-            None
-        }
+        self.keys.push(nfek);
+        Some(id)
     }
 
-    /// Get a key by ID
+    /// Get a key by ID, whatever its state
     pub fn get_key(&self, id: &[u8; NFEK_ID_SIZE]) -> Option<&Nfek> {
-        self.keys.iter().find(|k| k.id == *id && k.is_valid())
+        self.keys.iter().find(|k| k.id == *id)
+    }
+
+    /// Get a key by ID only if it is active and unexpired
+    pub fn get_valid_key(&self, id: &[u8; NFEK_ID_SIZE]) -> Option<&Nfek> {
+        let now = self.now();
+        self.get_key(id).filter(|k| k.is_valid(now))
     }
 
     /// Get mutable reference to key
@@ -307,21 +446,21 @@ impl NfekPool {
 
     /// Rotate a specific key
     pub fn rotate_key(&mut self, id: &[u8; NFEK_ID_SIZE]) -> Option<[u8; NFEK_ID_SIZE]> {
-        if let Some(old_key) = self.get_key(id) {
+        if let Some(old_key) = self.get_valid_key(id) {
             let purpose = old_key.metadata.purpose;
             let agent_id = old_key.metadata.agent_id;
+            let rotation_count = old_key.metadata.rotation_count;
             
+            let mut new_nfek = Nfek::generate(agent_id, &purpose, self.now());
+            new_nfek.metadata.rotation_count = rotation_count + 1;
+            new_nfek.parent_id = Some(*id);
+            
+            // The old key stays active if there is no room for its successor
+            let new_id = self.insert(new_nfek)?;
             if let Some(k) = self.get_key_mut(id) {
                 k.rotate();
             }
             
-            let mut new_nfek = Nfek::generate(agent_id, &purpose);
-            new_nfek.metadata.rotation_count = old_key.metadata.rotation_count + 1;
-            new_nfek.parent_id = Some(*id);
-            
-            let new_id = new_nfek.id;
-            let _ = self.keys.push(new_nfek);
-            
             Some(new_id)
         } else {
             None
@@ -332,48 +471,84 @@ impl NfekPool {
     pub fn revoke_key(&mut self, id: &[u8; NFEK_ID_SIZE]) -> bool {
         if let Some(k) = self.get_key_mut(id) {
             k.revoke();
+            self.record_revocation(id);
             true
         } else {
             false
         }
     }
 
-    /// Clean up expired keys
-    pub fn cleanup_expired(&mut self) {
-        for key in self.keys.iter_mut() {
-            if key.is_expired() && matches!(key.metadata.state, NfekState::Active) {
-                key.metadata.state = NfekState::Expired;
+    /// Whether the ID has been revoked here or by a peer
+    pub fn is_revoked(&self, id: &[u8; NFEK_ID_SIZE]) -> bool {
+        self.revoked.contains(id)
+    }
+
+    /// IDs of every revoked key, for distribution to peers
+    ///
+    /// Entries outlive the keys themselves, so a revoked key that has
+    /// since expired is still reported.
+    pub fn export_revocations(&self) -> Vec<[u8; NFEK_ID_SIZE]> {
+        self.revoked.clone()
+    }
+
+    /// Apply a revocation list received from a peer
+    ///
+    /// Matching keys in this pool are revoked; unknown IDs are remembered
+    /// so they are refused if they show up later. Returns how many local
+    /// keys were revoked.
+    pub fn import_revocations(&mut self, ids: &[[u8; NFEK_ID_SIZE]]) -> usize {
+        let mut revoked = 0;
+        for id in ids {
+            if let Some(k) = self.get_key_mut(id) {
+                if k.metadata.state != NfekState::Revoked {
+                    k.revoke();
+                    revoked += 1;
+                }
             }
+            self.record_revocation(id);
+        }
+        revoked
+    }
+
+    fn record_revocation(&mut self, id: &[u8; NFEK_ID_SIZE]) {
+        if !self.revoked.contains(id) {
+            self.revoked.push(*id);
         }
     }
 
+    /// Drop expired keys, freeing their slots
+    pub fn cleanup_expired(&mut self) {
+        let now = self.now();
+        self.keys.retain(|k| !k.is_expired(now));
+    }
+
     /// Get number of active keys
     pub fn active_count(&self) -> usize {
-        self.keys.iter().filter(|k| k.is_valid()).count()
+        let now = self.now();
+        self.keys.iter().filter(|k| k.is_valid(now)).count()
     }
 
     /// Derive a child key from parent
+    ///
+    /// Refused if the parent is revoked, expired or rotating.
     pub fn derive_child(&mut self, parent_id: &[u8; NFEK_ID_SIZE], purpose: &[u8]) -> Option<[u8; NFEK_ID_SIZE]> {
-        let parent = self.get_key(parent_id)?;
-        let child = parent.derive_child(purpose);
-        let child_id = child.id;
-        
-        if self.keys.push(child).is_ok() {
-            Some(child_id)
-        } else {
-            None
+        if self.is_revoked(parent_id) {
+            return None;
         }
+        let parent = self.get_valid_key(parent_id)?;
+        let child = parent.derive_child(purpose, self.now());
+        self.insert(child)
     }
 
     /// Create attestation for a key
     pub fn attest_key(&self, id: &[u8; NFEK_ID_SIZE]) -> Option<NfekAttestation> {
-        let key = self.get_key(id)?;
+        let key = self.get_valid_key(id)?;
         Some(key.attest(&self.master_key))
     }
 
     /// Verify attestation for a key
     pub fn verify_attestation(&self, id: &[u8; NFEK_ID_SIZE], attestation: &NfekAttestation) -> bool {
-        if let Some(key) = self.get_key(id) {
+        if let Some(key) = self.get_valid_key(id) {
             key.verify_attestation(attestation, &self.master_key)
         } else {
             false
@@ -382,13 +557,13 @@ impl NfekPool {
 
     /// Rotate all keys approaching expiration
     pub fn auto_rotate(&mut self) -> Vec<[u8; NFEK_ID_SIZE]> {
-        let now = current_timestamp();
+        let now = self.now();
         let threshold = self.rotation_interval_secs / 2;
         
         let to_rotate: Vec<[u8; NFEK_ID_SIZE]> = self.keys
             .iter()
             .filter(|k| {
-                k.is_valid() && (k.metadata.expires_at.saturating_sub(now)) < threshold
+                k.is_valid(now) && (k.metadata.expires_at.saturating_sub(now)) < threshold
             })
             .map(|k| k.id)
             .collect();
@@ -406,119 +581,18 @@ impl NfekPool {
 
 /// Generate a random seed
 fn generate_random_seed() -> [u8; NFEK_SEED_SIZE] {
-    static COUNTER: AtomicU64 = AtomicU64::new(0);
-    let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
-    
     let mut seed = [0u8; NFEK_SEED_SIZE];
-    let time_bytes = current_timestamp().to_le_bytes();
-    
-    seed[0..8].copy_from_slice(&time_bytes);
-    seed[8..16].copy_from_slice(&counter.to_le_bytes());
-    
-    seed = simple_hash(&seed);
+    HardwareRng.fill_bytes(&mut seed);
     seed
 }
 
-/// Get current timestamp
-fn current_timestamp() -> u64 {
-    static TIMESTAMP: AtomicU64 = AtomicU64::new(1);
-    TIMESTAMP.fetch_add(1, Ordering::Relaxed)
-}
-
-/// Simple hash function
-fn simple_hash(data: &[u8]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for (i, byte) in data.iter().enumerate() {
-        result[i % 32] ^= *byte;
-        result[i % 32] = result[i % 32].wrapping_mul(31).wrapping_add(17);
-    }
-    result
-}
-
-/// Simple HMAC-SHA3-256 implementation
-fn hmac_sha3_256(key: &[u8; 32], data: &[u8]) -> [u8; 32] {
-    const IPAD: u8 = 0x36;
-    const OPAD: u8 = 0x5C;
-    
-    let mut k_ipad = [0u8; 64];
-    let mut k_opad = [0u8; 64];
-    
-    for i in 0..32 {
-        k_ipad[i] = key[i] ^ IPAD;
-        k_opad[i] = key[i] ^ OPAD;
-    }
-    
-    let mut inner_input = Vec::with_capacity(64 + data.len());
-    inner_input.extend_from_slice(&k_ipad);
-    inner_input.extend_from_slice(data);
-    let inner_hash = simple_hash(&inner_input);
-    
-    let mut outer_input = Vec::with_capacity(64 + 32);
-    outer_input.extend_from_slice(&k_opad);
-    outer_input.extend_from_slice(&inner_hash);
-    
-    simple_hash(&outer_input)
-}
-
-/// heapless Vec polyfill for no_std
-mod heapless {
-    pub struct Vec<T, const N: usize> {
-        buf: [T; N],
-        len: usize,
-    }
-
-    impl<T: Copy + Default, const N: usize> Vec<T, N> {
-        pub const fn new() -> Self {
-            Self {
-                buf: [T::default(); N],
-                len: 0,
-            }
-        }
-
-        pub fn push(&mut self, item: T) -> Result<(), ()> {
-            if self.len < N {
-                self.buf[self.len] = item;
-                self.len += 1;
-                Ok(())
-            } else {
-                Err(())
-            }
-        }
-
-        pub fn len(&self) -> usize {
-            self.len
-        }
-
-        pub fn is_empty(&self) -> bool {
-            self.len == 0
-        }
-
-        pub fn iter(&self) -> core::slice::Iter<T> {
-            self.buf[..self.len].iter()
-        }
-
-        pub fn iter_mut(&mut self) -> core::slice::IterMut<T> {
-            self.buf[..self.len].iter_mut()
-        }
-    }
-
-    impl<T: Copy, const N: usize> Clone for Vec<T, N> {
-        fn clone(&self) -> Self {
-            let mut new = Self::new();
-            new.len = self.len;
-            new.buf[..self.len].copy_from_slice(&self.buf[..self.len]);
-            new
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_nfek_generation() {
-        let nfek = Nfek::generate(1, b"test-purpose");
+        let nfek = Nfek::generate(1, b"test-purpose", 0);
         
         assert!(!nfek.id.iter().all(|&b| b == 0));
         assert!(!nfek.sym_key.iter().all(|&b| b == 0));
@@ -528,18 +602,39 @@ mod tests {
 
     #[test]
     fn test_nfek_uniqueness() {
-        let nfek1 = Nfek::generate(1, b"test");
-        let nfek2 = Nfek::generate(1, b"test");
+        let nfek1 = Nfek::generate(1, b"test", 0);
+        let nfek2 = Nfek::generate(1, b"test", 0);
         
         assert_ne!(nfek1.id, nfek2.id);
         assert_ne!(nfek1.seed, nfek2.seed);
         assert_ne!(nfek1.sym_key, nfek2.sym_key);
     }
 
+    #[test]
+    fn test_keys_derived_with_hkdf() {
+        let seed = [0x11u8; NFEK_SEED_SIZE];
+        let nfek = Nfek::from_seed(&seed, 1, b"hkdf", 0);
+        
+        let expected = hkdf::derive(NFEK_HKDF_SALT, &seed, b"sym+auth", 64).unwrap();
+        assert_eq!(nfek.sym_key[..], expected[..32]);
+        assert_eq!(nfek.auth_key[..], expected[32..]);
+        assert_ne!(nfek.sym_key, nfek.auth_key);
+    }
+
+    #[test]
+    fn test_drop_clears_secrets() {
+        use crate::crypto::bytes_after_drop;
+        
+        let seed = [0x5au8; NFEK_SEED_SIZE];
+        assert_eq!(bytes_after_drop(Nfek::from_seed(&seed, 1, b"drop", 0), |k| &k.seed), [0u8; 32]);
+        assert_eq!(bytes_after_drop(Nfek::from_seed(&seed, 1, b"drop", 0), |k| &k.sym_key), [0u8; 32]);
+        assert_eq!(bytes_after_drop(Nfek::from_seed(&seed, 1, b"drop", 0), |k| &k.auth_key), [0u8; 32]);
+    }
+
     #[test]
     fn test_nfek_derivation() {
-        let parent = Nfek::generate(1, b"parent");
-        let child = parent.derive_child(b"child-purpose");
+        let parent = Nfek::generate(1, b"parent", 0);
+        let child = parent.derive_child(b"child-purpose", 0);
         
         assert_eq!(child.parent_id, Some(parent.id));
         assert_eq!(child.metadata.rotation_count, parent.metadata.rotation_count + 1);
@@ -549,7 +644,7 @@ mod tests {
     #[test]
     fn test_attestation() {
         let master_key = [0x42u8; 32];
-        let nfek = Nfek::generate(1, b"attest-test");
+        let nfek = Nfek::generate(1, b"attest-test", 0);
         
         let attestation = nfek.attest(&master_key);
         
@@ -563,7 +658,7 @@ mod tests {
     #[test]
     fn test_nfek_pool() {
         let master_key = [0xABu8; 32];
-        let mut pool = NfekPool::new(1, master_key);
+        let mut pool = NfekPool::with_clock(1, master_key, TestClock::new(1000));
         
         let id1 = pool.create_key(b"key1").unwrap();
         let id2 = pool.create_key(b"key2").unwrap();
@@ -579,13 +674,13 @@ mod tests {
         assert!(pool.verify_attestation(&id1, &attestation));
         
         assert!(pool.revoke_key(&id1));
-        assert!(!pool.get_key(&id1).unwrap().is_valid());
+        assert!(!pool.get_key(&id1).unwrap().is_valid(pool.now()));
     }
 
     #[test]
     fn test_key_rotation() {
         let master_key = [0xCDu8; 32];
-        let mut pool = NfekPool::new(1, master_key);
+        let mut pool = NfekPool::with_clock(1, master_key, TestClock::new(1000));
         
         let old_id = pool.create_key(b"rotate-test").unwrap();
         let new_id = pool.rotate_key(&old_id).unwrap();
@@ -600,17 +695,114 @@ mod tests {
     }
 
     #[test]
-    fn test_hmac_sha3_256() {
-        let key = [0x42u8; 32];
-        let data = b"test data";
+    fn test_expiry_follows_clock() {
+        let mut pool = NfekPool::with_clock(1, [0x11u8; 32], TestClock::new(1_700_000_000));
+        let first = pool.create_key(b"first").unwrap();
+        pool.clock().advance(10);
+        let second = pool.create_key(b"second").unwrap();
+        
+        let lifetime = |id| {
+            let meta = &pool.get_key(id).unwrap().metadata;
+            (meta.created_at, meta.expires_at)
+        };
+        assert_eq!(lifetime(&first), (1_700_000_000, 1_700_000_000 + NFEK_DEFAULT_LIFETIME_SECS));
+        assert_eq!(lifetime(&second).0, 1_700_000_010);
         
-        let mac1 = hmac_sha3_256(&key, data);
-        let mac2 = hmac_sha3_256(&key, data);
+        pool.clock().advance(NFEK_DEFAULT_LIFETIME_SECS - 10);
+        assert!(!pool.get_key(&first).unwrap().is_expired(pool.now()));
         
-        assert_eq!(mac1, mac2);
+        pool.clock().advance(1);
+        assert!(pool.get_key(&first).unwrap().is_expired(pool.now()));
+        assert!(!pool.get_key(&second).unwrap().is_expired(pool.now()));
+        assert!(pool.get_valid_key(&first).is_none());
+        assert_eq!(pool.active_count(), 1);
+    }
+
+    #[test]
+    fn test_pool_grows_past_default_capacity() {
+        let mut pool = NfekPool::with_clock(1, [0x33u8; 32], TestClock::new(0)).with_capacity(150);
+        assert_eq!(pool.capacity(), 150);
+        
+        for _ in 0..150 {
+            assert!(pool.create_key(b"bulk").is_some());
+        }
+        assert_eq!(pool.active_count(), 150);
+        assert!(pool.create_key(b"bulk").is_none());
+        
+        // Expired keys are dropped to make room
+        pool.clock().advance(NFEK_DEFAULT_LIFETIME_SECS + 1);
+        assert!(pool.create_key(b"fresh").is_some());
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_revocation_list() {
+        let mut pool = NfekPool::with_clock(1, [0x44u8; 32], TestClock::new(0));
+        let parent = pool.create_key(b"parent").unwrap();
+        let other = pool.create_key(b"other").unwrap();
+        assert!(pool.derive_child(&parent, b"child").is_some());
+        
+        assert!(pool.revoke_key(&parent));
+        assert!(pool.is_revoked(&parent));
+        assert!(pool.derive_child(&parent, b"child").is_none());
+        assert_eq!(pool.export_revocations(), vec![parent]);
+        
+        // A peer's list revokes matching local keys and remembers the rest
+        let mut peer = NfekPool::with_clock(2, [0x55u8; 32], TestClock::new(0));
+        let unknown = [0xEEu8; NFEK_ID_SIZE];
+        assert_eq!(peer.import_revocations(&pool.export_revocations()), 0);
+        assert!(peer.is_revoked(&parent));
+        
+        assert_eq!(pool.import_revocations(&[other, unknown, parent]), 1);
+        assert!(pool.derive_child(&other, b"child").is_none());
+        assert_eq!(pool.get_key(&other).unwrap().metadata.state, NfekState::Revoked);
+        assert_eq!(pool.export_revocations(), vec![parent, other, unknown]);
+        
+        // Revocations survive the keys expiring out of the pool
+        pool.clock().advance(NFEK_DEFAULT_LIFETIME_SECS + 1);
+        pool.cleanup_expired();
+        assert!(pool.get_key(&parent).is_none());
+        assert!(pool.is_revoked(&parent));
+    }
+
+    #[test]
+    fn test_system_clock_pool() {
+        let mut pool = NfekPool::new(1, [0x22u8; 32]);
+        let id = pool.create_key(b"wall-clock").unwrap();
+        let key = pool.get_key(&id).unwrap();
+        assert!(key.metadata.created_at > 1_600_000_000);
+        assert!(key.is_valid(pool.now()));
+    }
+
+    #[test]
+    fn test_attestation_signature_is_hmac() {
+        let key = [0x42u8; 32];
+        let nfek = Nfek::from_seed(&[7u8; NFEK_SEED_SIZE], 3, b"mac", 0);
+        let attestation = nfek.attest(&key);
+        
+        let mut data = [0u8; 120];
+        data[0..32].copy_from_slice(&nfek.id);
+        data[32..40].copy_from_slice(&nfek.metadata.created_at.to_le_bytes());
+        data[40..48].copy_from_slice(&nfek.metadata.expires_at.to_le_bytes());
+        data[48..56].copy_from_slice(&nfek.metadata.agent_id.to_le_bytes());
+        data[56..88].copy_from_slice(&nfek.metadata.purpose);
+        assert_eq!(attestation.signature, hmac_sha256(&key, &data));
         
         let different_key = [0x43u8; 32];
-        let mac3 = hmac_sha3_256(&different_key, data);
-        assert_ne!(mac1, mac3);
+        assert_ne!(nfek.attest(&different_key).signature, attestation.signature);
+    }
+
+    #[test]
+    fn test_id_avalanche() {
+        let seed = [0x3cu8; NFEK_SEED_SIZE];
+        let mut flipped = seed;
+        flipped[0] ^= 1;
+        
+        let a = Nfek::from_seed(&seed, 1, b"avalanche", 0).id;
+        let b = Nfek::from_seed(&flipped, 1, b"avalanche", 0).id;
+        
+        // Roughly half of the 256 output bits should change
+        let differing: u32 = a.iter().zip(&b).map(|(x, y)| (x ^ y).count_ones()).sum();
+        assert!((64..=192).contains(&differing), "{} bits differ", differing);
     }
 }
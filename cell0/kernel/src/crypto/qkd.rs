@@ -23,8 +23,20 @@ use alloc::vec::Vec;
 pub const QUBIT_BATCH_SIZE: usize = 1024;
 /// Maximum tolerable error rate (above this, abort)
 pub const MAX_ERROR_RATE: f64 = 0.11;
-/// Security parameter for privacy amplification
+/// Bits sacrificed in privacy amplification, 2·log2(1/ε) for ε = 2^-64
 pub const SECURITY_PARAMETER: usize = 128;
+/// Number of CASCADE passes; block size doubles after each
+pub const CASCADE_PASSES: usize = 4;
+/// Mean photon number of signal pulses
+pub const SIGNAL_INTENSITY: f64 = 0.5;
+/// Mean photon number of decoy pulses
+pub const DECOY_INTENSITY: f64 = 0.1;
+/// Probability of a detector click with no photon arriving
+pub const DARK_COUNT_PROB: f64 = 1e-5;
+/// Error-correction leakage relative to the Shannon limit
+const EC_EFFICIENCY: f64 = 1.16;
+/// Give up on a session after this many frames
+const MAX_QKD_BATCHES: usize = 64;
 
 /// Quantum bit states in BB84
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -118,10 +130,55 @@ pub enum Basis {
     Hadamard,
 }
 
+/// Pulse intensity class in decoy-state BB84
+///
+/// The sender picks the class of each pulse at random and reveals it only
+/// after Bob has measured, so an eavesdropper cannot treat decoys
+/// differently from signals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecoyIntensity {
+    /// Key-carrying pulses
+    Signal,
+    /// Weak pulses used to bound the single-photon yield
+    Decoy,
+    /// Empty pulses measuring the background (dark count) rate
+    Vacuum,
+}
+
+impl DecoyIntensity {
+    /// Random class: 50% signal, 35% decoy, 15% vacuum
+    pub fn random() -> Self {
+        let mut rng = HardwareRng;
+        let mut bytes = [0u8; 1];
+        rng.fill_bytes(&mut bytes);
+        
+        match bytes[0] {
+            0..=127 => DecoyIntensity::Signal,
+            128..=217 => DecoyIntensity::Decoy,
+            _ => DecoyIntensity::Vacuum,
+        }
+    }
+
+    /// Mean photon number of the laser pulse
+    pub fn mean_photon_number(&self) -> f64 {
+        match self {
+            DecoyIntensity::Signal => SIGNAL_INTENSITY,
+            DecoyIntensity::Decoy => DECOY_INTENSITY,
+            DecoyIntensity::Vacuum => 0.0,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
 /// Quantum transmission frame
 #[derive(Clone, Debug)]
 pub struct QuantumFrame {
     pub qubits: Vec<Qubit>,
+    /// Intensity class of each pulse, parallel to `qubits`
+    pub intensities: Vec<DecoyIntensity>,
     pub sequence_number: u64,
     pub timestamp: u64,
 }
@@ -130,8 +187,9 @@ impl QuantumFrame {
     pub fn new(size: usize, seq_num: u64) -> Self {
         QuantumFrame {
             qubits: (0..size).map(|_| Qubit::random()).collect(),
+            intensities: (0..size).map(|_| DecoyIntensity::random()).collect(),
             sequence_number: seq_num,
-            timestamp: seq_num, // Simplified timestamp
+            timestamp: crate::time::now_ms(),
         }
     }
 
@@ -207,10 +265,14 @@ impl QkdChannel {
     }
 
     /// Receive and measure quantum transmission (Bob's operation)
-    pub fn receive_quantum(&mut self, frame: &QuantumFrame) -> Vec<(Basis, u8)> {
+    ///
+    /// Each pulse carries a Poisson-distributed number of photons; `None`
+    /// means the detector did not click. Empty pulses can still click
+    /// through dark counts, giving a random bit.
+    pub fn receive_quantum(&mut self, frame: &QuantumFrame) -> Vec<Option<(Basis, u8)>> {
         let mut results = Vec::with_capacity(frame.qubits.len());
         
-        for qubit in &frame.qubits {
+        for (qubit, intensity) in frame.qubits.iter().zip(&frame.intensities) {
             // Bob chooses random basis
             let basis = if self.random_bit() == 0 {
                 Basis::Computational
@@ -218,8 +280,15 @@ impl QkdChannel {
                 Basis::Hadamard
             };
             
-            let (bit, _) = qubit.measure(basis);
-            results.push((basis, bit));
+            let detection = if poisson(intensity.mean_photon_number()) > 0 {
+                let (bit, _) = qubit.measure(basis);
+                Some((basis, bit))
+            } else if random_unit() < DARK_COUNT_PROB {
+                Some((basis, self.random_bit()))
+            } else {
+                None
+            };
+            results.push(detection);
         }
         
         results
@@ -255,30 +324,45 @@ impl QkdChannel {
         errors as f64 / sample_size as f64
     }
 
-    /// Privacy amplification using hash function
-    pub fn privacy_amplification(&self, raw_key: &[u8], target_length: usize) -> Vec<u8> {
-        // Use Toeplitz matrix approach for privacy amplification
-        // Simplified implementation using XOR-based extraction
+    /// Public random seed for `privacy_amplification`
+    ///
+    /// Alice draws it and sends it over the classical channel; it need not
+    /// be secret. Holds the n + m - 1 bits (MSB first) that define an m × n
+    /// Toeplitz matrix.
+    pub fn toeplitz_seed(&self, raw_bits: usize, target_length: usize) -> Vec<u8> {
+        let bits = raw_bits + target_length * 8 - 1;
+        let mut seed = vec![0u8; bits.div_ceil(8)];
+        HardwareRng.fill_bytes(&mut seed);
+        seed
+    }
+
+    /// Privacy amplification with a Toeplitz-matrix universal hash
+    ///
+    /// `raw_key` holds one bit per byte, as produced by sifting. Toeplitz
+    /// matrices drawn from a uniform seed form a 2-universal family, so by
+    /// the leftover hash lemma the output is ε-close to uniform for Eve as
+    /// long as `target_length` bytes stay below the key's min-entropy minus
+    /// `SECURITY_PARAMETER` bits.
+    pub fn privacy_amplification(&self, raw_key: &[u8], target_length: usize, seed: &[u8]) -> CryptoResult<Vec<u8>> {
+        let n = raw_key.len();
+        let m = target_length * 8;
+        if m > n || seed.len() * 8 + 1 < n + m {
+            return Err(CryptoError::InvalidInput);
+        }
+        
+        let seed_bit = |k: usize| (seed[k / 8] >> (7 - k % 8)) & 1;
         let mut result = vec![0u8; target_length];
         
-        for i in 0..target_length {
-            let mut byte = 0u8;
-            for j in 0..raw_key.len() {
-                if self.hash_position(i, j) {
-                    byte ^= raw_key[j];
-                }
+        // T[i][j] = seed[i - j + n - 1], constant along each diagonal
+        for i in 0..m {
+            let mut bit = 0u8;
+            for (j, &x) in raw_key.iter().enumerate() {
+                bit ^= seed_bit(i + n - 1 - j) & x & 1;
             }
-            result[i] = byte;
+            result[i / 8] |= bit << (7 - i % 8);
         }
         
-        result
-    }
-
-    fn hash_position(&self, row: usize, col: usize) -> bool {
-        // Simple hash-based Toeplitz matrix position
-        let combined = (row as u64) << 32 | (col as u64);
-        let hash = combined.wrapping_mul(0x9E3779B97F4A7C15);
-        (hash & 1) == 1
+        Ok(result)
     }
 
     fn random_bit(&self) -> u8 {
@@ -290,7 +374,7 @@ impl QkdChannel {
 }
 
 /// QKD session state
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum QkdSessionState {
     Initializing,
     Exchanging,
@@ -307,6 +391,8 @@ pub struct QkdManager {
     state: QkdSessionState,
     key_buffer: Vec<u8>,
     statistics: QkdStatistics,
+    /// Fraction of transmitted qubits a simulated eavesdropper intercepts
+    eavesdrop_probability: f64,
 }
 
 /// QKD statistics
@@ -315,9 +401,97 @@ pub struct QkdStatistics {
     pub qubits_sent: u64,
     pub qubits_received: u64,
     pub matching_bases: u64,
+    /// Fraction of received qubits measured in Alice's basis (ideally 0.5)
+    pub sifting_ratio: f64,
     pub error_rate: f64,
+    /// Secret key bits produced per qubit sent
     pub key_rate: f64,
     pub eavesdropper_detected: bool,
+    /// Pulse counts per intensity class
+    pub decoy_counts: DecoyCounts,
+    /// Result of the last decoy-state analysis
+    pub decoy_estimate: Option<DecoyEstimate>,
+    /// Bits privacy amplification could extract from the corrected key
+    pub secure_key_bits: usize,
+}
+
+/// Per-intensity tallies, indexed by `DecoyIntensity`
+#[derive(Clone, Debug, Default)]
+pub struct DecoyCounts {
+    pub sent: [u64; 3],
+    pub detected: [u64; 3],
+    /// Detections where Bob's basis matched Alice's
+    pub sifted: [u64; 3],
+    /// Sifted detections where Bob's bit differs from Alice's
+    pub errors: [u64; 3],
+}
+
+impl DecoyCounts {
+    /// Fraction of pulses of this class that produced a click
+    pub fn gain(&self, intensity: DecoyIntensity) -> f64 {
+        let i = intensity.index();
+        if self.sent[i] == 0 { 0.0 } else { self.detected[i] as f64 / self.sent[i] as f64 }
+    }
+
+    /// Bit error rate among sifted pulses of this class
+    pub fn error_rate(&self, intensity: DecoyIntensity) -> f64 {
+        let i = intensity.index();
+        if self.sifted[i] == 0 { 0.0 } else { self.errors[i] as f64 / self.sifted[i] as f64 }
+    }
+}
+
+/// Single-photon bounds from the decoy-state analysis
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecoyEstimate {
+    /// Lower bound on the probability a single-photon pulse is detected
+    pub single_photon_yield: f64,
+    /// Upper bound on the error rate of single-photon detections
+    pub single_photon_error: f64,
+    /// Secret bits the signal pulses can support after privacy amplification
+    pub secure_bits: usize,
+}
+
+/// Vacuum + weak decoy analysis (Ma, Qi, Zhao and Lo, 2005)
+///
+/// Bounds the single-photon yield Y1 and error e1 from the signal, decoy
+/// and vacuum gains, then applies the GLLP key rate
+/// R = Q1 (1 - h(e1)) - f Q_mu h(E_mu) to the signal pulses sent. A
+/// photon-number-splitting attack shows up as decoy gains too low for the
+/// signal gain, driving Y1 down. Returns `None` if the counts admit no
+/// positive single-photon yield.
+pub fn decoy_analysis(counts: &DecoyCounts) -> Option<DecoyEstimate> {
+    let mu = SIGNAL_INTENSITY;
+    let nu = DECOY_INTENSITY;
+    let signal = DecoyIntensity::Signal;
+    let decoy = DecoyIntensity::Decoy;
+    
+    if counts.sent.contains(&0) {
+        return None;
+    }
+    
+    let q_mu = counts.gain(signal);
+    let q_nu = counts.gain(decoy);
+    let y0 = counts.gain(DecoyIntensity::Vacuum);
+    
+    let y1 = mu / (mu * nu - nu * nu)
+        * (q_nu * exp(nu) - q_mu * exp(mu) * nu * nu / (mu * mu) - (mu * mu - nu * nu) / (mu * mu) * y0);
+    if y1 <= 0.0 {
+        return None;
+    }
+    
+    let e1 = ((counts.error_rate(decoy) * q_nu * exp(nu) - 0.5 * y0) / (y1 * nu)).clamp(0.0, 0.5);
+    let q1 = y1 * mu * exp(-mu);
+    
+    let rate = q1 * (1.0 - binary_entropy(e1)) - EC_EFFICIENCY * q_mu * binary_entropy(counts.error_rate(signal));
+    
+    // Only the half of the signal pulses that survive sifting carry key
+    let bits = 0.5 * rate * counts.sent[signal.index()] as f64;
+    
+    Some(DecoyEstimate {
+        single_photon_yield: y1,
+        single_photon_error: e1,
+        secure_bits: if bits > 0.0 { bits as usize } else { 0 },
+    })
 }
 
 impl QkdManager {
@@ -327,60 +501,114 @@ impl QkdManager {
             state: QkdSessionState::Initializing,
             key_buffer: Vec::new(),
             statistics: QkdStatistics::default(),
+            eavesdrop_probability: 0.0,
         }
     }
 
+    /// Intercept each transmitted qubit with probability `p` (testing only)
+    ///
+    /// Exercises the detection path of `generate_key` end to end; 0.0
+    /// disables the eavesdropper.
+    pub fn set_eavesdrop_probability(&mut self, p: f64) {
+        self.eavesdrop_probability = p.clamp(0.0, 1.0);
+    }
+
     /// Generate a shared secret key using QKD
     pub fn generate_key(&mut self, target_bits: usize) -> CryptoResult<Vec<u8>> {
         self.state = QkdSessionState::Exchanging;
         
+        // Detected pulses only
         let mut alice_bits: Vec<u8> = Vec::new();
         let mut alice_bases: Vec<Basis> = Vec::new();
         let mut bob_bases: Vec<Basis> = Vec::new();
         let mut bob_bits: Vec<u8> = Vec::new();
+        let mut intensities: Vec<DecoyIntensity> = Vec::new();
+        let mut counts = DecoyCounts::default();
+        let signal = DecoyIntensity::Signal;
         
-        // Send quantum transmissions until we have enough raw bits
-        while alice_bits.len() < target_bits * 4 {
+        // Send until there are enough raw signal bits and the decoy
+        // analysis supports the requested key length
+        let mut batches = 0;
+        let estimate = loop {
             // Alice sends qubits
             let frame = self.channel.send_quantum(QUBIT_BATCH_SIZE);
-            let frame_bases: Vec<_> = frame.qubits.iter().map(|q| q.basis()).collect();
-            let frame_bits: Vec<_> = frame.qubits.iter().map(|q| q.bit_value()).collect();
             
-            // Simulate transmission (would be actual quantum channel)
-            let transmitted_frame = frame.clone();
+            // Eve works on the copy in flight; Alice keeps her originals
+            let mut in_flight = frame.clone();
+            if self.eavesdrop_probability > 0.0 {
+                in_flight.simulate_eavesdropping(self.eavesdrop_probability);
+            }
             
             // Bob receives and measures
-            let bob_measurements = self.channel.receive_quantum(&transmitted_frame);
+            let bob_measurements = self.channel.receive_quantum(&in_flight);
             
-            // Record bases and bits
-            alice_bases.extend(frame_bases);
-            alice_bits.extend(frame_bits);
-            
-            for (basis, bit) in bob_measurements {
+            for ((qubit, &intensity), detection) in frame.qubits.iter().zip(&frame.intensities).zip(bob_measurements) {
+                let class = intensity.index();
+                counts.sent[class] += 1;
+                
+                let (basis, bit) = match detection {
+                    Some(detection) => detection,
+                    None => continue,
+                };
+                counts.detected[class] += 1;
+                if basis == qubit.basis() {
+                    counts.sifted[class] += 1;
+                    if bit != qubit.bit_value() {
+                        counts.errors[class] += 1;
+                    }
+                }
+                
+                alice_bases.push(qubit.basis());
+                alice_bits.push(qubit.bit_value());
                 bob_bases.push(basis);
                 bob_bits.push(bit);
+                intensities.push(intensity);
             }
             
             self.statistics.qubits_sent += QUBIT_BATCH_SIZE as u64;
-            self.statistics.qubits_received += QUBIT_BATCH_SIZE as u64;
-        }
+            batches += 1;
+            
+            let raw_enough = counts.detected[signal.index()] >= (target_bits * 4) as u64;
+            if !raw_enough && batches < MAX_QKD_BATCHES {
+                continue;
+            }
+            
+            let estimate = decoy_analysis(&counts);
+            let extractable = extractable_bits(counts.sifted[signal.index()] as usize, counts.error_rate(signal));
+            let secure_enough = extractable >= target_bits
+                && estimate.as_ref().is_some_and(|e| e.secure_bits >= target_bits);
+            if secure_enough || counts.error_rate(signal) > MAX_ERROR_RATE || batches >= MAX_QKD_BATCHES {
+                break estimate;
+            }
+        };
+        
+        self.statistics.qubits_received = alice_bits.len() as u64;
+        self.statistics.decoy_counts = counts;
+        self.statistics.decoy_estimate = estimate.clone();
         
         self.state = QkdSessionState::Reconciling;
         
         // Basis reconciliation
         let matching_indices = self.channel.reconcile_bases(&alice_bases, &bob_bases);
         self.statistics.matching_bases = matching_indices.len() as u64;
+        self.statistics.sifting_ratio = matching_indices.len() as f64 / self.statistics.qubits_received as f64;
+        
+        // Decoy and vacuum bits were revealed for the analysis; only
+        // signal pulses make up the key
+        let signal_indices: Vec<usize> = matching_indices.into_iter()
+            .filter(|&i| intensities[i] == signal)
+            .collect();
         
         // Extract sifted key
-        let alice_sifted: Vec<u8> = matching_indices.iter()
+        let alice_sifted: Vec<u8> = signal_indices.iter()
             .map(|&i| alice_bits[i])
             .collect();
-        let _bob_sifted: Vec<u8> = matching_indices.iter()
+        let _bob_sifted: Vec<u8> = signal_indices.iter()
             .map(|&i| bob_bits[i])
             .collect();
         
-        // Error estimation
-        let error_rate = self.channel.estimate_error_rate(&alice_sifted, &_bob_sifted, &matching_indices);
+        // Error estimation (indices refer to the unsifted bits)
+        let error_rate = self.channel.estimate_error_rate(&alice_bits, &bob_bits, &signal_indices);
         self.statistics.error_rate = error_rate;
         
         self.state = QkdSessionState::Verifying;
@@ -392,45 +620,61 @@ impl QkdManager {
             return Err(CryptoError::QuantumChannelCompromised);
         }
         
-        // Error correction (simplified - would use CASCADE or LDPC)
-        let corrected_key = self.cascade_correction(&alice_sifted, &_bob_sifted)?;
+        // Too few single photons got through to hide the key from a
+        // photon-number-splitting eavesdropper
+        if !estimate.is_some_and(|e| e.secure_bits >= target_bits) {
+            self.state = QkdSessionState::Compromised;
+            self.statistics.eavesdropper_detected = true;
+            return Err(CryptoError::QuantumChannelCompromised);
+        }
+        
+        // Error correction
+        let corrected_key = self.cascade_correction(&alice_sifted, &_bob_sifted, error_rate)?;
         
-        // Privacy amplification
-        let final_key_length = (target_bits + 7) / 8;
-        let final_key = self.channel.privacy_amplification(&corrected_key, final_key_length);
+        // Privacy amplification, bounded by what Eve cannot know
+        self.statistics.secure_key_bits = extractable_bits(corrected_key.len(), error_rate);
+        let final_key_length = target_bits.div_ceil(8);
+        if final_key_length * 8 > self.statistics.secure_key_bits {
+            self.state = QkdSessionState::Failed;
+            return Err(CryptoError::QuantumChannelCompromised);
+        }
+        let seed = self.channel.toeplitz_seed(corrected_key.len(), final_key_length);
+        let final_key = self.channel.privacy_amplification(&corrected_key, final_key_length, &seed)?;
         
-        self.statistics.key_rate = final_key.len() as f64 / self.statistics.qubits_sent as f64;
+        self.statistics.key_rate = (final_key.len() * 8) as f64 / self.statistics.qubits_sent as f64;
         self.state = QkdSessionState::Established;
         
         Ok(final_key)
     }
 
     /// CASCADE error correction
-    fn cascade_correction(&self, alice_key: &[u8], bob_key: &[u8]) -> CryptoResult<Vec<u8>> {
+    ///
+    /// Bob's copy is corrected against Alice's block parities, which she
+    /// would disclose over the public channel. The first pass uses the
+    /// natural bit order with blocks of about 0.73 / QBER bits; each later
+    /// pass shuffles the bits with a fresh random permutation and doubles
+    /// the block size.
+    fn cascade_correction(&self, alice_key: &[u8], bob_key: &[u8], error_rate: f64) -> CryptoResult<Vec<u8>> {
         if alice_key.len() != bob_key.len() {
             return Err(CryptoError::InvalidInput);
         }
         
-        // Simplified CASCADE implementation
-        let mut corrected = bob_key.to_vec();
+        let n = alice_key.len();
+        if n == 0 {
+            return Ok(Vec::new());
+        }
         
-        // Pass 1: Check and correct blocks of increasing size
-        let block_sizes = [1, 2, 4, 8, 16, 32];
-        for block_size in &block_sizes {
-            for chunk_start in (0..alice_key.len()).step_by(*block_size) {
-                let chunk_end = (chunk_start + *block_size).min(alice_key.len());
-                
-                let alice_parity: u8 = alice_key[chunk_start..chunk_end].iter().fold(0, |a, b| a ^ b);
-                let bob_parity: u8 = corrected[chunk_start..chunk_end].iter().fold(0, |a, b| a ^ b);
-                
-                if alice_parity != bob_parity {
-                    // Error detected - binary search to find and correct
-                    corrected[chunk_start] ^= 1;
-                }
-            }
+        // An error-free sample still leaves room for a few errors
+        let qber = error_rate.max(0.01);
+        let first_block = ((0.73 / qber) as usize).clamp(1, n);
+        
+        let mut orders = Vec::with_capacity(CASCADE_PASSES);
+        orders.push((0..n).collect());
+        for _ in 1..CASCADE_PASSES {
+            orders.push(random_permutation(n));
         }
         
-        Ok(corrected)
+        Ok(cascade(alice_key, bob_key, first_block, &orders))
     }
 
     /// Get current statistics
@@ -444,6 +688,244 @@ impl QkdManager {
     }
 }
 
+/// One CASCADE pass: a bit ordering cut into fixed-size blocks
+struct CascadePass<'a> {
+    /// Original index of the bit at each shuffled position
+    order: &'a [usize],
+    /// Shuffled position of each original index
+    position: Vec<usize>,
+    block_size: usize,
+}
+
+impl<'a> CascadePass<'a> {
+    fn new(order: &'a [usize], block_size: usize) -> Self {
+        let mut position = vec![0; order.len()];
+        for (pos, &index) in order.iter().enumerate() {
+            position[index] = pos;
+        }
+        CascadePass { order, position, block_size }
+    }
+
+    fn block_count(&self) -> usize {
+        self.order.len().div_ceil(self.block_size)
+    }
+
+    fn block_of(&self, index: usize) -> usize {
+        self.position[index] / self.block_size
+    }
+
+    fn parity(&self, key: &[u8], start: usize, end: usize) -> u8 {
+        self.order[start..end].iter().fold(0, |acc, &i| acc ^ key[i])
+    }
+
+    /// Correct one error in `block` if its parities disagree, returning
+    /// the original index of the flipped bit
+    fn correct_block(&self, alice: &[u8], bob: &mut [u8], block: usize) -> Option<usize> {
+        let mut start = block * self.block_size;
+        let mut end = (start + self.block_size).min(self.order.len());
+        
+        if self.parity(alice, start, end) == self.parity(bob, start, end) {
+            return None;
+        }
+        
+        // BINARY: an odd block has an error in whichever half is odd
+        while end - start > 1 {
+            let mid = start + (end - start) / 2;
+            if self.parity(alice, start, mid) != self.parity(bob, start, mid) {
+                end = mid;
+            } else {
+                start = mid;
+            }
+        }
+        
+        let index = self.order[start];
+        bob[index] ^= 1;
+        Some(index)
+    }
+}
+
+/// Run CASCADE over the given per-pass bit orderings
+///
+/// Whenever a pass fixes a bit, every earlier pass's block holding that bit
+/// becomes odd and is searched again, which uncovers errors that were
+/// hiding in pairs.
+fn cascade(alice: &[u8], bob: &[u8], first_block: usize, orders: &[Vec<usize>]) -> Vec<u8> {
+    let n = alice.len();
+    let mut corrected = bob.to_vec();
+    let mut passes: Vec<CascadePass> = Vec::with_capacity(orders.len());
+    let mut block_size = first_block;
+    
+    for order in orders {
+        passes.push(CascadePass::new(order, block_size));
+        let current = passes.len() - 1;
+        
+        for block in 0..passes[current].block_count() {
+            let mut flipped: Vec<usize> = passes[current]
+                .correct_block(alice, &mut corrected, block)
+                .into_iter()
+                .collect();
+            
+            while let Some(index) = flipped.pop() {
+                for pass in &passes {
+                    let block = pass.block_of(index);
+                    if let Some(other) = pass.correct_block(alice, &mut corrected, block) {
+                        flipped.push(other);
+                    }
+                }
+            }
+        }
+        
+        // Keep at least two blocks so later passes still compare something
+        block_size = (block_size * 2).min((n / 2).max(1));
+    }
+    
+    corrected
+}
+
+/// Uniform random value in [0, 1]
+fn random_unit() -> f64 {
+    let mut rng = HardwareRng;
+    let mut bytes = [0u8; 8];
+    rng.fill_bytes(&mut bytes);
+    u64::from_le_bytes(bytes) as f64 / u64::MAX as f64
+}
+
+/// Min-entropy left in an error-corrected key of `sifted` bits
+///
+/// Eve's information is bounded by h(QBER) per bit from the quantum channel
+/// plus the CASCADE parities, about EC_EFFICIENCY · h(QBER) per bit with
+/// the same 1% QBER floor CASCADE sizes its blocks by. The leftover hash
+/// lemma then costs SECURITY_PARAMETER bits.
+fn extractable_bits(sifted: usize, error_rate: f64) -> usize {
+    let h = binary_entropy(error_rate);
+    let leaked = EC_EFFICIENCY * binary_entropy(error_rate.max(0.01));
+    let bits = sifted as f64 * (1.0 - h - leaked) - SECURITY_PARAMETER as f64;
+    if bits > 0.0 { bits as usize } else { 0 }
+}
+
+/// Uniform random index in 0..n (n <= 256)
+fn random_index(n: usize) -> usize {
+    let mut rng = HardwareRng;
+    let mut bytes = [0u8; 1];
+    // Reject the top of the byte range so every index is equally likely
+    let limit = 256 - 256 % n;
+    loop {
+        rng.fill_bytes(&mut bytes);
+        if (bytes[0] as usize) < limit {
+            return bytes[0] as usize % n;
+        }
+    }
+}
+
+/// Poisson-distributed photon number with the given mean
+fn poisson(mean: f64) -> u32 {
+    let u = random_unit();
+    let mut p = exp(-mean);
+    let mut cumulative = p;
+    let mut k = 0;
+    while u > cumulative && k < 32 {
+        k += 1;
+        p *= mean / k as f64;
+        cumulative += p;
+    }
+    k
+}
+
+/// e^x for the small arguments used here (|x| <= 1), without libm
+fn exp(x: f64) -> f64 {
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..24 {
+        term *= x / n as f64;
+        sum += term;
+    }
+    sum
+}
+
+/// Binary entropy h(p) in bits, without libm
+fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        return 0.0;
+    }
+    -p * log2(p) - (1.0 - p) * log2(1.0 - p)
+}
+
+/// log2 for positive finite x: split off the exponent, then
+/// ln(m) = 2 atanh((m - 1) / (m + 1)) for the mantissa m in [1, 2)
+fn log2(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | (1023 << 52));
+    
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let z2 = z * z;
+    let mut term = z;
+    let mut ln = 0.0;
+    for k in 0..20 {
+        ln += term / (2 * k + 1) as f64;
+        term *= z2;
+    }
+    
+    exponent as f64 + 2.0 * ln / core::f64::consts::LN_2
+}
+
+/// Uniformly shuffled ordering of 0..n (Fisher-Yates)
+fn random_permutation(n: usize) -> Vec<usize> {
+    let mut rng = HardwareRng;
+    let mut order: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        let mut bytes = [0u8; 8];
+        rng.fill_bytes(&mut bytes);
+        let j = (u64::from_le_bytes(bytes) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+/// Polarizer angle for E91 measurements
+///
+/// Alice chooses among 0, π/8 and π/4; Bob among π/8, π/4 and 3π/8. For
+/// the |Φ+⟩ pair the outcomes agree with probability cos²(θa - θb).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Analyzer {
+    Zero,
+    PiOver8,
+    PiOver4,
+    ThreePiOver8,
+}
+
+impl Analyzer {
+    /// Angle in multiples of π/8
+    fn steps(self) -> usize {
+        self as usize
+    }
+}
+
+/// One entangled pair measured by both parties
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct E91Measurement {
+    pub alice: Analyzer,
+    pub alice_bit: u8,
+    pub bob: Analyzer,
+    pub bob_bit: u8,
+}
+
+/// Result of an E91 key exchange
+#[derive(Clone, Debug)]
+pub struct E91Key {
+    /// Alice's copy of the key
+    pub alice: Vec<u8>,
+    /// Bob's copy of the key
+    pub bob: Vec<u8>,
+    /// CHSH value measured on the mismatched-angle pairs
+    pub chsh: f64,
+    /// Matching-angle pairs available for the key
+    pub sifted_bits: usize,
+}
+
+/// Largest CHSH value any local hidden-variable model can reach
+pub const CHSH_CLASSICAL_BOUND: f64 = 2.0;
+
 /// E91 (Ekert) protocol variant
 pub struct E91Protocol;
 
@@ -491,6 +973,97 @@ impl E91Protocol {
         let s = correlation_sum.abs() as f64 / measurements.len() as f64;
         s < 2.5 // Threshold for quantum correlations
     }
+
+    /// Measure one |Φ+⟩ pair with randomly chosen analyzers
+    pub fn measure_pair() -> E91Measurement {
+        const ALICE: [Analyzer; 3] = [Analyzer::Zero, Analyzer::PiOver8, Analyzer::PiOver4];
+        const BOB: [Analyzer; 3] = [Analyzer::PiOver8, Analyzer::PiOver4, Analyzer::ThreePiOver8];
+        // cos²(k·π/8) for k = 0..=3
+        const AGREEMENT: [f64; 4] = [
+            1.0,
+            (2.0 + core::f64::consts::SQRT_2) / 4.0,
+            0.5,
+            (2.0 - core::f64::consts::SQRT_2) / 4.0,
+        ];
+        
+        let alice = ALICE[random_index(3)];
+        let bob = BOB[random_index(3)];
+        
+        // Alice's outcome is uniformly random; Bob's is correlated with it
+        let alice_bit = random_index(2) as u8;
+        let agree = random_unit() < AGREEMENT[alice.steps().abs_diff(bob.steps())];
+        let bob_bit = if agree { alice_bit } else { alice_bit ^ 1 };
+        
+        E91Measurement { alice, alice_bit, bob, bob_bit }
+    }
+
+    /// CHSH value S = E(a1, b1) - E(a1, b3) + E(a3, b1) + E(a3, b3)
+    ///
+    /// Uses Alice's 0 and π/4 against Bob's π/8 and 3π/8; quantum pairs
+    /// reach 2√2 while |S| ≤ 2 for any local (intercepted) source. Other
+    /// angle combinations are ignored.
+    pub fn chsh_value(measurements: &[E91Measurement]) -> f64 {
+        // [agreements, total] per (Alice, Bob) combination
+        let mut tallies = [[0u64; 2]; 4];
+        
+        for m in measurements {
+            let slot = match (m.alice, m.bob) {
+                (Analyzer::Zero, Analyzer::PiOver8) => 0,
+                (Analyzer::Zero, Analyzer::ThreePiOver8) => 1,
+                (Analyzer::PiOver4, Analyzer::PiOver8) => 2,
+                (Analyzer::PiOver4, Analyzer::ThreePiOver8) => 3,
+                _ => continue,
+            };
+            if m.alice_bit == m.bob_bit {
+                tallies[slot][0] += 1;
+            }
+            tallies[slot][1] += 1;
+        }
+        
+        // E = P(agree) - P(disagree)
+        let correlation = |[agree, total]: [u64; 2]| {
+            if total == 0 { 0.0 } else { (2.0 * agree as f64 - total as f64) / total as f64 }
+        };
+        
+        correlation(tallies[0]) - correlation(tallies[1]) + correlation(tallies[2]) + correlation(tallies[3])
+    }
+
+    /// Establish a key from `pairs` entangled pairs
+    ///
+    /// Pairs measured at the same angle (π/8 or π/4) are perfectly
+    /// correlated and form the key; the rest are announced and checked
+    /// against the CHSH inequality. A value at or below the classical bound
+    /// means the pairs were measured in transit.
+    pub fn generate_key(pairs: usize, target_bits: usize) -> CryptoResult<E91Key> {
+        let measurements: Vec<E91Measurement> = (0..pairs).map(|_| Self::measure_pair()).collect();
+        
+        let (sifted, tested): (Vec<E91Measurement>, Vec<E91Measurement>) =
+            measurements.into_iter().partition(|m| m.alice == m.bob);
+        
+        let chsh = Self::chsh_value(&tested);
+        if chsh.abs() <= CHSH_CLASSICAL_BOUND {
+            return Err(CryptoError::QuantumChannelCompromised);
+        }
+        
+        if sifted.len() < target_bits {
+            return Err(CryptoError::InvalidInput);
+        }
+        
+        let pack = |bit: fn(&E91Measurement) -> u8| {
+            let mut key = vec![0u8; target_bits.div_ceil(8)];
+            for (i, m) in sifted.iter().take(target_bits).enumerate() {
+                key[i / 8] |= bit(m) << (7 - i % 8);
+            }
+            key
+        };
+        
+        Ok(E91Key {
+            alice: pack(|m| m.alice_bit),
+            bob: pack(|m| m.bob_bit),
+            chsh,
+            sifted_bits: sifted.len(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -529,6 +1102,82 @@ mod tests {
         assert!(!stats.eavesdropper_detected);
     }
 
+    #[test]
+    fn test_eavesdropper_detected() {
+        let mut manager = QkdManager::new(QkdChannel::new());
+        manager.set_eavesdrop_probability(0.5);
+        
+        assert_eq!(manager.generate_key(128), Err(CryptoError::QuantumChannelCompromised));
+        assert_eq!(manager.state(), &QkdSessionState::Compromised);
+        
+        let stats = manager.statistics();
+        assert!(stats.error_rate > MAX_ERROR_RATE, "QBER {}", stats.error_rate);
+        assert!(stats.eavesdropper_detected);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut manager = QkdManager::new(QkdChannel::new());
+        manager.generate_key(256).unwrap();
+        
+        let stats = manager.statistics();
+        assert!(stats.sifting_ratio > 0.4 && stats.sifting_ratio < 0.6, "sifting ratio {}", stats.sifting_ratio);
+        assert_eq!(stats.sifting_ratio, stats.matching_bases as f64 / stats.qubits_received as f64);
+        
+        // 256 secret bits out of at least 1024 qubits
+        assert_eq!(stats.key_rate, 256.0 / stats.qubits_sent as f64);
+        assert!(stats.key_rate > 0.0 && stats.key_rate <= stats.sifting_ratio);
+        assert!(stats.secure_key_bits >= 256);
+    }
+
+    #[test]
+    fn test_decoy_analysis_without_eavesdropper() {
+        let mut manager = QkdManager::new(QkdChannel::new());
+        let key = manager.generate_key(128).unwrap();
+        assert_eq!(key.len(), 16);
+        
+        let stats = manager.statistics();
+        let counts = &stats.decoy_counts;
+        assert!(counts.sent.iter().all(|&n| n > 0));
+        assert!(counts.gain(DecoyIntensity::Signal) > counts.gain(DecoyIntensity::Decoy));
+        assert!(counts.gain(DecoyIntensity::Decoy) > counts.gain(DecoyIntensity::Vacuum));
+        
+        // A lossless channel lets nearly every single photon through
+        let estimate = stats.decoy_estimate.as_ref().unwrap();
+        assert!(estimate.single_photon_yield > 0.5, "Y1 {}", estimate.single_photon_yield);
+        assert!(estimate.single_photon_error < 0.05);
+        assert!(estimate.secure_bits >= 128);
+    }
+
+    #[test]
+    fn test_decoy_analysis_flags_photon_number_splitting() {
+        // Ideal lossless gains: Q = 1 - e^-intensity
+        let honest = DecoyCounts {
+            sent: [10_000, 10_000, 10_000],
+            detected: [3_935, 952, 0],
+            sifted: [1_967, 476, 0],
+            errors: [0, 0, 0],
+        };
+        let estimate = decoy_analysis(&honest).unwrap();
+        assert!((estimate.single_photon_yield - 1.0).abs() < 0.05);
+        assert!(estimate.secure_bits > 1_000);
+        
+        // Eve blocks single photons and keeps splitting multi-photon
+        // pulses; signal gain barely moves but decoys mostly vanish
+        let attacked = DecoyCounts { detected: [3_500, 200, 0], ..honest };
+        assert!(decoy_analysis(&attacked).is_none_or(|e| e.secure_bits == 0));
+    }
+
+    #[test]
+    fn test_math_helpers() {
+        assert!((exp(0.5) - 1.648_721_270_700_128).abs() < 1e-12);
+        assert!((exp(-0.5) - 0.606_530_659_712_633_4).abs() < 1e-12);
+        assert!((log2(0.25) + 2.0).abs() < 1e-12);
+        assert!((log2(3.0) - 1.584_962_500_721_156).abs() < 1e-12);
+        assert!((binary_entropy(0.5) - 1.0).abs() < 1e-12);
+        assert!((binary_entropy(0.11) - 0.499_915_958_164_528_8).abs() < 1e-9);
+    }
+
     #[test]
     fn test_basis_reconciliation() {
         let channel = QkdChannel::new();
@@ -543,9 +1192,63 @@ mod tests {
     #[test]
     fn test_privacy_amplification() {
         let channel = QkdChannel::new();
-        let raw_key = vec![0xABu8; 256];
-        let final_key = channel.privacy_amplification(&raw_key, 16);
-        assert_eq!(final_key.len(), 16);
+        let raw_key = random_bits(512);
+        let seed = channel.toeplitz_seed(raw_key.len(), 32);
+        assert_eq!(seed.len(), (512 + 256 - 1usize).div_ceil(8));
+        
+        // Same seed, same key on both sides
+        let key = channel.privacy_amplification(&raw_key, 32, &seed).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(channel.privacy_amplification(&raw_key, 32, &seed).unwrap(), key);
+        
+        // A fresh seed gives an unrelated key: about half the bits differ
+        let other_seed = channel.toeplitz_seed(raw_key.len(), 32);
+        let other = channel.privacy_amplification(&raw_key, 32, &other_seed).unwrap();
+        let distance: u32 = key.iter().zip(&other).map(|(a, b)| (a ^ b).count_ones()).sum();
+        assert!((80..=176).contains(&distance), "hamming distance {}", distance);
+        
+        // Seed too short, or more output than input
+        assert_eq!(channel.privacy_amplification(&raw_key, 32, &seed[1..]), Err(CryptoError::InvalidInput));
+        assert_eq!(channel.privacy_amplification(&raw_key, 65, &seed), Err(CryptoError::InvalidInput));
+    }
+
+    fn random_bits(n: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; n];
+        HardwareRng.fill_bytes(&mut bytes);
+        bytes.iter().map(|b| b & 1).collect()
+    }
+
+    #[test]
+    fn test_cascade_corrects_separated_errors() {
+        let manager = QkdManager::new(QkdChannel::new());
+        let alice = random_bits(256);
+        let mut bob = alice.clone();
+        for i in [5, 100, 200] {
+            bob[i] ^= 1;
+        }
+        
+        let corrected = manager.cascade_correction(&alice, &bob, 3.0 / 256.0).unwrap();
+        assert_eq!(corrected, alice);
+    }
+
+    #[test]
+    fn test_cascade_finds_paired_errors_in_later_pass() {
+        let alice = random_bits(256);
+        let mut bob = alice.clone();
+        // 10 and 20 share a first-pass block and cancel out there
+        for i in [10, 20, 200] {
+            bob[i] ^= 1;
+        }
+        
+        // Second pass order puts original index i at position 13 * i mod 256
+        let identity: Vec<usize> = (0..256).collect();
+        let mut shuffled = vec![0; 256];
+        for i in 0..256 {
+            shuffled[(i * 13) % 256] = i;
+        }
+        
+        let corrected = cascade(&alice, &bob, 64, &[identity, shuffled]);
+        assert_eq!(corrected, alice);
     }
 
     #[test]
@@ -553,4 +1256,32 @@ mod tests {
         let pairs = E91Protocol::generate_bell_pairs(10);
         assert_eq!(pairs.len(), 10);
     }
+
+    #[test]
+    fn test_e91_key_generation() {
+        let result = E91Protocol::generate_key(4000, 256).unwrap();
+        assert_eq!(result.alice.len(), 32);
+        assert_eq!(result.alice, result.bob);
+        assert!(result.sifted_bits >= 256);
+        
+        // Tsirelson's bound 2√2, up to sampling noise
+        let tsirelson = 2.0 * core::f64::consts::SQRT_2;
+        assert!(result.chsh > CHSH_CLASSICAL_BOUND);
+        assert!((result.chsh - tsirelson).abs() < 0.4, "S = {}", result.chsh);
+    }
+
+    #[test]
+    fn test_chsh_classical_source() {
+        // Pairs prepared with a fixed shared bit, as an intercept-resend
+        // attacker would: outcomes always agree regardless of angle
+        let measurements: Vec<E91Measurement> = (0..400)
+            .map(|i| E91Measurement {
+                alice: if i % 2 == 0 { Analyzer::Zero } else { Analyzer::PiOver4 },
+                alice_bit: (i / 4 % 2) as u8,
+                bob: if i / 2 % 2 == 0 { Analyzer::PiOver8 } else { Analyzer::ThreePiOver8 },
+                bob_bit: (i / 4 % 2) as u8,
+            })
+            .collect();
+        assert_eq!(E91Protocol::chsh_value(&measurements), 2.0);
+    }
 }
@@ -48,6 +48,13 @@ pub const MAX_IMAGE_SIZE: usize = 16 * 1024 * 1024; // 16MB
 pub const HASH_SIZE: usize = 32;
 /// Trusted key storage size
 pub const MAX_TRUSTED_KEYS: usize = 8;
+/// RSA-PSS-2048 modulus and signature size
+pub const RSA2048_SIZE: usize = 256;
+/// RSA-PSS-4096 modulus and signature size
+pub const RSA4096_SIZE: usize = 512;
+
+/// Number of boot stages
+pub const BOOT_STAGE_COUNT: usize = 6;
 
 /// Boot image magic number
 pub const BOOT_MAGIC: [u8; 4] = *b"CEB0"; // Cell0 Boot
@@ -64,6 +71,16 @@ pub enum SignatureType {
     EcdsaP256 = 0x04,
 }
 
+/// Key ID of a signer: the first 8 bytes of SHA3-256 over its `pubkey` field
+pub fn key_id_for(pubkey: &[u8]) -> [u8; 8] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(pubkey);
+    let digest = hasher.finalize();
+    let mut key_id = [0u8; 8];
+    key_id.copy_from_slice(&digest[..8]);
+    key_id
+}
+
 /// Boot stage identifiers
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -77,6 +94,18 @@ pub enum BootStage {
 }
 
 impl BootStage {
+    pub fn from_u8(value: u8) -> Option<BootStage> {
+        match value {
+            0 => Some(BootStage::Rom),
+            1 => Some(BootStage::Stage1),
+            2 => Some(BootStage::Stage2),
+            3 => Some(BootStage::Kernel),
+            4 => Some(BootStage::InitRamfs),
+            5 => Some(BootStage::DeviceTree),
+            _ => None,
+        }
+    }
+
     pub fn next(&self) -> Option<BootStage> {
         match self {
             BootStage::Rom => Some(BootStage::Stage1),
@@ -101,6 +130,7 @@ impl BootStage {
 
 /// Boot image header
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct BootHeader {
     /// Magic number
@@ -115,6 +145,8 @@ pub struct BootHeader {
     pub flags: u16,
     /// Image size (excluding header)
     pub image_size: u32,
+    /// Image security version, checked against the rollback floor
+    pub image_version: u32,
     /// Load address
     pub load_address: u64,
     /// Entry point
@@ -126,9 +158,52 @@ pub struct BootHeader {
     /// Image hash (SHA3-256)
     pub image_hash: [u8; HASH_SIZE],
     /// Header signature
+    #[cfg_attr(feature = "serde", serde(with = "signature_serde"))]
     pub header_signature: [u8; ED25519_SIG_SIZE],
 }
 
+/// Serde support for the signature field; derive only covers arrays up to 32
+#[cfg(feature = "serde")]
+mod signature_serde {
+    use super::ED25519_SIG_SIZE;
+    use core::fmt;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; ED25519_SIG_SIZE], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; ED25519_SIG_SIZE], D::Error> {
+        struct SignatureVisitor;
+
+        impl<'de> Visitor<'de> for SignatureVisitor {
+            type Value = [u8; ED25519_SIG_SIZE];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} signature bytes", ED25519_SIG_SIZE)
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = [0u8; ED25519_SIG_SIZE];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(i, &self))?;
+                }
+                if seq.next_element::<u8>()?.is_some() {
+                    return Err(A::Error::invalid_length(ED25519_SIG_SIZE + 1, &self));
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_bytes(SignatureVisitor)
+    }
+}
+
 impl BootHeader {
     pub fn new(stage: BootStage, image_size: u32, load_addr: u64, entry: u64) -> Self {
         BootHeader {
@@ -138,6 +213,7 @@ impl BootHeader {
             _reserved1: 0,
             flags: 0,
             image_size,
+            image_version: 0,
             load_address: load_addr,
             entry_point: entry,
             num_signatures: 0,
@@ -160,6 +236,7 @@ impl BootHeader {
         bytes.push(self._reserved1);
         bytes.extend_from_slice(&self.flags.to_le_bytes());
         bytes.extend_from_slice(&self.image_size.to_le_bytes());
+        bytes.extend_from_slice(&self.image_version.to_le_bytes());
         bytes.extend_from_slice(&self.load_address.to_le_bytes());
         bytes.extend_from_slice(&self.entry_point.to_le_bytes());
         bytes.push(self.num_signatures);
@@ -170,6 +247,10 @@ impl BootHeader {
 }
 
 /// Signature block
+///
+/// For RSA-PSS the `pubkey` field holds the modulus length as a
+/// little-endian `u16`, followed by the big-endian modulus and then the
+/// big-endian public exponent. Signatures use SHA-256 and MGF1-SHA-256.
 #[derive(Clone, Debug)]
 pub struct SignatureBlock {
     pub sig_type: SignatureType,
@@ -188,6 +269,66 @@ impl SignatureBlock {
         }
     }
 
+    /// RSA-PSS signature block; the type follows from the modulus length
+    pub fn new_rsa_pss(key_id: [u8; 8], signature: Vec<u8>, modulus: &[u8], exponent: &[u8]) -> CryptoResult<Self> {
+        let sig_type = match modulus.len() {
+            RSA2048_SIZE => SignatureType::RsaPss2048,
+            RSA4096_SIZE => SignatureType::RsaPss4096,
+            _ => return Err(CryptoError::InvalidKey),
+        };
+
+        let mut pubkey = Vec::with_capacity(2 + modulus.len() + exponent.len());
+        pubkey.extend_from_slice(&(modulus.len() as u16).to_le_bytes());
+        pubkey.extend_from_slice(modulus);
+        pubkey.extend_from_slice(exponent);
+
+        Ok(SignatureBlock {
+            sig_type,
+            key_id,
+            signature,
+            pubkey,
+        })
+    }
+
+    /// Split an RSA `pubkey` field into modulus and exponent
+    pub fn rsa_components(&self) -> CryptoResult<(&[u8], &[u8])> {
+        if self.pubkey.len() < 2 {
+            return Err(CryptoError::InvalidKey);
+        }
+        let modulus_len = u16::from_le_bytes([self.pubkey[0], self.pubkey[1]]) as usize;
+        let rest = &self.pubkey[2..];
+        if rest.len() <= modulus_len {
+            return Err(CryptoError::InvalidKey);
+        }
+        Ok(rest.split_at(modulus_len))
+    }
+
+    #[cfg(feature = "real-crypto")]
+    fn verify_rsa_pss(&self, data: &[u8]) -> CryptoResult<()> {
+        use rsa::sha2::{Digest, Sha256};
+        use rsa::{BigUint, Pss, RsaPublicKey};
+
+        let size = match self.sig_type {
+            SignatureType::RsaPss2048 => RSA2048_SIZE,
+            _ => RSA4096_SIZE,
+        };
+        let (modulus, exponent) = self.rsa_components()?;
+        if modulus.len() != size || self.signature.len() != size {
+            return Err(CryptoError::InvalidSignature);
+        }
+
+        let key = RsaPublicKey::new(BigUint::from_bytes_be(modulus), BigUint::from_bytes_be(exponent))
+            .map_err(|_| CryptoError::InvalidKey)?;
+        let digest = Sha256::digest(data);
+        key.verify(Pss::new::<Sha256>(), &digest, &self.signature)
+            .map_err(|_| CryptoError::VerificationFailed)
+    }
+
+    #[cfg(not(feature = "real-crypto"))]
+    fn verify_rsa_pss(&self, _data: &[u8]) -> CryptoResult<()> {
+        Err(CryptoError::AlgorithmNotSupported)
+    }
+
     pub fn verify(&self, data: &[u8]) -> CryptoResult<()> {
         match self.sig_type {
             SignatureType::Ed25519 => {
@@ -198,6 +339,7 @@ impl SignatureBlock {
                 let sig: [u8; ED25519_SIG_SIZE] = self.signature[..].try_into().unwrap();
                 verify_signature(&pk, data, &sig)
             }
+            SignatureType::RsaPss2048 | SignatureType::RsaPss4096 => self.verify_rsa_pss(data),
             _ => Err(CryptoError::AlgorithmNotSupported),
         }
     }
@@ -227,6 +369,12 @@ impl BootImage {
         }
     }
 
+    /// Set the security version; must happen before signing
+    pub fn with_image_version(mut self, version: u32) -> Self {
+        self.header.image_version = version;
+        self
+    }
+
     pub fn add_signature(&mut self, signature: SignatureBlock) -> CryptoResult<()> {
         if self.signatures.len() >= MAX_SIGNATURES {
             return Err(CryptoError::InvalidInput);
@@ -236,26 +384,51 @@ impl BootImage {
         Ok(())
     }
 
-    pub fn verify_signatures(&self, keyring: &KeyRing) -> CryptoResult<()> {
-        // Create signed data (header + payload)
-        let mut signed_data = self.header.header_bytes();
+    /// Bytes covered by each signature: header and payload
+    ///
+    /// `num_signatures` is zeroed so adding a signature does not
+    /// invalidate the ones already present.
+    pub fn signed_data(&self) -> Vec<u8> {
+        let mut header = self.header.clone();
+        header.num_signatures = 0;
+        let mut signed_data = header.header_bytes();
         signed_data.extend_from_slice(&self.payload);
+        signed_data
+    }
+
+    /// Check the image carries valid signatures from enough distinct
+    /// trusted keys. A block's `key_id` must be the ID of its own `pubkey`,
+    /// so trust and de-duplication follow the key material, not the label.
+    pub fn verify_signatures(&self, keyring: &KeyRing) -> CryptoResult<()> {
+        let signed_data = self.signed_data();
         
-        let mut valid_sigs = 0;
+        // Distinct trusted keys with a valid signature
+        let mut valid_keys: Vec<[u8; 8]> = Vec::new();
         
         for sig_block in &self.signatures {
+            // The label must name the key that actually signed
+            let key_id = key_id_for(&sig_block.pubkey);
+            if sig_block.key_id != key_id {
+                continue;
+            }
+            
             // Check if key is trusted
-            if !keyring.is_trusted(&sig_block.key_id) {
+            if !keyring.is_trusted(&key_id) {
+                continue;
+            }
+            
+            // A key only counts once towards the threshold
+            if valid_keys.contains(&key_id) {
                 continue;
             }
             
             // Verify signature
             if sig_block.verify(&signed_data).is_ok() {
-                valid_sigs += 1;
+                valid_keys.push(key_id);
             }
         }
         
-        if valid_sigs == 0 {
+        if valid_keys.len() < keyring.required_signatures() as usize {
             return Err(CryptoError::SecureBootViolation);
         }
         
@@ -295,6 +468,10 @@ pub struct KeyRing {
     key_count: usize,
     revoked_keys: [[u8; 8]; MAX_TRUSTED_KEYS],
     revoked_count: usize,
+    /// Distinct trusted keys that must sign an image
+    required_signatures: u8,
+    /// Lowest image version accepted per boot stage (rollback floor)
+    min_versions: [u32; BOOT_STAGE_COUNT],
 }
 
 impl KeyRing {
@@ -304,6 +481,8 @@ impl KeyRing {
             key_count: 0,
             revoked_keys: [[0; 8]; MAX_TRUSTED_KEYS],
             revoked_count: 0,
+            required_signatures: 1,
+            min_versions: [0; BOOT_STAGE_COUNT],
         }
     }
 
@@ -330,6 +509,38 @@ impl KeyRing {
         Ok(())
     }
 
+    /// Require signatures from `count` distinct trusted keys (k-of-n)
+    pub fn set_required_signatures(&mut self, count: u8) -> CryptoResult<()> {
+        if count == 0 || count as usize > MAX_SIGNATURES {
+            return Err(CryptoError::InvalidInput);
+        }
+        self.required_signatures = count;
+        Ok(())
+    }
+
+    pub fn required_signatures(&self) -> u8 {
+        self.required_signatures
+    }
+
+    /// Rollback floor for `stage`
+    pub fn min_version(&self, stage: BootStage) -> u32 {
+        self.min_versions[stage as usize]
+    }
+
+    /// Reject images for `stage` older than the stored floor
+    pub fn check_rollback(&self, stage: BootStage, version: u32) -> CryptoResult<()> {
+        if version < self.min_version(stage) {
+            return Err(CryptoError::SecureBootViolation);
+        }
+        Ok(())
+    }
+
+    /// Raise the rollback floor for `stage`; never lowers it
+    pub fn advance_min_version(&mut self, stage: BootStage, version: u32) {
+        let floor = &mut self.min_versions[stage as usize];
+        *floor = (*floor).max(version);
+    }
+
     pub fn revoke_key(&mut self, key_id: [u8; 8]) -> CryptoResult<()> {
         if self.revoked_count >= MAX_TRUSTED_KEYS {
             return Err(CryptoError::InvalidInput);
@@ -400,7 +611,10 @@ impl PcrBank {
     }
 
     /// Quote PCRs (create signed attestation)
-    pub fn quote(&self, _signing_key: &Ed25519Keypair, pcr_indices: &[usize]) -> PcrQuote {
+    ///
+    /// The selected PCR values are signed together with the verifier's
+    /// `nonce`, which proves the quote is fresh.
+    pub fn quote(&self, signing_key: &Ed25519Keypair, pcr_indices: &[usize], nonce: &[u8]) -> PcrQuote {
         let mut values = Vec::new();
         for &idx in pcr_indices {
             if idx < 24 {
@@ -408,10 +622,12 @@ impl PcrBank {
             }
         }
         
-        PcrQuote {
+        let mut quote = PcrQuote {
             pcr_values: values,
-            signature: [0; ED25519_SIG_SIZE], // Would be signed in real impl
-        }
+            signature: [0; ED25519_SIG_SIZE],
+        };
+        quote.signature = signing_key.sign(&quote.signed_data(nonce));
+        quote
     }
 }
 
@@ -422,6 +638,26 @@ pub struct PcrQuote {
     pub signature: [u8; ED25519_SIG_SIZE],
 }
 
+impl PcrQuote {
+    /// Bytes covered by the signature: the nonce length (u32 LE), the
+    /// nonce, then each PCR index (u32 LE) followed by its value
+    pub fn signed_data(&self, nonce: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(4 + nonce.len() + self.pcr_values.len() * (4 + HASH_SIZE));
+        data.extend_from_slice(&(nonce.len() as u32).to_le_bytes());
+        data.extend_from_slice(nonce);
+        for (pcr, value) in &self.pcr_values {
+            data.extend_from_slice(&pcr.to_le_bytes());
+            data.extend_from_slice(value);
+        }
+        data
+    }
+}
+
+/// Verify a PCR quote against the attestation key and expected nonce
+pub fn verify_quote(public_key: &[u8; ED25519_PK_SIZE], nonce: &[u8], quote: &PcrQuote) -> CryptoResult<()> {
+    verify_signature(public_key, &quote.signed_data(nonce), &quote.signature)
+}
+
 /// Measured boot manager
 pub struct MeasuredBoot {
     pcr_bank: PcrBank,
@@ -429,7 +665,7 @@ pub struct MeasuredBoot {
 }
 
 /// Single measurement entry
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Measurement {
     pub stage: BootStage,
     pub hash: [u8; HASH_SIZE],
@@ -476,6 +712,88 @@ impl MeasuredBoot {
     pub fn pcr_bank(&self) -> &PcrBank {
         &self.pcr_bank
     }
+
+    /// Serialize the measurement log as a TCG-style event log
+    ///
+    /// Each event is laid out little-endian as:
+    ///
+    /// | Field            | Size                   |
+    /// |------------------|------------------------|
+    /// | `pcr_index`      | 4                      |
+    /// | `event_type`     | 4                      |
+    /// | `digest`         | 32 (SHA3-256)          |
+    /// | `event_data_len` | 4                      |
+    /// | `event_data`     | `event_data_len`       |
+    pub fn export_event_log(&self) -> Vec<u8> {
+        let mut log = Vec::with_capacity(self.measurement_log.len() * (EVENT_HEADER_SIZE + 32));
+        for m in &self.measurement_log {
+            log.extend_from_slice(&m.stage.pcr_index().to_le_bytes());
+            log.extend_from_slice(&event_type(m.stage).to_le_bytes());
+            log.extend_from_slice(&m.hash);
+            log.extend_from_slice(&(m.details.len() as u32).to_le_bytes());
+            log.extend_from_slice(&m.details);
+        }
+        log
+    }
+}
+
+/// TCG event type for the static root of trust
+pub const EV_S_CRTM_CONTENTS: u32 = 0x07;
+/// TCG event type for initial program loader stages
+pub const EV_IPL: u32 = 0x0D;
+
+/// Fixed part of an exported event: PCR index, type, digest and data length
+const EVENT_HEADER_SIZE: usize = 4 + 4 + HASH_SIZE + 4;
+
+fn event_type(stage: BootStage) -> u32 {
+    match stage {
+        BootStage::Rom => EV_S_CRTM_CONTENTS,
+        _ => EV_IPL,
+    }
+}
+
+/// Parse an event log produced by `MeasuredBoot::export_event_log`
+pub fn parse_event_log(bytes: &[u8]) -> CryptoResult<Vec<Measurement>> {
+    let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+    let mut measurements = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if bytes.len() - offset < EVENT_HEADER_SIZE {
+            return Err(CryptoError::InvalidInput);
+        }
+
+        let pcr_index = read_u32(offset);
+        let stage = u8::try_from(pcr_index).ok()
+            .and_then(BootStage::from_u8)
+            .ok_or(CryptoError::InvalidInput)?;
+        if read_u32(offset + 4) != event_type(stage) {
+            return Err(CryptoError::InvalidInput);
+        }
+
+        let hash: [u8; HASH_SIZE] = bytes[offset + 8..offset + 8 + HASH_SIZE].try_into().unwrap();
+        let data_len = read_u32(offset + 8 + HASH_SIZE) as usize;
+        offset += EVENT_HEADER_SIZE;
+
+        if data_len > 32 || bytes.len() - offset < data_len {
+            return Err(CryptoError::InvalidInput);
+        }
+        let mut details = [0u8; 32];
+        details[..data_len].copy_from_slice(&bytes[offset..offset + data_len]);
+        offset += data_len;
+
+        measurements.push(Measurement { stage, hash, details });
+    }
+    Ok(measurements)
+}
+
+/// Recompute the PCR values an event log should have produced
+pub fn replay_event_log(measurements: &[Measurement]) -> CryptoResult<PcrBank> {
+    let mut bank = PcrBank::new();
+    for m in measurements {
+        bank.extend(m.stage.pcr_index() as usize, &m.hash)?;
+    }
+    Ok(bank)
 }
 
 /// Secure boot manager
@@ -516,25 +834,27 @@ impl SecureBootManager {
         // Verify signatures
         image.verify_signatures(&self.keyring)?;
 
+        let stage = BootStage::from_u8(image.header.stage).ok_or(CryptoError::InvalidInput)?;
+
+        // Rollback protection
+        self.keyring.check_rollback(stage, image.header.image_version)?;
+
         // Measure the image
-        let stage = match image.header.stage {
-            0 => BootStage::Rom,
-            1 => BootStage::Stage1,
-            2 => BootStage::Stage2,
-            3 => BootStage::Kernel,
-            4 => BootStage::InitRamfs,
-            5 => BootStage::DeviceTree,
-            _ => return Err(CryptoError::InvalidInput),
-        };
         self.measured_boot.measure(stage, &image.payload)?;
 
         // Update state
+        self.keyring.advance_min_version(stage, image.header.image_version);
         self.verified_stages.push(stage);
         self.current_stage = stage;
 
         Ok(())
     }
 
+    /// Get key ring, including the rollback floors to persist
+    pub fn keyring(&self) -> &KeyRing {
+        &self.keyring
+    }
+
     /// Get current boot stage
     pub fn current_stage(&self) -> BootStage {
         self.current_stage
@@ -551,9 +871,9 @@ impl SecureBootManager {
     }
 
     /// Generate attestation quote
-    pub fn generate_quote(&self, signing_key: &Ed25519Keypair) -> PcrQuote {
+    pub fn generate_quote(&self, signing_key: &Ed25519Keypair, nonce: &[u8]) -> PcrQuote {
         let pcr_indices: Vec<usize> = self.verified_stages.iter().map(|s: &BootStage| s.pcr_index() as usize).collect();
-        self.measured_boot.pcr_bank.quote(signing_key, &pcr_indices)
+        self.measured_boot.pcr_bank.quote(signing_key, &pcr_indices, nonce)
     }
 }
 
@@ -569,13 +889,12 @@ impl SecureUpdater {
 
     /// Verify update package
     pub fn verify_update(&self, update: &BootImage) -> CryptoResult<()> {
-        // Updates must have rollback protection
-        if update.header.flags & 0x1 == 0 {
-            // No rollback protection
-        }
-        
         // Verify signature with update keys
         update.verify_signatures(&self.keyring)?;
+
+        // Updates must not roll back below the stored version
+        let stage = BootStage::from_u8(update.header.stage).ok_or(CryptoError::InvalidInput)?;
+        self.keyring.check_rollback(stage, update.header.image_version)?;
         
         Ok(())
     }
@@ -591,22 +910,20 @@ impl SecureUpdater {
 pub struct BootSigner;
 
 impl BootSigner {
-    /// Sign boot image with Ed25519
+    /// Sign boot image with Ed25519, labelled with the key's own ID
     pub fn sign_ed25519(
         image: &mut BootImage,
         keypair: &Ed25519Keypair,
-        key_id: [u8; 8],
     ) -> CryptoResult<()> {
         // Create signed data
-        let mut signed_data = image.header.header_bytes();
-        signed_data.extend_from_slice(&image.payload);
+        let signed_data = image.signed_data();
         
         // Sign
         let signature = keypair.sign(&signed_data);
         
         // Add signature block
         let sig_block = SignatureBlock::new_ed25519(
-            key_id,
+            key_id_for(keypair.public_key()),
             signature,
             *keypair.public_key(),
         );
@@ -627,17 +944,34 @@ mod tests {
         assert_eq!(header.stage, BootStage::Kernel as u8);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_boot_header_serde_round_trip() {
+        let mut header = BootHeader::new(BootStage::Kernel, 0x10000, 0x80000000, 0x80010000);
+        header.image_hash = [0x5A; HASH_SIZE];
+        header.header_signature = core::array::from_fn(|i| i as u8);
+        
+        let json = serde_json::to_string(&header).unwrap();
+        let decoded: BootHeader = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.header_bytes(), header.header_bytes());
+        assert_eq!(decoded.header_signature, header.header_signature);
+        
+        // A truncated signature is rejected
+        let short = json.replace(",63]", "]");
+        assert!(serde_json::from_str::<BootHeader>(&short).is_err());
+    }
+
     #[test]
     fn test_boot_image_signing() {
         let keypair = Ed25519Keypair::generate();
-        let key_id = [0xABu8; 8];
         
         let payload = b"Test kernel image".to_vec();
         let mut image = BootImage::new(BootStage::Kernel, payload, 0x80000000, 0x80010000);
         
-        BootSigner::sign_ed25519(&mut image, &keypair, key_id).unwrap();
+        BootSigner::sign_ed25519(&mut image, &keypair).unwrap();
         
         assert_eq!(image.signatures.len(), 1);
+        assert_eq!(image.signatures[0].key_id, key_id_for(keypair.public_key()));
     }
 
     #[test]
@@ -652,6 +986,115 @@ mod tests {
         assert!(!ring.is_trusted(&key_id));
     }
 
+    fn threshold_setup() -> (KeyRing, Vec<Ed25519Keypair>) {
+        let signers: Vec<Ed25519Keypair> = (0..3).map(|_| Ed25519Keypair::generate()).collect();
+        let ids: Vec<[u8; 8]> = signers.iter().map(|k| key_id_for(k.public_key())).collect();
+
+        let mut ring = KeyRing::with_trusted_keys(&ids);
+        ring.set_required_signatures(2).unwrap();
+        (ring, signers)
+    }
+
+    #[test]
+    fn test_threshold_two_of_three() {
+        let (ring, signers) = threshold_setup();
+        let mut image = BootImage::new(BootStage::Kernel, b"kernel".to_vec(), 0x80000000, 0x80010000);
+
+        BootSigner::sign_ed25519(&mut image, &signers[0]).unwrap();
+        assert_eq!(image.verify_signatures(&ring), Err(CryptoError::SecureBootViolation));
+
+        BootSigner::sign_ed25519(&mut image, &signers[2]).unwrap();
+        assert!(image.verify_signatures(&ring).is_ok());
+    }
+
+    #[test]
+    fn test_threshold_rejects_duplicate_key() {
+        let (ring, signers) = threshold_setup();
+        let mut image = BootImage::new(BootStage::Kernel, b"kernel".to_vec(), 0x80000000, 0x80010000);
+
+        // The same key signing twice is still only one signer
+        BootSigner::sign_ed25519(&mut image, &signers[1]).unwrap();
+        BootSigner::sign_ed25519(&mut image, &signers[1]).unwrap();
+        assert_eq!(image.signatures.len(), 2);
+        assert_eq!(image.verify_signatures(&ring), Err(CryptoError::SecureBootViolation));
+
+        let mut ring = ring;
+        assert_eq!(ring.set_required_signatures(0), Err(CryptoError::InvalidInput));
+        assert_eq!(ring.set_required_signatures(MAX_SIGNATURES as u8 + 1), Err(CryptoError::InvalidInput));
+    }
+
+    #[test]
+    fn test_threshold_rejects_one_key_under_two_ids() {
+        let keypair = Ed25519Keypair::generate();
+        let mut ring = KeyRing::with_trusted_keys(&[[0x01; 8], [0x02; 8], key_id_for(keypair.public_key())]);
+        ring.set_required_signatures(2).unwrap();
+
+        let mut image = BootImage::new(BootStage::Kernel, b"kernel".to_vec(), 0x80000000, 0x80010000);
+        let signature = keypair.sign(&image.signed_data());
+
+        // One keypair claiming two trusted labels is not two signers
+        image.add_signature(SignatureBlock::new_ed25519([0x01; 8], signature, *keypair.public_key())).unwrap();
+        image.add_signature(SignatureBlock::new_ed25519([0x02; 8], signature, *keypair.public_key())).unwrap();
+        assert_eq!(image.verify_signatures(&ring), Err(CryptoError::SecureBootViolation));
+
+        // Nor does adding its honest label make it count twice
+        BootSigner::sign_ed25519(&mut image, &keypair).unwrap();
+        assert_eq!(image.verify_signatures(&ring), Err(CryptoError::SecureBootViolation));
+
+        let mut ring = ring;
+        ring.set_required_signatures(1).unwrap();
+        assert!(image.verify_signatures(&ring).is_ok());
+    }
+
+    fn signed_stage1(keypair: &Ed25519Keypair, version: u32) -> BootImage {
+        let mut image = BootImage::new(BootStage::Stage1, b"stage1".to_vec(), 0x7c00, 0x7c00)
+            .with_image_version(version);
+        BootSigner::sign_ed25519(&mut image, keypair).unwrap();
+        image
+    }
+
+    #[test]
+    fn test_rollback_rejects_older_version() {
+        let keypair = Ed25519Keypair::generate();
+        let key_id = key_id_for(keypair.public_key());
+
+        let mut manager = SecureBootManager::new(KeyRing::with_trusted_keys(&[key_id]));
+        manager.verify_and_boot(&signed_stage1(&keypair, 5)).unwrap();
+        assert_eq!(manager.keyring().min_version(BootStage::Stage1), 5);
+
+        // Next boot with the persisted key ring refuses the older image
+        let keyring = manager.keyring().clone();
+        let mut manager = SecureBootManager::new(keyring.clone());
+        assert_eq!(
+            manager.verify_and_boot(&signed_stage1(&keypair, 4)),
+            Err(CryptoError::SecureBootViolation)
+        );
+        assert_eq!(manager.keyring().min_version(BootStage::Stage1), 5);
+
+        let updater = SecureUpdater::new(keyring);
+        assert_eq!(
+            updater.verify_update(&signed_stage1(&keypair, 4)),
+            Err(CryptoError::SecureBootViolation)
+        );
+        assert!(updater.verify_update(&signed_stage1(&keypair, 5)).is_ok());
+    }
+
+    #[test]
+    fn test_rollback_newer_version_advances_floor() {
+        let keypair = Ed25519Keypair::generate();
+        let key_id = key_id_for(keypair.public_key());
+
+        let mut keyring = KeyRing::with_trusted_keys(&[key_id]);
+        keyring.advance_min_version(BootStage::Stage1, 2);
+        keyring.advance_min_version(BootStage::Stage1, 1);
+        assert_eq!(keyring.min_version(BootStage::Stage1), 2);
+
+        let mut manager = SecureBootManager::new(keyring);
+        manager.verify_and_boot(&signed_stage1(&keypair, 3)).unwrap();
+        assert_eq!(manager.keyring().min_version(BootStage::Stage1), 3);
+        assert_eq!(manager.keyring().min_version(BootStage::Kernel), 0);
+    }
+
     #[test]
     fn test_pcr_bank() {
         let mut pcr = PcrBank::new();
@@ -670,6 +1113,34 @@ mod tests {
         assert_ne!(value1, value2);
     }
 
+    #[test]
+    fn test_pcr_quote_signed() {
+        let mut pcr = PcrBank::new();
+        for i in 0..4 {
+            pcr.extend(i, &[i as u8; 32]).unwrap();
+        }
+        let key = Ed25519Keypair::from_seed(&[0x51u8; 32]);
+        let nonce = b"verifier nonce";
+
+        let quote = pcr.quote(&key, &[0, 1, 2, 3], nonce);
+        assert_eq!(quote.pcr_values.len(), 4);
+        assert!(verify_quote(key.public_key(), nonce, &quote).is_ok());
+
+        // Replaying the quote against a different challenge fails
+        assert!(verify_quote(key.public_key(), b"other nonce", &quote).is_err());
+    }
+
+    #[test]
+    fn test_pcr_quote_tampered_value() {
+        let mut pcr = PcrBank::new();
+        pcr.extend(0, b"firmware").unwrap();
+        let key = Ed25519Keypair::from_seed(&[0x51u8; 32]);
+
+        let mut quote = pcr.quote(&key, &[0, 1, 2, 3], b"nonce");
+        quote.pcr_values[0].1[0] ^= 0x01;
+        assert_eq!(verify_quote(key.public_key(), b"nonce", &quote), Err(CryptoError::VerificationFailed));
+    }
+
     #[test]
     fn test_measured_boot() {
         let mut measured = MeasuredBoot::new();
@@ -680,6 +1151,40 @@ mod tests {
         assert_eq!(measured.measurement_log().len(), 2);
     }
 
+    #[test]
+    fn test_event_log_round_trip() {
+        let mut measured = MeasuredBoot::new();
+        measured.measure(BootStage::Rom, b"rom").unwrap();
+        measured.measure(BootStage::Kernel, b"a kernel image longer than thirty-two bytes").unwrap();
+
+        let log = measured.export_event_log();
+        assert_eq!(log.len(), 2 * (EVENT_HEADER_SIZE + 32));
+        assert_eq!(&log[..4], &0u32.to_le_bytes());
+        assert_eq!(&log[4..8], &EV_S_CRTM_CONTENTS.to_le_bytes());
+
+        let parsed = parse_event_log(&log).unwrap();
+        assert_eq!(parsed, measured.measurement_log());
+
+        assert_eq!(parse_event_log(&log[..log.len() - 1]), Err(CryptoError::InvalidInput));
+    }
+
+    #[test]
+    fn test_event_log_replay_matches_pcrs() {
+        let mut measured = MeasuredBoot::new();
+        measured.measure(BootStage::Stage1, b"stage1").unwrap();
+        measured.measure(BootStage::Kernel, b"kernel").unwrap();
+        measured.measure(BootStage::Kernel, b"kernel module").unwrap();
+
+        let replayed = replay_event_log(&parse_event_log(&measured.export_event_log()).unwrap()).unwrap();
+        assert_eq!(replayed.pcrs, measured.pcr_bank().pcrs);
+
+        // A doctored digest no longer replays to the reported PCRs
+        let mut log = measured.export_event_log();
+        log[8] ^= 0xff;
+        let replayed = replay_event_log(&parse_event_log(&log).unwrap()).unwrap();
+        assert_ne!(replayed.pcrs, measured.pcr_bank().pcrs);
+    }
+
     #[test]
     fn test_boot_stage_sequence() {
         assert_eq!(BootStage::Rom.next(), Some(BootStage::Stage1));
@@ -709,4 +1214,111 @@ mod tests {
         let wrong_data = b"Wrong data";
         assert!(sig_block.verify(wrong_data).is_err());
     }
+
+    #[cfg(feature = "real-crypto")]
+    mod rsa_pss {
+        use super::*;
+        use rsa::rand_core::{self, RngCore};
+        use rsa::sha2::{Digest, Sha256};
+        use rsa::traits::PublicKeyParts;
+        use rsa::{BigUint, Pss, RsaPrivateKey};
+
+        /// Salt source for test-only signing
+        struct TestRng;
+
+        impl RngCore for TestRng {
+            fn next_u32(&mut self) -> u32 {
+                let mut bytes = [0u8; 4];
+                self.fill_bytes(&mut bytes);
+                u32::from_le_bytes(bytes)
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                let mut bytes = [0u8; 8];
+                self.fill_bytes(&mut bytes);
+                u64::from_le_bytes(bytes)
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                crate::crypto::CryptoRng::fill_bytes(&mut HardwareRng, dest);
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        impl rand_core::CryptoRng for TestRng {}
+
+        fn test_key() -> RsaPrivateKey {
+            let p = BigUint::parse_bytes(
+                b"eeb224a0bb3e65966dbceadfa1fe8165b91779688e55957183b4d4adbe00cf20\
+                  809c6e2f50530a9257ff793619f1f3b4599cdb2207e4303cb2144bd43cff5f88\
+                  2b9fd3a9d57c2295d02c08f9285803917c39caf115380d9a8e4b81e4d3256eba\
+                  4d39c6fbea2f1f8c842621ea4aa08141aaeb89789f0cb6d7885d2dc348c42837",
+                16,
+            );
+            let q = BigUint::parse_bytes(
+                b"e8b683243b198a39d39427e0d1275b49122065020a81e1fd31c3c0328612e1a5\
+                  452ec993f425cb8e50eb72cd4f9efb4e6c593650765d6ddf078ca6cf845297fc\
+                  9419d8d7c01a14138925dd9689f0c632bf3e7ec5b96289aaede9dd4e45cb300c\
+                  d5345283fb83825ab14e6f518566b1b8625443bbcb5aeb9195d17691ebacf9a5",
+                16,
+            );
+            RsaPrivateKey::from_p_q(p.unwrap(), q.unwrap(), BigUint::from(65537u32)).unwrap()
+        }
+
+        fn rsa_key_id(key: &RsaPrivateKey) -> [u8; 8] {
+            let public = key.to_public_key();
+            let block = SignatureBlock::new_rsa_pss(
+                [0; 8],
+                Vec::new(),
+                &public.n().to_bytes_be(),
+                &public.e().to_bytes_be(),
+            ).unwrap();
+            key_id_for(&block.pubkey)
+        }
+
+        fn sign_rsa_pss(image: &mut BootImage, key: &RsaPrivateKey, key_id: [u8; 8]) {
+            let signed_data = image.signed_data();
+            let digest = Sha256::digest(&signed_data);
+            let signature = key.sign_with_rng(&mut TestRng, Pss::new::<Sha256>(), &digest).unwrap();
+
+            let public = key.to_public_key();
+            let block = SignatureBlock::new_rsa_pss(
+                key_id,
+                signature,
+                &public.n().to_bytes_be(),
+                &public.e().to_bytes_be(),
+            ).unwrap();
+            assert_eq!(block.sig_type, SignatureType::RsaPss2048);
+            image.add_signature(block).unwrap();
+        }
+
+        #[test]
+        fn test_rsa_pss_2048_boot_header() {
+            let key_id = rsa_key_id(&test_key());
+            let keyring = KeyRing::with_trusted_keys(&[key_id]);
+
+            let mut image = BootImage::new(BootStage::Kernel, b"RSA signed kernel".to_vec(), 0x80000000, 0x80010000);
+            sign_rsa_pss(&mut image, &test_key(), key_id);
+
+            assert!(image.verify_signatures(&keyring).is_ok());
+        }
+
+        #[test]
+        fn test_rsa_pss_corrupted_signature() {
+            let key_id = rsa_key_id(&test_key());
+            let keyring = KeyRing::with_trusted_keys(&[key_id]);
+
+            let mut image = BootImage::new(BootStage::Kernel, b"RSA signed kernel".to_vec(), 0x80000000, 0x80010000);
+            sign_rsa_pss(&mut image, &test_key(), key_id);
+            image.signatures[0].signature[17] ^= 0x01;
+
+            let signed_data = image.signed_data();
+            assert_eq!(image.signatures[0].verify(&signed_data), Err(CryptoError::VerificationFailed));
+            assert_eq!(image.verify_signatures(&keyring), Err(CryptoError::SecureBootViolation));
+        }
+    }
 }
@@ -1,19 +1,36 @@
 //! SHA-3 (Keccak) Hash Functions
 //! 
 //! Implementation of SHA3-256, SHA3-512, and SHAKE extendable-output functions.
+//!
+//! With the `real-crypto` feature the hashers delegate to the RustCrypto
+//! `sha3` crate; otherwise the built-in Keccak-f[1600] is used.
 
 use super::CryptoResult;
 
+#[cfg(feature = "real-crypto")]
+use ::sha3::Digest;
+
 pub const SHA3_256_SIZE: usize = 32;
 pub const SHA3_512_SIZE: usize = 64;
+/// Sponge rate (block size) of SHA3-256 in bytes
+pub const SHA3_256_RATE: usize = 136;
+/// Sponge rate (block size) of SHA3-512 in bytes
+pub const SHA3_512_RATE: usize = 72;
 
 /// SHA3-256 hasher
+#[cfg(not(feature = "real-crypto"))]
 pub struct Sha3_256 {
     state: [u64; 25],
     rate: usize,
     absorbed: usize,
 }
 
+/// SHA3-256 hasher
+#[cfg(feature = "real-crypto")]
+pub struct Sha3_256 {
+    inner: ::sha3::Sha3_256,
+}
+
 /// Keccak-f[1600] permutation rounds
 const ROUNDS: usize = 24;
 
@@ -39,11 +56,55 @@ const PI: [usize; 24] = [
     15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
 ];
 
+/// Keccak-f[1600] permutation
+pub(crate) fn keccak_f1600(state: &mut [u64; 25]) {
+    for rc in RC.iter().take(ROUNDS) {
+        // Theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+        
+        // Rho and Pi: walk the lane cycle starting at (1, 0)
+        let mut current = state[1];
+        for i in 0..24 {
+            let next = state[PI[i]];
+            state[PI[i]] = current.rotate_left(RHO[i]);
+            current = next;
+        }
+        
+        // Chi
+        for y in 0..5 {
+            let row = [
+                state[5 * y],
+                state[5 * y + 1],
+                state[5 * y + 2],
+                state[5 * y + 3],
+                state[5 * y + 4],
+            ];
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        
+        // Iota
+        state[0] ^= rc;
+    }
+}
+
+#[cfg(not(feature = "real-crypto"))]
 impl Sha3_256 {
     pub fn new() -> Self {
         Sha3_256 {
             state: [0u64; 25],
-            rate: 136, // 168 - 2*32 for SHA3-256
+            rate: SHA3_256_RATE, // 200 - 2*32 for SHA3-256
             absorbed: 0,
         }
     }
@@ -79,53 +140,40 @@ impl Sha3_256 {
         result
     }
 
+    /// Discard absorbed input, returning to the freshly created state
+    pub fn reset(&mut self) {
+        self.state = [0u64; 25];
+        self.absorbed = 0;
+    }
+
     fn keccak_f(&mut self) {
-        for round in 0..ROUNDS {
-            self.round(RC[round]);
-        }
+        keccak_f1600(&mut self.state);
     }
+}
 
-    fn round(&mut self, rc: u64) {
-        // Theta
-        let mut c = [0u64; 5];
-        for x in 0..5 {
-            c[x] = self.state[x] ^ self.state[x + 5] ^ self.state[x + 10] 
-                   ^ self.state[x + 15] ^ self.state[x + 20];
-        }
-        
-        let mut d = [0u64; 5];
-        for x in 0..5 {
-            d[x] = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
-        }
-        
-        for x in 0..5 {
-            for y in 0..5 {
-                self.state[x + 5 * y] ^= d[x];
-            }
-        }
-        
-        // Rho and Pi combined with Chi
-        let mut b = [0u64; 25];
-        for x in 0..5 {
-            for y in 0..5 {
-                let idx = x + 5 * y;
-                let new_idx = PI[idx];
-                b[new_idx] = self.state[idx].rotate_left(RHO[idx]);
-            }
-        }
-        
-        // Chi
-        for y in 0..5 {
-            for x in 0..5 {
-                let idx = x + 5 * y;
-                self.state[idx] = b[idx] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
-            }
+#[cfg(feature = "real-crypto")]
+impl Sha3_256 {
+    pub fn new() -> Self {
+        Sha3_256 {
+            inner: ::sha3::Sha3_256::new(),
         }
-        
-        // Iota
-        self.state[0] ^= rc;
     }
 
+    pub fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.inner, data);
+    }
+
+    pub fn finalize(self) -> [u8; SHA3_256_SIZE] {
+        self.inner.finalize().into()
+    }
+
+    /// Discard absorbed input, returning to the freshly created state
+    pub fn reset(&mut self) {
+        Digest::reset(&mut self.inner);
+    }
+}
+
+impl Sha3_256 {
     /// One-shot hash
     pub fn hash(data: &[u8]) -> [u8; SHA3_256_SIZE] {
         let mut hasher = Self::new();
@@ -137,20 +185,39 @@ impl Sha3_256 {
     pub fn digest(data: &[u8]) -> [u8; SHA3_256_SIZE] {
         Self::hash(data)
     }
+
+    /// Finish the hash and reset, so the hasher can be reused
+    pub fn finalize_reset(&mut self) -> [u8; SHA3_256_SIZE] {
+        core::mem::take(self).finalize()
+    }
+}
+
+impl Default for Sha3_256 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// SHA3-512 hasher
+#[cfg(not(feature = "real-crypto"))]
 pub struct Sha3_512 {
     state: [u64; 25],
     rate: usize,
     absorbed: usize,
 }
 
+/// SHA3-512 hasher
+#[cfg(feature = "real-crypto")]
+pub struct Sha3_512 {
+    inner: ::sha3::Sha3_512,
+}
+
+#[cfg(not(feature = "real-crypto"))]
 impl Sha3_512 {
     pub fn new() -> Self {
         Sha3_512 {
             state: [0u64; 25],
-            rate: 72, // 168 - 2*48 for SHA3-512
+            rate: SHA3_512_RATE, // 200 - 2*64 for SHA3-512
             absorbed: 0,
         }
     }
@@ -185,11 +252,28 @@ impl Sha3_512 {
     }
 
     fn keccak_f(&mut self) {
-        for _round in 0..ROUNDS {
-            // Simplified - would use full Keccak-f
+        keccak_f1600(&mut self.state);
+    }
+}
+
+#[cfg(feature = "real-crypto")]
+impl Sha3_512 {
+    pub fn new() -> Self {
+        Sha3_512 {
+            inner: ::sha3::Sha3_512::new(),
         }
     }
 
+    pub fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.inner, data);
+    }
+
+    pub fn finalize(self) -> [u8; SHA3_512_SIZE] {
+        self.inner.finalize().into()
+    }
+}
+
+impl Sha3_512 {
     pub fn hash(data: &[u8]) -> [u8; SHA3_512_SIZE] {
         let mut hasher = Self::new();
         hasher.update(data);
@@ -217,10 +301,78 @@ mod tests {
         assert_eq!(hash, hash2);
     }
 
+    #[test]
+    fn test_sha3_256_reset() {
+        let a = b"measured boot stage 1";
+        let b = [0x5Au8; 300]; // spans more than one block
+        
+        let mut hasher = Sha3_256::new();
+        hasher.update(a);
+        assert_eq!(hasher.finalize_reset(), Sha3_256::hash(a));
+        
+        hasher.update(&b);
+        assert_eq!(hasher.finalize_reset(), Sha3_256::hash(&b));
+        
+        // Input absorbed before a reset does not leak into the next hash
+        hasher.update(&b[..150]);
+        hasher.reset();
+        hasher.update(a);
+        assert_eq!(hasher.finalize_reset(), Sha3_256::hash(a));
+    }
+
     #[test]
     fn test_sha3_512() {
         let data = b"Hello, SHA3-512!";
         let hash = Sha3_512::hash(data);
         assert_eq!(hash.len(), 64);
     }
+
+    #[test]
+    fn test_sha3_known_answers() {
+        // FIPS 202 digests of "abc"
+        assert_eq!(
+            Sha3_256::hash(b"abc"),
+            [
+                0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3,
+                0x90, 0xbd, 0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45,
+                0x11, 0x43, 0x15, 0x32,
+            ]
+        );
+        assert_eq!(
+            Sha3_512::hash(b"abc")[..8],
+            [0xb7, 0x51, 0x85, 0x0b, 0x1a, 0x57, 0x16, 0x8a]
+        );
+        
+        // Input spanning more than one block
+        let long = [0x61u8; 200];
+        let mut hasher = Sha3_256::new();
+        hasher.update(&long[..100]);
+        hasher.update(&long[100..]);
+        assert_eq!(hasher.finalize(), Sha3_256::hash(&long));
+    }
+
+    #[cfg(feature = "real-crypto")]
+    #[test]
+    fn test_real_crypto_nist_vectors() {
+        // NIST SHA3-256 / SHA3-512 of the empty message
+        assert_eq!(
+            Sha3_256::hash(b""),
+            [
+                0xa7, 0xff, 0xc6, 0xf8, 0xbf, 0x1e, 0xd7, 0x66, 0x51, 0xc1, 0x47, 0x56, 0xa0, 0x61,
+                0xd6, 0x62, 0xf5, 0x80, 0xff, 0x4d, 0xe4, 0x3b, 0x49, 0xfa, 0x82, 0xd8, 0x0a, 0x4b,
+                0x80, 0xf8, 0x43, 0x4a,
+            ]
+        );
+        assert_eq!(
+            Sha3_512::hash(b"")[..8],
+            [0xa6, 0x9f, 0x73, 0xcc, 0xa2, 0x3a, 0x9a, 0xc5]
+        );
+        
+        // 448-bit message "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        let msg = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(
+            Sha3_256::hash(msg)[..8],
+            [0x41, 0xc0, 0xdb, 0xa2, 0xa9, 0xd6, 0x24, 0x08]
+        );
+    }
 }
@@ -0,0 +1,210 @@
+//! SHAKE Extendable-Output Functions
+//!
+//! SHAKE128 and SHAKE256 (FIPS 202) built on the Keccak-f[1600]
+//! permutation from the `sha3` module. Output length is chosen by the
+//! caller, which makes these suitable for Kyber/Dilithium sampling and
+//! Fiat-Shamir challenges.
+
+use super::sha3::keccak_f1600;
+
+/// SHAKE128 sponge rate in bytes
+pub const SHAKE128_RATE: usize = 168;
+/// SHAKE256 sponge rate in bytes
+pub const SHAKE256_RATE: usize = 136;
+
+/// SHAKE domain separation suffix
+const SHAKE_SUFFIX: u8 = 0x1F;
+
+/// Keccak sponge shared by both SHAKE variants
+struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    absorbed: usize,
+}
+
+impl Sponge {
+    const fn new(rate: usize) -> Self {
+        Sponge {
+            state: [0u64; 25],
+            rate,
+            absorbed: 0,
+        }
+    }
+
+    fn xor_byte(&mut self, pos: usize, byte: u8) {
+        self.state[pos / 8] ^= (byte as u64) << ((pos % 8) * 8);
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.xor_byte(self.absorbed, byte);
+            self.absorbed += 1;
+
+            if self.absorbed == self.rate {
+                keccak_f1600(&mut self.state);
+                self.absorbed = 0;
+            }
+        }
+    }
+
+    /// Pad, then squeeze `out.len()` bytes; leaves the sponge reset
+    fn squeeze(&mut self, out: &mut [u8]) {
+        self.xor_byte(self.absorbed, SHAKE_SUFFIX);
+        self.xor_byte(self.rate - 1, 0x80);
+        keccak_f1600(&mut self.state);
+
+        for (i, chunk) in out.chunks_mut(self.rate).enumerate() {
+            if i > 0 {
+                keccak_f1600(&mut self.state);
+            }
+            for (j, byte) in chunk.iter_mut().enumerate() {
+                *byte = (self.state[j / 8] >> ((j % 8) * 8)) as u8;
+            }
+        }
+
+        *self = Sponge::new(self.rate);
+    }
+}
+
+/// SHAKE128 extendable-output function
+pub struct Shake128 {
+    sponge: Sponge,
+}
+
+impl Shake128 {
+    pub const fn new() -> Self {
+        Shake128 {
+            sponge: Sponge::new(SHAKE128_RATE),
+        }
+    }
+
+    /// Absorb more input
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.sponge.absorb(data);
+        self
+    }
+
+    /// Fill `out` with output of any length
+    ///
+    /// The hasher is reset afterwards and can absorb a new message.
+    pub fn finalize_xof(&mut self, out: &mut [u8]) {
+        self.sponge.squeeze(out);
+    }
+
+    /// One-shot SHAKE128
+    pub fn digest(data: &[u8], out: &mut [u8]) {
+        Self::new().update(data).finalize_xof(out);
+    }
+}
+
+impl Default for Shake128 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SHAKE256 extendable-output function
+pub struct Shake256 {
+    sponge: Sponge,
+}
+
+impl Shake256 {
+    pub const fn new() -> Self {
+        Shake256 {
+            sponge: Sponge::new(SHAKE256_RATE),
+        }
+    }
+
+    /// Absorb more input
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.sponge.absorb(data);
+        self
+    }
+
+    /// Fill `out` with output of any length
+    ///
+    /// The hasher is reset afterwards and can absorb a new message.
+    pub fn finalize_xof(&mut self, out: &mut [u8]) {
+        self.sponge.squeeze(out);
+    }
+
+    /// One-shot SHAKE256
+    pub fn digest(data: &[u8], out: &mut [u8]) {
+        Self::new().update(data).finalize_xof(out);
+    }
+}
+
+impl Default for Shake256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shake_known_answers() {
+        // FIPS 202 outputs for the empty message
+        let mut out = [0u8; 16];
+        Shake128::new().finalize_xof(&mut out);
+        assert_eq!(
+            out,
+            [
+                0x7f, 0x9c, 0x2b, 0xa4, 0xe8, 0x8f, 0x82, 0x7d, 0x61, 0x60, 0x45, 0x50, 0x76, 0x05,
+                0x85, 0x3e,
+            ]
+        );
+
+        Shake256::digest(b"", &mut out);
+        assert_eq!(
+            out,
+            [
+                0x46, 0xb9, 0xdd, 0x2b, 0x0b, 0xa8, 0x8d, 0x13, 0x23, 0x3b, 0x3f, 0xeb, 0x74, 0x3e,
+                0xeb, 0x24,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shake128_prefix_stability() {
+        let data = b"abc".repeat(100);
+
+        let mut short = [0u8; 16];
+        let mut medium = [0u8; 64];
+        let mut long = [0u8; 200];
+        Shake128::digest(&data, &mut short);
+        Shake128::digest(&data, &mut medium);
+        Shake128::new().update(&data[..7]).update(&data[7..]).finalize_xof(&mut long);
+
+        assert_eq!(short[..], medium[..16]);
+        assert_eq!(medium[..], long[..64]);
+
+        // Tail of a squeeze spanning two blocks
+        assert_eq!(
+            long[184..],
+            [
+                0x27, 0x54, 0x98, 0x3b, 0x7f, 0x00, 0x45, 0x46, 0x06, 0xc8, 0xc9, 0x4e, 0xe8, 0x92,
+                0xc1, 0xa5,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shake256_prefix_stability() {
+        let mut shake = Shake256::new();
+        let mut long = [0u8; 200];
+        shake.update(b"cell0").finalize_xof(&mut long);
+
+        // The hasher was reset, so the same input gives the same stream
+        let mut short = [0u8; 16];
+        let mut medium = [0u8; 64];
+        shake.update(b"cell0").finalize_xof(&mut short);
+        Shake256::digest(b"cell0", &mut medium);
+
+        assert_eq!(short[..], long[..16]);
+        assert_eq!(medium[..], long[..64]);
+        assert_ne!(long[..16], long[136..152]);
+    }
+}
@@ -25,7 +25,10 @@
 //! ```
 
 use super::{
+    aes_gcm::{AesGcm, NONCE_SIZE as SEAL_NONCE_SIZE, TAG_SIZE as SEAL_TAG_SIZE},
     secure_boot::{PcrBank, PcrQuote},
+    hmac::hmac_sha256,
+    sha3::Sha3_256,
     ed25519::{Ed25519Keypair, PUBLIC_KEY_SIZE, SIGNATURE_SIZE},
     constant_time_eq, secure_clear, CryptoError, CryptoResult, CryptoRng, HardwareRng,
 };
@@ -54,6 +57,11 @@ pub enum TpmResponse {
     Handle = 0x08B,
     Value = 0x084,
     Memory = 0x090,
+    AuthFail = 0x08E,
+    Integrity = 0x09F,
+    NvRange = 0x146,
+    NvLocked = 0x148,
+    NvDefined = 0x14C,
     
     // Custom success codes
     TestSuccess = 0x001,
@@ -91,6 +99,32 @@ pub enum TpmKeyType {
     Identity,
 }
 
+/// NV attribute: the space can be written once, then is locked
+pub const TPMA_NV_WRITEDEFINE: u32 = 1 << 13;
+/// NV attribute: the space can be read-locked until the next startup
+pub const TPMA_NV_READ_STCLEAR: u32 = 1 << 31;
+
+/// Sealed blob header: auth digest, policy digest, nonce and tag
+const SEAL_HEADER_SIZE: usize = 32 + 32 + SEAL_NONCE_SIZE + SEAL_TAG_SIZE;
+
+/// Failed authorizations tolerated before lockout
+pub const DEFAULT_MAX_AUTH_FAILURES: u32 = 5;
+
+/// NV index and its contents
+struct NvSpace {
+    index: u32,
+    attributes: u32,
+    data: Vec<u8>,
+    written: bool,
+    read_locked: bool,
+}
+
+impl NvSpace {
+    fn has(&self, attribute: u32) -> bool {
+        self.attributes & attribute != 0
+    }
+}
+
 /// PCR selection structure
 #[derive(Clone, Debug)]
 pub struct PcrSelection {
@@ -125,10 +159,16 @@ impl PcrSelection {
 pub struct TpmContext {
     enabled: bool,
     pcr_banks: Vec<(TpmAlgId, PcrBank)>,
-    nv_storage: Vec<(u32, Vec<u8>)>,
+    nv_storage: Vec<NvSpace>,
     keys: Vec<TpmKey>,
+    /// Private halves of signing keys, never leave the TPM
+    key_material: Vec<(u32, Ed25519Keypair)>,
+    /// Storage root key protecting sealed blobs
+    storage_key: [u8; 32],
     lockout_counter: u32,
     locked_out: bool,
+    max_auth_failures: u32,
+    lockout_auth: [u8; 32],
     clock: u64,
     reset_count: u32,
     restart_count: u32,
@@ -141,8 +181,12 @@ impl TpmContext {
             pcr_banks: Vec::new(),
             nv_storage: Vec::new(),
             keys: Vec::new(),
+            key_material: Vec::new(),
+            storage_key: [0u8; 32],
             lockout_counter: 0,
             locked_out: false,
+            max_auth_failures: DEFAULT_MAX_AUTH_FAILURES,
+            lockout_auth: [0u8; 32],
             clock: 0,
             reset_count: 0,
             restart_count: 0,
@@ -151,6 +195,10 @@ impl TpmContext {
         // Initialize SHA-256 PCR bank
         ctx.pcr_banks.push((TpmAlgId::Sha256, PcrBank::new()));
         
+        let mut rng = HardwareRng;
+        rng.fill_bytes(&mut ctx.storage_key);
+        ctx.lockout_auth = ctx.auth_digest(&[]);
+        
         ctx
     }
 
@@ -168,6 +216,10 @@ impl TpmContext {
                 }
             }
             self.restart_count += 1;
+            
+            for space in &mut self.nv_storage {
+                space.read_locked = false;
+            }
         }
         
         TpmResponse::Success
@@ -216,70 +268,173 @@ impl TpmContext {
     }
 
     /// Quote PCRs
+    ///
+    /// Signs the selected PCR values and `nonce` with the key behind
+    /// `signing_key`; check the result with `secure_boot::verify_quote`.
     pub fn quote(
         &self,
-        _signing_key: &TpmKey,
+        signing_key: &TpmKey,
         pcr_selection: &PcrSelection,
         nonce: &[u8],
     ) -> Result<PcrQuote, TpmResponse> {
-        // Read PCR values
-        let pcr_values_usize = self.pcr_read(pcr_selection);
-        
-        // Convert to expected type (usize -> u32)
-        let pcr_values: Vec<(u32, [u8; 32])> = pcr_values_usize
-            .into_iter()
-            .map(|(idx, val)| (idx as u32, val))
-            .collect();
-        
-        // Create quote data
-        let mut quote_data = Vec::new();
-        quote_data.extend_from_slice(nonce);
-        for (pcr, value) in &pcr_values {
-            quote_data.extend_from_slice(&pcr.to_le_bytes());
-            quote_data.extend_from_slice(value);
-        }
-        
-        // Sign quote (simplified)
-        let signature = [0u8; 64];
+        let (_, keypair) = self.key_material
+            .iter()
+            .find(|(handle, _)| *handle == signing_key.handle)
+            .ok_or(TpmResponse::Handle)?;
+        let (_, bank) = self.pcr_banks
+            .iter()
+            .find(|(alg, _)| *alg == pcr_selection.hash_alg)
+            .ok_or(TpmResponse::Value)?;
         
-        Ok(PcrQuote {
-            pcr_values,
-            signature,
-        })
+        let pcr_indices: Vec<usize> = (0..24).filter(|&i| pcr_selection.is_selected(i)).collect();
+        Ok(bank.quote(keypair, &pcr_indices, nonce))
     }
 
     /// Create primary key
     pub fn create_primary(&mut self, key_type: TpmKeyType) -> Result<TpmKey, TpmResponse> {
         let handle = self.keys.len() as u32 + 0x80000000;
         
-        let mut public_key = vec![0u8; PUBLIC_KEY_SIZE];
-        let mut rng = HardwareRng;
-        rng.fill_bytes(&mut public_key);
-        
+        let keypair = Ed25519Keypair::generate();
         let key = TpmKey {
             handle,
             key_type,
-            public_key,
+            public_key: keypair.public_key().to_vec(),
         };
         
+        self.key_material.push((handle, keypair));
         self.keys.push(key.clone());
         Ok(key)
     }
 
+    /// Digest of the selected PCR values in the SHA-256 bank
+    fn policy_digest(&self, pcr_policy: &[usize]) -> Result<[u8; 32], TpmResponse> {
+        let (_, bank) = self.pcr_banks
+            .iter()
+            .find(|(alg, _)| *alg == TpmAlgId::Sha256)
+            .ok_or(TpmResponse::Failure)?;
+        
+        let mut hasher = Sha3_256::new();
+        for &pcr in pcr_policy {
+            let value = bank.read(pcr).map_err(|_| TpmResponse::Value)?;
+            hasher.update(&(pcr as u32).to_le_bytes());
+            hasher.update(&value);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// Digest stored in place of an authorization value
+    ///
+    /// Keyed with the storage key, so a sealed blob on its own cannot be
+    /// used to check guesses at its authorization offline.
+    fn auth_digest(&self, auth: &[u8]) -> [u8; 32] {
+        let mut message = [b"cell0-tpm-auth".as_slice(), auth].concat();
+        let digest = hmac_sha256(&self.storage_key, &message);
+        secure_clear(&mut message);
+        digest
+    }
+
     /// Seal data with TPM
-    pub fn seal(&self, data: &[u8], _pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
-        // Simplified sealing - would use actual TPM seal operation
-        let mut sealed = vec![0u8; data.len() + 32];
-        sealed[32..].copy_from_slice(data);
+    ///
+    /// The blob is bound to the current values of the PCRs in `pcr_policy`
+    /// and can only be unsealed while they are unchanged.
+    pub fn seal(&self, data: &[u8], pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
+        self.seal_with_auth(data, pcr_policy, &[])
+    }
+
+    /// Seal data that additionally requires `auth` to unseal
+    pub fn seal_with_auth(&self, data: &[u8], pcr_policy: &[usize], auth: &[u8]) -> Result<Vec<u8>, TpmResponse> {
+        let digest = self.policy_digest(pcr_policy)?;
+        let auth_hash = self.auth_digest(auth);
+        
+        let mut nonce = [0u8; SEAL_NONCE_SIZE];
+        let mut rng = HardwareRng;
+        rng.fill_bytes(&mut nonce);
+        
+        let cipher = AesGcm::new(&self.storage_key).map_err(|_| TpmResponse::Failure)?;
+        let (ciphertext, tag) = cipher.encrypt(&nonce, data, &[auth_hash, digest].concat());
+        
+        let mut sealed = Vec::with_capacity(SEAL_HEADER_SIZE + ciphertext.len());
+        sealed.extend_from_slice(&auth_hash);
+        sealed.extend_from_slice(&digest);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&tag);
+        sealed.extend_from_slice(&ciphertext);
         Ok(sealed)
     }
 
     /// Unseal data
-    pub fn unseal(&self, sealed_data: &[u8], _pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
-        if sealed_data.len() < 32 {
+    ///
+    /// Fails with `TpmResponse::Value` if the PCRs no longer match the
+    /// state the blob was sealed under.
+    pub fn unseal(&mut self, sealed_data: &[u8], pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
+        self.unseal_with_auth(sealed_data, pcr_policy, &[])
+    }
+
+    /// Unseal data sealed with `seal_with_auth`
+    ///
+    /// A wrong `auth` counts towards dictionary-attack lockout; once locked
+    /// out every authorized operation returns `TpmResponse::Disabled`.
+    pub fn unseal_with_auth(&mut self, sealed_data: &[u8], pcr_policy: &[usize], auth: &[u8]) -> Result<Vec<u8>, TpmResponse> {
+        if self.locked_out {
+            return Err(TpmResponse::Disabled);
+        }
+        if sealed_data.len() < SEAL_HEADER_SIZE {
+            return Err(TpmResponse::Value);
+        }
+        
+        let digest = self.policy_digest(pcr_policy)?;
+        if !constant_time_eq(&digest, &sealed_data[32..64]) {
             return Err(TpmResponse::Value);
         }
-        Ok(sealed_data[32..].to_vec())
+        
+        let auth_hash = self.auth_digest(auth);
+        if !constant_time_eq(&auth_hash, &sealed_data[..32]) {
+            self.record_auth_failure();
+            return Err(TpmResponse::AuthFail);
+        }
+        
+        let nonce: [u8; SEAL_NONCE_SIZE] = sealed_data[64..64 + SEAL_NONCE_SIZE].try_into().unwrap();
+        let tag: [u8; SEAL_TAG_SIZE] = sealed_data[64 + SEAL_NONCE_SIZE..SEAL_HEADER_SIZE].try_into().unwrap();
+        let cipher = AesGcm::new(&self.storage_key).map_err(|_| TpmResponse::Failure)?;
+        cipher
+            .decrypt(&nonce, &sealed_data[SEAL_HEADER_SIZE..], &[auth_hash, digest].concat(), &tag)
+            .map_err(|_| TpmResponse::Integrity)
+    }
+
+    fn record_auth_failure(&mut self) {
+        self.lockout_counter += 1;
+        if self.lockout_counter >= self.max_auth_failures {
+            self.locked_out = true;
+        }
+    }
+
+    /// Set how many failed authorizations trigger lockout
+    pub fn set_max_auth_failures(&mut self, max_failures: u32) {
+        self.max_auth_failures = max_failures.max(1);
+    }
+
+    /// Set the authorization value required by `dictionary_attack_reset`
+    pub fn set_lockout_auth(&mut self, lockout_auth: &[u8]) {
+        self.lockout_auth = self.auth_digest(lockout_auth);
+    }
+
+    /// Failed authorizations since the last reset
+    pub fn lockout_counter(&self) -> u32 {
+        self.lockout_counter
+    }
+
+    pub fn is_locked_out(&self) -> bool {
+        self.locked_out
+    }
+
+    /// Clear the failure counter and lift lockout
+    pub fn dictionary_attack_reset(&mut self, lockout_auth: &[u8]) -> TpmResponse {
+        if !constant_time_eq(&self.auth_digest(lockout_auth), &self.lockout_auth) {
+            return TpmResponse::AuthFail;
+        }
+        self.lockout_counter = 0;
+        self.locked_out = false;
+        TpmResponse::Success
     }
 
     /// Get random bytes
@@ -295,41 +450,63 @@ impl TpmContext {
         // Mix entropy into TPM RNG state
     }
 
-    /// NV Read
-    pub fn nv_read(&self, index: u32, size: usize, offset: usize) -> Result<Vec<u8>, TpmResponse> {
-        if let Some((_idx, data)) = self.nv_storage.iter().find(|(i, _)| *i == index) {
-            let data_len: usize = data.len();
-            if offset + size > data_len {
-                return Err(TpmResponse::Value);
-            }
-            Ok(data[offset..offset + size].to_vec())
-        } else {
-            Err(TpmResponse::Handle)
+    /// NV Define space
+    pub fn nv_define_space(&mut self, index: u32, size: usize, attributes: u32) -> TpmResponse {
+        if self.nv_storage.iter().any(|space| space.index == index) {
+            return TpmResponse::NvDefined;
         }
+        self.nv_storage.push(NvSpace {
+            index,
+            attributes,
+            data: vec![0u8; size],
+            written: false,
+            read_locked: false,
+        });
+        TpmResponse::Success
     }
 
     /// NV Write
-    pub fn nv_write(&mut self, index: u32, data: &[u8], offset: usize) -> TpmResponse {
-        if let Some((_idx, existing)) = self.nv_storage.iter_mut().find(|(i, _)| *i == index) {
-            let data_len: usize = data.len();
-            let existing_len: usize = existing.len();
-            if offset + data_len > existing_len {
-                existing.resize(offset + data_len, 0);
-            }
-            existing[offset..offset + data_len].copy_from_slice(data);
-        } else {
-            let mut new_data = vec![0u8; offset + data.len()];
-            new_data[offset..].copy_from_slice(data);
-            self.nv_storage.push((index, new_data));
+    pub fn nv_write(&mut self, index: u32, offset: usize, data: &[u8]) -> TpmResponse {
+        let space = match self.nv_storage.iter_mut().find(|space| space.index == index) {
+            Some(space) => space,
+            None => return TpmResponse::Handle,
+        };
+        if space.has(TPMA_NV_WRITEDEFINE) && space.written {
+            return TpmResponse::NvLocked;
         }
+        if offset + data.len() > space.data.len() {
+            return TpmResponse::NvRange;
+        }
+        space.data[offset..offset + data.len()].copy_from_slice(data);
+        space.written = true;
         TpmResponse::Success
     }
 
-    /// NV Define space
-    pub fn nv_define_space(&mut self, index: u32, size: usize, _attributes: u32) -> TpmResponse {
-        let initial_data = vec![0u8; size];
-        self.nv_storage.push((index, initial_data));
-        TpmResponse::Success
+    /// NV Read
+    pub fn nv_read(&self, index: u32, offset: usize, len: usize) -> Result<Vec<u8>, TpmResponse> {
+        let space = self.nv_storage
+            .iter()
+            .find(|space| space.index == index)
+            .ok_or(TpmResponse::Handle)?;
+        if space.read_locked {
+            return Err(TpmResponse::NvLocked);
+        }
+        if offset + len > space.data.len() {
+            return Err(TpmResponse::NvRange);
+        }
+        Ok(space.data[offset..offset + len].to_vec())
+    }
+
+    /// NV Read lock, held until the next `startup(true)`
+    pub fn nv_read_lock(&mut self, index: u32) -> TpmResponse {
+        match self.nv_storage.iter_mut().find(|space| space.index == index) {
+            Some(space) if space.has(TPMA_NV_READ_STCLEAR) => {
+                space.read_locked = true;
+                TpmResponse::Success
+            }
+            Some(_) => TpmResponse::Value,
+            None => TpmResponse::Handle,
+        }
     }
 
     /// Get capability
@@ -411,7 +588,7 @@ impl TpmKeyStore {
         self.context.seal(key, pcr_policy)
     }
 
-    pub fn unseal_key(&self, sealed: &[u8], pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
+    pub fn unseal_key(&mut self, sealed: &[u8], pcr_policy: &[usize]) -> Result<Vec<u8>, TpmResponse> {
         self.context.unseal(sealed, pcr_policy)
     }
 }
@@ -419,6 +596,7 @@ impl TpmKeyStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::secure_boot::verify_quote;
 
     #[test]
     fn test_tpm_startup() {
@@ -444,7 +622,7 @@ mod tests {
 
     #[test]
     fn test_tpm_seal_unseal() {
-        let tpm = TpmContext::new();
+        let mut tpm = TpmContext::new();
         let data = b"secret data";
         let policy = vec![0, 1, 2];
         
@@ -454,6 +632,75 @@ mod tests {
         assert_eq!(unsealed, data);
     }
 
+    #[test]
+    fn test_tpm_seal_bound_to_pcrs() {
+        let mut tpm = TpmContext::new();
+        tpm.pcr_extend(0, &[(TpmAlgId::Sha256, b"trusted firmware".as_slice())]);
+        
+        let sealed = tpm.seal(b"disk key", &[0]).unwrap();
+        assert!(!sealed.windows(8).any(|w| w == b"disk key"));
+        assert_eq!(tpm.unseal(&sealed, &[0]).unwrap(), b"disk key");
+        
+        // Measuring anything else into PCR0 changes the policy digest
+        tpm.pcr_extend(0, &[(TpmAlgId::Sha256, b"rootkit".as_slice())]);
+        assert_eq!(tpm.unseal(&sealed, &[0]), Err(TpmResponse::Value));
+    }
+
+    #[test]
+    fn test_tpm_seal_rejects_tampering() {
+        let mut tpm = TpmContext::new();
+        let mut sealed = tpm.seal(b"disk key", &[0, 1]).unwrap();
+        
+        // A different PCR selection is a different policy
+        assert_eq!(tpm.unseal(&sealed, &[0]), Err(TpmResponse::Value));
+        
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0x01;
+        assert_eq!(tpm.unseal(&sealed, &[0, 1]), Err(TpmResponse::Integrity));
+        assert_eq!(tpm.seal(b"x", &[24]), Err(TpmResponse::Value));
+    }
+
+    #[test]
+    fn test_tpm_dictionary_attack_lockout() {
+        let mut tpm = TpmContext::new();
+        tpm.set_max_auth_failures(3);
+        tpm.set_lockout_auth(b"owner");
+        
+        let sealed = tpm.seal_with_auth(b"secret", &[0], b"pin").unwrap();
+        for attempt in 1..=3 {
+            assert_eq!(tpm.unseal_with_auth(&sealed, &[0], b"guess"), Err(TpmResponse::AuthFail));
+            assert_eq!(tpm.lockout_counter(), attempt);
+        }
+        assert!(tpm.is_locked_out());
+        
+        // Even the right value is refused while locked out
+        assert_eq!(tpm.unseal_with_auth(&sealed, &[0], b"pin"), Err(TpmResponse::Disabled));
+        assert_eq!(tpm.lockout_counter(), 3);
+        
+        assert_eq!(tpm.dictionary_attack_reset(b"wrong"), TpmResponse::AuthFail);
+        assert!(tpm.is_locked_out());
+        assert_eq!(tpm.dictionary_attack_reset(b"owner"), TpmResponse::Success);
+        assert!(!tpm.is_locked_out());
+        assert_eq!(tpm.lockout_counter(), 0);
+        assert_eq!(tpm.unseal_with_auth(&sealed, &[0], b"pin").unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_tpm_sealed_auth_is_keyed() {
+        let tpm = TpmContext::new();
+        let other = TpmContext::new();
+        let sealed = tpm.seal_with_auth(b"secret", &[0], b"1234").unwrap();
+        
+        // The stored check value depends on the TPM's storage key, so it
+        // is neither a plain hash of the PIN nor reproducible elsewhere
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"cell0-tpm-auth");
+        hasher.update(b"1234");
+        assert_ne!(sealed[..32], hasher.finalize());
+        assert_ne!(sealed[..32], other.auth_digest(b"1234"));
+        assert_eq!(sealed[..32], tpm.auth_digest(b"1234"));
+    }
+
     #[test]
     fn test_tpm_nv_storage() {
         let mut tpm = TpmContext::new();
@@ -462,11 +709,44 @@ mod tests {
         assert_eq!(tpm.nv_define_space(0x01000001, 64, 0), TpmResponse::Success);
         
         // Write
-        assert_eq!(tpm.nv_write(0x01000001, b"test data", 0), TpmResponse::Success);
+        assert_eq!(tpm.nv_write(0x01000001, 0, b"test data"), TpmResponse::Success);
+        assert_eq!(tpm.nv_write(0x01000001, 9, b"!"), TpmResponse::Success);
         
         // Read
-        let data = tpm.nv_read(0x01000001, 9, 0).unwrap();
-        assert_eq!(data, b"test data");
+        let data = tpm.nv_read(0x01000001, 0, 10).unwrap();
+        assert_eq!(data, b"test data!");
+        
+        assert_eq!(tpm.nv_define_space(0x01000001, 64, 0), TpmResponse::NvDefined);
+        assert_eq!(tpm.nv_write(0x01000001, 60, b"too long"), TpmResponse::NvRange);
+        assert_eq!(tpm.nv_read(0x01000001, 60, 8), Err(TpmResponse::NvRange));
+        assert_eq!(tpm.nv_read(0x01000002, 0, 1), Err(TpmResponse::Handle));
+    }
+
+    #[test]
+    fn test_tpm_nv_write_once() {
+        let mut tpm = TpmContext::new();
+        let index = 0x01000010;
+        
+        assert_eq!(tpm.nv_define_space(index, 16, TPMA_NV_WRITEDEFINE), TpmResponse::Success);
+        assert_eq!(tpm.nv_write(index, 0, b"provisioned"), TpmResponse::Success);
+        assert_eq!(tpm.nv_write(index, 0, b"overwritten"), TpmResponse::NvLocked);
+        assert_eq!(tpm.nv_read(index, 0, 11).unwrap(), b"provisioned");
+    }
+
+    #[test]
+    fn test_tpm_nv_read_lock() {
+        let mut tpm = TpmContext::new();
+        tpm.nv_define_space(0x01000020, 8, TPMA_NV_READ_STCLEAR);
+        tpm.nv_define_space(0x01000021, 8, 0);
+        tpm.nv_write(0x01000020, 0, b"secret");
+        
+        assert_eq!(tpm.nv_read_lock(0x01000021), TpmResponse::Value);
+        assert_eq!(tpm.nv_read_lock(0x01000020), TpmResponse::Success);
+        assert_eq!(tpm.nv_read(0x01000020, 0, 6), Err(TpmResponse::NvLocked));
+        
+        // The lock lasts until the next clear startup
+        tpm.startup(true);
+        assert_eq!(tpm.nv_read(0x01000020, 0, 6).unwrap(), b"secret");
     }
 
     #[test]
@@ -517,7 +797,13 @@ mod tests {
         
         let quote = tpm.quote(&key, &selection, b"nonce").unwrap();
         assert_eq!(quote.pcr_values.len(), 3);
-        assert!(!quote.signature.is_empty());
+        
+        let public_key: [u8; PUBLIC_KEY_SIZE] = key.public_key[..].try_into().unwrap();
+        assert!(verify_quote(&public_key, b"nonce", &quote).is_ok());
+        
+        tpm.pcr_extend(1, &[(TpmAlgId::Sha256, b"later".as_slice())]);
+        let stale = PcrQuote { signature: quote.signature, ..tpm.quote(&key, &selection, b"nonce").unwrap() };
+        assert!(verify_quote(&public_key, b"nonce", &stale).is_err());
     }
 
     #[test]
@@ -53,6 +53,11 @@ fn x25519_scalar_mult(scalar: &[u8; 32], point_u: &[u8; 32]) -> [u8; 32] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[test] fn test_drop_clears_secrets() {
+        use crate::crypto::bytes_after_drop;
+        let keypair = X25519Keypair::from_secret_key([0x33u8; KEY_SIZE]);
+        assert_eq!(bytes_after_drop(keypair, |k| &k.secret_key), [0u8; KEY_SIZE]);
+    }
     #[test] fn test_exchange() {
         let alice = X25519Keypair::generate();
         let bob = X25519Keypair::generate();
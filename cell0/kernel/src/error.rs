@@ -0,0 +1,122 @@
+//! Kernel-wide error type
+//!
+//! Each subsystem keeps its own error enum; `KernelError` wraps them so
+//! code crossing subsystem boundaries (syscall handlers in particular) can
+//! propagate any of them with `?`. Display output is the wrapped error's.
+
+use core::fmt;
+
+use crate::crypto::CryptoError;
+use crate::ipc::IpcError;
+use crate::memory::MemoryError;
+use crate::process::ProcessError;
+use crate::sypas::SypasError;
+
+/// Error from any kernel subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelError {
+    Memory(MemoryError),
+    Process(ProcessError),
+    Ipc(IpcError),
+    Sypas(SypasError),
+    Crypto(CryptoError),
+    /// Malformed arguments from the caller
+    InvalidArgument,
+    /// Caller passed a pointer that cannot be accessed
+    BadAddress,
+}
+
+/// Result type alias for cross-subsystem operations
+pub type KernelResult<T> = Result<T, KernelError>;
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KernelError::Memory(e) => e.fmt(f),
+            KernelError::Process(e) => e.fmt(f),
+            KernelError::Ipc(e) => e.fmt(f),
+            KernelError::Sypas(e) => e.fmt(f),
+            KernelError::Crypto(e) => e.fmt(f),
+            KernelError::InvalidArgument => write!(f, "Invalid argument"),
+            KernelError::BadAddress => write!(f, "Bad address"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KernelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KernelError::Memory(e) => Some(e),
+            KernelError::Process(e) => Some(e),
+            KernelError::Ipc(e) => Some(e),
+            KernelError::Sypas(e) => Some(e),
+            KernelError::Crypto(e) => Some(e),
+            KernelError::InvalidArgument | KernelError::BadAddress => None,
+        }
+    }
+}
+
+impl From<MemoryError> for KernelError {
+    fn from(e: MemoryError) -> Self {
+        KernelError::Memory(e)
+    }
+}
+
+impl From<ProcessError> for KernelError {
+    fn from(e: ProcessError) -> Self {
+        KernelError::Process(e)
+    }
+}
+
+impl From<IpcError> for KernelError {
+    fn from(e: IpcError) -> Self {
+        KernelError::Ipc(e)
+    }
+}
+
+impl From<SypasError> for KernelError {
+    fn from(e: SypasError) -> Self {
+        KernelError::Sypas(e)
+    }
+}
+
+impl From<CryptoError> for KernelError {
+    fn from(e: CryptoError) -> Self {
+        KernelError::Crypto(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::string::ToString;
+
+    fn forward(result: Result<u8, IpcError>) -> KernelResult<u8> {
+        let value = result?;
+        Ok(value + 1)
+    }
+
+    #[test]
+    fn test_question_mark_conversion() {
+        assert_eq!(forward(Ok(1)), Ok(2));
+        assert_eq!(forward(Err(IpcError::WouldBlock)), Err(KernelError::Ipc(IpcError::WouldBlock)));
+    }
+
+    #[test]
+    fn test_display_is_preserved() {
+        let cases: [(KernelError, std::string::String); 5] = [
+            (MemoryError::DoubleFree.into(), MemoryError::DoubleFree.to_string()),
+            (ProcessError::ProcessNotFound.into(), ProcessError::ProcessNotFound.to_string()),
+            (IpcError::ChannelClosed.into(), IpcError::ChannelClosed.to_string()),
+            (SypasError::AccessDenied.into(), SypasError::AccessDenied.to_string()),
+            (CryptoError::InvalidKey.into(), CryptoError::InvalidKey.to_string()),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(error.to_string(), expected);
+            assert_eq!(error.source().unwrap().to_string(), expected);
+        }
+        assert_eq!(KernelError::from(IpcError::ChannelClosed).to_string(), "Channel closed");
+    }
+}
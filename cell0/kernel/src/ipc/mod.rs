@@ -8,14 +8,22 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
+use crate::process::{Capabilities, Capability, PROCESS_TABLE};
+use crate::sypas::{self, CapabilityHandle, SypasError};
+use spin::{Mutex, Once};
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 #[cfg(feature = "std")]
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "std")]
+use std::string::String;
 
 /// Maximum message size
 pub const MAX_MESSAGE_SIZE: usize = 4096;
@@ -52,6 +60,8 @@ pub struct MessageHeader {
     pub flags: u32,
     /// Timestamp
     pub timestamp: u64,
+    /// Time after which the message is discarded undelivered
+    pub deadline: Option<u64>,
 }
 
 /// IPC message
@@ -59,6 +69,17 @@ pub struct MessageHeader {
 pub struct Message {
     pub header: MessageHeader,
     pub payload: Vec<u8>,
+    /// Capability passed along with the message
+    ///
+    /// Holds the sender's handle in transit; on receive it is delegated to
+    /// the receiver and replaced with the receiver's own handle, or cleared
+    /// if the delegation is refused.
+    pub attached_cap: Option<CapabilityHandle>,
+    /// Sending process as known to the kernel, stamped by `send_from`
+    ///
+    /// Unlike `header.source` the sender cannot set it, so attached
+    /// capabilities are delegated from this PID.
+    sender: Option<u64>,
 }
 
 impl Message {
@@ -69,15 +90,29 @@ impl Message {
                 destination,
                 msg_type,
                 flags: 0,
-                timestamp: 0,
+                timestamp: crate::time::now_ms(),
+                deadline: None,
             },
             payload: payload.to_vec(),
+            attached_cap: None,
+            sender: None,
         }
     }
     
+    /// Attach a capability for the receiver to inherit
+    pub fn with_capability(mut self, handle: CapabilityHandle) -> Self {
+        self.attached_cap = Some(handle);
+        self
+    }
+    
     pub fn size(&self) -> usize {
         core::mem::size_of::<MessageHeader>() + self.payload.len()
     }
+    
+    /// Check whether the message's deadline has passed
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.header.deadline, Some(deadline) if now > deadline)
+    }
 }
 
 /// Channel type
@@ -114,10 +149,21 @@ pub struct Channel {
     pub state: ChannelState,
     pub owner: u64,
     pub peer: Option<u64>,
+    /// Paired channel that receives what this channel sends
+    pub peer_channel: Option<ChannelId>,
+    /// Inbound messages waiting to be received
     pub message_queue: VecDeque<Message>,
+    /// Subscribed processes (broadcast channels only)
+    pub subscribers: Vec<u64>,
+    /// Per-subscriber inbound queues (broadcast channels only)
+    subscriber_queues: BTreeMap<u64, VecDeque<Message>>,
     pub max_queue_size: usize,
     pub blocking_send: bool,
     pub blocking_recv: bool,
+    /// Senders turned away by a full queue, woken in order as slots free up
+    blocked_senders: VecDeque<u64>,
+    /// Makes a blocked sender runnable again
+    sender_waker: fn(u64),
 }
 
 impl Channel {
@@ -128,13 +174,23 @@ impl Channel {
             state: ChannelState::Connecting,
             owner,
             peer: None,
+            peer_channel: None,
             message_queue: VecDeque::new(),
+            subscribers: Vec::new(),
+            subscriber_queues: BTreeMap::new(),
             max_queue_size: MAX_PENDING_MESSAGES,
             blocking_send: true,
             blocking_recv: true,
+            blocked_senders: VecDeque::new(),
+            sender_waker: wake_process,
         }
     }
     
+    /// Replace the sender wake-up hook (defaults to unblocking in the global process table)
+    pub fn set_sender_waker(&mut self, waker: fn(u64)) {
+        self.sender_waker = waker;
+    }
+    
     /// Connect to a peer process
     pub fn connect(&mut self, peer: u64) -> Result<(), IpcError> {
         if self.state != ChannelState::Connecting {
@@ -156,41 +212,160 @@ impl Channel {
             return Err(IpcError::MessageTooLarge);
         }
         
-        if self.message_queue.len() >= self.max_queue_size {
-            if self.blocking_send {
-                return Err(IpcError::WouldBlock);
-            } else {
-                // Drop oldest message
-                self.message_queue.pop_front();
-            }
+        if self.channel_type == ChannelType::Broadcast {
+            return self.fan_out(message);
+        }
+        
+        self.enqueue(message)
+    }
+    
+    /// Place a message on this channel's inbound queue
+    fn enqueue(&mut self, message: Message) -> Result<(), IpcError> {
+        push_bounded(&mut self.message_queue, message, self.max_queue_size, self.blocking_send)
+    }
+    
+    /// Remember a sender to wake once a queue slot frees up
+    ///
+    /// `pid` comes from the kernel's view of the caller, never from a
+    /// message header, which the sender controls.
+    fn wait_for_slot(&mut self, pid: u64) {
+        if !self.blocked_senders.contains(&pid) {
+            self.blocked_senders.push_back(pid);
+        }
+    }
+    
+    /// A receive freed a slot: wake the longest-waiting sender
+    fn slot_freed(&mut self) {
+        if let Some(pid) = self.blocked_senders.pop_front() {
+            (self.sender_waker)(pid);
+        }
+    }
+    
+    /// Pass a dequeued message through, waking a sender if it freed a slot
+    fn received(&mut self, message: Option<Message>) -> Option<Message> {
+        if message.is_some() {
+            self.slot_freed();
+        }
+        message
+    }
+    
+    /// Copy a message into every subscriber's queue
+    ///
+    /// Each queue is bounded on its own. With `blocking_send`, a single full
+    /// subscriber blocks the whole send; otherwise only that subscriber
+    /// loses its oldest message.
+    fn fan_out(&mut self, message: Message) -> Result<(), IpcError> {
+        if self.blocking_send
+            && self.subscriber_queues.values().any(|q| q.len() >= self.max_queue_size)
+        {
+            return Err(IpcError::WouldBlock);
+        }
+        
+        for queue in self.subscriber_queues.values_mut() {
+            push_bounded(queue, message.clone(), self.max_queue_size, self.blocking_send)?;
+        }
+        Ok(())
+    }
+    
+    /// Subscribe a process to a broadcast channel
+    pub fn subscribe(&mut self, pid: u64) -> Result<(), IpcError> {
+        if self.channel_type != ChannelType::Broadcast {
+            return Err(IpcError::InvalidState);
+        }
+        if self.state == ChannelState::Closed {
+            return Err(IpcError::ChannelClosed);
+        }
+        if self.subscribers.contains(&pid) {
+            return Ok(());
         }
         
-        self.message_queue.push_back(message);
+        self.subscribers.push(pid);
+        self.subscriber_queues.insert(pid, VecDeque::new());
+        self.state = ChannelState::Connected;
         Ok(())
     }
     
+    /// Remove a subscriber, discarding its undelivered messages
+    pub fn unsubscribe(&mut self, pid: u64) -> Result<(), IpcError> {
+        if self.subscriber_queues.remove(&pid).is_none() {
+            return Err(IpcError::ResourceNotFound);
+        }
+        self.subscribers.retain(|&p| p != pid);
+        Ok(())
+    }
+    
+    /// Receive the next broadcast message for a subscriber
+    pub fn recv_subscriber(&mut self, pid: u64) -> Result<Message, IpcError> {
+        let queue = self.subscriber_queues.get_mut(&pid).ok_or(IpcError::PermissionDenied)?;
+        let message = queue.pop_front();
+        self.received(message).ok_or(IpcError::NoMessage)
+    }
+    
     /// Receive a message from the channel
+    ///
+    /// Freeing a slot in a full queue wakes the oldest sender it turned away.
     pub fn recv(&mut self) -> Result<Message, IpcError> {
-        if let Some(msg) = self.message_queue.pop_front() {
+        let message = self.message_queue.pop_front();
+        if let Some(msg) = self.received(message) {
             Ok(msg)
-        } else if self.state == ChannelState::Closed {
-            Err(IpcError::ChannelClosed)
+        } else {
+            Err(self.nothing_to_recv())
+        }
+    }
+    
+    /// Receive the oldest message whose type shares a bit with `type_mask`
+    ///
+    /// Messages of other types stay queued in their original order, so
+    /// requests and responses can be multiplexed over one channel by
+    /// giving each kind its own type bit.
+    pub fn recv_filtered(&mut self, type_mask: u32) -> Result<Message, IpcError> {
+        let position = self.message_queue
+            .iter()
+            .position(|m| m.header.msg_type & type_mask != 0);
+        let message = position.and_then(|i| self.message_queue.remove(i));
+        if let Some(msg) = self.received(message) {
+            Ok(msg)
+        } else {
+            Err(self.nothing_to_recv())
+        }
+    }
+    
+    /// Why a receive found nothing
+    fn nothing_to_recv(&self) -> IpcError {
+        if self.state == ChannelState::Closed {
+            IpcError::ChannelClosed
         } else if self.blocking_recv {
-            Err(IpcError::WouldBlock)
+            IpcError::WouldBlock
         } else {
-            Err(IpcError::NoMessage)
+            IpcError::NoMessage
         }
     }
     
     /// Try to receive without blocking
     pub fn try_recv(&mut self) -> Result<Message, IpcError> {
-        self.message_queue.pop_front().ok_or(IpcError::NoMessage)
+        let message = self.message_queue.pop_front();
+        self.received(message).ok_or(IpcError::NoMessage)
+    }
+    
+    /// Process that receives from the channel's inbound queue
+    ///
+    /// A paired channel's queue belongs to its owner; otherwise the
+    /// connected peer reads what the owner sends.
+    pub fn receiver(&self) -> u64 {
+        match (self.peer_channel, self.peer) {
+            (None, Some(peer)) => peer,
+            _ => self.owner,
+        }
     }
     
-    /// Close the channel
+    /// Close the channel, waking every blocked sender to see it closed
     pub fn close(&mut self) {
         self.state = ChannelState::Closed;
         self.peer = None;
+        self.peer_channel = None;
+        while !self.blocked_senders.is_empty() {
+            self.slot_freed();
+        }
     }
     
     /// Check if channel has pending messages
@@ -202,6 +377,45 @@ impl Channel {
     pub fn pending_count(&self) -> usize {
         self.message_queue.len()
     }
+    
+    /// Drop expired messages from every queue, returning how many were dropped
+    pub fn expire_messages(&mut self, now: u64) -> usize {
+        let before = self.message_queue.len();
+        self.message_queue.retain(|m| !m.is_expired(now));
+        let mut dropped = before - self.message_queue.len();
+        
+        for queue in self.subscriber_queues.values_mut() {
+            let before = queue.len();
+            queue.retain(|m| !m.is_expired(now));
+            dropped += before - queue.len();
+        }
+        dropped
+    }
+}
+
+/// Make a process blocked on a full channel runnable again
+fn wake_process(pid: u64) {
+    let _ = PROCESS_TABLE.unblock(pid);
+}
+
+/// Push onto a bounded queue, blocking or dropping the oldest when full
+fn push_bounded(
+    queue: &mut VecDeque<Message>,
+    message: Message,
+    max_queue_size: usize,
+    blocking_send: bool,
+) -> Result<(), IpcError> {
+    if queue.len() >= max_queue_size {
+        if blocking_send {
+            return Err(IpcError::WouldBlock);
+        } else {
+            // Drop oldest message
+            queue.pop_front();
+        }
+    }
+    
+    queue.push_back(message);
+    Ok(())
 }
 
 /// Shared memory region
@@ -215,6 +429,9 @@ pub struct SharedMemory {
     pub mapped_processes: Vec<u64>,
 }
 
+// The base address names physical memory, not memory owned by this value
+unsafe impl Send for SharedMemory {}
+
 /// Shared memory permissions
 #[derive(Debug, Clone, Copy)]
 pub struct SharedMemoryPermissions {
@@ -265,11 +482,184 @@ impl SharedMemory {
     }
 }
 
+/// Magic value identifying an initialized ring ("RING")
+const RING_MAGIC: u32 = 0x52494E47;
+/// Length prefix stored before each frame
+const FRAME_PREFIX: usize = core::mem::size_of::<u32>();
+
+/// Ring buffer header, stored at the start of the shared region
+#[repr(C)]
+struct RingHeader {
+    magic: AtomicU32,
+    /// Total bytes ever written (producer-owned)
+    head: AtomicUsize,
+    /// Total bytes ever consumed (consumer-owned)
+    tail: AtomicUsize,
+    /// Size of the data area following the header
+    capacity: usize,
+}
+
+/// Single-producer/single-consumer frame ring over a shared memory region
+///
+/// Each process maps the same region and holds its own handle; the
+/// producer only advances `head` and the consumer only advances `tail`,
+/// so no locking is needed.
+pub struct SharedRingBuffer {
+    header: *const RingHeader,
+    data: *mut u8,
+    capacity: usize,
+}
+
+unsafe impl Send for SharedRingBuffer {}
+
+impl SharedRingBuffer {
+    /// Initialize a ring in a writable shared memory region
+    ///
+    /// # Safety
+    /// `shm.base_address` must point to `shm.size` bytes that stay mapped
+    /// for the lifetime of every handle.
+    pub unsafe fn create(shm: &SharedMemory) -> Result<Self, IpcError> {
+        if !shm.permissions.writable {
+            return Err(IpcError::PermissionDenied);
+        }
+        let ring = Self::from_region(shm)?;
+        
+        let header = &*ring.header;
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(0, Ordering::Relaxed);
+        (*(ring.header as *mut RingHeader)).capacity = ring.capacity;
+        header.magic.store(RING_MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+    
+    /// Attach to a ring another process already initialized
+    ///
+    /// # Safety
+    /// Same requirements as [`SharedRingBuffer::create`].
+    pub unsafe fn attach(shm: &SharedMemory) -> Result<Self, IpcError> {
+        let ring = Self::from_region(shm)?;
+        let header = &*ring.header;
+        
+        if header.magic.load(Ordering::Acquire) != RING_MAGIC || header.capacity != ring.capacity {
+            return Err(IpcError::InvalidState);
+        }
+        Ok(ring)
+    }
+    
+    unsafe fn from_region(shm: &SharedMemory) -> Result<Self, IpcError> {
+        let header_size = core::mem::size_of::<RingHeader>();
+        
+        if shm.base_address.is_null()
+            || (shm.base_address as usize) % core::mem::align_of::<RingHeader>() != 0
+        {
+            return Err(IpcError::InvalidState);
+        }
+        if shm.size <= header_size + FRAME_PREFIX {
+            return Err(IpcError::ResourceLimit);
+        }
+        
+        Ok(SharedRingBuffer {
+            header: shm.base_address as *const RingHeader,
+            data: shm.base_address.add(header_size),
+            capacity: shm.size - header_size,
+        })
+    }
+    
+    /// Append a frame (producer side)
+    pub fn push(&self, frame: &[u8]) -> Result<(), IpcError> {
+        if frame.len() > MAX_MESSAGE_SIZE {
+            return Err(IpcError::MessageTooLarge);
+        }
+        
+        let header = unsafe { &*self.header };
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Acquire);
+        let needed = FRAME_PREFIX + frame.len();
+        
+        // A frame larger than the whole ring would never fit, however
+        // much the consumer drains
+        if needed > self.capacity {
+            return Err(IpcError::MessageTooLarge);
+        }
+        if needed > self.capacity - (head - tail) {
+            return Err(IpcError::WouldBlock);
+        }
+        
+        self.write_at(head, &(frame.len() as u32).to_le_bytes());
+        self.write_at(head + FRAME_PREFIX, frame);
+        header.head.store(head + needed, Ordering::Release);
+        Ok(())
+    }
+    
+    /// Take the next frame into `buf` (consumer side), returning its length
+    pub fn pop(&self, buf: &mut [u8]) -> Result<usize, IpcError> {
+        let header = unsafe { &*self.header };
+        let tail = header.tail.load(Ordering::Relaxed);
+        let head = header.head.load(Ordering::Acquire);
+        
+        if head == tail {
+            return Err(IpcError::NoMessage);
+        }
+        
+        let mut prefix = [0u8; FRAME_PREFIX];
+        self.read_at(tail, &mut prefix);
+        let len = u32::from_le_bytes(prefix) as usize;
+        
+        // Leave the frame queued so the caller can retry with a larger buffer
+        if len > buf.len() {
+            return Err(IpcError::MessageTooLarge);
+        }
+        
+        self.read_at(tail + FRAME_PREFIX, &mut buf[..len]);
+        header.tail.store(tail + FRAME_PREFIX + len, Ordering::Release);
+        Ok(len)
+    }
+    
+    /// Bytes currently queued, including frame prefixes
+    pub fn used(&self) -> usize {
+        let header = unsafe { &*self.header };
+        header.head.load(Ordering::Acquire) - header.tail.load(Ordering::Acquire)
+    }
+    
+    /// Copy into the data area at a logical position, wrapping at the end
+    fn write_at(&self, pos: usize, bytes: &[u8]) {
+        let offset = pos % self.capacity;
+        let first = core::cmp::min(bytes.len(), self.capacity - offset);
+        unsafe {
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), self.data.add(offset), first);
+            core::ptr::copy_nonoverlapping(bytes.as_ptr().add(first), self.data, bytes.len() - first);
+        }
+    }
+    
+    /// Copy out of the data area at a logical position, wrapping at the end
+    fn read_at(&self, pos: usize, out: &mut [u8]) {
+        let offset = pos % self.capacity;
+        let first = core::cmp::min(out.len(), self.capacity - offset);
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.data.add(offset), out.as_mut_ptr(), first);
+            core::ptr::copy_nonoverlapping(self.data, out.as_mut_ptr().add(first), out.len() - first);
+        }
+    }
+}
+
 /// IPC manager
 pub struct IpcManager {
     channels: Vec<Channel>,
     next_channel_id: AtomicU64,
     shared_memory: Vec<SharedMemory>,
+    /// Well-known service names for channel discovery
+    names: BTreeMap<String, ChannelId>,
+    /// Resolves a PID to its capabilities for permission checks
+    capability_lookup: fn(u64) -> Option<Capabilities>,
+    /// Hands a capability attached by the sender (first PID) to the receiver
+    delegate: fn(u64, CapabilityHandle, u64) -> Result<CapabilityHandle, SypasError>,
+    /// Checks that a sender owns the capability it attaches
+    owns_capability: fn(u64, CapabilityHandle) -> bool,
+}
+
+/// Look up a process's capabilities in the global process table
+fn process_capabilities(pid: u64) -> Option<Capabilities> {
+    PROCESS_TABLE.get_process(pid).map(|p| p.capabilities)
 }
 
 impl IpcManager {
@@ -278,15 +668,59 @@ impl IpcManager {
             channels: Vec::new(),
             next_channel_id: AtomicU64::new(1),
             shared_memory: Vec::new(),
+            names: BTreeMap::new(),
+            capability_lookup: process_capabilities,
+            delegate: sypas::delegate_owned_capability,
+            owns_capability: sypas::owns_capability,
+        }
+    }
+    
+    /// Replace the delegation of attached capabilities (defaults to SYPAS)
+    pub fn set_delegate(&mut self, delegate: fn(u64, CapabilityHandle, u64) -> Result<CapabilityHandle, SypasError>) {
+        self.delegate = delegate;
+    }
+    
+    /// Replace the ownership check on attached capabilities (defaults to SYPAS)
+    pub fn set_ownership_check(&mut self, owns: fn(u64, CapabilityHandle) -> bool) {
+        self.owns_capability = owns;
+    }
+    
+    /// Pass a received message's attached capability on to `receiver`
+    ///
+    /// The capability is delegated from the sender recorded at send time.
+    /// SYPAS delegation limits apply; a refused capability is dropped and
+    /// the message is delivered without it.
+    fn deliver(&self, mut message: Message, receiver: u64) -> Message {
+        if let Some(handle) = message.attached_cap {
+            message.attached_cap = message.sender
+                .and_then(|sender| (self.delegate)(sender, handle, receiver).ok());
+        }
+        message
+    }
+    
+    /// Replace the capability source (defaults to the global process table)
+    pub fn set_capability_lookup(&mut self, lookup: fn(u64) -> Option<Capabilities>) {
+        self.capability_lookup = lookup;
+    }
+    
+    /// Check that a process holds a capability (Admin always passes)
+    fn require_capability(&self, pid: u64, cap: Capability) -> Result<(), IpcError> {
+        match (self.capability_lookup)(pid) {
+            Some(caps) if caps.has(cap) => Ok(()),
+            _ => Err(IpcError::PermissionDenied),
         }
     }
     
     /// Create a new channel
+    ///
+    /// The owner must hold `IpcCreate`.
     pub fn create_channel(
         &mut self,
         owner: u64,
         channel_type: ChannelType,
     ) -> Result<ChannelId, IpcError> {
+        self.require_capability(owner, Capability::IpcCreate)?;
+        
         let id = ChannelId(self.next_channel_id.fetch_add(1, Ordering::SeqCst));
         let channel = Channel::new(id, owner, channel_type);
         self.channels.push(channel);
@@ -298,6 +732,53 @@ impl IpcManager {
         self.channels.iter_mut().find(|c| c.id == id)
     }
     
+    /// Index of a channel in the table
+    fn channel_index(&self, id: ChannelId) -> Result<usize, IpcError> {
+        self.channels
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or(IpcError::ChannelNotFound)
+    }
+    
+    /// Connect a process to a channel as its peer
+    ///
+    /// The joining process must hold `IpcJoin`.
+    pub fn connect(&mut self, channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+        self.require_capability(pid, Capability::IpcJoin)?;
+        self.get_channel(channel_id)
+            .ok_or(IpcError::ChannelNotFound)?
+            .connect(pid)
+    }
+    
+    /// Link two channels so each one's sends arrive on the other's queue
+    ///
+    /// Both owners must hold `IpcJoin`.
+    pub fn connect_pair(&mut self, a: ChannelId, b: ChannelId) -> Result<(), IpcError> {
+        let a_idx = self.channel_index(a)?;
+        let b_idx = self.channel_index(b)?;
+        
+        if a_idx == b_idx {
+            return Err(IpcError::InvalidState);
+        }
+        
+        let a_owner = self.channels[a_idx].owner;
+        let b_owner = self.channels[b_idx].owner;
+        self.require_capability(a_owner, Capability::IpcJoin)?;
+        self.require_capability(b_owner, Capability::IpcJoin)?;
+        
+        self.channels[a_idx].connect(b_owner)?;
+        if let Err(e) = self.channels[b_idx].connect(a_owner) {
+            // Roll back the first half
+            self.channels[a_idx].peer = None;
+            self.channels[a_idx].state = ChannelState::Connecting;
+            return Err(e);
+        }
+        
+        self.channels[a_idx].peer_channel = Some(b);
+        self.channels[b_idx].peer_channel = Some(a);
+        Ok(())
+    }
+    
     /// Close and remove a channel
     pub fn close_channel(&mut self, id: ChannelId) -> Result<(), IpcError> {
         if let Some(channel) = self.get_channel(id) {
@@ -308,22 +789,192 @@ impl IpcManager {
         }
     }
     
+    /// Close a channel and drop it from the table, releasing its names
+    pub fn destroy_channel(&mut self, id: ChannelId) -> Result<(), IpcError> {
+        let idx = self.channel_index(id)?;
+        self.channels.remove(idx).close();
+        self.names.retain(|_, named| *named != id);
+        Ok(())
+    }
+    
+    /// Create a channel that is destroyed when the returned guard drops
+    pub fn create_channel_guarded(
+        manager: &Mutex<IpcManager>,
+        owner: u64,
+        channel_type: ChannelType,
+    ) -> Result<ChannelGuard<'_>, IpcError> {
+        let id = manager.lock().create_channel(owner, channel_type)?;
+        Ok(ChannelGuard { id, manager })
+    }
+    
     /// Send message through channel
+    ///
+    /// On a paired channel the message is delivered to the peer's inbound
+    /// queue; otherwise it is queued on the channel itself. A full queue
+    /// gives `WouldBlock`; callers that then block use [`Self::send_from`]
+    /// so a receive wakes them.
+    ///
+    /// Capabilities can only be attached through `send_from`, which knows
+    /// who is sending; here they fail with `PermissionDenied`.
     pub fn send(&mut self, channel_id: ChannelId, message: Message) -> Result<(), IpcError> {
-        if let Some(channel) = self.get_channel(channel_id) {
-            channel.send(message)
-        } else {
-            Err(IpcError::ChannelNotFound)
+        if message.attached_cap.is_some() {
+            return Err(IpcError::PermissionDenied);
+        }
+        self.route(channel_id, message)
+    }
+    
+    /// Queue a message on the channel that receives it
+    fn route(&mut self, channel_id: ChannelId, message: Message) -> Result<(), IpcError> {
+        let idx = self.channel_index(channel_id)?;
+        
+        let peer_channel = match self.channels[idx].peer_channel {
+            Some(peer_channel) => peer_channel,
+            None => return self.channels[idx].send(message),
+        };
+        
+        if self.channels[idx].state != ChannelState::Connected {
+            return Err(IpcError::ChannelClosed);
         }
+        if message.payload.len() > MAX_MESSAGE_SIZE {
+            return Err(IpcError::MessageTooLarge);
+        }
+        
+        let peer_idx = self.channel_index(peer_channel).map_err(|_| IpcError::ChannelClosed)?;
+        if self.channels[peer_idx].state != ChannelState::Connected {
+            return Err(IpcError::ChannelClosed);
+        }
+        self.channels[peer_idx].enqueue(message)
+    }
+    
+    /// Send on behalf of process `sender`, which is woken once a slot frees
+    /// up if this returns `WouldBlock`
+    ///
+    /// An attached capability must be owned by `sender`, or the send fails
+    /// with `PermissionDenied`; the receiver inherits it from `sender`
+    /// whatever the header claims.
+    pub fn send_from(&mut self, sender: u64, channel_id: ChannelId, mut message: Message) -> Result<(), IpcError> {
+        if let Some(handle) = message.attached_cap {
+            if !(self.owns_capability)(sender, handle) {
+                return Err(IpcError::PermissionDenied);
+            }
+        }
+        message.sender = Some(sender);
+        
+        let result = self.route(channel_id, message);
+        if result == Err(IpcError::WouldBlock) {
+            let idx = self.channel_index(channel_id)?;
+            let idx = match self.channels[idx].peer_channel {
+                Some(peer_channel) => self.channel_index(peer_channel)?,
+                None => idx,
+            };
+            self.channels[idx].wait_for_slot(sender);
+        }
+        result
+    }
+    
+    /// Send a message that expires `ttl_ms` after `now`
+    pub fn send_with_ttl(
+        &mut self,
+        channel_id: ChannelId,
+        mut message: Message,
+        ttl_ms: u64,
+        now: u64,
+    ) -> Result<(), IpcError> {
+        message.header.timestamp = now;
+        message.header.deadline = Some(now.saturating_add(ttl_ms));
+        self.send(channel_id, message)
+    }
+    
+    /// Drop expired messages from all channels, returning how many were dropped
+    pub fn expire_messages(&mut self, now: u64) -> usize {
+        self.channels.iter_mut().map(|c| c.expire_messages(now)).sum()
     }
     
     /// Receive message from channel
+    ///
+    /// An attached capability is delegated to the channel's receiver.
     pub fn recv(&mut self, channel_id: ChannelId) -> Result<Message, IpcError> {
-        if let Some(channel) = self.get_channel(channel_id) {
-            channel.recv()
-        } else {
-            Err(IpcError::ChannelNotFound)
+        let channel = self.get_channel(channel_id).ok_or(IpcError::ChannelNotFound)?;
+        let receiver = channel.receiver();
+        let message = channel.recv()?;
+        Ok(self.deliver(message, receiver))
+    }
+    
+    /// Receive the oldest message matching `type_mask`; see [`Channel::recv_filtered`]
+    pub fn recv_filtered(&mut self, channel_id: ChannelId, type_mask: u32) -> Result<Message, IpcError> {
+        let channel = self.get_channel(channel_id).ok_or(IpcError::ChannelNotFound)?;
+        let receiver = channel.receiver();
+        let message = channel.recv_filtered(type_mask)?;
+        Ok(self.deliver(message, receiver))
+    }
+    
+    /// Report which of the given channels have pending messages
+    ///
+    /// Returns each ready channel with its pending count, in the order
+    /// given. Unknown channel IDs are ignored.
+    pub fn poll(&self, channel_ids: &[ChannelId]) -> Vec<(ChannelId, usize)> {
+        channel_ids
+            .iter()
+            .filter_map(|&id| self.channels.iter().find(|c| c.id == id))
+            .filter(|c| c.has_messages())
+            .map(|c| (c.id, c.pending_count()))
+            .collect()
+    }
+    
+    /// Return the first of the given channels with a pending message
+    ///
+    /// Returns `WouldBlock` when none are ready; the caller should yield
+    /// and retry.
+    pub fn select(&self, channel_ids: &[ChannelId]) -> Result<ChannelId, IpcError> {
+        channel_ids
+            .iter()
+            .find(|&&id| self.channels.iter().any(|c| c.id == id && c.has_messages()))
+            .copied()
+            .ok_or(IpcError::WouldBlock)
+    }
+    
+    /// Publish a channel under a well-known name
+    ///
+    /// Returns `ResourceLimit` if the name is already taken.
+    pub fn register_name(&mut self, channel_id: ChannelId, name: &str) -> Result<(), IpcError> {
+        self.channel_index(channel_id)?;
+        
+        if self.names.contains_key(name) {
+            return Err(IpcError::ResourceLimit);
         }
+        
+        self.names.insert(String::from(name), channel_id);
+        Ok(())
+    }
+    
+    /// Resolve a well-known name to its channel
+    pub fn lookup_name(&self, name: &str) -> Option<ChannelId> {
+        self.names.get(name).copied()
+    }
+    
+    /// Subscribe a process to a broadcast channel
+    ///
+    /// The subscriber must hold `IpcJoin`.
+    pub fn subscribe(&mut self, channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+        self.require_capability(pid, Capability::IpcJoin)?;
+        self.get_channel(channel_id)
+            .ok_or(IpcError::ChannelNotFound)?
+            .subscribe(pid)
+    }
+    
+    /// Unsubscribe a process from a broadcast channel
+    pub fn unsubscribe(&mut self, channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+        self.get_channel(channel_id)
+            .ok_or(IpcError::ChannelNotFound)?
+            .unsubscribe(pid)
+    }
+    
+    /// Receive a broadcast message on behalf of a subscriber
+    pub fn recv_subscriber(&mut self, channel_id: ChannelId, pid: u64) -> Result<Message, IpcError> {
+        let message = self.get_channel(channel_id)
+            .ok_or(IpcError::ChannelNotFound)?
+            .recv_subscriber(pid)?;
+        Ok(self.deliver(message, pid))
     }
     
     /// Create shared memory region
@@ -332,12 +983,24 @@ impl IpcManager {
         owner: u64,
         size: usize,
     ) -> Result<u64, IpcError> {
+        self.require_capability(owner, Capability::IpcCreate)?;
+        
         let id = self.next_channel_id.fetch_add(1, Ordering::SeqCst);
         let shm = SharedMemory::new(id, owner, size);
         self.shared_memory.push(shm);
         Ok(id)
     }
     
+    /// Create a shared memory region that is destroyed when the returned guard drops
+    pub fn create_shared_memory_guarded(
+        manager: &Mutex<IpcManager>,
+        owner: u64,
+        size: usize,
+    ) -> Result<SharedMemoryGuard<'_>, IpcError> {
+        let id = manager.lock().create_shared_memory(owner, size)?;
+        Ok(SharedMemoryGuard { id, manager })
+    }
+    
     /// Get shared memory region
     pub fn get_shared_memory(&mut self, id: u64) -> Option<&mut SharedMemory> {
         self.shared_memory.iter_mut().find(|s| s.id == id)
@@ -356,8 +1019,23 @@ impl IpcManager {
     
     /// Clean up resources for a terminated process
     pub fn cleanup_process(&mut self, process_id: u64) {
-        // Close channels owned by this process
-        self.channels.retain(|c| c.owner != process_id);
+        // Close channels owned by this process, waking anyone blocked on them
+        let (owned, kept): (Vec<Channel>, Vec<Channel>) = core::mem::take(&mut self.channels)
+            .into_iter()
+            .partition(|c| c.owner == process_id);
+        self.channels = kept;
+        for mut channel in owned {
+            channel.close();
+        }
+        
+        // Release names that pointed at those channels
+        let channels = &self.channels;
+        self.names.retain(|_, id| channels.iter().any(|c| c.id == *id));
+        
+        // Drop its broadcast subscriptions
+        for channel in &mut self.channels {
+            let _ = channel.unsubscribe(process_id);
+        }
         
         // Unmap shared memory
         for shm in &mut self.shared_memory {
@@ -366,6 +1044,63 @@ impl IpcManager {
     }
 }
 
+/// Destroys its channel when dropped
+///
+/// The manager is locked on drop, so a guard must not be dropped while
+/// the same manager is already locked.
+#[must_use = "the channel is destroyed as soon as the guard is dropped"]
+pub struct ChannelGuard<'a> {
+    id: ChannelId,
+    manager: &'a Mutex<IpcManager>,
+}
+
+impl ChannelGuard<'_> {
+    pub fn id(&self) -> ChannelId {
+        self.id
+    }
+    
+    /// Keep the channel alive past the guard, handing back its ID
+    pub fn release(self) -> ChannelId {
+        let id = self.id;
+        core::mem::forget(self);
+        id
+    }
+}
+
+impl Drop for ChannelGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.manager.lock().destroy_channel(self.id);
+    }
+}
+
+/// Destroys its shared memory region when dropped
+///
+/// The same locking caveat as [`ChannelGuard`] applies.
+#[must_use = "the region is destroyed as soon as the guard is dropped"]
+pub struct SharedMemoryGuard<'a> {
+    id: u64,
+    manager: &'a Mutex<IpcManager>,
+}
+
+impl SharedMemoryGuard<'_> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    
+    /// Keep the region alive past the guard, handing back its ID
+    pub fn release(self) -> u64 {
+        let id = self.id;
+        core::mem::forget(self);
+        id
+    }
+}
+
+impl Drop for SharedMemoryGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.manager.lock().destroy_shared_memory(self.id);
+    }
+}
+
 /// IPC errors
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IpcError {
@@ -380,84 +1115,167 @@ pub enum IpcError {
     ResourceLimit,
 }
 
-/// Global IPC manager
-static mut IPC_MANAGER: Option<IpcManager> = None;
+impl core::fmt::Display for IpcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IpcError::ChannelNotFound => write!(f, "Channel not found"),
+            IpcError::ChannelClosed => write!(f, "Channel closed"),
+            IpcError::InvalidState => write!(f, "Invalid channel state"),
+            IpcError::MessageTooLarge => write!(f, "Message too large"),
+            IpcError::WouldBlock => write!(f, "Operation would block"),
+            IpcError::NoMessage => write!(f, "No message available"),
+            IpcError::PermissionDenied => write!(f, "Permission denied"),
+            IpcError::ResourceNotFound => write!(f, "IPC resource not found"),
+            IpcError::ResourceLimit => write!(f, "IPC resource limit reached"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IpcError {}
+
+/// Global IPC manager, created once by `init`
+static IPC_MANAGER: Once<Mutex<IpcManager>> = Once::new();
 
 /// Initialize IPC subsystem
+///
+/// Only the first call creates the manager; later calls leave it intact.
 pub fn init() {
-    unsafe {
-        IPC_MANAGER = Some(IpcManager::new());
-    }
+    IPC_MANAGER.call_once(|| Mutex::new(IpcManager::new()));
+}
+
+/// Run `f` with the global manager locked, or `None` before `init`
+fn with_manager<R>(f: impl FnOnce(&mut IpcManager) -> R) -> Option<R> {
+    IPC_MANAGER.get().map(|manager| f(&mut manager.lock()))
 }
 
 /// Create a channel
 pub fn create_channel(owner: u64, channel_type: ChannelType) -> Result<ChannelId, IpcError> {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.create_channel(owner, channel_type)
-        } else {
-            Err(IpcError::ResourceNotFound)
-        }
-    }
+    with_manager(|manager| manager.create_channel(owner, channel_type))
+        .unwrap_or(Err(IpcError::ResourceNotFound))
 }
 
 /// Send message
 pub fn send(channel_id: ChannelId, message: Message) -> Result<(), IpcError> {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.send(channel_id, message)
-        } else {
-            Err(IpcError::ChannelNotFound)
-        }
-    }
+    with_manager(|manager| manager.send(channel_id, message))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Send message on behalf of `sender`, waking it when a full queue drains
+pub fn send_from(sender: u64, channel_id: ChannelId, message: Message) -> Result<(), IpcError> {
+    with_manager(|manager| manager.send_from(sender, channel_id, message))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
 }
 
 /// Receive message
 pub fn recv(channel_id: ChannelId) -> Result<Message, IpcError> {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.recv(channel_id)
-        } else {
-            Err(IpcError::ChannelNotFound)
-        }
-    }
+    with_manager(|manager| manager.recv(channel_id))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Connect a process to a channel
+pub fn connect(channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+    with_manager(|manager| manager.connect(channel_id, pid))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Pair two channels
+pub fn connect_pair(a: ChannelId, b: ChannelId) -> Result<(), IpcError> {
+    with_manager(|manager| manager.connect_pair(a, b))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Poll several channels for pending messages
+pub fn poll(channel_ids: &[ChannelId]) -> Vec<(ChannelId, usize)> {
+    with_manager(|manager| manager.poll(channel_ids))
+        .unwrap_or_else(Vec::new)
+}
+
+/// Wait on several channels, returning the first one that is ready
+pub fn select(channel_ids: &[ChannelId]) -> Result<ChannelId, IpcError> {
+    with_manager(|manager| manager.select(channel_ids))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Publish a channel under a well-known name
+pub fn register_name(channel_id: ChannelId, name: &str) -> Result<(), IpcError> {
+    with_manager(|manager| manager.register_name(channel_id, name))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Resolve a well-known channel name
+pub fn lookup_name(name: &str) -> Option<ChannelId> {
+    with_manager(|manager| manager.lookup_name(name)).flatten()
+}
+
+/// Subscribe to a broadcast channel
+pub fn subscribe(channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+    with_manager(|manager| manager.subscribe(channel_id, pid))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Unsubscribe from a broadcast channel
+pub fn unsubscribe(channel_id: ChannelId, pid: u64) -> Result<(), IpcError> {
+    with_manager(|manager| manager.unsubscribe(channel_id, pid))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
 }
 
 /// Close channel
 pub fn close_channel(channel_id: ChannelId) -> Result<(), IpcError> {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.close_channel(channel_id)
-        } else {
-            Err(IpcError::ChannelNotFound)
-        }
-    }
+    with_manager(|manager| manager.close_channel(channel_id))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Close channel and remove it from the manager
+pub fn destroy_channel(channel_id: ChannelId) -> Result<(), IpcError> {
+    with_manager(|manager| manager.destroy_channel(channel_id))
+        .unwrap_or(Err(IpcError::ChannelNotFound))
+}
+
+/// Create a channel destroyed when its guard drops
+pub fn create_channel_guarded(
+    owner: u64,
+    channel_type: ChannelType,
+) -> Result<ChannelGuard<'static>, IpcError> {
+    let manager = IPC_MANAGER.get().ok_or(IpcError::ChannelNotFound)?;
+    IpcManager::create_channel_guarded(manager, owner, channel_type)
 }
 
 /// Create shared memory
 pub fn create_shared_memory(owner: u64, size: usize) -> Result<u64, IpcError> {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.create_shared_memory(owner, size)
-        } else {
-            Err(IpcError::ResourceNotFound)
-        }
-    }
+    with_manager(|manager| manager.create_shared_memory(owner, size))
+        .unwrap_or(Err(IpcError::ResourceNotFound))
+}
+
+/// Create shared memory destroyed when its guard drops
+pub fn create_shared_memory_guarded(owner: u64, size: usize) -> Result<SharedMemoryGuard<'static>, IpcError> {
+    let manager = IPC_MANAGER.get().ok_or(IpcError::ResourceNotFound)?;
+    IpcManager::create_shared_memory_guarded(manager, owner, size)
 }
 
 /// Cleanup process resources
 pub fn cleanup_process(process_id: u64) {
-    unsafe {
-        if let Some(ref mut manager) = IPC_MANAGER {
-            manager.cleanup_process(process_id);
-        }
-    }
+    with_manager(|manager| manager.cleanup_process(process_id));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Every process may create and join channels
+    fn ipc_caps(_pid: u64) -> Option<Capabilities> {
+        let mut caps = Capabilities::new();
+        caps.set(Capability::IpcCreate);
+        caps.set(Capability::IpcJoin);
+        Some(caps)
+    }
+
+    fn test_manager() -> IpcManager {
+        let mut manager = IpcManager::new();
+        manager.set_capability_lookup(ipc_caps);
+        manager
+    }
+
     #[test]
     fn test_channel_id() {
         let id = ChannelId::new(42);
@@ -497,6 +1315,28 @@ mod tests {
         assert_eq!(channel.state, ChannelState::Closed);
     }
 
+    #[test]
+    fn test_recv_filtered() {
+        const REQUEST: u32 = 0x1;
+        const RESPONSE: u32 = 0x2;
+        const EVENT: u32 = 0x4;
+        
+        let mut channel = Channel::new(ChannelId::new(1), 1, ChannelType::Unidirectional);
+        channel.connect(2).unwrap();
+        for (msg_type, payload) in [(REQUEST, b"req1"), (EVENT, b"evt1"), (RESPONSE, b"rsp1"), (REQUEST, b"req2")] {
+            channel.send(Message::new(2, 1, msg_type, payload)).unwrap();
+        }
+        
+        assert_eq!(channel.recv_filtered(RESPONSE).unwrap().payload, b"rsp1");
+        assert!(matches!(channel.recv_filtered(RESPONSE), Err(IpcError::WouldBlock)));
+        
+        // A mask can select several types; the others keep their order
+        assert_eq!(channel.recv_filtered(RESPONSE | EVENT).unwrap().payload, b"evt1");
+        assert_eq!(channel.pending_count(), 2);
+        assert_eq!(channel.recv().unwrap().payload, b"req1");
+        assert_eq!(channel.recv().unwrap().payload, b"req2");
+    }
+
     #[test]
     fn test_message_size_limit() {
         let mut channel = Channel::new(ChannelId::new(1), 1, ChannelType::Unidirectional);
@@ -509,6 +1349,446 @@ mod tests {
         assert!(matches!(channel.send(msg), Err(IpcError::MessageTooLarge)));
     }
 
+    #[test]
+    fn test_bidirectional_pair_delivers_to_peer() {
+        let mut manager = test_manager();
+        let a = manager.create_channel(1, ChannelType::Bidirectional).unwrap();
+        let b = manager.create_channel(2, ChannelType::Bidirectional).unwrap();
+        manager.connect_pair(a, b).unwrap();
+        
+        // Process 1 sends on its end, process 2 receives on the other
+        manager.send(a, Message::new(1, 2, 0, b"ping")).unwrap();
+        assert_eq!(manager.recv(b).unwrap().payload, b"ping");
+        
+        // Nothing was looped back to the sender
+        assert!(matches!(manager.recv(a), Err(IpcError::WouldBlock)));
+        
+        // And the reply path works the same way
+        manager.send(b, Message::new(2, 1, 0, b"pong")).unwrap();
+        assert_eq!(manager.recv(a).unwrap().payload, b"pong");
+        
+        // Once one side closes, the other can no longer deliver
+        manager.close_channel(b).unwrap();
+        assert!(matches!(
+            manager.send(a, Message::new(1, 2, 0, b"late")),
+            Err(IpcError::ChannelClosed)
+        ));
+    }
+
+    #[test]
+    fn test_broadcast_fan_out() {
+        let mut manager = test_manager();
+        let bus = manager.create_channel(1, ChannelType::Broadcast).unwrap();
+        for pid in [10, 11, 12] {
+            manager.subscribe(bus, pid).unwrap();
+        }
+        
+        manager.send(bus, Message::new(1, 0, 0, b"hello all")).unwrap();
+        for pid in [10, 11, 12] {
+            assert_eq!(manager.recv_subscriber(bus, pid).unwrap().payload, b"hello all");
+        }
+        
+        // Non-subscribers have no queue
+        assert!(matches!(manager.recv_subscriber(bus, 99), Err(IpcError::PermissionDenied)));
+    }
+
+    #[test]
+    fn test_broadcast_slow_subscriber_overflows_alone() {
+        let mut manager = test_manager();
+        let bus = manager.create_channel(1, ChannelType::Broadcast).unwrap();
+        for pid in [10, 11, 12] {
+            manager.subscribe(bus, pid).unwrap();
+        }
+        {
+            let channel = manager.get_channel(bus).unwrap();
+            channel.max_queue_size = 4;
+            channel.blocking_send = false;
+        }
+        
+        // 10 and 11 keep up, 12 never drains
+        for i in 0u8..6 {
+            manager.send(bus, Message::new(1, 0, 0, &[i])).unwrap();
+            assert_eq!(manager.recv_subscriber(bus, 10).unwrap().payload, [i]);
+            assert_eq!(manager.recv_subscriber(bus, 11).unwrap().payload, [i]);
+        }
+        
+        // The slow subscriber lost only its own two oldest messages
+        for i in 2u8..6 {
+            assert_eq!(manager.recv_subscriber(bus, 12).unwrap().payload, [i]);
+        }
+        assert!(matches!(manager.recv_subscriber(bus, 12), Err(IpcError::NoMessage)));
+        
+        // In blocking mode a full subscriber stalls the send instead
+        manager.get_channel(bus).unwrap().blocking_send = true;
+        for i in 0u8..4 {
+            manager.send(bus, Message::new(1, 0, 0, &[i])).unwrap();
+        }
+        assert!(matches!(
+            manager.send(bus, Message::new(1, 0, 0, b"x")),
+            Err(IpcError::WouldBlock)
+        ));
+    }
+
+    #[test]
+    fn test_backpressure_wakes_blocked_sender() {
+        use crate::process::{Priority, ProcessState, ProcessTable, KERNEL_PID};
+        
+        static TABLE: ProcessTable = ProcessTable::new();
+        fn wake(pid: u64) {
+            TABLE.unblock(pid).unwrap();
+        }
+        
+        TABLE.init();
+        let sender = TABLE.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        TABLE.context_switch(sender);
+        
+        let mut manager = test_manager();
+        let id = manager.create_channel(1, ChannelType::Unidirectional).unwrap();
+        manager.connect(id, 2).unwrap();
+        {
+            let channel = manager.get_channel(id).unwrap();
+            channel.max_queue_size = 2;
+            channel.set_sender_waker(wake);
+        }
+        
+        for i in 0u8..2 {
+            manager.send(id, Message::new(sender, 2, 0, &[i])).unwrap();
+        }
+        // A plain send leaves nobody waiting
+        assert_eq!(manager.send(id, Message::new(sender, 2, 0, b"x")), Err(IpcError::WouldBlock));
+        assert!(manager.get_channel(id).unwrap().blocked_senders.is_empty());
+        
+        // The header's source is the sender's to forge; the caller's PID
+        // is what gets woken
+        assert_eq!(
+            manager.send_from(sender, id, Message::new(KERNEL_PID, 2, 0, b"x")),
+            Err(IpcError::WouldBlock)
+        );
+        assert_eq!(manager.get_channel(id).unwrap().blocked_senders, [sender]);
+        TABLE.block(sender).unwrap();
+        assert_eq!(TABLE.get_process(sender).unwrap().state, ProcessState::Blocked);
+        
+        // Draining one message frees a slot and wakes the sender to retry
+        assert_eq!(manager.recv(id).unwrap().payload, [0]);
+        assert_eq!(TABLE.get_process(sender).unwrap().state, ProcessState::Ready);
+        manager.send(id, Message::new(sender, 2, 0, b"x")).unwrap();
+        
+        // Nobody is waiting any more, so further receives wake no one
+        assert_eq!(manager.recv(id).unwrap().payload, [1]);
+        assert_eq!(manager.recv(id).unwrap().payload, b"x");
+    }
+
+    #[test]
+    fn test_receiver_exit_wakes_blocked_sender() {
+        use crate::process::{Priority, ProcessState, ProcessTable, KERNEL_PID};
+        
+        static TABLE: ProcessTable = ProcessTable::new();
+        fn wake(pid: u64) {
+            TABLE.unblock(pid).unwrap();
+        }
+        
+        TABLE.init();
+        let receiver = TABLE.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let sender = TABLE.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        TABLE.context_switch(sender);
+        
+        let mut manager = test_manager();
+        let id = manager.create_channel(receiver, ChannelType::Unidirectional).unwrap();
+        manager.connect(id, sender).unwrap();
+        {
+            let channel = manager.get_channel(id).unwrap();
+            channel.max_queue_size = 1;
+            channel.set_sender_waker(wake);
+        }
+        
+        manager.send_from(sender, id, Message::new(sender, receiver, 0, b"a")).unwrap();
+        assert_eq!(
+            manager.send_from(sender, id, Message::new(sender, receiver, 0, b"b")),
+            Err(IpcError::WouldBlock)
+        );
+        TABLE.block(sender).unwrap();
+        
+        // The receiver exits; its channel goes and the sender is released
+        manager.cleanup_process(receiver);
+        assert_eq!(TABLE.get_process(sender).unwrap().state, ProcessState::Ready);
+        assert!(manager.get_channel(id).is_none());
+    }
+
+    #[test]
+    fn test_attached_capability_passes_to_receiver() {
+        use crate::sypas::{AccessRights, ResourceId, ResourceType};
+        
+        const SENDER: u64 = 700;
+        const RECEIVER: u64 = 701;
+        let file = ResourceId::new(ResourceType::File, b"/etc/motd");
+        
+        sypas::init();
+        let handle = sypas::grant_capability(SENDER, Capability::FileRead, None).unwrap();
+        assert!(sypas::check_access(RECEIVER, &file, AccessRights::READ, 0).is_err());
+        
+        let mut manager = test_manager();
+        let id = manager.create_channel(SENDER, ChannelType::Unidirectional).unwrap();
+        manager.connect(id, RECEIVER).unwrap();
+        manager.send_from(SENDER, id, Message::new(SENDER, RECEIVER, 0, b"here").with_capability(handle)).unwrap();
+        
+        let message = manager.recv(id).unwrap();
+        let inherited = message.attached_cap.unwrap();
+        assert_ne!(inherited, handle);
+        assert!(sypas::check_access(RECEIVER, &file, AccessRights::READ, 0).is_ok());
+        
+        // Revoking the sender's capability takes the delegated one with it
+        sypas::revoke_capability(handle).unwrap();
+        assert!(sypas::check_access(RECEIVER, &file, AccessRights::READ, 0).is_err());
+        
+        // A handle the sender does not own is refused when sent
+        let foreign = sypas::grant_capability(RECEIVER + 1, Capability::FileRead, None).unwrap();
+        assert_eq!(
+            manager.send_from(SENDER, id, Message::new(SENDER, RECEIVER, 0, b"").with_capability(foreign)),
+            Err(IpcError::PermissionDenied)
+        );
+        assert!(!manager.get_channel(id).unwrap().has_messages());
+    }
+
+    #[test]
+    fn test_forged_source_does_not_transfer_capability() {
+        use crate::sypas::{AccessRights, ResourceId, ResourceType};
+        
+        const VICTIM: u64 = 710;
+        const ATTACKER: u64 = 711;
+        const RECEIVER: u64 = 712;
+        let file = ResourceId::new(ResourceType::File, b"/etc/shadow");
+        
+        sypas::init();
+        let stolen = sypas::grant_capability(VICTIM, Capability::FileRead, None).unwrap();
+        
+        let mut manager = test_manager();
+        let id = manager.create_channel(ATTACKER, ChannelType::Unidirectional).unwrap();
+        manager.connect(id, RECEIVER).unwrap();
+        
+        // The header names the victim, but the kernel knows who is sending
+        let forged = Message::new(VICTIM, RECEIVER, 0, b"").with_capability(stolen);
+        assert_eq!(manager.send_from(ATTACKER, id, forged.clone()), Err(IpcError::PermissionDenied));
+        // Without a known sender nothing can be attached at all
+        assert_eq!(manager.send(id, forged), Err(IpcError::PermissionDenied));
+        
+        assert!(!manager.get_channel(id).unwrap().has_messages());
+        assert!(sypas::check_access(RECEIVER, &file, AccessRights::READ, 0).is_err());
+    }
+
+    #[test]
+    fn test_guards_release_on_drop() {
+        let manager = Mutex::new(test_manager());
+        
+        let channel = IpcManager::create_channel_guarded(&manager, 1, ChannelType::Unidirectional).unwrap();
+        let id = channel.id();
+        manager.lock().register_name(id, "test.guarded").unwrap();
+        let shm = IpcManager::create_shared_memory_guarded(&manager, 1, 4096).unwrap();
+        let shm_id = shm.id();
+        assert!(manager.lock().get_channel(id).is_some());
+        
+        drop(channel);
+        drop(shm);
+        let mut locked = manager.lock();
+        assert!(locked.get_channel(id).is_none());
+        assert_eq!(locked.lookup_name("test.guarded"), None);
+        assert!(locked.get_shared_memory(shm_id).is_none());
+        drop(locked);
+        
+        // A released guard leaves the channel in place
+        let kept = IpcManager::create_channel_guarded(&manager, 1, ChannelType::Unidirectional)
+            .unwrap()
+            .release();
+        assert!(manager.lock().get_channel(kept).is_some());
+    }
+
+    #[test]
+    fn test_poll_and_select() {
+        let mut manager = test_manager();
+        let ids: Vec<ChannelId> = (0..3)
+            .map(|_| {
+                let id = manager.create_channel(1, ChannelType::Unidirectional).unwrap();
+                manager.get_channel(id).unwrap().connect(2).unwrap();
+                id
+            })
+            .collect();
+        
+        assert!(manager.poll(&ids).is_empty());
+        assert!(matches!(manager.select(&ids), Err(IpcError::WouldBlock)));
+        
+        // Only the middle channel has data
+        manager.send(ids[1], Message::new(1, 2, 0, b"a")).unwrap();
+        manager.send(ids[1], Message::new(1, 2, 0, b"b")).unwrap();
+        
+        assert_eq!(manager.poll(&ids), vec![(ids[1], 2)]);
+        assert_eq!(manager.select(&ids), Ok(ids[1]));
+    }
+
+    #[test]
+    fn test_message_expiry() {
+        let mut manager = test_manager();
+        let id = manager.create_channel(1, ChannelType::Unidirectional).unwrap();
+        {
+            let channel = manager.get_channel(id).unwrap();
+            channel.connect(2).unwrap();
+            channel.blocking_recv = false;
+        }
+        
+        manager.send_with_ttl(id, Message::new(1, 2, 0, b"short"), 10, 100).unwrap();
+        
+        // Still live before the deadline
+        assert_eq!(manager.expire_messages(105), 0);
+        assert_eq!(manager.get_channel(id).unwrap().pending_count(), 1);
+        
+        // Advance past it
+        assert_eq!(manager.expire_messages(111), 1);
+        assert!(matches!(manager.recv(id), Err(IpcError::NoMessage)));
+        
+        // Messages without a deadline are never expired
+        manager.send(id, Message::new(1, 2, 0, b"forever")).unwrap();
+        assert_eq!(manager.expire_messages(u64::MAX), 0);
+        assert_eq!(manager.recv(id).unwrap().payload, b"forever");
+    }
+
+    #[test]
+    fn test_shared_ring_buffer_between_processes() {
+        const FRAMES: u32 = 1000;
+        
+        let mut backing = vec![0u64; 512];
+        let mut shm = SharedMemory::new(1, 1, backing.len() * 8);
+        shm.base_address = backing.as_mut_ptr() as *mut u8;
+        shm.permissions = SharedMemoryPermissions::READ_WRITE;
+        
+        // Both processes map the same region
+        let producer_base = shm.map(1).unwrap();
+        let consumer_base = shm.map(2).unwrap();
+        assert_eq!(producer_base, consumer_base);
+        
+        let producer = unsafe { SharedRingBuffer::create(&shm).unwrap() };
+        let consumer = unsafe { SharedRingBuffer::attach(&shm).unwrap() };
+        
+        let handle = std::thread::spawn(move || {
+            for i in 0..FRAMES {
+                // Variable-size frames exercise wrap-around
+                let frame = vec![i as u8; (i % 97) as usize + 1];
+                let mut bytes = i.to_le_bytes().to_vec();
+                bytes.extend_from_slice(&frame);
+                while producer.push(&bytes) == Err(IpcError::WouldBlock) {
+                    std::thread::yield_now();
+                }
+            }
+        });
+        
+        let mut buf = [0u8; 256];
+        let mut received = 0;
+        while received < FRAMES {
+            match consumer.pop(&mut buf) {
+                Ok(len) => {
+                    let seq = u32::from_le_bytes(buf[..4].try_into().unwrap());
+                    assert_eq!(seq, received);
+                    assert_eq!(len, 4 + (seq % 97) as usize + 1);
+                    assert!(buf[4..len].iter().all(|&b| b == seq as u8));
+                    received += 1;
+                }
+                Err(IpcError::NoMessage) => std::thread::yield_now(),
+                Err(e) => panic!("unexpected ring error: {:?}", e),
+            }
+        }
+        
+        handle.join().unwrap();
+        assert_eq!(consumer.used(), 0);
+    }
+
+    #[test]
+    fn test_shared_ring_buffer_rejects_read_only_region() {
+        let mut backing = vec![0u64; 64];
+        let mut shm = SharedMemory::new(1, 1, backing.len() * 8);
+        shm.base_address = backing.as_mut_ptr() as *mut u8;
+        
+        assert!(matches!(unsafe { SharedRingBuffer::create(&shm) }, Err(IpcError::PermissionDenied)));
+        assert!(matches!(unsafe { SharedRingBuffer::attach(&shm) }, Err(IpcError::InvalidState)));
+    }
+
+    #[test]
+    fn test_shared_ring_buffer_rejects_frame_larger_than_ring() {
+        let mut backing = vec![0u64; 16];
+        let mut shm = SharedMemory::new(1, 1, backing.len() * 8);
+        shm.base_address = backing.as_mut_ptr() as *mut u8;
+        shm.permissions = SharedMemoryPermissions::READ_WRITE;
+        
+        let ring = unsafe { SharedRingBuffer::create(&shm).unwrap() };
+        let frame = vec![0xAB; backing.len() * 8];
+        assert_eq!(ring.push(&frame), Err(IpcError::MessageTooLarge));
+        
+        // An empty ring still accepts a frame that fits
+        let mut buf = [0u8; 8];
+        ring.push(&[1, 2, 3]).unwrap();
+        assert_eq!(ring.pop(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], &[1, 2, 3]);
+    }
+
+    /// pid 1: no caps, pid 2: IpcCreate only, pid 3: Admin, pid 4: IpcJoin only
+    fn mixed_caps(pid: u64) -> Option<Capabilities> {
+        let mut caps = Capabilities::new();
+        match pid {
+            1 => {}
+            2 => caps.set(Capability::IpcCreate),
+            3 => caps.set(Capability::Admin),
+            4 => caps.set(Capability::IpcJoin),
+            _ => return None,
+        }
+        Some(caps)
+    }
+
+    #[test]
+    fn test_create_requires_ipc_create() {
+        let mut manager = IpcManager::new();
+        manager.set_capability_lookup(mixed_caps);
+        
+        assert_eq!(
+            manager.create_channel(1, ChannelType::Unidirectional),
+            Err(IpcError::PermissionDenied)
+        );
+        assert_eq!(manager.create_shared_memory(1, 4096), Err(IpcError::PermissionDenied));
+        assert_eq!(
+            manager.create_channel(99, ChannelType::Unidirectional),
+            Err(IpcError::PermissionDenied)
+        );
+        
+        let id = manager.create_channel(2, ChannelType::Unidirectional).unwrap();
+        assert!(manager.create_shared_memory(2, 4096).is_ok());
+        
+        // Admin bypasses the individual capability bits
+        assert!(manager.create_channel(3, ChannelType::Bidirectional).is_ok());
+        
+        // Joining needs IpcJoin
+        assert_eq!(manager.connect(id, 2), Err(IpcError::PermissionDenied));
+        assert!(manager.connect(id, 4).is_ok());
+    }
+
+    #[test]
+    fn test_named_channel_registry() {
+        let mut manager = test_manager();
+        
+        let sink = manager.create_channel(10, ChannelType::Unidirectional).unwrap();
+        let other = manager.create_channel(11, ChannelType::Unidirectional).unwrap();
+        manager.register_name(sink, "log.sink").unwrap();
+        
+        // A second registration of the same name collides
+        assert_eq!(manager.register_name(other, "log.sink"), Err(IpcError::ResourceLimit));
+        
+        // Another process discovers the channel by name and connects
+        let found = manager.lookup_name("log.sink").unwrap();
+        assert_eq!(found, sink);
+        manager.connect(found, 11).unwrap();
+        
+        manager.cleanup_process(10);
+        assert_eq!(manager.lookup_name("log.sink"), None);
+        
+        // The name is free again
+        manager.register_name(other, "log.sink").unwrap();
+        assert_eq!(manager.lookup_name("log.sink"), Some(other));
+    }
+
     #[test]
     fn test_shared_memory_permissions() {
         let perms = SharedMemoryPermissions::READ_WRITE;
@@ -516,4 +1796,49 @@ mod tests {
         assert!(perms.writable);
         assert!(!perms.executable);
     }
+
+    #[test]
+    fn test_global_manager_across_threads() {
+        /// Puts the global lookup back however the test exits
+        struct RestoreLookup(fn(u64) -> Option<Capabilities>);
+        
+        impl Drop for RestoreLookup {
+            fn drop(&mut self) {
+                with_manager(|manager| manager.set_capability_lookup(self.0));
+            }
+        }
+        
+        init();
+        let _restore = with_manager(|manager| {
+            let previous = RestoreLookup(manager.capability_lookup);
+            manager.set_capability_lookup(ipc_caps);
+            previous
+        });
+        let named = create_channel(1, ChannelType::Unidirectional).unwrap();
+        register_name(named, "test.global.threads").unwrap();
+        
+        // A second init keeps the existing manager and its channels
+        init();
+        assert_eq!(lookup_name("test.global.threads"), Some(named));
+        
+        let workers: Vec<_> = (0..2u64)
+            .map(|worker| {
+                std::thread::spawn(move || {
+                    let pid = 100 + worker;
+                    for i in 0..200 {
+                        let channel = create_channel(pid, ChannelType::Unidirectional).unwrap();
+                        connect(channel, pid).unwrap();
+                        send(channel, Message::new(pid, pid, i, &i.to_le_bytes())).unwrap();
+                        assert_eq!(recv(channel).unwrap().payload, i.to_le_bytes());
+                        close_channel(channel).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        
+        assert_eq!(lookup_name("test.global.threads"), Some(named));
+    }
 }
@@ -0,0 +1,348 @@
+//! PS/2 Keyboard Input Module
+//!
+//! The keyboard interrupt (IRQ1) hands each scancode from port 0x60 to
+//! `handle_scancode`, which only drops it into a lock-free queue; the
+//! interrupt never waits on a lock the code it interrupted may hold.
+//! `read_char` decodes queued scancodes (set 1) through a US layout,
+//! tracking shift and caps lock; the resulting characters wait in a
+//! bounded ring buffer until read.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+
+/// Characters held before further keystrokes are dropped
+pub const INPUT_BUFFER_SIZE: usize = 128;
+/// Raw scancodes held between the interrupt and the next `read_char`
+pub const SCANCODE_QUEUE_SIZE: usize = 64;
+
+/// Set-1 scancodes with special meaning
+const SC_LEFT_SHIFT: u8 = 0x2A;
+const SC_RIGHT_SHIFT: u8 = 0x36;
+const SC_CAPS_LOCK: u8 = 0x3A;
+/// Prefix of two-byte (extended) scancodes
+const SC_EXTENDED: u8 = 0xE0;
+/// Set on the release code of a key
+const SC_RELEASE: u8 = 0x80;
+
+/// US layout, unshifted, indexed by make code (0 = no character)
+const US_LAYOUT: [u8; 0x3A] = [
+    0, 0x1B, b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'0', b'-', b'=', 0x08, b'\t',
+    b'q', b'w', b'e', b'r', b't', b'y', b'u', b'i', b'o', b'p', b'[', b']', b'\n', 0, b'a', b's',
+    b'd', b'f', b'g', b'h', b'j', b'k', b'l', b';', b'\'', b'`', 0, b'\\', b'z', b'x', b'c', b'v',
+    b'b', b'n', b'm', b',', b'.', b'/', 0, b'*', 0, b' ',
+];
+
+/// US layout with shift held
+const US_LAYOUT_SHIFTED: [u8; 0x3A] = [
+    0, 0x1B, b'!', b'@', b'#', b'$', b'%', b'^', b'&', b'*', b'(', b')', b'_', b'+', 0x08, b'\t',
+    b'Q', b'W', b'E', b'R', b'T', b'Y', b'U', b'I', b'O', b'P', b'{', b'}', b'\n', 0, b'A', b'S',
+    b'D', b'F', b'G', b'H', b'J', b'K', b'L', b':', b'"', b'~', 0, b'|', b'Z', b'X', b'C', b'V',
+    b'B', b'N', b'M', b'<', b'>', b'?', 0, b'*', 0, b' ',
+];
+
+/// Set-1 scancode decoder for a US keyboard
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScancodeDecoder {
+    left_shift: bool,
+    right_shift: bool,
+    caps_lock: bool,
+    /// The previous byte was the extended prefix
+    extended: bool,
+}
+
+impl ScancodeDecoder {
+    pub const fn new() -> Self {
+        ScancodeDecoder {
+            left_shift: false,
+            right_shift: false,
+            caps_lock: false,
+            extended: false,
+        }
+    }
+
+    /// Feed one scancode byte, returning the character it produces
+    ///
+    /// Releases, modifiers and extended keys (arrows, keypad enter, ...)
+    /// produce nothing.
+    pub fn decode(&mut self, scancode: u8) -> Option<u8> {
+        if scancode == SC_EXTENDED {
+            self.extended = true;
+            return None;
+        }
+        if core::mem::take(&mut self.extended) {
+            return None;
+        }
+
+        let released = scancode & SC_RELEASE != 0;
+        let code = scancode & !SC_RELEASE;
+        match code {
+            SC_LEFT_SHIFT => self.left_shift = !released,
+            SC_RIGHT_SHIFT => self.right_shift = !released,
+            SC_CAPS_LOCK if !released => self.caps_lock = !self.caps_lock,
+            _ if !released => return self.map(code),
+            _ => {}
+        }
+        None
+    }
+
+    /// Whether either shift key is held
+    pub fn shift(&self) -> bool {
+        self.left_shift || self.right_shift
+    }
+
+    fn map(&self, code: u8) -> Option<u8> {
+        let plain = *US_LAYOUT.get(code as usize)?;
+        // Caps lock inverts shift for letters only
+        let shifted = self.shift() ^ (self.caps_lock && plain.is_ascii_lowercase());
+        let byte = if shifted {
+            US_LAYOUT_SHIFTED[code as usize]
+        } else {
+            plain
+        };
+        (byte != 0).then_some(byte)
+    }
+}
+
+/// Bounded FIFO of decoded characters
+#[derive(Debug, Clone)]
+pub struct InputBuffer {
+    data: [u8; INPUT_BUFFER_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl InputBuffer {
+    pub const fn new() -> Self {
+        InputBuffer {
+            data: [0; INPUT_BUFFER_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Append a character; returns `false` (dropping it) when full
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.len == INPUT_BUFFER_SIZE {
+            return false;
+        }
+        self.data[(self.head + self.len) % INPUT_BUFFER_SIZE] = byte;
+        self.len += 1;
+        true
+    }
+
+    /// Take the oldest character
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.data[self.head];
+        self.head = (self.head + 1) % INPUT_BUFFER_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decoder state together with the characters waiting to be read
+pub struct Keyboard {
+    decoder: ScancodeDecoder,
+    buffer: InputBuffer,
+}
+
+impl Keyboard {
+    pub const fn new() -> Self {
+        Keyboard {
+            decoder: ScancodeDecoder::new(),
+            buffer: InputBuffer::new(),
+        }
+    }
+
+    /// Decode a scancode and queue any character it produces
+    pub fn handle_scancode(&mut self, scancode: u8) {
+        if let Some(byte) = self.decoder.decode(scancode) {
+            self.buffer.push(byte);
+        }
+    }
+
+    /// Take the oldest typed character
+    pub fn read_char(&mut self) -> Option<char> {
+        self.buffer.pop().map(char::from)
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Single-producer, single-consumer ring of raw scancodes
+///
+/// The IRQ1 handler is the only producer. Consumers must be serialized by
+/// the caller; the global one is only drained with `KEYBOARD` locked.
+struct ScancodeQueue {
+    data: UnsafeCell<[u8; SCANCODE_QUEUE_SIZE]>,
+    /// Next slot to read; only the consumer advances it
+    head: AtomicUsize,
+    /// Next slot to write; only the producer advances it
+    tail: AtomicUsize,
+}
+
+unsafe impl Sync for ScancodeQueue {}
+
+impl ScancodeQueue {
+    const fn new() -> Self {
+        ScancodeQueue {
+            data: UnsafeCell::new([0; SCANCODE_QUEUE_SIZE]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Append a scancode; returns `false` (dropping it) when full
+    fn push(&self, scancode: u8) -> bool {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == SCANCODE_QUEUE_SIZE {
+            return false;
+        }
+        // The consumer does not read this slot until `tail` is published
+        unsafe {
+            (*self.data.get())[tail % SCANCODE_QUEUE_SIZE] = scancode;
+        }
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// Take the oldest scancode
+    fn pop(&self) -> Option<u8> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        // The producer does not reuse this slot until `head` moves past it
+        let scancode = unsafe { (*self.data.get())[head % SCANCODE_QUEUE_SIZE] };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(scancode)
+    }
+}
+
+/// Scancodes queued by the IRQ1 handler
+static SCANCODES: ScancodeQueue = ScancodeQueue::new();
+
+/// Global keyboard state, only touched outside interrupt context
+static KEYBOARD: Mutex<Keyboard> = Mutex::new(Keyboard::new());
+
+/// Handle a scancode read from the keyboard controller
+///
+/// Safe to call from the interrupt handler: it takes no lock.
+pub fn handle_scancode(scancode: u8) {
+    SCANCODES.push(scancode);
+}
+
+/// Take the oldest typed character, if any
+pub fn read_char() -> Option<char> {
+    let mut keyboard = KEYBOARD.lock();
+    while let Some(scancode) = SCANCODES.pop() {
+        keyboard.handle_scancode(scancode);
+    }
+    keyboard.read_char()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_scancodes(scancodes: &[u8]) -> String {
+        let mut keyboard = Keyboard::new();
+        for &scancode in scancodes {
+            keyboard.handle_scancode(scancode);
+        }
+        core::iter::from_fn(|| keyboard.read_char()).collect()
+    }
+
+    #[test]
+    fn test_plain_keys() {
+        // h, i, space, 4, 2, enter - each pressed and released
+        let scancodes = [0x23, 0xA3, 0x17, 0x97, 0x39, 0xB9, 0x05, 0x85, 0x03, 0x83, 0x1C, 0x9C];
+        assert_eq!(type_scancodes(&scancodes), "hi 42\n");
+    }
+
+    #[test]
+    fn test_shift() {
+        // Left shift + h, i, right shift + 1, then a with shift released
+        let scancodes = [
+            0x2A, 0x23, 0xA3, 0xAA,
+            0x17, 0x97,
+            0x36, 0x02, 0x82, 0xB6,
+            0x1E, 0x9E,
+        ];
+        assert_eq!(type_scancodes(&scancodes), "Hi!a");
+    }
+
+    #[test]
+    fn test_caps_lock_and_extended() {
+        // Caps lock upper-cases letters but not digits; shift inverts it
+        let scancodes = [
+            0x3A, 0xBA, 0x1E, 0x02, 0x2A, 0x1E, 0xAA,
+            // Right arrow (E0 4D) produces nothing
+            0xE0, 0x4D, 0xE0, 0xCD,
+            0x3A, 0xBA, 0x1E,
+        ];
+        assert_eq!(type_scancodes(&scancodes), "A1aa");
+    }
+
+    #[test]
+    fn test_input_buffer_is_bounded() {
+        let mut keyboard = Keyboard::new();
+        for _ in 0..INPUT_BUFFER_SIZE + 10 {
+            keyboard.handle_scancode(0x1E);
+        }
+        assert_eq!(keyboard.buffer.len(), INPUT_BUFFER_SIZE);
+
+        keyboard.handle_scancode(0x30);
+        assert_eq!((0..INPUT_BUFFER_SIZE).filter_map(|_| keyboard.read_char()).count(), INPUT_BUFFER_SIZE);
+        assert_eq!(keyboard.read_char(), None);
+        assert!(keyboard.buffer.is_empty());
+    }
+
+    #[test]
+    fn test_scancode_queue_is_bounded() {
+        let queue = ScancodeQueue::new();
+        for i in 0..SCANCODE_QUEUE_SIZE {
+            assert!(queue.push(i as u8));
+        }
+        assert!(!queue.push(0xFF));
+
+        assert_eq!(queue.pop(), Some(0));
+        assert!(queue.push(0xFF));
+        let rest: Vec<u8> = core::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(rest.len(), SCANCODE_QUEUE_SIZE);
+        assert_eq!(rest.last(), Some(&0xFF));
+    }
+
+    #[test]
+    fn test_interrupt_while_reader_holds_lock() {
+        // An IRQ landing inside read_char must not wait on the keyboard lock
+        let keyboard = KEYBOARD.lock();
+        handle_scancode(0x23);
+        handle_scancode(0xA3);
+        drop(keyboard);
+
+        assert_eq!(read_char(), Some('h'));
+        assert_eq!(read_char(), None);
+    }
+}
@@ -77,6 +77,7 @@ macro_rules! println {
 }
 
 // Core modules
+pub mod error;
 pub mod crypto;
 pub mod memory;
 pub mod process;
@@ -84,12 +85,13 @@ pub mod sypas;
 pub mod ipc;
 pub mod syscall;
 pub mod consensus;
+pub mod raft;
+pub mod time;
 
-// VGA and serial only available on x86_64 bare metal
-#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+// Console drivers; the print macros only exist on x86_64 bare metal
 pub mod vga_buffer;
-#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 pub mod serial;
+pub mod keyboard;
 
 // Boot module for bare metal
 #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
@@ -97,6 +99,7 @@ pub mod boot;
 
 // Re-export crypto module for easy access
 pub use crypto::*;
+pub use error::{KernelError, KernelResult};
 
 /// Kernel version
 pub const VERSION: &str = "1.2.0";
@@ -119,8 +122,9 @@ pub fn init() {
         unsafe {
             // In a real system, we'd get the heap location from the bootloader
             // For now, use a static allocation
+            // The static lives in .bss, so it starts out zero-filled
             static mut HEAP: [u8; 1024 * 1024] = [0; 1024 * 1024]; // 1MB heap
-            memory::init(HEAP.as_mut_ptr(), HEAP.len());
+            memory::init_zeroed(HEAP.as_mut_ptr(), HEAP.len());
         }
         
         // Initialize process subsystem
@@ -135,6 +139,10 @@ pub fn init() {
         serial_println!("[kernel] Initializing IPC subsystem...");
         ipc::init();
         
+        // Initialize syscall table
+        serial_println!("[kernel] Initializing syscall table...");
+        syscall::init();
+        
         // Initialize serial output
         serial::init();
         
@@ -160,7 +168,16 @@ pub fn self_test() -> bool {
     #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
     serial_println!("[kernel] Heap verification: {}", if heap_ok { "OK" } else { "FAIL" });
     
-    heap_ok
+    // Known-answer tests for the crypto primitives
+    let crypto_result = crypto::self_test();
+    
+    #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+    match crypto_result {
+        Ok(()) => serial_println!("[kernel] Crypto self-test: OK"),
+        Err(e) => serial_println!("[kernel] Crypto self-test: FAIL ({})", e),
+    }
+    
+    heap_ok && crypto_result.is_ok()
 }
 
 /// Get kernel statistics
@@ -168,6 +185,7 @@ pub fn get_stats() -> KernelStats {
     KernelStats {
         version: VERSION,
         memory_stats: memory::get_stats(),
+        scheduler_stats: process::scheduler_stats(),
     }
 }
 
@@ -176,6 +194,7 @@ pub fn get_stats() -> KernelStats {
 pub struct KernelStats {
     pub version: &'static str,
     pub memory_stats: memory::MemoryStats,
+    pub scheduler_stats: process::SchedulerStats,
 }
 
 /// Panic handler for no_std environments
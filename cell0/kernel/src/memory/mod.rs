@@ -12,7 +12,9 @@
 
 use core::alloc::{GlobalAlloc, Layout};
 use core::cell::UnsafeCell;
-use core::sync::atomic::{AtomicUsize, AtomicU64, AtomicBool, Ordering};
+use core::sync::atomic::{AtomicUsize, AtomicU64, AtomicU32, AtomicU8, AtomicBool, Ordering};
+
+use crate::crypto::{CryptoRng, HardwareRng};
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -23,10 +25,20 @@ pub const PAGE_SIZE: usize = 4096;
 pub const NUM_PAGES: usize = 16384; // 64MB total
 /// Total heap size
 pub const HEAP_SIZE: usize = PAGE_SIZE * NUM_PAGES;
-/// Memory canary value for overflow detection
-pub const CANARY_VALUE: u8 = 0xDE;
-/// Canary size in bytes
-pub const CANARY_SIZE: usize = 8;
+/// Canary size in bytes (one random 64-bit value per block)
+pub const CANARY_SIZE: usize = core::mem::size_of::<u64>();
+/// Maximum number of corrupted heap regions held in quarantine
+pub const MAX_QUARANTINED_BLOCKS: usize = 64;
+/// Maximum number of memory-pressure callbacks
+pub const MAX_PRESSURE_CALLBACKS: usize = 8;
+/// Callback that releases memory (drops caches) under pressure
+pub type PressureCallback = fn();
+/// Guard region appended to each allocation in guard-page mode
+pub const GUARD_SIZE: usize = 64;
+/// Fill pattern of guard regions
+const GUARD_BYTE: u8 = 0xFD;
+/// Fill pattern of freed blocks in guard-page mode
+const POISON_BYTE: u8 = 0xDD;
 
 /// Memory allocation error types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +49,7 @@ pub enum MemoryError {
     InvalidPointer,
     AlignmentError,
     AllocationTooLarge,
+    ReservedPage,
 }
 
 impl core::fmt::Display for MemoryError {
@@ -48,10 +61,14 @@ impl core::fmt::Display for MemoryError {
             MemoryError::InvalidPointer => write!(f, "Invalid pointer"),
             MemoryError::AlignmentError => write!(f, "Alignment error"),
             MemoryError::AllocationTooLarge => write!(f, "Allocation too large"),
+            MemoryError::ReservedPage => write!(f, "Page is reserved"),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for MemoryError {}
+
 /// Page frame allocation state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -65,8 +82,11 @@ pub enum PageState {
 /// Memory statistics for monitoring
 #[derive(Debug, Clone, Default)]
 pub struct MemoryStats {
+    /// Page frames managed by the page allocator
     pub total_pages: usize,
+    /// Free page frames in the page allocator
     pub free_pages: usize,
+    /// Allocated page frames in the page allocator
     pub allocated_pages: usize,
     pub corrupted_pages: usize,
     pub total_allocations: u64,
@@ -74,24 +94,49 @@ pub struct MemoryStats {
     pub failed_allocations: u64,
     pub corruption_events: u64,
     pub recovered_pages: u64,
+    /// Size of the largest free heap block in bytes
+    pub largest_free_block: usize,
+    /// Number of free heap blocks
+    pub free_block_count: usize,
+    /// Total free heap bytes across all free blocks
+    pub free_bytes: usize,
+    /// Total user bytes held by allocated heap blocks
+    pub allocated_bytes: usize,
+}
+
+impl MemoryStats {
+    /// Heap fragmentation: 0.0 when all free memory is one block,
+    /// approaching 1.0 as it is scattered across many small blocks
+    pub fn fragmentation_ratio(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_block as f64 / self.free_bytes as f64
+    }
 }
 
 /// Page frame allocator with bitmap tracking
+///
+/// Page states are changed with compare-and-swap on the bitmap bytes, so
+/// concurrent callers never claim the same page and `free_pages` stays
+/// exact.
 pub struct PageFrameAllocator {
     /// Bitmap of page states (2 bits per page)
-    bitmap: UnsafeCell<[u8; NUM_PAGES / 4]>,
+    bitmap: [AtomicU8; NUM_PAGES / 4],
     /// Next page to search from (optimization)
     next_page: AtomicUsize,
     /// Number of free pages
     free_pages: AtomicUsize,
 }
 
-unsafe impl Sync for PageFrameAllocator {}
+/// Bitmap byte with all four pages free
+#[allow(clippy::declare_interior_mutable_const)]
+const FREE_BITMAP_BYTE: AtomicU8 = AtomicU8::new(0);
 
 impl PageFrameAllocator {
     pub const fn new() -> Self {
         PageFrameAllocator {
-            bitmap: UnsafeCell::new([0u8; NUM_PAGES / 4]),
+            bitmap: [FREE_BITMAP_BYTE; NUM_PAGES / 4],
             next_page: AtomicUsize::new(0),
             free_pages: AtomicUsize::new(NUM_PAGES),
         }
@@ -115,23 +160,32 @@ impl PageFrameAllocator {
 
     /// Allocate contiguous pages
     pub fn alloc_pages(&self, count: usize) -> Option<usize> {
-        if count == 0 || count > NUM_PAGES {
+        self.alloc_pages_aligned(count, 1)
+    }
+
+    /// Allocate contiguous pages whose start index is a multiple of `align_pages`
+    ///
+    /// Use 512 for 2MB-aligned regions (large pages, DMA buffers).
+    pub fn alloc_pages_aligned(&self, count: usize, align_pages: usize) -> Option<usize> {
+        if count == 0 || count > NUM_PAGES || align_pages == 0 {
             return None;
         }
 
-        'outer: for start in 0..=NUM_PAGES - count {
-            // Check if all pages are free
+        let mut start = 0;
+        'outer: while start + count <= NUM_PAGES {
+            // Claim the pages one by one
             for i in 0..count {
-                if self.get_page_state(start + i) != PageState::Free {
+                if !self.try_alloc_page(start + i) {
+                    // Hand back what was claimed, then resume at the first
+                    // aligned start past the busy page
+                    for page in start..start + i {
+                        self.compare_exchange_state(page, PageState::Allocated, PageState::Free);
+                    }
+                    start = (start + i + 1).next_multiple_of(align_pages);
                     continue 'outer;
                 }
             }
             
-            // Allocate all pages
-            for i in 0..count {
-                self.set_page_state(start + i, PageState::Allocated);
-            }
-            
             self.free_pages.fetch_sub(count, Ordering::Relaxed);
             return Some(start);
         }
@@ -145,67 +199,97 @@ impl PageFrameAllocator {
             return Err(MemoryError::InvalidPointer);
         }
 
-        match self.get_page_state(page) {
-            PageState::Free => Err(MemoryError::DoubleFree),
-            PageState::Allocated => {
-                self.set_page_state(page, PageState::Free);
-                self.free_pages.fetch_add(1, Ordering::Relaxed);
-                Ok(())
-            }
-            PageState::Reserved => {
-                self.set_page_state(page, PageState::Free);
-                self.free_pages.fetch_add(1, Ordering::Relaxed);
-                Ok(())
-            }
-            PageState::Corrupted => {
-                // Attempt recovery
-                self.set_page_state(page, PageState::Free);
-                self.free_pages.fetch_add(1, Ordering::Relaxed);
-                Ok(())
+        loop {
+            let state = self.get_page_state(page);
+            match state {
+                PageState::Free => return Err(MemoryError::DoubleFree),
+                // Reserved ranges are never handed out, so never given back
+                PageState::Reserved => return Err(MemoryError::ReservedPage),
+                // Corrupted pages are freed as an attempt at recovery
+                PageState::Allocated | PageState::Corrupted => {
+                    if self.compare_exchange_state(page, state, PageState::Free) {
+                        self.free_pages.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    // Changed under us; look again
+                }
             }
         }
     }
 
-    /// Get page state
-    fn get_page_state(&self, page: usize) -> PageState {
-        let bitmap = unsafe { &*self.bitmap.get() };
-        let byte_idx = page / 4;
-        let shift = (page % 4) * 2;
-        let bits = (bitmap[byte_idx] >> shift) & 0b11;
+    /// Permanently withhold `count` pages starting at `start_page`
+    ///
+    /// For firmware tables, MMIO windows and the kernel image. Free pages
+    /// stop counting as free; pages already allocated (e.g. by early boot)
+    /// are reserved too and can no longer be freed.
+    pub fn reserve_range(&self, start_page: usize, count: usize) -> Result<(), MemoryError> {
+        let end = start_page.checked_add(count).ok_or(MemoryError::InvalidPointer)?;
+        if end > NUM_PAGES {
+            return Err(MemoryError::InvalidPointer);
+        }
         
-        match bits {
+        for page in start_page..end {
+            if self.set_page_state(page, PageState::Reserved) == PageState::Free {
+                self.free_pages.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Bitmap byte holding a page, and the page's bit offset within it
+    fn bitmap_slot(&self, page: usize) -> (&AtomicU8, usize) {
+        (&self.bitmap[page / 4], (page % 4) * 2)
+    }
+
+    /// Decode a page's 2-bit field from its bitmap byte
+    fn decode_state(byte: u8, shift: usize) -> PageState {
+        match (byte >> shift) & 0b11 {
             0 => PageState::Free,
             1 => PageState::Allocated,
             2 => PageState::Reserved,
-            3 => PageState::Corrupted,
-            _ => unreachable!(),
+            _ => PageState::Corrupted,
         }
     }
 
-    /// Set page state
-    fn set_page_state(&self, page: usize, state: PageState) {
-        let bitmap = unsafe { &mut *self.bitmap.get() };
-        let byte_idx = page / 4;
-        let shift = (page % 4) * 2;
-        let bits = state as u8;
-        
-        bitmap[byte_idx] = (bitmap[byte_idx] & !(0b11 << shift)) | (bits << shift);
+    /// Get page state
+    fn get_page_state(&self, page: usize) -> PageState {
+        let (slot, shift) = self.bitmap_slot(page);
+        Self::decode_state(slot.load(Ordering::Acquire), shift)
+    }
+
+    /// Set page state unconditionally, returning the previous state
+    fn set_page_state(&self, page: usize, state: PageState) -> PageState {
+        let (slot, shift) = self.bitmap_slot(page);
+        let previous = slot
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |byte| {
+                Some((byte & !(0b11 << shift)) | ((state as u8) << shift))
+            })
+            .unwrap_or_else(|byte| byte);
+        Self::decode_state(previous, shift)
+    }
+
+    /// Move a page from `current` to `new`; fails if it is in any other state
+    ///
+    /// Neighbouring pages in the same byte may change concurrently; only a
+    /// change to this page's own field makes the swap fail.
+    fn compare_exchange_state(&self, page: usize, current: PageState, new: PageState) -> bool {
+        let (slot, shift) = self.bitmap_slot(page);
+        slot.fetch_update(Ordering::AcqRel, Ordering::Acquire, |byte| {
+            (Self::decode_state(byte, shift) == current)
+                .then(|| (byte & !(0b11 << shift)) | ((new as u8) << shift))
+        })
+        .is_ok()
     }
 
     /// Try to atomically allocate a page
     fn try_alloc_page(&self, page: usize) -> bool {
-        if self.get_page_state(page) == PageState::Free {
-            self.set_page_state(page, PageState::Allocated);
-            true
-        } else {
-            false
-        }
+        self.compare_exchange_state(page, PageState::Free, PageState::Allocated)
     }
 
     /// Mark page as corrupted (for fault isolation)
     pub fn mark_corrupted(&self, page: usize) {
-        if page < NUM_PAGES {
-            self.set_page_state(page, PageState::Corrupted);
+        if page < NUM_PAGES && self.set_page_state(page, PageState::Corrupted) == PageState::Free {
+            self.free_pages.fetch_sub(1, Ordering::Relaxed);
         }
     }
 
@@ -214,6 +298,11 @@ impl PageFrameAllocator {
         self.free_pages.load(Ordering::Relaxed)
     }
 
+    /// Number of pages currently in `state`
+    pub fn count_pages(&self, state: PageState) -> usize {
+        (0..NUM_PAGES).filter(|&page| self.get_page_state(page) == state).count()
+    }
+
     /// Run garbage collection / defragmentation
     pub fn gc(&self) {
         // In a real implementation, this would consolidate fragmented allocations
@@ -229,8 +318,18 @@ struct BlockHeader {
     size: usize,
     /// Whether this block is allocated
     is_allocated: bool,
+    /// User region is known to be zero (fresh page, never handed out)
+    was_zeroed: bool,
+    /// Allocated block whose last `GUARD_SIZE` bytes are a guard region
+    guarded: bool,
+    /// Free block whose user region is filled with `POISON_BYTE`
+    poisoned: bool,
     /// Magic value for validation
     magic: u32,
+    /// Bumped every time the block is freed, to tell stale handles apart
+    generation: u32,
+    /// Expected canary value written after the user region
+    canary: u64,
     /// Previous block in linked list
     prev: Option<usize>,
     /// Next block in linked list
@@ -239,6 +338,51 @@ struct BlockHeader {
 
 const BLOCK_MAGIC: u32 = 0x424C4B5F; // "BLK_"
 
+/// Block sizes are rounded to this so every header stays aligned
+const BLOCK_ALIGN: usize = core::mem::align_of::<BlockHeader>();
+
+/// How the heap picks a free block for an allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum AllocStrategy {
+    /// Take the first block that fits (fast)
+    #[default]
+    FirstFit = 0,
+    /// Take the smallest block that fits, keeping large blocks intact
+    BestFit = 1,
+}
+
+/// Heap pointer tagged with the generation of the block it came from
+///
+/// Returned by `alloc_checked`; `dealloc_checked` refuses it once the
+/// block has been freed, even if the same address was handed out again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckedPtr {
+    ptr: *mut u8,
+    generation: u32,
+}
+
+impl CheckedPtr {
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Snapshot of a heap block, as reported by `walk_blocks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// Address of the user data (what `alloc` returned)
+    pub address: usize,
+    /// Size of the user data in bytes
+    pub size: usize,
+    /// Whether the block is currently allocated
+    pub is_allocated: bool,
+}
+
 /// Self-healing heap allocator
 pub struct HealingHeapAllocator {
     /// Base address of the heap
@@ -251,6 +395,28 @@ pub struct HealingHeapAllocator {
     stats: UnsafeCell<MemoryStats>,
     /// Self-healing enabled
     healing_enabled: AtomicBool,
+    /// Canary generator state, seeded from the hardware RNG at init
+    canary_state: AtomicU64,
+    /// Corrupted regions (start, end) removed from the block list
+    quarantined_blocks: UnsafeCell<[(usize, usize); MAX_QUARANTINED_BLOCKS]>,
+    /// Number of quarantined regions
+    quarantine_count: AtomicUsize,
+    /// Called when an allocation fails; `true` requests a single retry
+    oom_handler: UnsafeCell<Option<fn() -> bool>>,
+    /// Block selection strategy, an `AllocStrategy` discriminant
+    strategy: AtomicU8,
+    /// Hardened mode: guard regions after allocations, poison on free
+    guard_pages: AtomicBool,
+    /// Starting generation of the next new block header
+    next_generation: AtomicU32,
+    /// Free heap bytes below which pressure callbacks run (0 disables)
+    pressure_threshold: AtomicUsize,
+    /// Callbacks already ran for the current low-memory episode
+    under_pressure: AtomicBool,
+    /// Called once when free memory first drops below the threshold
+    pressure_callbacks: UnsafeCell<[Option<PressureCallback>; MAX_PRESSURE_CALLBACKS]>,
+    /// Number of registered pressure callbacks
+    pressure_callback_count: AtomicUsize,
 }
 
 unsafe impl Sync for HealingHeapAllocator {}
@@ -263,8 +429,8 @@ impl HealingHeapAllocator {
             heap_size: AtomicUsize::new(0),
             free_list: AtomicUsize::new(0),
             stats: UnsafeCell::new(MemoryStats {
-                total_pages: NUM_PAGES,
-                free_pages: NUM_PAGES,
+                total_pages: 0,
+                free_pages: 0,
                 allocated_pages: 0,
                 corrupted_pages: 0,
                 total_allocations: 0,
@@ -272,54 +438,192 @@ impl HealingHeapAllocator {
                 failed_allocations: 0,
                 corruption_events: 0,
                 recovered_pages: 0,
+                largest_free_block: 0,
+                free_block_count: 0,
+                free_bytes: 0,
+                allocated_bytes: 0,
             }),
             healing_enabled: AtomicBool::new(true),
+            canary_state: AtomicU64::new(0),
+            quarantined_blocks: UnsafeCell::new([(0, 0); MAX_QUARANTINED_BLOCKS]),
+            quarantine_count: AtomicUsize::new(0),
+            oom_handler: UnsafeCell::new(None),
+            strategy: AtomicU8::new(AllocStrategy::FirstFit as u8),
+            guard_pages: AtomicBool::new(false),
+            next_generation: AtomicU32::new(0),
+            pressure_threshold: AtomicUsize::new(0),
+            under_pressure: AtomicBool::new(false),
+            pressure_callbacks: UnsafeCell::new([None; MAX_PRESSURE_CALLBACKS]),
+            pressure_callback_count: AtomicUsize::new(0),
         }
     }
 
     /// Initialize the heap with a memory region
     pub unsafe fn init(&self, heap_start: *mut u8, heap_size: usize) {
+        self.init_region(heap_start, heap_size, false);
+    }
+
+    /// Initialize the heap with a memory region known to be zero-filled
+    ///
+    /// Blocks carved from the fresh region are handed out by `alloc_zeroed`
+    /// without being cleared again.
+    ///
+    /// # Safety
+    /// The region must be valid for `heap_size` bytes and entirely zero.
+    pub unsafe fn init_zeroed(&self, heap_start: *mut u8, heap_size: usize) {
+        self.init_region(heap_start, heap_size, true);
+    }
+
+    unsafe fn init_region(&self, heap_start: *mut u8, heap_size: usize, zeroed: bool) {
         *self.heap_base.get() = heap_start;
         self.heap_size.store(heap_size, Ordering::SeqCst);
         
+        // Seed canary generation
+        let mut seed = [0u8; 8];
+        HardwareRng.fill_bytes(&mut seed);
+        self.canary_state.store(u64::from_le_bytes(seed), Ordering::SeqCst);
+        
         // Initialize first block
         let first_block = heap_start as *mut BlockHeader;
         (*first_block).size = heap_size - core::mem::size_of::<BlockHeader>() - CANARY_SIZE;
         (*first_block).is_allocated = false;
+        (*first_block).was_zeroed = zeroed;
+        (*first_block).guarded = false;
+        (*first_block).generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        (*first_block).poisoned = false;
         (*first_block).magic = BLOCK_MAGIC;
+        (*first_block).canary = self.next_canary(first_block as usize);
         (*first_block).prev = None;
         (*first_block).next = None;
         
         // Write canary
-        let canary_addr = heap_start.add(core::mem::size_of::<BlockHeader>()
-            + (*first_block).size) as *mut u8;
-        for i in 0..CANARY_SIZE {
-            canary_addr.add(i).write(CANARY_VALUE);
-        }
+        self.write_canary(first_block);
         
         self.free_list.store(heap_start as usize, Ordering::SeqCst);
-        
-        let stats = &mut *self.stats.get();
-        stats.free_pages = heap_size / PAGE_SIZE;
     }
 
     /// Allocate memory with canary protection
     pub unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.alloc_block(layout).0
+    }
+
+    /// Allocate memory and tag it with its block's generation
+    ///
+    /// # Safety
+    /// Same requirements as [`GlobalAlloc::alloc`].
+    pub unsafe fn alloc_checked(&self, layout: Layout) -> Result<CheckedPtr, MemoryError> {
+        let ptr = self.alloc(layout);
+        if ptr.is_null() {
+            return Err(MemoryError::OutOfMemory);
+        }
+        let generation = match layout.size() {
+            0 => 0,
+            _ => (*Self::header_of(ptr)).generation,
+        };
+        Ok(CheckedPtr { ptr, generation })
+    }
+
+    /// Free memory from `alloc_checked`, refusing stale or foreign handles
+    ///
+    /// A handle is stale once its block has been freed, whether or not the
+    /// block has been reallocated since; it is rejected with
+    /// `MemoryError::InvalidPointer` and nothing is freed.
+    ///
+    /// # Safety
+    /// `layout` must be the one passed to `alloc_checked`.
+    pub unsafe fn dealloc_checked(&self, handle: CheckedPtr, layout: Layout) -> Result<(), MemoryError> {
+        if layout.size() == 0 {
+            return Ok(());
+        }
+        
+        let heap_base = *self.heap_base.get() as usize;
+        let heap_end = heap_base + self.heap_size.load(Ordering::Relaxed);
+        let addr = (handle.ptr as usize).wrapping_sub(core::mem::size_of::<BlockHeader>());
+        if addr < heap_base || addr >= heap_end || addr % BLOCK_ALIGN != 0 {
+            return Err(MemoryError::InvalidPointer);
+        }
+        
+        let block = Self::header_of(handle.ptr);
+        if (*block).magic != BLOCK_MAGIC
+            || !(*block).is_allocated
+            || (*block).generation != handle.generation
+        {
+            return Err(MemoryError::InvalidPointer);
+        }
+        
+        self.dealloc(handle.ptr, layout);
+        Ok(())
+    }
+
+    /// Header of the block whose user region starts at `ptr`
+    fn header_of(ptr: *mut u8) -> *mut BlockHeader {
+        (ptr as usize - core::mem::size_of::<BlockHeader>()) as *mut BlockHeader
+    }
+
+    /// Allocate zero-initialized memory
+    ///
+    /// Blocks still marked as fresh are returned as-is; reused blocks are
+    /// cleared explicitly.
+    ///
+    /// # Safety
+    /// Same requirements as [`GlobalAlloc::alloc_zeroed`].
+    pub unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let (ptr, was_zeroed) = self.alloc_block(layout);
+        if !ptr.is_null() && !was_zeroed {
+            ptr.write_bytes(0, layout.size());
+        }
+        ptr
+    }
+
+    /// Find and claim a block, returning the user pointer and whether its
+    /// contents were already known to be zero
+    unsafe fn alloc_block(&self, layout: Layout) -> (*mut u8, bool) {
         let size = layout.size();
         let align = layout.align();
         
         if size == 0 {
-            return align as *mut u8;
+            return (align as *mut u8, true);
         }
         
         if size > self.heap_size.load(Ordering::Relaxed) {
             let stats = &mut *self.stats.get();
             stats.failed_allocations += 1;
-            return core::ptr::null_mut();
+            return (core::ptr::null_mut(), false);
         }
 
-        let total_size = size + CANARY_SIZE;
-        let header_size = core::mem::size_of::<BlockHeader>();
+        let guarded = self.guard_pages.load(Ordering::Relaxed);
+        let size = size.next_multiple_of(BLOCK_ALIGN) + if guarded { GUARD_SIZE } else { 0 };
+        let mut result = self.search_free_list(size);
+        
+        // Give the OOM handler one chance to release memory, then retry once
+        if result.0.is_null() {
+            if let Some(handler) = *self.oom_handler.get() {
+                if handler() {
+                    result = self.search_free_list(size);
+                }
+            }
+        }
+        
+        if result.0.is_null() {
+            let stats = &mut *self.stats.get();
+            stats.failed_allocations += 1;
+            return result;
+        }
+        
+        if guarded {
+            let block = Self::header_of(result.0);
+            (*block).guarded = true;
+            Self::guard_region(block).fill(GUARD_BYTE);
+        }
+        self.check_pressure();
+        result
+    }
+
+    /// Claim a free block that fits `size` (already rounded), chosen by
+    /// the allocation strategy
+    unsafe fn search_free_list(&self, size: usize) -> (*mut u8, bool) {
+        let best_fit = self.strategy() == AllocStrategy::BestFit;
+        let mut best: Option<*mut BlockHeader> = None;
 
         // Search free list
         let mut current = self.free_list.load(Ordering::Relaxed);
@@ -328,77 +632,70 @@ impl HealingHeapAllocator {
             let block = current as *mut BlockHeader;
             
             if (*block).magic != BLOCK_MAGIC {
-                // Corrupted block - attempt healing
+                let stats = &mut *self.stats.get();
+                stats.corruption_events += 1;
+                
+                // Corrupted block - quarantine it and resume past it
                 if self.healing_enabled.load(Ordering::Relaxed) {
-                    if self.heal_block(block).is_err() {
-                        let stats = &mut *self.stats.get();
-                        stats.corruption_events += 1;
-                        current = (*block).next.unwrap_or(0);
+                    if let Ok(next) = self.heal_block(block) {
+                        current = next.unwrap_or(0);
                         continue;
                     }
-                } else {
-                    let stats = &mut *self.stats.get();
-                    stats.corruption_events += 1;
-                    return core::ptr::null_mut();
                 }
+                
+                // Links can't be trusted, stop searching
+                return (core::ptr::null_mut(), false);
             }
             
-            if !(*block).is_allocated && (*block).size >= total_size {
-                // Split block if large enough
-                let remaining = (*block).size - total_size;
-                
-                if remaining >= header_size + CANARY_SIZE + 16 {
-                    // Split the block
-                    let new_block_addr = current + header_size + total_size;
-                    let new_block = new_block_addr as *mut BlockHeader;
-                    
-                    (*new_block).size = remaining - header_size - CANARY_SIZE;
-                    (*new_block).is_allocated = false;
-                    (*new_block).magic = BLOCK_MAGIC;
-                    (*new_block).prev = Some(current);
-                    (*new_block).next = (*block).next;
-                    
-                    // Write canary for new block
-                    let new_canary = new_block_addr + header_size + (*new_block).size;
-                    for i in 0..CANARY_SIZE {
-                        (new_canary as *mut u8).add(i).write(CANARY_VALUE);
-                    }
-                    
-                    (*block).next = Some(new_block_addr);
-                    (*block).size = total_size - CANARY_SIZE;
-                    
-                    // Update free list if needed
-                    if self.free_list.load(Ordering::Relaxed) == current {
-                        self.free_list.store(new_block_addr, Ordering::Relaxed);
-                    }
+            if !(*block).is_allocated && (*block).size >= size {
+                // An exact fit cannot be beaten, so best-fit stops there too
+                if !best_fit || (*block).size == size {
+                    return self.claim_block(block, size);
                 }
-                
-                // Allocate this block
-                (*block).is_allocated = true;
-                
-                // Write canary
-                let canary_addr = current + header_size + (*block).size;
-                for i in 0..CANARY_SIZE {
-                    (canary_addr as *mut u8).add(i).write(CANARY_VALUE);
+                if best.map_or(true, |b| (*block).size < (*b).size) {
+                    best = Some(block);
                 }
-                
-                // Update stats
-                let stats = &mut *self.stats.get();
-                stats.total_allocations += 1;
-                stats.allocated_pages += (total_size + PAGE_SIZE - 1) / PAGE_SIZE;
-                stats.free_pages = stats.free_pages.saturating_sub((total_size + PAGE_SIZE - 1) / PAGE_SIZE);
-                
-                // Return user data pointer
-                return (current + header_size) as *mut u8;
             }
             
             current = (*block).next.unwrap_or(0);
         }
         
-        // No suitable block found
+        match best {
+            Some(block) => self.claim_block(block, size),
+            // No suitable block found
+            None => (core::ptr::null_mut(), false),
+        }
+    }
+
+    /// Allocate a free block, splitting off any surplus beyond `size`
+    unsafe fn claim_block(&self, block: *mut BlockHeader, size: usize) -> (*mut u8, bool) {
+        let current = block as usize;
+        
+        // Split block if large enough
+        if let Some(new_block_addr) = self.split_block(block, size) {
+            // Update free list if needed
+            if self.free_list.load(Ordering::Relaxed) == current {
+                self.free_list.store(new_block_addr, Ordering::Relaxed);
+            }
+        }
+        
+        // Allocate this block; the caller owns the contents from now on
+        (*block).is_allocated = true;
+        let was_zeroed = (*block).was_zeroed;
+        (*block).was_zeroed = false;
+        (*block).guarded = false;
+        (*block).poisoned = false;
+        
+        // Write a fresh canary for this allocation
+        (*block).canary = self.next_canary(current);
+        self.write_canary(block);
+        
+        // Update stats
         let stats = &mut *self.stats.get();
-        stats.failed_allocations += 1;
-        core::ptr::null_mut()
+        stats.total_allocations += 1;
+        
+        // Return user data pointer
+        ((current + core::mem::size_of::<BlockHeader>()) as *mut u8, was_zeroed)
     }
 
     /// Free memory with corruption detection
@@ -429,99 +726,572 @@ impl HealingHeapAllocator {
         }
         
         // Check canary
-        let canary_addr = (block as usize) + header_size + (*block).size;
-        if !self.check_canary(canary_addr as *const u8) {
+        if !self.check_canary(block) {
             // Buffer overflow detected
             let stats = &mut *self.stats.get();
             stats.corruption_events += 1;
             
             if self.healing_enabled.load(Ordering::Relaxed) {
-                self.repair_canary(canary_addr as *mut u8);
+                self.repair_canary(block);
             }
         }
         
+        // Overflow that stopped short of the canary lands in the guard
+        if (*block).guarded && !Self::guard_intact(block) {
+            let stats = &mut *self.stats.get();
+            stats.corruption_events += 1;
+        }
+        
         // Mark as free
         (*block).is_allocated = false;
+        (*block).guarded = false;
+        (*block).generation = (*block).generation.wrapping_add(1);
+        if self.guard_pages.load(Ordering::Relaxed) {
+            Self::poison(block);
+        }
         
         // Update stats
         let stats = &mut *self.stats.get();
         stats.total_deallocations += 1;
-        let size = (*block).size + header_size + CANARY_SIZE;
-        stats.allocated_pages = stats.allocated_pages.saturating_sub((size + PAGE_SIZE - 1) / PAGE_SIZE);
-        stats.free_pages += (size + PAGE_SIZE - 1) / PAGE_SIZE;
         
         // Coalesce with next block if free
-        if let Some(next_addr) = (*block).next {
-            let next = next_addr as *mut BlockHeader;
-            if !(*next).is_allocated {
-                // Merge
-                (*block).size += header_size + CANARY_SIZE + (*next).size;
-                (*block).next = (*next).next;
-                
-                if let Some(next_next) = (*next).next {
-                    (*(next_next as *mut BlockHeader)).prev = Some(block as usize);
-                }
-            }
-        }
+        self.merge_with_next(block);
         
         // Coalesce with previous block if free
+        let mut free_addr = block as usize;
         if let Some(prev_addr) = (*block).prev {
             let prev = prev_addr as *mut BlockHeader;
-            if !(*prev).is_allocated {
-                // Merge
-                (*prev).size += header_size + CANARY_SIZE + (*block).size;
-                (*prev).next = (*block).next;
+            if !(*prev).is_allocated && self.merge_with_next(prev) {
+                free_addr = prev_addr;
+            }
+        }
+        
+        // Keep the free list head at or below the lowest free block, which
+        // also moves it off any header that was just merged away
+        let head = self.free_list.load(Ordering::Relaxed);
+        if head == 0 || free_addr < head {
+            self.free_list.store(free_addr, Ordering::Relaxed);
+        }
+        
+        self.check_pressure();
+    }
+
+    /// Run the pressure callbacks when free memory first drops below the
+    /// threshold, and re-arm them once it has recovered
+    fn check_pressure(&self) {
+        let threshold = self.pressure_threshold.load(Ordering::Relaxed);
+        if threshold == 0 {
+            return;
+        }
+        
+        if self.free_bytes() >= threshold {
+            self.under_pressure.store(false, Ordering::Relaxed);
+        } else if !self.under_pressure.swap(true, Ordering::Relaxed) {
+            let count = self.pressure_callback_count.load(Ordering::Relaxed);
+            let callbacks = unsafe { *self.pressure_callbacks.get() };
+            for callback in callbacks[..count].iter().flatten() {
+                callback();
+            }
+        }
+    }
+
+    /// Reallocate memory, growing in place when the next block is free
+    ///
+    /// Falls back to allocate-copy-free when the block cannot be extended.
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by this allocator with `layout`.
+    pub unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        
+        if ptr.is_null() || layout.size() == 0 {
+            return self.alloc(new_layout);
+        }
+        
+        if new_size == 0 {
+            self.dealloc(ptr, layout);
+            return new_layout.align() as *mut u8;
+        }
+        
+        let header_size = core::mem::size_of::<BlockHeader>();
+        let current = ptr as usize - header_size;
+        let block = current as *mut BlockHeader;
+        
+        // Guarded blocks always move, so the guard stays behind the data
+        if (*block).magic == BLOCK_MAGIC && (*block).is_allocated && !(*block).guarded {
+            // Block already large enough (shrink or slack from an unsplit block)
+            if new_size <= (*block).size {
+                return ptr;
+            }
+            
+            if let Some(next_addr) = (*block).next {
+                let next = next_addr as *mut BlockHeader;
+                let combined = (*block).size + header_size + CANARY_SIZE + (*next).size;
                 
-                if let Some(next) = (*block).next {
-                    (*(next as *mut BlockHeader)).prev = Some(prev_addr);
+                if (*next).magic == BLOCK_MAGIC
+                    && !(*next).is_allocated
+                    && Self::is_adjacent(block, next_addr)
+                    && combined >= new_size
+                {
+                    // Absorb the next block
+                    (*block).size = combined;
+                    (*block).next = (*next).next;
+                    if let Some(next_next) = (*next).next {
+                        (*(next_next as *mut BlockHeader)).prev = Some(current);
+                    }
+                    
+                    // The absorbed header no longer exists, restart searches here
+                    if self.free_list.load(Ordering::Relaxed) == next_addr {
+                        self.free_list.store(current, Ordering::Relaxed);
+                    }
+                    
+                    // Hand any surplus back as a free block
+                    self.split_block(block, new_size.next_multiple_of(BLOCK_ALIGN));
+                    
+                    self.write_canary(block);
+                    
+                    return ptr;
                 }
             }
         }
+        
+        // Fallback: allocate, copy, free
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, core::cmp::min(layout.size(), new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
     }
 
-    /// Check if canary is intact
-    unsafe fn check_canary(&self, canary: *const u8) -> bool {
-        for i in 0..CANARY_SIZE {
-            if canary.add(i).read() != CANARY_VALUE {
-                return false;
-            }
+    /// Merge the next block into `block` if it is free, intact and directly
+    /// adjacent (never across a quarantined hole)
+    unsafe fn merge_with_next(&self, block: *mut BlockHeader) -> bool {
+        let next_addr = match (*block).next {
+            Some(next_addr) if Self::is_adjacent(block, next_addr) => next_addr,
+            _ => return false,
+        };
+        
+        let next = next_addr as *mut BlockHeader;
+        if (*next).magic != BLOCK_MAGIC || (*next).is_allocated {
+            return false;
+        }
+        
+        (*block).size += core::mem::size_of::<BlockHeader>() + CANARY_SIZE + (*next).size;
+        (*block).was_zeroed = false;
+        (*block).next = (*next).next;
+        self.write_canary(block);
+        
+        if let Some(next_next) = (*next).next {
+            (*(next_next as *mut BlockHeader)).prev = Some(block as usize);
+        }
+        
+        // Cover the absorbed header too, or drop the poison claim
+        if self.guard_pages.load(Ordering::Relaxed) {
+            Self::poison(block);
+        } else {
+            (*block).poisoned = false;
         }
         true
     }
 
-    /// Repair corrupted canary
-    unsafe fn repair_canary(&self, canary: *mut u8) {
-        for i in 0..CANARY_SIZE {
-            canary.add(i).write(CANARY_VALUE);
+    /// Whether `next_addr` starts right after `block`'s canary
+    unsafe fn is_adjacent(block: *const BlockHeader, next_addr: usize) -> bool {
+        block as usize + core::mem::size_of::<BlockHeader>() + (*block).size + CANARY_SIZE == next_addr
+    }
+
+    /// Split a block so it holds exactly `size` user bytes
+    ///
+    /// Returns the address of the new free block, or `None` if the surplus
+    /// is too small to hold a header, canary and a minimum payload.
+    unsafe fn split_block(&self, block: *mut BlockHeader, size: usize) -> Option<usize> {
+        let header_size = core::mem::size_of::<BlockHeader>();
+        let remaining = (*block).size - size;
+        
+        if remaining < header_size + CANARY_SIZE + 16 {
+            return None;
         }
+        
+        let current = block as usize;
+        let new_block_addr = current + header_size + size + CANARY_SIZE;
+        let new_block = new_block_addr as *mut BlockHeader;
+        
+        (*new_block).size = remaining - header_size - CANARY_SIZE;
+        (*new_block).is_allocated = false;
+        (*new_block).was_zeroed = (*block).was_zeroed;
+        (*new_block).guarded = false;
+        // A fresh number, so a stale handle to an old header here never matches
+        (*new_block).generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        // The surplus of a poisoned free block is still poison
+        (*new_block).poisoned = (*block).poisoned && !(*block).is_allocated;
+        (*new_block).magic = BLOCK_MAGIC;
+        (*new_block).canary = self.next_canary(new_block_addr);
+        (*new_block).prev = Some(current);
+        (*new_block).next = (*block).next;
+        
+        if let Some(next) = (*block).next {
+            (*(next as *mut BlockHeader)).prev = Some(new_block_addr);
+        }
+        
+        // Write canary for new block
+        self.write_canary(new_block);
+        
+        (*block).next = Some(new_block_addr);
+        (*block).size = size;
+        
+        Some(new_block_addr)
+    }
+
+    /// Derive a per-block canary value
+    fn next_canary(&self, block_addr: usize) -> u64 {
+        // SplitMix64 over the seeded state, mixed with the block address
+        let mut z = self.canary_state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            ^ block_addr as u64;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Address of the canary following a block's user region
+    unsafe fn canary_addr(block: *const BlockHeader) -> *mut u64 {
+        (block as usize + core::mem::size_of::<BlockHeader>() + (*block).size) as *mut u64
+    }
+
+    /// Write the block's canary after its user region
+    unsafe fn write_canary(&self, block: *mut BlockHeader) {
+        Self::canary_addr(block).write_unaligned((*block).canary);
+    }
+
+    /// Check if canary matches the value recorded in the header
+    unsafe fn check_canary(&self, block: *const BlockHeader) -> bool {
+        Self::canary_addr(block).read_unaligned() == (*block).canary
+    }
+
+    /// User bytes of a block
+    unsafe fn user_region<'a>(block: *const BlockHeader) -> &'a mut [u8] {
+        let start = (block as usize + core::mem::size_of::<BlockHeader>()) as *mut u8;
+        core::slice::from_raw_parts_mut(start, (*block).size)
+    }
+
+    /// Guard region at the end of a guarded block's user bytes
+    unsafe fn guard_region<'a>(block: *const BlockHeader) -> &'a mut [u8] {
+        let user = Self::user_region(block);
+        let len = user.len();
+        &mut user[len - GUARD_SIZE..]
+    }
+
+    /// Check that a guarded block's guard region is untouched
+    unsafe fn guard_intact(block: *const BlockHeader) -> bool {
+        Self::guard_region(block).iter().all(|&b| b == GUARD_BYTE)
+    }
+
+    /// Fill a free block with the poison pattern
+    unsafe fn poison(block: *mut BlockHeader) {
+        Self::user_region(block).fill(POISON_BYTE);
+        (*block).poisoned = true;
     }
 
-    /// Attempt to heal a corrupted block
-    unsafe fn heal_block(&self, block: *mut BlockHeader) -> Result<(), MemoryError> {
-        // Simple healing: reinitialize the block header
-        (*block).magic = BLOCK_MAGIC;
-        (*block).is_allocated = true; // Assume allocated to prevent double-free
+    /// Check that a poisoned block was not written after being freed
+    unsafe fn poison_intact(block: *const BlockHeader) -> bool {
+        Self::user_region(block).iter().all(|&b| b == POISON_BYTE)
+    }
+
+    /// Repair corrupted canary from the value recorded in the header
+    unsafe fn repair_canary(&self, block: *mut BlockHeader) {
+        self.write_canary(block);
+    }
+
+    /// Quarantine a corrupted block so it is never handed out again
+    ///
+    /// The region from the block up to its successor (or the heap end, if
+    /// the successor link can't be trusted) is unlinked from the block list
+    /// and recorded in this heap's quarantine. The heap's region is not
+    /// necessarily backed by `PAGE_ALLOCATOR` frames, so the quarantine stays
+    /// local; the pages it spans are only counted. Returns the successor
+    /// block, if any.
+    unsafe fn heal_block(&self, block: *mut BlockHeader) -> Result<Option<usize>, MemoryError> {
+        let header_size = core::mem::size_of::<BlockHeader>();
+        let heap_base = *self.heap_base.get() as usize;
+        let heap_end = heap_base + self.heap_size.load(Ordering::Relaxed);
+        let addr = block as usize;
+        
+        if addr < heap_base || addr + header_size > heap_end {
+            return Err(MemoryError::InvalidPointer);
+        }
+        
+        let count = self.quarantine_count.load(Ordering::Relaxed);
+        if count >= MAX_QUARANTINED_BLOCKS {
+            return Err(MemoryError::OutOfMemory);
+        }
+        
+        // Only follow the forward link if it lands on an intact header
+        let succ = match (*block).next {
+            Some(next) if next > addr
+                && next % BLOCK_ALIGN == 0
+                && next + header_size <= heap_end
+                && (*(next as *const BlockHeader)).magic == BLOCK_MAGIC => Some(next),
+            _ => None,
+        };
+        let region_end = succ.unwrap_or(heap_end);
+        let prev = self.find_prev(addr);
+        
+        // Unlink the region
+        if let Some(prev) = prev {
+            (*(prev as *mut BlockHeader)).next = succ;
+        }
+        if let Some(succ) = succ {
+            (*(succ as *mut BlockHeader)).prev = prev;
+        }
+        let head = self.free_list.load(Ordering::Relaxed);
+        if head >= addr && head < region_end {
+            self.free_list.store(prev.or(succ).unwrap_or(0), Ordering::Relaxed);
+        }
+        
+        (*self.quarantined_blocks.get())[count] = (addr, region_end);
+        self.quarantine_count.store(count + 1, Ordering::Relaxed);
+        
+        // Heap pages the region touches
+        let first_page = (addr - heap_base) / PAGE_SIZE;
+        let last_page = (region_end - 1 - heap_base) / PAGE_SIZE;
         
         let stats = &mut *self.stats.get();
-        stats.recovered_pages += 1;
+        stats.corrupted_pages += last_page - first_page + 1;
         
-        Ok(())
+        Ok(succ)
+    }
+
+    /// Find the block whose forward link points at `addr`
+    unsafe fn find_prev(&self, addr: usize) -> Option<usize> {
+        let mut prev = None;
+        self.walk_blocks(|info| {
+            let current = info.address - core::mem::size_of::<BlockHeader>();
+            if (*(current as *const BlockHeader)).next == Some(addr) {
+                prev = Some(current);
+            }
+        });
+        prev
+    }
+
+    /// End of the quarantined region starting at `addr`, if any
+    fn quarantined_end(&self, addr: usize) -> Option<usize> {
+        let count = self.quarantine_count.load(Ordering::Relaxed);
+        let quarantined = unsafe { &*self.quarantined_blocks.get() };
+        quarantined[..count]
+            .iter()
+            .find(|&&(start, _)| start == addr)
+            .map(|&(_, end)| end)
+    }
+
+    /// Number of corrupted regions held in quarantine
+    pub fn quarantined_count(&self) -> usize {
+        self.quarantine_count.load(Ordering::Relaxed)
     }
 
     /// Get memory statistics
+    ///
+    /// Byte counts come from the block list; the page fields are left for
+    /// `get_stats` to fill in from the page allocator.
     pub fn stats(&self) -> MemoryStats {
+        self.compute_fragmentation();
         unsafe { (*self.stats.get()).clone() }
     }
 
+    /// Refresh the byte counts and fragmentation figures from the block list
+    pub fn compute_fragmentation(&self) {
+        let mut largest = 0;
+        let mut count = 0;
+        let mut free = 0;
+        let mut allocated = 0;
+        
+        self.walk_blocks(|info| {
+            if info.is_allocated {
+                allocated += info.size;
+            } else {
+                largest = core::cmp::max(largest, info.size);
+                count += 1;
+                free += info.size;
+            }
+        });
+        
+        let stats = unsafe { &mut *self.stats.get() };
+        stats.largest_free_block = largest;
+        stats.free_block_count = count;
+        stats.free_bytes = free;
+        stats.allocated_bytes = allocated;
+    }
+
+    /// Visit every block in address order
+    ///
+    /// The walk stops early at a block with a bad magic or a link that
+    /// does not move forward inside the heap.
+    pub fn walk_blocks(&self, mut f: impl FnMut(BlockInfo)) {
+        let heap_base = unsafe { *self.heap_base.get() };
+        if heap_base.is_null() {
+            return;
+        }
+        
+        let header_size = core::mem::size_of::<BlockHeader>();
+        let heap_end = heap_base as usize + self.heap_size.load(Ordering::Relaxed);
+        let mut current = self.quarantined_end(heap_base as usize).unwrap_or(heap_base as usize);
+        if current + header_size > heap_end {
+            return;
+        }
+        
+        loop {
+            let block = current as *const BlockHeader;
+            
+            unsafe {
+                if (*block).magic != BLOCK_MAGIC {
+                    break;
+                }
+                
+                let guard = if (*block).guarded { GUARD_SIZE } else { 0 };
+                f(BlockInfo {
+                    address: current + header_size,
+                    size: (*block).size - guard,
+                    is_allocated: (*block).is_allocated,
+                });
+                
+                match (*block).next {
+                    Some(next) if next > current && next + header_size <= heap_end => current = next,
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Number of blocks currently allocated
+    pub fn live_allocation_count(&self) -> usize {
+        let mut count = 0;
+        self.walk_blocks(|info| {
+            if info.is_allocated {
+                count += 1;
+            }
+        });
+        count
+    }
+
+    /// Total user bytes held by allocated blocks
+    pub fn live_bytes(&self) -> usize {
+        let mut bytes = 0;
+        self.walk_blocks(|info| {
+            if info.is_allocated {
+                bytes += info.size;
+            }
+        });
+        bytes
+    }
+
+    /// Total user bytes available in free blocks
+    pub fn free_bytes(&self) -> usize {
+        let mut bytes = 0;
+        self.walk_blocks(|info| {
+            if !info.is_allocated {
+                bytes += info.size;
+            }
+        });
+        bytes
+    }
+
+    /// Notify pressure callbacks when free heap bytes fall below `free_bytes`
+    ///
+    /// Callbacks run once per low-memory episode: after they fire, they are
+    /// only re-armed when free memory climbs back to the threshold. 0
+    /// disables the check, which otherwise walks the heap on every
+    /// allocation and free.
+    pub fn set_pressure_threshold(&self, free_bytes: usize) {
+        self.pressure_threshold.store(free_bytes, Ordering::Relaxed);
+        self.under_pressure.store(false, Ordering::Relaxed);
+    }
+
+    /// Register a callback to shed memory (drop caches) under pressure
+    pub fn register_pressure_callback(&self, callback: PressureCallback) -> Result<(), MemoryError> {
+        let count = self.pressure_callback_count.load(Ordering::Relaxed);
+        if count >= MAX_PRESSURE_CALLBACKS {
+            return Err(MemoryError::OutOfMemory);
+        }
+        unsafe { (*self.pressure_callbacks.get())[count] = Some(callback) };
+        self.pressure_callback_count.store(count + 1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Register a handler invoked when an allocation fails
+    ///
+    /// Returning `true` (e.g. after running `gc` or dropping caches) makes
+    /// the allocator retry the failed allocation once.
+    pub fn set_oom_handler(&self, handler: fn() -> bool) {
+        unsafe { *self.oom_handler.get() = Some(handler) };
+    }
+
+    /// Choose how free blocks are selected (first-fit by default)
+    pub fn set_strategy(&self, strategy: AllocStrategy) {
+        self.strategy.store(strategy as u8, Ordering::Relaxed);
+    }
+
+    /// Current block selection strategy
+    pub fn strategy(&self) -> AllocStrategy {
+        match self.strategy.load(Ordering::Relaxed) {
+            1 => AllocStrategy::BestFit,
+            _ => AllocStrategy::FirstFit,
+        }
+    }
+
+    /// Enable/disable the hardened debugging mode
+    ///
+    /// Each new allocation is followed by a `GUARD_SIZE` region filled with
+    /// a known pattern, and freed blocks are poisoned. `verify_heap` then
+    /// reports overflows into a guard and writes to freed memory. The
+    /// guard is not hardware-protected; damage is found when checked.
+    pub fn set_guard_pages(&self, enabled: bool) {
+        self.guard_pages.store(enabled, Ordering::Relaxed);
+    }
+
     /// Enable/disable self-healing
     pub fn set_healing_enabled(&self, enabled: bool) {
         self.healing_enabled.store(enabled, Ordering::Relaxed);
     }
 
     /// Run memory defragmentation
-    pub fn defragment(&self) {
-        // This would consolidate free blocks
-        // For now, just a placeholder
+    ///
+    /// Walks the block list in address order, coalescing every run of
+    /// adjacent free blocks, and resets the free list head to the lowest
+    /// free block. Returns the number of merges performed.
+    pub fn defragment(&self) -> usize {
+        let heap_base = unsafe { *self.heap_base.get() };
+        if heap_base.is_null() {
+            return 0;
+        }
+        
+        let mut merges = 0;
+        let mut lowest_free = None;
+        let mut current = self.quarantined_end(heap_base as usize).unwrap_or(heap_base as usize);
+        let heap_end = heap_base as usize + self.heap_size.load(Ordering::Relaxed);
+        
+        while current != 0 && current + core::mem::size_of::<BlockHeader>() <= heap_end {
+            let block = current as *mut BlockHeader;
+            
+            unsafe {
+                if (*block).magic != BLOCK_MAGIC {
+                    break;
+                }
+                
+                if !(*block).is_allocated {
+                    lowest_free.get_or_insert(current);
+                    while self.merge_with_next(block) {
+                        merges += 1;
+                    }
+                }
+                
+                current = match (*block).next {
+                    Some(next) if next > current => next,
+                    _ => 0,
+                };
+            }
+        }
+        
+        self.free_list.store(lowest_free.unwrap_or(0), Ordering::Relaxed);
+        merges
     }
 
     /// Check entire heap for corruption
@@ -537,16 +1307,21 @@ impl HealingHeapAllocator {
         let heap_end = current + self.heap_size.load(Ordering::Relaxed);
         
         while current < heap_end {
+            // Quarantined regions are known-bad and no longer part of the heap
+            if let Some(end) = self.quarantined_end(current) {
+                current = end;
+                continue;
+            }
+            
             let block = current as *mut BlockHeader;
             
             unsafe {
-                if (*block).magic != BLOCK_MAGIC {
+                let corrupted = (*block).magic != BLOCK_MAGIC
+                    || ((*block).is_allocated && !self.check_canary(block))
+                    || ((*block).is_allocated && (*block).guarded && !Self::guard_intact(block))
+                    || (!(*block).is_allocated && (*block).poisoned && !Self::poison_intact(block));
+                if corrupted {
                     errors += 1;
-                } else if (*block).is_allocated {
-                    let canary_addr = current + core::mem::size_of::<BlockHeader>() + (*block).size;
-                    if !self.check_canary(canary_addr as *const u8) {
-                        errors += 1;
-                    }
                 }
                 
                 // Move to next block
@@ -574,6 +1349,14 @@ pub unsafe fn init(heap_start: *mut u8, heap_size: usize) {
     HEAP_ALLOCATOR.init(heap_start, heap_size);
 }
 
+/// Initialize memory subsystem with a region known to be zero-filled
+///
+/// # Safety
+/// See [`HealingHeapAllocator::init_zeroed`].
+pub unsafe fn init_zeroed(heap_start: *mut u8, heap_size: usize) {
+    HEAP_ALLOCATOR.init_zeroed(heap_start, heap_size);
+}
+
 /// GlobalAlloc implementation for the heap allocator
 pub struct GlobalHeapAllocator;
 
@@ -585,11 +1368,23 @@ unsafe impl GlobalAlloc for GlobalHeapAllocator {
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         HEAP_ALLOCATOR.dealloc(ptr, layout);
     }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        HEAP_ALLOCATOR.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        HEAP_ALLOCATOR.realloc(ptr, layout, new_size)
+    }
 }
 
 /// Get current memory statistics
 pub fn get_stats() -> MemoryStats {
-    HEAP_ALLOCATOR.stats()
+    let mut stats = HEAP_ALLOCATOR.stats();
+    stats.total_pages = NUM_PAGES;
+    stats.free_pages = PAGE_ALLOCATOR.free_pages();
+    stats.allocated_pages = PAGE_ALLOCATOR.count_pages(PageState::Allocated);
+    stats
 }
 
 /// Verify heap integrity
@@ -608,6 +1403,31 @@ pub fn set_self_healing(enabled: bool) {
     HEAP_ALLOCATOR.set_healing_enabled(enabled);
 }
 
+/// Choose the kernel heap's block selection strategy
+pub fn set_alloc_strategy(strategy: AllocStrategy) {
+    HEAP_ALLOCATOR.set_strategy(strategy);
+}
+
+/// Enable/disable guard regions and free-poisoning on the kernel heap
+pub fn set_guard_pages(enabled: bool) {
+    HEAP_ALLOCATOR.set_guard_pages(enabled);
+}
+
+/// Set the kernel heap's memory-pressure threshold in free bytes
+pub fn set_pressure_threshold(free_bytes: usize) {
+    HEAP_ALLOCATOR.set_pressure_threshold(free_bytes);
+}
+
+/// Register a callback run when the kernel heap comes under memory pressure
+pub fn register_pressure_callback(callback: PressureCallback) -> Result<(), MemoryError> {
+    HEAP_ALLOCATOR.register_pressure_callback(callback)
+}
+
+/// Register the kernel heap's out-of-memory handler
+pub fn set_oom_handler(handler: fn() -> bool) {
+    HEAP_ALLOCATOR.set_oom_handler(handler);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -632,6 +1452,520 @@ mod tests {
         assert!(alloc.free_page(page1.unwrap()).is_err());
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_alloc_page_unique() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 1000;
+        let alloc = PageFrameAllocator::new();
+        
+        let mut pages: Vec<usize> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|_| scope.spawn(|| (0..PER_THREAD).map(|_| alloc.alloc_page().unwrap()).collect::<Vec<_>>()))
+                .collect();
+            workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+        });
+        
+        pages.sort_unstable();
+        pages.dedup();
+        assert_eq!(pages.len(), THREADS * PER_THREAD);
+        assert_eq!(alloc.free_pages(), NUM_PAGES - THREADS * PER_THREAD);
+        assert_eq!(alloc.count_pages(PageState::Allocated), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn test_reserve_range() {
+        let alloc = PageFrameAllocator::new();
+        
+        // Reserve the first pages, the way the kernel image would be
+        alloc.reserve_range(0, 8).unwrap();
+        assert_eq!(alloc.free_pages(), NUM_PAGES - 8);
+        assert_eq!(alloc.count_pages(PageState::Reserved), 8);
+        
+        assert_eq!(alloc.alloc_page(), Some(8));
+        assert_eq!(alloc.alloc_pages(4), Some(9));
+        
+        // A range overlapping an allocated page reserves it too, but only
+        // the free page comes out of the free count
+        alloc.reserve_range(12, 2).unwrap();
+        assert_eq!(alloc.free_pages(), NUM_PAGES - 8 - 5 - 1);
+        
+        assert_eq!(alloc.free_page(3), Err(MemoryError::ReservedPage));
+        assert_eq!(alloc.free_page(12), Err(MemoryError::ReservedPage));
+        assert!(alloc.free_page(11).is_ok());
+        assert_eq!(alloc.reserve_range(NUM_PAGES - 1, 2), Err(MemoryError::InvalidPointer));
+    }
+
+    #[test]
+    fn test_alloc_pages_aligned() {
+        let alloc = PageFrameAllocator::new();
+        
+        // Occupy page 0 so the first 2MB-aligned run starts at 512
+        assert_eq!(alloc.alloc_page(), Some(0));
+        let start = alloc.alloc_pages_aligned(4, 512).unwrap();
+        assert_eq!(start, 512);
+        assert_eq!(alloc.get_page_state(515), PageState::Allocated);
+        assert_eq!(alloc.free_pages(), NUM_PAGES - 5);
+        
+        // Plain contiguous allocation still fills the gap
+        assert_eq!(alloc.alloc_pages(4), Some(1));
+    }
+
+    #[test]
+    fn test_alloc_pages_aligned_no_fit() {
+        let alloc = PageFrameAllocator::new();
+        
+        // Block every 2MB boundary; unaligned runs remain but none qualify
+        for page in (0..NUM_PAGES).step_by(512) {
+            alloc.mark_corrupted(page);
+        }
+        assert_eq!(alloc.alloc_pages_aligned(4, 512), None);
+        assert!(alloc.alloc_pages(4).is_some());
+    }
+
+    fn test_heap(size: usize) -> (HealingHeapAllocator, Vec<u64>) {
+        let mut backing = vec![0u64; size / 8];
+        let heap = HealingHeapAllocator::new();
+        unsafe { heap.init(backing.as_mut_ptr() as *mut u8, size) };
+        (heap, backing)
+    }
+
+    #[test]
+    fn test_realloc_in_place() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        
+        unsafe {
+            let layout = Layout::from_size_align(64, 8).unwrap();
+            let ptr = heap.alloc(layout);
+            assert!(!ptr.is_null());
+            ptr.write_bytes(0xAB, 64);
+            
+            // Next block is the free remainder, so growth happens in place
+            let grown = heap.realloc(ptr, layout, 256);
+            assert_eq!(grown, ptr);
+            assert!((0..64).all(|i| *grown.add(i) == 0xAB));
+            
+            assert!(heap.verify_heap().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_realloc_relocates() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        
+        unsafe {
+            let layout = Layout::from_size_align(64, 8).unwrap();
+            let a = heap.alloc(layout);
+            let b = heap.alloc(layout);
+            assert!(!a.is_null() && !b.is_null());
+            a.write_bytes(0xCD, 64);
+            
+            // Blocked by an allocated neighbour, so the block must move
+            let moved = heap.realloc(a, layout, 512);
+            assert!(!moved.is_null());
+            assert_ne!(moved, a);
+            assert!((0..64).all(|i| *moved.add(i) == 0xCD));
+            
+            assert!(heap.verify_heap().is_ok());
+            assert_eq!(heap.stats().total_deallocations, 1);
+        }
+    }
+
+    #[test]
+    fn test_alloc_zeroed() {
+        let mut backing = vec![0u64; 64 * 1024 / 8];
+        let heap = HealingHeapAllocator::new();
+        
+        unsafe {
+            heap.init_zeroed(backing.as_mut_ptr() as *mut u8, 64 * 1024);
+            let layout = Layout::from_size_align(128, 8).unwrap();
+            
+            // Fresh block straight from the zeroed region
+            let a = heap.alloc_zeroed(layout);
+            assert!(!a.is_null());
+            assert!((0..128).all(|i| *a.add(i) == 0));
+            
+            // Keep a neighbour allocated so `a` is reused rather than merged
+            let b = heap.alloc(layout);
+            assert!(!b.is_null());
+            
+            // Dirty the block, free it, and get it back zeroed
+            a.write_bytes(0xFF, 128);
+            heap.dealloc(a, layout);
+            let reused = heap.alloc_zeroed(layout);
+            assert_eq!(reused, a);
+            assert!((0..128).all(|i| *reused.add(i) == 0));
+        }
+    }
+
+    #[test]
+    fn test_canary_detects_overflow() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        
+        unsafe {
+            let layout = Layout::from_size_align(64, 8).unwrap();
+            let a = heap.alloc(layout);
+            let b = heap.alloc(layout);
+            assert!(heap.verify_heap().is_ok());
+            
+            // Each allocation gets its own canary
+            let header_size = core::mem::size_of::<BlockHeader>();
+            let block_a = (a as usize - header_size) as *const BlockHeader;
+            let block_b = (b as usize - header_size) as *const BlockHeader;
+            assert_ne!((*block_a).canary, (*block_b).canary);
+            
+            // One byte past the user region lands on the canary
+            *a.add(64) = !*a.add(64);
+            assert_eq!(heap.verify_heap(), Err(MemoryError::CorruptionDetected));
+        }
+    }
+
+    #[test]
+    fn test_walk_blocks() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        
+        unsafe {
+            // Sizes are rounded up to keep block headers aligned
+            let sizes = [32usize, 100, 256];
+            let rounded = [32usize, 104, 256];
+            let ptrs: Vec<*mut u8> = sizes
+                .iter()
+                .map(|&size| heap.alloc(Layout::from_size_align(size, 8).unwrap()))
+                .collect();
+            
+            let mut allocated = Vec::new();
+            heap.walk_blocks(|info| {
+                if info.is_allocated {
+                    allocated.push(info);
+                }
+            });
+            
+            assert_eq!(allocated.len(), 3);
+            for ((info, &ptr), &size) in allocated.iter().zip(&ptrs).zip(&rounded) {
+                assert_eq!(info.address, ptr as usize);
+                assert_eq!(info.size, size);
+            }
+            assert_eq!(heap.live_allocation_count(), 3);
+            assert_eq!(heap.live_bytes(), 32 + 104 + 256);
+            
+            heap.dealloc(ptrs[1], Layout::from_size_align(100, 8).unwrap());
+            assert_eq!(heap.live_allocation_count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_best_fit_fills_small_hole() {
+        let layout = |size| Layout::from_size_align(size, 8).unwrap();
+        
+        for strategy in [AllocStrategy::FirstFit, AllocStrategy::BestFit] {
+            let (heap, _backing) = test_heap(64 * 1024);
+            heap.set_strategy(strategy);
+            assert_eq!(heap.strategy(), strategy);
+            
+            unsafe {
+                // A large hole followed by a small one, each fenced by a live block
+                let large = heap.alloc(layout(1024));
+                let _fence1 = heap.alloc(layout(32));
+                let small = heap.alloc(layout(64));
+                let _fence2 = heap.alloc(layout(32));
+                heap.dealloc(large, layout(1024));
+                heap.dealloc(small, layout(64));
+                
+                let ptr = heap.alloc(layout(48));
+                let mut blocks = Vec::new();
+                heap.walk_blocks(|info| blocks.push(info));
+                let large_block = blocks.iter().find(|b| b.address == large as usize).unwrap();
+                
+                match strategy {
+                    AllocStrategy::FirstFit => {
+                        // The large block was split for the small request
+                        assert_eq!(ptr, large);
+                        assert_eq!(large_block.size, 48);
+                    }
+                    AllocStrategy::BestFit => {
+                        assert_eq!(ptr, small);
+                        assert_eq!(*large_block, BlockInfo { address: large as usize, size: 1024, is_allocated: false });
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_guard_pages_detect_use_after_free() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        heap.set_guard_pages(true);
+        let layout = Layout::from_size_align(128, 8).unwrap();
+        
+        unsafe {
+            let a = heap.alloc(layout);
+            let b = heap.alloc(layout);
+            assert_eq!(heap.live_bytes(), 256);
+            a.write_bytes(0xAA, 128);
+            assert_eq!(heap.verify_heap(), Ok(0));
+            
+            // Freed memory is poisoned; a stale write is caught
+            heap.dealloc(a, layout);
+            assert_eq!(heap.verify_heap(), Ok(0));
+            *a.add(5) = 0x42;
+            assert_eq!(heap.verify_heap(), Err(MemoryError::CorruptionDetected));
+            *a.add(5) = POISON_BYTE;
+            assert_eq!(heap.verify_heap(), Ok(0));
+            
+            // Running past the end lands in the guard before the canary
+            *b.add(128 + 8) = 0;
+            assert_eq!(heap.verify_heap(), Err(MemoryError::CorruptionDetected));
+            heap.dealloc(b, layout);
+            assert_eq!(heap.stats().corruption_events, 1);
+            assert_eq!(heap.verify_heap(), Ok(0));
+        }
+    }
+
+    #[test]
+    fn test_stale_generation_handle_rejected() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        
+        unsafe {
+            let first = heap.alloc_checked(layout).unwrap();
+            heap.dealloc_checked(first, layout).unwrap();
+            
+            // The same block comes back under a new generation
+            let second = heap.alloc_checked(layout).unwrap();
+            assert_eq!(second.as_ptr(), first.as_ptr());
+            assert_ne!(second.generation(), first.generation());
+            
+            // The stale handle can free neither the new allocation nor twice
+            assert_eq!(heap.dealloc_checked(first, layout), Err(MemoryError::InvalidPointer));
+            assert_eq!(heap.live_allocation_count(), 1);
+            heap.dealloc_checked(second, layout).unwrap();
+            assert_eq!(heap.dealloc_checked(second, layout), Err(MemoryError::InvalidPointer));
+            assert_eq!(heap.live_allocation_count(), 0);
+            assert_eq!(heap.stats().corruption_events, 0);
+        }
+    }
+
+    #[test]
+    fn test_pressure_callback_debounced() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn shed_caches() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+        
+        let (heap, _backing) = test_heap(64 * 1024);
+        heap.register_pressure_callback(shed_caches).unwrap();
+        heap.set_pressure_threshold(32 * 1024);
+        let layout = Layout::from_size_align(8 * 1024, 8).unwrap();
+        
+        unsafe {
+            let mut ptrs = Vec::new();
+            while heap.free_bytes() >= 32 * 1024 {
+                assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+                ptrs.push(heap.alloc(layout));
+            }
+            assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+            
+            // Still under pressure: no repeat notification
+            ptrs.push(heap.alloc(layout));
+            assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+            
+            // Recover, then cross the threshold again
+            for ptr in ptrs.drain(..) {
+                heap.dealloc(ptr, layout);
+            }
+            while heap.free_bytes() >= 32 * 1024 {
+                ptrs.push(heap.alloc(layout));
+            }
+            assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[test]
+    fn test_alloc_free_cycles_balance_bytes() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        let initial_free = heap.stats().free_bytes;
+        
+        unsafe {
+            for round in 0..8 {
+                let layouts: Vec<Layout> = [24usize, 100, 512, 3000]
+                    .iter()
+                    .map(|&size| Layout::from_size_align(size + round * 8, 8).unwrap())
+                    .collect();
+                let ptrs: Vec<*mut u8> = layouts.iter().map(|&layout| heap.alloc(layout)).collect();
+                assert!(ptrs.iter().all(|ptr| !ptr.is_null()));
+                
+                let stats = heap.stats();
+                assert_eq!(stats.allocated_bytes, heap.live_bytes());
+                assert!(stats.allocated_bytes >= layouts.iter().map(Layout::size).sum::<usize>());
+                
+                // Grow one block in place, then free everything out of order
+                let grown = heap.realloc(ptrs[3], layouts[3], 4000);
+                assert_eq!(heap.stats().allocated_bytes, heap.live_bytes());
+                heap.dealloc(ptrs[1], layouts[1]);
+                heap.dealloc(grown, Layout::from_size_align(4000, 8).unwrap());
+                heap.dealloc(ptrs[0], layouts[0]);
+                heap.dealloc(ptrs[2], layouts[2]);
+                
+                let stats = heap.stats();
+                assert_eq!(stats.allocated_bytes, 0);
+                assert_eq!(stats.free_bytes, initial_free);
+            }
+        }
+        
+        // The heap never touches the page allocator's counts
+        let stats = heap.stats();
+        assert_eq!(stats.total_pages, 0);
+        assert_eq!(stats.allocated_pages, 0);
+    }
+
+    #[test]
+    fn test_fragmentation_ratio() {
+        let (heap, _backing) = test_heap(16 * 1024);
+        let layout = Layout::from_size_align(128, 8).unwrap();
+        
+        // Unfragmented heap: a single free block
+        let stats = heap.stats();
+        assert_eq!(stats.free_block_count, 1);
+        assert_eq!(stats.fragmentation_ratio(), 0.0);
+        
+        // Fill the heap, then free every other block
+        let mut ptrs = Vec::new();
+        unsafe {
+            loop {
+                let ptr = heap.alloc(layout);
+                if ptr.is_null() {
+                    break;
+                }
+                ptrs.push(ptr);
+            }
+            for ptr in ptrs.iter().step_by(2) {
+                heap.dealloc(*ptr, layout);
+            }
+        }
+        
+        let stats = heap.stats();
+        assert!(stats.free_block_count >= ptrs.len() / 2);
+        assert!(stats.fragmentation_ratio() > 0.9);
+    }
+
+    #[test]
+    fn test_heal_quarantines_corrupted_block() {
+        let (heap, _backing) = test_heap(64 * 1024);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        
+        unsafe {
+            let a = heap.alloc(layout);
+            let b = heap.alloc(layout);
+            let c = heap.alloc(layout);
+            heap.dealloc(b, layout);
+            
+            // Smash the freed block's header
+            let block_b = (b as usize - core::mem::size_of::<BlockHeader>()) as *mut BlockHeader;
+            (*block_b).magic = 0;
+            
+            // The corrupted region is never handed out again
+            for _ in 0..16 {
+                let ptr = heap.alloc(layout);
+                assert!(!ptr.is_null());
+                assert_ne!(ptr, b);
+            }
+            assert_eq!(heap.quarantined_count(), 1);
+            assert!(heap.stats().corruption_events >= 1);
+            assert!(heap.stats().corrupted_pages >= 1);
+            // Page frames belong to the page allocator, not to this heap
+            assert_eq!(PAGE_ALLOCATOR.count_pages(PageState::Corrupted), 0);
+            
+            // The remaining heap is consistent and still reaches both neighbours
+            assert!(heap.verify_heap().is_ok());
+            let mut seen = Vec::new();
+            heap.walk_blocks(|info| seen.push(info.address));
+            assert!(seen.contains(&(a as usize)));
+            assert!(seen.contains(&(c as usize)));
+            assert!(!seen.contains(&(b as usize)));
+        }
+    }
+
+    #[test]
+    fn test_defragment() {
+        let (heap, _backing) = test_heap(16 * 1024);
+        let layout = Layout::from_size_align(128, 8).unwrap();
+        let header_size = core::mem::size_of::<BlockHeader>();
+        
+        unsafe {
+            let mut ptrs = Vec::new();
+            loop {
+                let ptr = heap.alloc(layout);
+                if ptr.is_null() {
+                    break;
+                }
+                ptrs.push(ptr);
+            }
+            assert!(ptrs.len() > 8);
+            
+            // Release a run of blocks without coalescing, as a partial free
+            // path would leave them
+            for ptr in &ptrs[2..6] {
+                (*((*ptr as usize - header_size) as *mut BlockHeader)).is_allocated = false;
+            }
+            // And a lone block elsewhere through the normal path
+            heap.dealloc(ptrs[8], layout);
+            assert_eq!(heap.stats().largest_free_block, 128);
+            
+            assert_eq!(heap.defragment(), 3);
+            
+            // The run became a single block spanning all four
+            let stats = heap.stats();
+            assert_eq!(stats.largest_free_block, 4 * 128 + 3 * (header_size + CANARY_SIZE));
+            assert_eq!(heap.free_list.load(Ordering::Relaxed), ptrs[2] as usize - header_size);
+            assert!(heap.verify_heap().is_ok());
+            
+            // Nothing left to merge
+            assert_eq!(heap.defragment(), 0);
+        }
+    }
+
+    #[test]
+    fn test_oom_handler_retries() {
+        static HEAP: HealingHeapAllocator = HealingHeapAllocator::new();
+        static RESERVE: AtomicUsize = AtomicUsize::new(0);
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        
+        fn release_reserve() -> bool {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            let ptr = RESERVE.swap(0, Ordering::SeqCst);
+            if ptr == 0 {
+                return false;
+            }
+            unsafe { HEAP.dealloc(ptr as *mut u8, Layout::from_size_align(2048, 8).unwrap()) };
+            true
+        }
+        
+        let backing: &'static mut [u64] = Vec::leak(vec![0u64; 16 * 1024 / 8]);
+        
+        unsafe {
+            HEAP.init(backing.as_mut_ptr() as *mut u8, 16 * 1024);
+            
+            let reserve = HEAP.alloc(Layout::from_size_align(2048, 8).unwrap());
+            RESERVE.store(reserve as usize, Ordering::SeqCst);
+            
+            // Exhaust the rest of the heap
+            let small = Layout::from_size_align(256, 8).unwrap();
+            while !HEAP.alloc(small).is_null() {}
+            
+            let big = Layout::from_size_align(1024, 8).unwrap();
+            assert!(HEAP.alloc(big).is_null());
+            
+            HEAP.set_oom_handler(release_reserve);
+            let ptr = HEAP.alloc(big);
+            assert!(!ptr.is_null());
+            assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+            
+            // Nothing left to release: the handler declines and alloc fails
+            assert!(HEAP.alloc(Layout::from_size_align(2048, 8).unwrap()).is_null());
+            assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+        }
+    }
+
     #[test]
     fn test_page_state() {
         let alloc = PageFrameAllocator::new();
@@ -9,6 +9,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod thread;
+
 use core::sync::atomic::{AtomicU64, Ordering};
 use core::cell::UnsafeCell;
 
@@ -20,6 +22,8 @@ use alloc::collections::BTreeMap;
 #[cfg(feature = "std")]
 use std::collections::BTreeMap;
 
+use thread::{Thread, ThreadEntry, ThreadTable, MAIN_TID, THREAD_STACK_SIZE};
+
 /// Maximum number of processes
 pub const MAX_PROCESSES: usize = 256;
 /// Default time slice in milliseconds
@@ -28,6 +32,8 @@ pub const DEFAULT_TIME_SLICE: u64 = 10;
 pub const NUM_PRIORITIES: usize = 8;
 /// Kernel process ID
 pub const KERNEL_PID: u64 = 0;
+/// Scheduler ticks a process may wait in a ready queue before it is boosted
+pub const DEFAULT_AGING_THRESHOLD: u64 = 50;
 
 /// Process states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -123,6 +129,34 @@ pub enum Capability {
     Admin = 63,
 }
 
+impl Capability {
+    /// Decode a capability from its numeric value
+    pub fn from_u8(value: u8) -> Option<Self> {
+        use Capability::*;
+        
+        let cap = match value {
+            0 => FileRead,
+            1 => FileWrite,
+            2 => FileCreate,
+            3 => FileDelete,
+            4 => Network,
+            5 => ProcessSpawn,
+            6 => ProcessKill,
+            7 => MemoryAlloc,
+            8 => Execute,
+            9 => HardwareAccess,
+            10 => SetTime,
+            11 => LoadModule,
+            12 => SignalSend,
+            13 => IpcCreate,
+            14 => IpcJoin,
+            63 => Admin,
+            _ => return None,
+        };
+        Some(cap)
+    }
+}
+
 impl Capabilities {
     pub const fn new() -> Self {
         Capabilities { bits: 0 }
@@ -180,6 +214,8 @@ pub struct ResourceLimits {
     pub max_open_files: u32,
     /// Maximum number of processes this process can spawn
     pub max_children: u32,
+    /// Maximum number of threads this process can spawn
+    pub max_threads: u32,
 }
 
 impl Default for ResourceLimits {
@@ -189,6 +225,7 @@ impl Default for ResourceLimits {
             max_cpu_time: u64::MAX,
             max_open_files: 1024,
             max_children: 32,
+            max_threads: 16,
         }
     }
 }
@@ -219,6 +256,10 @@ pub struct Process {
     pub pid: u64,
     /// Parent process ID
     pub parent: Option<u64>,
+    /// Process group ID (signals can target a whole group)
+    pub pgid: u64,
+    /// Session ID
+    pub sid: u64,
     /// Current state
     pub state: ProcessState,
     /// Priority level
@@ -239,6 +280,12 @@ pub struct Process {
     pub children: Vec<u64>,
     /// Waiting for PID (for waitpid)
     pub waiting_for: Option<u64>,
+    /// Scheduler tick at which the process last ran or became ready
+    pub ready_since: u64,
+    /// Queued above its own priority by aging until it next runs
+    pub boosted: bool,
+    /// Woken from sleep by the timer but not yet back on a ready queue
+    pub woken: bool,
 }
 
 impl Process {
@@ -246,6 +293,8 @@ impl Process {
         Process {
             pid,
             parent,
+            pgid: pid,
+            sid: pid,
             state: ProcessState::Ready,
             priority,
             capabilities: Capabilities::new(),
@@ -256,6 +305,9 @@ impl Process {
             sleep_until: None,
             children: Vec::new(),
             waiting_for: None,
+            ready_since: 0,
+            boosted: false,
+            woken: false,
         }
     }
 
@@ -280,18 +332,51 @@ impl Process {
     }
 }
 
+/// Snapshot of scheduler activity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerStats {
+    /// Context switches since the table was created
+    pub context_switches: u64,
+    /// Ready processes and threads, indexed by their own priority
+    pub ready_counts: [usize; NUM_PRIORITIES],
+    /// Process currently on the CPU
+    pub running: Option<u64>,
+    /// Exited processes not yet reaped
+    pub zombies: usize,
+}
+
+/// Scheduling state of a run-queue entry, borrowed from its process or thread
+struct SchedFields<'a> {
+    state: &'a mut ProcessState,
+    priority: Priority,
+    ready_since: &'a mut u64,
+    boosted: &'a mut bool,
+}
+
 /// Process table
 pub struct ProcessTable {
     /// All processes indexed by PID
     processes: UnsafeCell<BTreeMap<u64, Process>>,
     /// Next available PID
     next_pid: AtomicU64,
-    /// Ready queues (one per priority)
-    ready_queues: UnsafeCell<[Vec<u64>; NUM_PRIORITIES]>,
+    /// Threads spawned by processes, beyond each one's main thread
+    threads: UnsafeCell<ThreadTable>,
+    /// Ready queues of `(pid, tid)` entries (one per priority)
+    ready_queues: UnsafeCell<[Vec<(u64, u64)>; NUM_PRIORITIES]>,
     /// Currently running process
     current_pid: UnsafeCell<Option<u64>>,
+    /// Thread of `current_pid` that is running
+    current_tid: UnsafeCell<u64>,
     /// Zombie processes waiting to be reaped
     zombies: UnsafeCell<Vec<u64>>,
+    /// Milliseconds since boot, used for sleep deadlines
+    clock: UnsafeCell<fn() -> u64>,
+    /// Number of scheduling decisions made
+    ticks: AtomicU64,
+    /// Ticks a ready process may wait before being boosted (0 disables aging)
+    aging_threshold: AtomicU64,
+    /// Context switches performed
+    context_switches: AtomicU64,
 }
 
 unsafe impl Sync for ProcessTable {}
@@ -301,15 +386,45 @@ impl ProcessTable {
         ProcessTable {
             processes: UnsafeCell::new(BTreeMap::new()),
             next_pid: AtomicU64::new(1),
+            threads: UnsafeCell::new(ThreadTable::new()),
             ready_queues: UnsafeCell::new([
                 Vec::new(), Vec::new(), Vec::new(), Vec::new(),
                 Vec::new(), Vec::new(), Vec::new(), Vec::new(),
             ]),
             current_pid: UnsafeCell::new(None),
+            current_tid: UnsafeCell::new(MAIN_TID),
             zombies: UnsafeCell::new(Vec::new()),
+            clock: UnsafeCell::new(crate::time::now_ms),
+            ticks: AtomicU64::new(0),
+            aging_threshold: AtomicU64::new(DEFAULT_AGING_THRESHOLD),
+            context_switches: AtomicU64::new(0),
         }
     }
 
+    /// Replace the clock (defaults to the boot timer)
+    pub fn set_clock(&self, clock: fn() -> u64) {
+        unsafe {
+            *self.clock.get() = clock;
+        }
+    }
+
+    /// Current time in milliseconds
+    pub fn now(&self) -> u64 {
+        unsafe { (*self.clock.get())() }
+    }
+
+    /// Set how many scheduler ticks a ready process may wait before it is
+    /// boosted; 0 turns aging off
+    pub fn set_aging_threshold(&self, ticks: u64) {
+        self.aging_threshold.store(ticks, Ordering::Relaxed);
+    }
+
+    /// Start a process's wait for the CPU as it joins a ready queue
+    fn mark_ready(&self, process: &mut Process) {
+        process.ready_since = self.ticks.load(Ordering::Relaxed);
+        process.boosted = false;
+    }
+
     /// Initialize with kernel process
     pub fn init(&self) {
         let mut kernel = Process::new(KERNEL_PID, None, Priority::Kernel);
@@ -319,6 +434,7 @@ impl ProcessTable {
         unsafe {
             (*self.processes.get()).insert(KERNEL_PID, kernel);
             *self.current_pid.get() = Some(KERNEL_PID);
+            *self.current_tid.get() = MAIN_TID;
         }
     }
 
@@ -346,6 +462,9 @@ impl ProcessTable {
             
             // Create new process with inherited capabilities (attenuated)
             let mut child = Process::new(pid, Some(parent_pid), priority);
+            child.pgid = parent.pgid;
+            child.sid = parent.sid;
+            self.mark_ready(&mut child);
             child.capabilities = parent.capabilities.derive(&[
                 Capability::FileRead,
                 Capability::FileWrite,
@@ -366,12 +485,78 @@ impl ProcessTable {
             
             // Add to ready queue
             let ready_queues = &mut *self.ready_queues.get();
-            ready_queues[priority as usize].push(pid);
+            ready_queues[priority as usize].push((pid, MAIN_TID));
             
             Ok(pid)
         }
     }
 
+    /// Spawn a thread inside a process
+    ///
+    /// The thread shares the process's capabilities and counts against its
+    /// `max_threads` and `max_memory` limits (one `THREAD_STACK_SIZE` stack).
+    pub fn spawn_thread(&self, pid: u64, entry: ThreadEntry, priority: Priority) -> Result<u64, ProcessError> {
+        unsafe {
+            let process = (*self.processes.get()).get_mut(&pid)
+                .ok_or(ProcessError::ProcessNotFound)?;
+            let threads = &mut *self.threads.get();
+            
+            if matches!(process.state, ProcessState::Zombie | ProcessState::Terminated) {
+                return Err(ProcessError::InvalidState);
+            }
+            
+            // Check thread and memory limits
+            if threads.count(pid) >= process.limits.max_threads as usize {
+                return Err(ProcessError::ResourceLimit);
+            }
+            let memory_used = process.stats.memory_used + THREAD_STACK_SIZE;
+            if memory_used > process.limits.max_memory {
+                return Err(ProcessError::ResourceLimit);
+            }
+            process.stats.memory_used = memory_used;
+            process.stats.peak_memory = process.stats.peak_memory.max(memory_used);
+            
+            let tid = threads.next_tid();
+            let mut thread = Thread::new(tid, pid, entry, priority);
+            thread.capabilities = process.capabilities;
+            thread.ready_since = self.ticks.load(Ordering::Relaxed);
+            threads.insert(thread);
+            
+            let ready_queues = &mut *self.ready_queues.get();
+            ready_queues[priority as usize].push((pid, tid));
+            
+            Ok(tid)
+        }
+    }
+
+    /// End a spawned thread, releasing its stack
+    pub fn exit_thread(&self, pid: u64, tid: u64) -> Result<(), ProcessError> {
+        unsafe {
+            (*self.threads.get()).remove(pid, tid)
+                .ok_or(ProcessError::ProcessNotFound)?;
+            
+            let ready_queues = &mut *self.ready_queues.get();
+            for queue in ready_queues.iter_mut() {
+                queue.retain(|&entry| entry != (pid, tid));
+            }
+            
+            if let Some(process) = (*self.processes.get()).get_mut(&pid) {
+                process.stats.memory_used = process.stats.memory_used.saturating_sub(THREAD_STACK_SIZE);
+            }
+            Ok(())
+        }
+    }
+
+    /// Get a spawned thread
+    pub fn get_thread(&self, pid: u64, tid: u64) -> Option<&Thread> {
+        unsafe { (*self.threads.get()).get(pid, tid) }
+    }
+
+    /// IDs of the threads a process has spawned
+    pub fn threads_of(&self, pid: u64) -> Vec<u64> {
+        unsafe { (*self.threads.get()).threads_of(pid) }
+    }
+
     /// Terminate a process
     pub fn terminate(&self, pid: u64, exit_code: i32) -> Result<(), ProcessError> {
         unsafe {
@@ -383,22 +568,40 @@ impl ProcessTable {
             process.state = ProcessState::Zombie;
             process.exit_code = Some(exit_code);
             
-            // Remove from ready queues
+            // Remove from ready queues, along with all of its threads
             let ready_queues = &mut *self.ready_queues.get();
             for queue in ready_queues.iter_mut() {
-                queue.retain(|&p| p != pid);
+                queue.retain(|&(p, _)| p != pid);
             }
+            (*self.threads.get()).remove_process(pid);
             
             // Add to zombies list
             (*self.zombies.get()).push(pid);
             
+            // Orphaned children, live or zombie, are adopted by the kernel,
+            // which takes over any wait this process had pending on them
+            let orphans = core::mem::take(&mut process.children);
+            process.waiting_for = None;
+            let parent = process.parent;
+            if pid != KERNEL_PID {
+                for &child in &orphans {
+                    if let Some(orphan) = processes.get_mut(&child) {
+                        orphan.parent = Some(KERNEL_PID);
+                    }
+                }
+                if let Some(kernel) = processes.get_mut(&KERNEL_PID) {
+                    kernel.children.extend(orphans);
+                }
+            }
+            
             // If this process has a parent waiting, wake it up
-            if let Some(parent_pid) = process.parent {
+            if let Some(parent_pid) = parent {
                 if let Some(parent) = processes.get_mut(&parent_pid) {
                     if parent.waiting_for == Some(pid) {
                         parent.state = ProcessState::Ready;
                         parent.waiting_for = None;
-                        ready_queues[parent.priority as usize].push(parent_pid);
+                        self.mark_ready(parent);
+                        ready_queues[parent.priority as usize].push((parent_pid, MAIN_TID));
                     }
                 }
             }
@@ -407,18 +610,105 @@ impl ProcessTable {
         }
     }
 
+    /// Move a process into a process group
+    ///
+    /// A `pgid` of 0 or equal to `pid` makes the process the leader of a
+    /// new group. Otherwise the group must already exist in the process's
+    /// session. Session leaders cannot change group.
+    pub fn set_pgid(&self, pid: u64, pgid: u64) -> Result<(), ProcessError> {
+        unsafe {
+            let processes = &mut *self.processes.get();
+            
+            let process = processes.get(&pid)
+                .ok_or(ProcessError::ProcessNotFound)?;
+            let sid = process.sid;
+            if sid == pid && pid != KERNEL_PID {
+                return Err(ProcessError::PermissionDenied);
+            }
+            
+            let pgid = if pgid == 0 { pid } else { pgid };
+            if pgid != pid {
+                let group_exists = processes.values()
+                    .any(|p| p.pgid == pgid && p.sid == sid);
+                if !group_exists {
+                    return Err(ProcessError::PermissionDenied);
+                }
+            }
+            
+            if let Some(process) = processes.get_mut(&pid) {
+                process.pgid = pgid;
+            }
+            Ok(())
+        }
+    }
+
+    /// Start a new session led by `pid`, returning the session ID
+    ///
+    /// The process also becomes leader of a new group. Group leaders cannot
+    /// start a session, as their group would be split across two sessions.
+    pub fn set_sid(&self, pid: u64) -> Result<u64, ProcessError> {
+        unsafe {
+            let process = (*self.processes.get()).get_mut(&pid)
+                .ok_or(ProcessError::ProcessNotFound)?;
+            
+            if process.pgid == pid {
+                return Err(ProcessError::PermissionDenied);
+            }
+            
+            process.sid = pid;
+            process.pgid = pid;
+            Ok(pid)
+        }
+    }
+
+    /// PIDs of every live process in a group
+    pub fn group_members(&self, pgid: u64) -> Vec<u64> {
+        unsafe {
+            (*self.processes.get())
+                .values()
+                .filter(|p| p.pgid == pgid && p.state != ProcessState::Zombie)
+                .map(|p| p.pid)
+                .collect()
+        }
+    }
+
     /// Get next process to run (scheduler)
+    ///
+    /// Ready processes that have waited longer than the aging threshold
+    /// are boosted: they run ahead of the busiest priority level (never
+    /// above `High`), longest-waiting first, and fall back to their own
+    /// priority once picked.
     pub fn schedule(&self) -> Option<u64> {
+        self.schedule_thread().map(|(pid, _)| pid)
+    }
+
+    /// Get the next `(pid, tid)` to run
+    ///
+    /// Threads compete as separate entries; the main thread is `MAIN_TID`.
+    pub fn schedule_thread(&self) -> Option<(u64, u64)> {
+        let tick = self.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+        self.age_ready_queues(tick);
+        
         unsafe {
             let ready_queues = &mut *self.ready_queues.get();
             
             // Find highest priority non-empty queue
             for priority in 0..NUM_PRIORITIES {
                 if !ready_queues[priority].is_empty() {
-                    // Round-robin within priority
-                    let pid = ready_queues[priority].remove(0);
-                    ready_queues[priority].push(pid); // Put at back for next time
-                    return Some(pid);
+                    // Boosted entries from lower queues go first, then
+                    // round-robin within priority
+                    let (queue, index) = if priority >= Priority::High as usize {
+                        self.oldest_boosted(ready_queues, priority).unwrap_or((priority, 0))
+                    } else {
+                        (priority, 0)
+                    };
+                    let entry = ready_queues[queue].remove(index);
+                    if let Some(sched) = self.sched_fields(entry) {
+                        *sched.ready_since = tick;
+                        *sched.boosted = false;
+                    }
+                    ready_queues[queue].push(entry); // Put at back for next time
+                    return Some(entry);
                 }
             }
             
@@ -426,28 +716,148 @@ impl ProcessTable {
         }
     }
 
+    /// Queue and index of the longest-waiting boosted entry below `level`
+    unsafe fn oldest_boosted(
+        &self,
+        ready_queues: &[Vec<(u64, u64)>; NUM_PRIORITIES],
+        level: usize,
+    ) -> Option<(usize, usize)> {
+        let mut oldest: Option<(u64, usize, usize)> = None;
+        for (queue, entries) in ready_queues.iter().enumerate().skip(level + 1) {
+            for (index, &entry) in entries.iter().enumerate() {
+                let since = match self.sched_fields(entry) {
+                    Some(sched) if *sched.boosted => *sched.ready_since,
+                    _ => continue,
+                };
+                match oldest {
+                    Some((oldest_since, _, _)) if oldest_since <= since => {}
+                    _ => oldest = Some((since, queue, index)),
+                }
+            }
+        }
+        oldest.map(|(_, queue, index)| (queue, index))
+    }
+
+    /// Scheduling fields of a run-queue entry, from its process or thread
+    unsafe fn sched_fields(&self, (pid, tid): (u64, u64)) -> Option<SchedFields<'_>> {
+        if tid == MAIN_TID {
+            let process = (*self.processes.get()).get_mut(&pid)?;
+            Some(SchedFields {
+                state: &mut process.state,
+                priority: process.priority,
+                ready_since: &mut process.ready_since,
+                boosted: &mut process.boosted,
+            })
+        } else {
+            let thread = (*self.threads.get()).get_mut(pid, tid)?;
+            Some(SchedFields {
+                state: &mut thread.state,
+                priority: thread.priority,
+                ready_since: &mut thread.ready_since,
+                boosted: &mut thread.boosted,
+            })
+        }
+    }
+
+    /// Boost ready processes that have waited more than the aging threshold
+    ///
+    /// Entries are only flagged, never moved, so this is safe to run from
+    /// the timer interrupt without touching the allocator.
+    fn age_ready_queues(&self, tick: u64) {
+        let threshold = self.aging_threshold.load(Ordering::Relaxed);
+        if threshold == 0 {
+            return;
+        }
+        
+        unsafe {
+            let ready_queues = &*self.ready_queues.get();
+            
+            let busiest = match ready_queues.iter().position(|queue| !queue.is_empty()) {
+                Some(level) => level.max(Priority::High as usize),
+                None => return,
+            };
+            
+            for &entry in ready_queues[busiest + 1..].iter().flatten() {
+                if let Some(sched) = self.sched_fields(entry) {
+                    if *sched.state == ProcessState::Ready && tick - *sched.ready_since > threshold {
+                        *sched.boosted = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Charge a timer tick to the running process
+    ///
+    /// Any of its threads may be the one running; they share the process's
+    /// time slice. Returns `true` once that slice is used up.
+    pub fn charge_tick(&self, elapsed_ms: u64) -> bool {
+        let (current, _) = match self.current_thread() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        
+        unsafe {
+            match (*self.processes.get()).get_mut(&current) {
+                Some(process) => {
+                    process.stats.cpu_time_ms += elapsed_ms;
+                    process.time_slice_remaining = process.time_slice_remaining.saturating_sub(elapsed_ms);
+                    process.time_slice_remaining == 0
+                }
+                None => false,
+            }
+        }
+    }
+
+    /// Handle a timer tick: charge the running thread and preempt it once
+    /// its time slice is spent
+    ///
+    /// Also picks a thread when nothing is running. Returns the `(pid, tid)`
+    /// switched to, if any. Called from the timer interrupt, and directly
+    /// to simulate ticks.
+    pub fn timer_tick(&self, elapsed_ms: u64) -> Option<(u64, u64)> {
+        let running = self.current_thread().is_some();
+        
+        if running && !self.charge_tick(elapsed_ms) {
+            return None;
+        }
+        
+        let next = self.schedule_thread()?;
+        self.context_switch_thread(next);
+        Some(next)
+    }
+
     /// Switch to a new process
     pub fn context_switch(&self, new_pid: u64) {
+        self.context_switch_thread((new_pid, MAIN_TID));
+    }
+
+    /// Switch to a `(pid, tid)` entry, as returned by `schedule_thread`
+    pub fn context_switch_thread(&self, (new_pid, new_tid): (u64, u64)) {
+        self.context_switches.fetch_add(1, Ordering::Relaxed);
+        
         unsafe {
-            let processes = &mut *self.processes.get();
-            
             // Mark current as ready
-            if let Some(current) = *self.current_pid.get() {
-                if let Some(proc) = processes.get_mut(&current) {
-                    if proc.state == ProcessState::Running {
-                        proc.state = ProcessState::Ready;
-                        proc.stats.context_switches += 1;
-                    }
+            if let Some(current) = self.current_thread() {
+                if let Some(sched) = self.sched_fields(current) {
+                    *sched.state = ProcessState::Ready;
+                }
+                if let Some(proc) = (*self.processes.get()).get_mut(&current.0) {
+                    proc.stats.context_switches += 1;
                 }
             }
             
             // Mark new as running
-            if let Some(proc) = processes.get_mut(&new_pid) {
-                proc.state = ProcessState::Running;
-                proc.time_slice_remaining = proc.priority.time_slice_ms();
+            if let Some(sched) = self.sched_fields((new_pid, new_tid)) {
+                *sched.state = ProcessState::Running;
+                let time_slice = sched.priority.time_slice_ms();
+                if let Some(proc) = (*self.processes.get()).get_mut(&new_pid) {
+                    proc.time_slice_remaining = time_slice;
+                }
             }
             
             *self.current_pid.get() = Some(new_pid);
+            *self.current_tid.get() = new_tid;
         }
     }
 
@@ -478,7 +888,8 @@ impl ProcessTable {
             
             if process.state == ProcessState::Blocked {
                 process.state = ProcessState::Ready;
-                ready_queues[process.priority as usize].push(pid);
+                self.mark_ready(process);
+                ready_queues[process.priority as usize].push((pid, MAIN_TID));
             }
             
             Ok(())
@@ -496,23 +907,30 @@ impl ProcessTable {
             process.state = ProcessState::Sleeping;
             process.sleep_until = Some(until);
             
+            // wake_sleepers requeues it
+            let ready_queues = &mut *self.ready_queues.get();
+            for queue in ready_queues.iter_mut() {
+                queue.retain(|&entry| entry != (pid, MAIN_TID));
+            }
+            
             Ok(())
         }
     }
 
     /// Wake up sleeping processes whose time has come
+    ///
+    /// Woken processes are only flagged, never queued, so this is safe to
+    /// run from the timer interrupt without touching the allocator;
+    /// `requeue_woken` puts them back on their ready queues.
     pub fn wake_sleepers(&self, current_time: u64) {
         unsafe {
-            let processes = &mut *self.processes.get();
-            let ready_queues = &mut *self.ready_queues.get();
-            
-            for (pid, process) in processes.iter_mut() {
+            for process in (*self.processes.get()).values_mut() {
                 if process.state == ProcessState::Sleeping {
                     if let Some(until) = process.sleep_until {
                         if current_time >= until {
                             process.state = ProcessState::Ready;
                             process.sleep_until = None;
-                            ready_queues[process.priority as usize].push(*pid);
+                            process.woken = true;
                         }
                     }
                 }
@@ -520,11 +938,43 @@ impl ProcessTable {
         }
     }
 
+    /// Queue processes flagged by `wake_sleepers`
+    ///
+    /// May allocate, so it runs outside interrupt context: before a yield
+    /// and from the kernel's idle loop.
+    pub fn requeue_woken(&self) {
+        unsafe {
+            let processes = &mut *self.processes.get();
+            let ready_queues = &mut *self.ready_queues.get();
+            
+            for (pid, process) in processes.iter_mut() {
+                if !process.woken {
+                    continue;
+                }
+                process.woken = false;
+                if process.state == ProcessState::Ready {
+                    self.mark_ready(process);
+                    ready_queues[process.priority as usize].push((*pid, MAIN_TID));
+                }
+            }
+        }
+    }
+
     /// Get current process ID
     pub fn current_pid(&self) -> Option<u64> {
         unsafe { *self.current_pid.get() }
     }
 
+    /// `(pid, tid)` of the thread in the `Running` state, if any
+    pub fn current_thread(&self) -> Option<(u64, u64)> {
+        unsafe {
+            let current = ((*self.current_pid.get())?, *self.current_tid.get());
+            let running = self.sched_fields(current)
+                .is_some_and(|sched| *sched.state == ProcessState::Running);
+            running.then_some(current)
+        }
+    }
+
     /// Get process by PID
     pub fn get_process(&self, pid: u64) -> Option<&Process> {
         unsafe { (*self.processes.get()).get(&pid) }
@@ -535,6 +985,34 @@ impl ProcessTable {
         unsafe { (*self.processes.get()).get_mut(&pid) }
     }
 
+    /// Scheduler activity: switches, ready work per priority, and zombies
+    pub fn scheduler_stats(&self) -> SchedulerStats {
+        let mut stats = SchedulerStats {
+            context_switches: self.context_switches.load(Ordering::Relaxed),
+            ..SchedulerStats::default()
+        };
+        
+        unsafe {
+            let processes = &*self.processes.get();
+            let threads = &*self.threads.get();
+            
+            let ready = processes.values()
+                .filter(|p| p.state == ProcessState::Ready)
+                .map(|p| p.priority)
+                .chain(threads.iter()
+                    .filter(|t| t.state == ProcessState::Ready)
+                    .map(|t| t.priority));
+            for priority in ready {
+                stats.ready_counts[priority as usize] += 1;
+            }
+            
+            stats.running = self.current_thread().map(|(pid, _)| pid);
+            stats.zombies = (*self.zombies.get()).len();
+        }
+        
+        stats
+    }
+
     /// Get all process IDs
     pub fn all_pids(&self) -> Vec<u64> {
         unsafe {
@@ -543,6 +1021,9 @@ impl ProcessTable {
     }
 
     /// Reap zombie processes
+    ///
+    /// Children of the kernel, including adopted orphans, are reaped
+    /// without an explicit wait.
     pub fn reap_zombies(&self) -> Vec<(u64, i32)> {
         unsafe {
             let processes = &mut *self.processes.get();
@@ -550,17 +1031,14 @@ impl ProcessTable {
             let mut reaped = Vec::new();
             
             zombies.retain(|&pid| {
-                if let Some(process) = processes.get(&pid) {
+                if let Some(&Process { parent: Some(parent_pid), exit_code: Some(exit_code), .. }) = processes.get(&pid) {
                     // Check if parent has reaped
-                    if let Some(parent_pid) = process.parent {
-                        if let Some(parent) = processes.get(&parent_pid) {
-                            if parent.waiting_for == Some(pid) {
-                                if let Some(exit_code) = process.exit_code {
-                                    reaped.push((pid, exit_code));
-                                    processes.remove(&pid);
-                                    return false; // Remove from zombies
-                                }
-                            }
+                    if let Some(parent) = processes.get_mut(&parent_pid) {
+                        if parent_pid == KERNEL_PID || parent.waiting_for == Some(pid) {
+                            parent.children.retain(|&c| c != pid);
+                            reaped.push((pid, exit_code));
+                            processes.remove(&pid);
+                            return false; // Remove from zombies
                         }
                     }
                 }
@@ -607,8 +1085,9 @@ impl ProcessTable {
                 Signal::Continue => {
                     if target.state == ProcessState::Stopped {
                         target.state = ProcessState::Ready;
+                        self.mark_ready(target);
                         let ready_queues = &mut *self.ready_queues.get();
-                        ready_queues[target.priority as usize].push(to);
+                        ready_queues[target.priority as usize].push((to, MAIN_TID));
                     }
                 }
                 _ => {}
@@ -617,6 +1096,32 @@ impl ProcessTable {
             Ok(())
         }
     }
+
+    /// Send a signal to every live member of a process group
+    ///
+    /// Each member is checked as in `send_signal`. Returns how many members
+    /// received the signal; fails only if none did.
+    pub fn send_signal_to_group(&self, from: u64, pgid: u64, signal: Signal) -> Result<usize, ProcessError> {
+        let members = self.group_members(pgid);
+        if members.is_empty() {
+            return Err(ProcessError::ProcessNotFound);
+        }
+        
+        let mut delivered = 0;
+        let mut last_error = ProcessError::ProcessNotFound;
+        for pid in members {
+            match self.send_signal(from, pid, signal) {
+                Ok(()) => delivered += 1,
+                Err(e) => last_error = e,
+            }
+        }
+        
+        if delivered > 0 {
+            Ok(delivered)
+        } else {
+            Err(last_error)
+        }
+    }
 }
 
 /// Process errors
@@ -630,6 +1135,22 @@ pub enum ProcessError {
     TableFull,
 }
 
+impl core::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProcessError::ProcessNotFound => write!(f, "Process not found"),
+            ProcessError::ParentNotFound => write!(f, "Parent process not found"),
+            ProcessError::PermissionDenied => write!(f, "Permission denied"),
+            ProcessError::ResourceLimit => write!(f, "Resource limit exceeded"),
+            ProcessError::InvalidState => write!(f, "Invalid process state"),
+            ProcessError::TableFull => write!(f, "Process table full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProcessError {}
+
 /// Signals
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -668,6 +1189,12 @@ pub fn spawn(parent: u64, priority: Priority) -> Result<u64, ProcessError> {
     PROCESS_TABLE.spawn(parent, priority)
 }
 
+/// Spawn a thread in the current process
+pub fn spawn_thread(entry: ThreadEntry, priority: Priority) -> Result<u64, ProcessError> {
+    let pid = current_pid().ok_or(ProcessError::ProcessNotFound)?;
+    PROCESS_TABLE.spawn_thread(pid, entry, priority)
+}
+
 /// Get current process ID
 pub fn current_pid() -> Option<u64> {
     PROCESS_TABLE.current_pid()
@@ -699,8 +1226,9 @@ pub fn schedule() -> Option<u64> {
 
 /// Yield CPU
 pub fn yield_cpu() {
-    if let Some(next) = schedule() {
-        PROCESS_TABLE.context_switch(next);
+    PROCESS_TABLE.requeue_woken();
+    if let Some(next) = PROCESS_TABLE.schedule_thread() {
+        PROCESS_TABLE.context_switch_thread(next);
     }
 }
 
@@ -714,10 +1242,34 @@ pub fn sleep(duration_ms: u64) -> Result<(), ProcessError> {
     }
 }
 
-/// Get current time in milliseconds (placeholder)
+/// Get current time in milliseconds
 fn get_current_time_ms() -> u64 {
-    // In real implementation, this would use hardware timer
-    0
+    PROCESS_TABLE.now()
+}
+
+/// Charge a timer tick to the running process; `true` once its slice is spent
+pub fn charge_tick(elapsed_ms: u64) -> bool {
+    PROCESS_TABLE.charge_tick(elapsed_ms)
+}
+
+/// Get scheduler statistics
+pub fn scheduler_stats() -> SchedulerStats {
+    PROCESS_TABLE.scheduler_stats()
+}
+
+/// Timer interrupt hook; preempts the running thread when its slice is spent
+pub fn timer_tick(elapsed_ms: u64) -> Option<(u64, u64)> {
+    PROCESS_TABLE.timer_tick(elapsed_ms)
+}
+
+/// Wake sleeping processes whose deadline has passed (interrupt-safe)
+pub fn wake_sleepers() {
+    PROCESS_TABLE.wake_sleepers(PROCESS_TABLE.now());
+}
+
+/// Queue processes woken since the last call; not for interrupt context
+pub fn requeue_woken() {
+    PROCESS_TABLE.requeue_woken();
 }
 
 /// Wait for a child process
@@ -811,4 +1363,317 @@ mod tests {
         let child_pid = child.unwrap();
         assert!(child_pid > KERNEL_PID);
     }
+
+    static TEST_NOW: AtomicU64 = AtomicU64::new(0);
+
+    fn test_clock() -> u64 {
+        TEST_NOW.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_sleep_wakes_on_clock() {
+        let table = ProcessTable::new();
+        table.init();
+        table.set_clock(test_clock);
+        TEST_NOW.store(1_000, Ordering::SeqCst);
+        
+        let pid = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        table.sleep(pid, table.now() + 50).unwrap();
+        assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Sleeping);
+        assert_eq!(table.schedule(), None);
+        
+        TEST_NOW.store(1_049, Ordering::SeqCst);
+        table.wake_sleepers(table.now());
+        assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Sleeping);
+        
+        TEST_NOW.store(1_050, Ordering::SeqCst);
+        table.wake_sleepers(table.now());
+        let process = table.get_process(pid).unwrap();
+        assert_eq!(process.state, ProcessState::Ready);
+        assert_eq!(process.sleep_until, None);
+        table.requeue_woken();
+        assert_eq!(table.schedule(), Some(pid));
+    }
+
+    #[test]
+    fn test_wake_sleepers_leaves_queues_alone() {
+        let table = ProcessTable::new();
+        table.init();
+        table.set_clock(test_clock);
+        TEST_NOW.store(2_000, Ordering::SeqCst);
+        
+        let sleepers: Vec<u64> = (0..4)
+            .map(|_| table.spawn(KERNEL_PID, Priority::Normal).unwrap())
+            .collect();
+        for &pid in &sleepers {
+            table.sleep(pid, table.now() + 10).unwrap();
+        }
+        
+        // The timer-side wake flags them without growing any queue
+        let queues = |table: &ProcessTable| unsafe {
+            (*table.ready_queues.get()).iter().map(|queue| (queue.len(), queue.capacity())).collect::<Vec<_>>()
+        };
+        let before = queues(&table);
+        TEST_NOW.store(2_010, Ordering::SeqCst);
+        table.wake_sleepers(table.now());
+        assert_eq!(queues(&table), before);
+        assert!(sleepers.iter().all(|&pid| table.get_process(pid).unwrap().woken));
+        assert_eq!(table.schedule(), None);
+        
+        // Requeued outside the interrupt, each runs once per round
+        table.requeue_woken();
+        assert!(sleepers.iter().all(|&pid| !table.get_process(pid).unwrap().woken));
+        let mut picks: Vec<u64> = (0..4).map(|_| table.schedule().unwrap()).collect();
+        picks.sort_unstable();
+        assert_eq!(picks, sleepers);
+        
+        // A second requeue adds nothing
+        table.requeue_woken();
+        assert_eq!(unsafe { (*table.ready_queues.get())[Priority::Normal as usize].len() }, 4);
+    }
+
+    #[test]
+    fn test_group_signal() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let leader = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let member = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let outsider = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let other_leader = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        
+        // Children start in their parent's group
+        assert_eq!(table.get_process(leader).unwrap().pgid, KERNEL_PID);
+        
+        table.set_pgid(leader, 0).unwrap();
+        table.set_pgid(member, leader).unwrap();
+        table.set_pgid(other_leader, other_leader).unwrap();
+        assert_eq!(table.set_pgid(outsider, 999), Err(ProcessError::PermissionDenied));
+        
+        // A child spawned after joining inherits the group
+        table.get_process_mut(member).unwrap().grant_capability(Capability::ProcessSpawn);
+        let grandchild = table.spawn(member, Priority::Normal).unwrap();
+        assert_eq!(table.get_process(grandchild).unwrap().pgid, leader);
+        
+        let mut members = table.group_members(leader);
+        members.sort_unstable();
+        assert_eq!(members, vec![leader, member, grandchild]);
+        
+        assert_eq!(table.send_signal_to_group(KERNEL_PID, leader, Signal::Terminate), Ok(3));
+        for pid in [leader, member, grandchild] {
+            assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Terminated);
+        }
+        for pid in [outsider, other_leader] {
+            assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Ready);
+        }
+        
+        // A session leader stays in its own group
+        let session = table.set_sid(outsider).unwrap();
+        assert_eq!(table.get_process(outsider).unwrap().pgid, session);
+        assert_eq!(table.set_pgid(outsider, other_leader), Err(ProcessError::PermissionDenied));
+    }
+
+    #[test]
+    fn test_aging_prevents_starvation() {
+        let table = ProcessTable::new();
+        table.init();
+        table.set_aging_threshold(10);
+        
+        let busy: Vec<u64> = (0..3)
+            .map(|_| table.spawn(KERNEL_PID, Priority::Normal).unwrap())
+            .collect();
+        let low = table.spawn(KERNEL_PID, Priority::Low).unwrap();
+        
+        // The Low process gets a turn within the threshold despite Normal work
+        let capacities = |table: &ProcessTable| unsafe {
+            (*table.ready_queues.get()).iter().map(|queue| queue.capacity()).collect::<Vec<_>>()
+        };
+        let before = capacities(&table);
+        let picks: Vec<u64> = (0..11).map(|_| table.schedule().unwrap()).collect();
+        assert_eq!(picks.iter().filter(|&&pid| pid == low).count(), 1);
+        assert_eq!(*picks.last().unwrap(), low);
+        // Boosting works in place; the queues never grow
+        assert_eq!(capacities(&table), before);
+        
+        // After running it is back at its own priority
+        let process = table.get_process(low).unwrap();
+        assert!(!process.boosted);
+        for _ in 0..10 {
+            assert!(busy.contains(&table.schedule().unwrap()));
+        }
+        assert_eq!(table.schedule(), Some(low));
+        
+        // Without aging it never runs
+        table.set_aging_threshold(0);
+        for _ in 0..100 {
+            assert!(busy.contains(&table.schedule().unwrap()));
+        }
+    }
+
+    fn worker() {}
+
+    #[test]
+    fn test_threads_scheduled_independently() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let pid = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let first = table.spawn_thread(pid, worker, Priority::Normal).unwrap();
+        let second = table.spawn_thread(pid, worker, Priority::Normal).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(table.threads_of(pid), vec![first, second]);
+        
+        // Threads carry the process's capabilities and stacks
+        let process = table.get_process(pid).unwrap();
+        assert_eq!(table.get_thread(pid, first).unwrap().capabilities, process.capabilities);
+        assert_eq!(process.stats.memory_used, 2 * thread::THREAD_STACK_SIZE);
+        
+        let picks: Vec<(u64, u64)> = (0..6).map(|_| table.schedule_thread().unwrap()).collect();
+        for tid in [MAIN_TID, first, second] {
+            assert_eq!(picks.iter().filter(|&&entry| entry == (pid, tid)).count(), 2);
+        }
+        
+        table.exit_thread(pid, first).unwrap();
+        assert!((0..4).all(|_| table.schedule_thread() != Some((pid, first))));
+        assert_eq!(table.get_process(pid).unwrap().stats.memory_used, thread::THREAD_STACK_SIZE);
+        
+        // Terminating the process takes its threads with it
+        table.terminate(pid, 0).unwrap();
+        assert!(table.threads_of(pid).is_empty());
+        assert_eq!(table.schedule_thread(), None);
+    }
+
+    #[test]
+    fn test_thread_limit() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let pid = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        table.get_process_mut(pid).unwrap().limits.max_threads = 2;
+        table.spawn_thread(pid, worker, Priority::Low).unwrap();
+        table.spawn_thread(pid, worker, Priority::Low).unwrap();
+        assert_eq!(table.spawn_thread(pid, worker, Priority::Low), Err(ProcessError::ResourceLimit));
+        
+        table.get_process_mut(pid).unwrap().limits.max_threads = 8;
+        table.get_process_mut(pid).unwrap().limits.max_memory = 2 * thread::THREAD_STACK_SIZE;
+        assert_eq!(table.spawn_thread(pid, worker, Priority::Low), Err(ProcessError::ResourceLimit));
+    }
+
+    #[test]
+    fn test_timer_preempts_round_robin() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let tasks: Vec<u64> = (0..3)
+            .map(|_| table.spawn(KERNEL_PID, Priority::Normal).unwrap())
+            .collect();
+        
+        // The kernel's 1ms slice runs out on the first tick
+        assert_eq!(table.timer_tick(1), Some((tasks[0], MAIN_TID)));
+        assert_eq!(table.get_process(KERNEL_PID).unwrap().state, ProcessState::Ready);
+        
+        // 1ms ticks: each task keeps the CPU for its whole 10ms slice
+        let slice = Priority::Normal.time_slice_ms();
+        let mut switches = Vec::new();
+        for _ in 0..3 * slice {
+            if let Some((pid, _)) = table.timer_tick(1) {
+                switches.push(pid);
+            }
+        }
+        assert_eq!(switches, vec![tasks[1], tasks[2], tasks[0]]);
+        
+        let running: Vec<u64> = tasks
+            .iter()
+            .copied()
+            .filter(|&pid| table.get_process(pid).unwrap().state == ProcessState::Running)
+            .collect();
+        assert_eq!(running, vec![tasks[0]]);
+        assert_eq!(table.get_process(tasks[1]).unwrap().stats.cpu_time_ms, slice);
+    }
+
+    #[test]
+    fn test_timer_runs_threads() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let pid = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        let tid = table.spawn_thread(pid, worker, Priority::Normal).unwrap();
+        
+        assert_eq!(table.timer_tick(1), Some((pid, MAIN_TID)));
+        
+        // The thread gets the CPU once the main thread's slice is spent
+        let slice = Priority::Normal.time_slice_ms();
+        let switches: Vec<(u64, u64)> = (0..slice).filter_map(|_| table.timer_tick(1)).collect();
+        assert_eq!(switches, vec![(pid, tid)]);
+        assert_eq!(table.current_thread(), Some((pid, tid)));
+        assert_eq!(table.get_thread(pid, tid).unwrap().state, ProcessState::Running);
+        assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Ready);
+        
+        // Its ticks are charged to the process
+        assert_eq!(table.timer_tick(1), None);
+        assert_eq!(table.get_process(pid).unwrap().stats.cpu_time_ms, slice + 1);
+        assert_eq!(table.scheduler_stats().running, Some(pid));
+    }
+
+    #[test]
+    fn test_scheduler_stats() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let priorities = [Priority::High, Priority::Normal, Priority::Normal, Priority::Low, Priority::Normal];
+        let pids: Vec<u64> = priorities
+            .iter()
+            .map(|&priority| table.spawn(KERNEL_PID, priority).unwrap())
+            .collect();
+        table.spawn_thread(pids[3], worker, Priority::Low).unwrap();
+        
+        let stats = table.scheduler_stats();
+        assert_eq!(stats.ready_counts[Priority::High as usize], 1);
+        assert_eq!(stats.ready_counts[Priority::Normal as usize], 3);
+        assert_eq!(stats.ready_counts[Priority::Low as usize], 2);
+        assert_eq!(stats.ready_counts.iter().sum::<usize>(), 6);
+        assert_eq!(stats.running, Some(KERNEL_PID));
+        assert_eq!(stats.context_switches, 0);
+        
+        // The High process takes the CPU and one Normal process exits
+        table.context_switch(table.schedule().unwrap());
+        table.terminate(pids[1], 0).unwrap();
+        
+        let stats = table.scheduler_stats();
+        assert_eq!(stats.running, Some(pids[0]));
+        assert_eq!(stats.context_switches, 1);
+        assert_eq!(stats.zombies, 1);
+        assert_eq!(stats.ready_counts[Priority::High as usize], 0);
+        assert_eq!(stats.ready_counts[Priority::Normal as usize], 2);
+        assert_eq!(stats.ready_counts[Priority::Kernel as usize], 1);
+    }
+
+    #[test]
+    fn test_orphans_adopted_by_kernel() {
+        let table = ProcessTable::new();
+        table.init();
+        
+        let parent = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        table.get_process_mut(parent).unwrap().grant_capability(Capability::ProcessSpawn);
+        let live = table.spawn(parent, Priority::Normal).unwrap();
+        let exited = table.spawn(parent, Priority::Normal).unwrap();
+        table.terminate(exited, 7).unwrap();
+        
+        table.terminate(parent, 0).unwrap();
+        assert!(table.get_process(parent).unwrap().children.is_empty());
+        for child in [live, exited] {
+            assert_eq!(table.get_process(child).unwrap().parent, Some(KERNEL_PID));
+            assert!(table.get_process(KERNEL_PID).unwrap().children.contains(&child));
+        }
+        
+        // The kernel reaps the adopted zombie and the parent itself
+        let mut reaped = table.reap_zombies();
+        reaped.sort();
+        assert_eq!(reaped, vec![(parent, 0), (exited, 7)]);
+        
+        table.terminate(live, 3).unwrap();
+        assert_eq!(table.reap_zombies(), vec![(live, 3)]);
+        assert!(table.get_process(live).is_none());
+        assert!(table.get_process(KERNEL_PID).unwrap().children.is_empty());
+    }
 }
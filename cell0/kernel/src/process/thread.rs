@@ -0,0 +1,134 @@
+//! Cooperative Threads Module
+//!
+//! Threads share their process's address space and capabilities but are
+//! scheduled on their own. Every process has an implicit main thread,
+//! `MAIN_TID`, whose state lives in the `Process` itself; additional
+//! threads are kept in a `ThreadTable` and enter the ready queues as
+//! `(pid, tid)` pairs next to it.
+
+use super::{Capabilities, Priority, ProcessState};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// Thread ID of a process's implicit main thread
+pub const MAIN_TID: u64 = 0;
+/// Stack reserved for each spawned thread, charged to the process's memory
+pub const THREAD_STACK_SIZE: usize = 64 * 1024;
+
+/// Thread entry point
+pub type ThreadEntry = fn();
+
+/// A thread running inside a process
+#[derive(Debug, Clone)]
+pub struct Thread {
+    /// Thread ID, unique across the system
+    pub tid: u64,
+    /// Owning process
+    pub parent_pid: u64,
+    /// Current state
+    pub state: ProcessState,
+    /// Priority level
+    pub priority: Priority,
+    /// Where the thread starts executing
+    pub entry: ThreadEntry,
+    /// Capabilities, copied from the process at spawn
+    pub capabilities: Capabilities,
+    /// Scheduler tick at which the thread last ran or became ready
+    pub ready_since: u64,
+    /// Queued above its own priority by aging until it next runs
+    pub boosted: bool,
+}
+
+impl Thread {
+    pub fn new(tid: u64, parent_pid: u64, entry: ThreadEntry, priority: Priority) -> Self {
+        Thread {
+            tid,
+            parent_pid,
+            state: ProcessState::Ready,
+            priority,
+            entry,
+            capabilities: Capabilities::new(),
+            ready_since: 0,
+            boosted: false,
+        }
+    }
+}
+
+/// Threads of every process, keyed by `(pid, tid)`
+pub struct ThreadTable {
+    threads: BTreeMap<(u64, u64), Thread>,
+    next_tid: u64,
+}
+
+impl ThreadTable {
+    pub const fn new() -> Self {
+        ThreadTable {
+            threads: BTreeMap::new(),
+            next_tid: MAIN_TID + 1,
+        }
+    }
+
+    /// Allocate a fresh thread ID
+    pub fn next_tid(&mut self) -> u64 {
+        let tid = self.next_tid;
+        self.next_tid += 1;
+        tid
+    }
+
+    /// Add a thread
+    pub fn insert(&mut self, thread: Thread) {
+        self.threads.insert((thread.parent_pid, thread.tid), thread);
+    }
+
+    /// Remove a thread
+    pub fn remove(&mut self, pid: u64, tid: u64) -> Option<Thread> {
+        self.threads.remove(&(pid, tid))
+    }
+
+    /// Remove every thread of a process, returning how many there were
+    pub fn remove_process(&mut self, pid: u64) -> usize {
+        let before = self.threads.len();
+        self.threads.retain(|&(owner, _), _| owner != pid);
+        before - self.threads.len()
+    }
+
+    /// Get a thread
+    pub fn get(&self, pid: u64, tid: u64) -> Option<&Thread> {
+        self.threads.get(&(pid, tid))
+    }
+
+    /// Get a mutable thread
+    pub fn get_mut(&mut self, pid: u64, tid: u64) -> Option<&mut Thread> {
+        self.threads.get_mut(&(pid, tid))
+    }
+
+    /// Thread IDs of a process, excluding the main thread
+    pub fn threads_of(&self, pid: u64) -> Vec<u64> {
+        self.threads
+            .range((pid, 0)..=(pid, u64::MAX))
+            .map(|(&(_, tid), _)| tid)
+            .collect()
+    }
+
+    /// Every spawned thread, ordered by `(pid, tid)`
+    pub fn iter(&self) -> impl Iterator<Item = &Thread> {
+        self.threads.values()
+    }
+
+    /// Number of spawned threads in a process
+    pub fn count(&self, pid: u64) -> usize {
+        self.threads.range((pid, 0)..=(pid, u64::MAX)).count()
+    }
+}
+
+impl Default for ThreadTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
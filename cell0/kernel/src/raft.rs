@@ -1,81 +1,82 @@
 //! Cell0 Raft Consensus Module
-//! 
-//! Implements the Raft consensus algorithm for distributed agreement.
-//! Designed for civilization-grade reliability and multi-node Cell0 clusters.
+//!
+//! Byte-payload front end to the Raft implementation in `crate::consensus`.
+//! `RaftNode` wraps `Raft<Vec<u8>>` and exposes a synchronous API: handlers
+//! return the RPC to send, other core events (persistence, commits,
+//! replication) are collected with `take_events`, and log entries carry raw
+//! bytes. All protocol logic lives in the generic core.
 //!
 //! # Features
-//! - Leader election with randomized timeouts
+//! - Leader election with randomized timeouts and pre-vote
 //! - Log replication with commit safety
-//! - Membership changes (joint consensus)
+//! - Membership changes (single-server)
 //! - Snapshotting for log compaction
-//! - Byzantine fault tolerance integration
+//! - ReadIndex for linearizable reads
 //!
 //! Reference: "In Search of an Understandable Consensus Algorithm" (Ongaro & Ousterhout, 2014)
 
-#![cfg_attr(not(feature = "std"), no_std)]
-
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
-#[cfg(not(feature = "std"))]
-use alloc::collections::BTreeMap;
-#[cfg(not(feature = "std"))]
-use alloc::string::String;
 #[cfg(feature = "std")]
 use std::vec::Vec;
-#[cfg(feature = "std")]
-use std::collections::BTreeMap;
-#[cfg(feature = "std")]
-use std::string::String;
 
 use core::fmt;
 
-/// Node ID type - unique identifier for each node in the cluster
-pub type NodeId = u64;
-
-/// Term number - monotonically increasing counter for leadership epochs
-pub type Term = u64;
+use crate::consensus::{self, AppendEntriesArgs, AppendEntriesReply, Config, Event, Raft, RequestVoteReply};
 
-/// Log index - position in the replicated log
-pub type LogIndex = u64;
+pub use crate::consensus::{LogIndex, MembershipChange, NodeId, Snapshot, Term};
 
 /// Raft server state
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum RaftState {
-    /// Follower - passive, responds to leader
-    Follower,
-    /// Candidate - attempting to become leader
-    Candidate,
-    /// Leader - handles client requests and log replication
-    Leader,
-}
+pub use crate::consensus::NodeState as RaftState;
 
-impl fmt::Display for RaftState {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            RaftState::Follower => write!(f, "Follower"),
-            RaftState::Candidate => write!(f, "Candidate"),
-            RaftState::Leader => write!(f, "Leader"),
-        }
+/// Vote request RPC
+pub use crate::consensus::RequestVoteArgs as RequestVoteRequest;
+
+/// Pre-vote request RPC
+///
+/// Asks whether the candidate could win an election at `term` without
+/// anyone changing state; the sender's own term is not incremented.
+pub use crate::consensus::RequestVoteArgs as PreVoteRequest;
+
+/// Persistent state of the underlying core
+pub type PersistentState = consensus::PersistentState<Vec<u8>>;
+
+impl PersistentState {
+    /// Append entries to log
+    #[deprecated(note = "the log is owned by the core; entries arrive through `RaftNode` handlers")]
+    pub fn append_entries(&mut self, entries: &[LogEntry]) {
+        self.log.extend(entries.iter().cloned().map(Into::into));
     }
 }
 
+/// Volatile state
+#[derive(Debug, Clone, Default)]
+pub struct VolatileState {
+    /// Index of highest log entry known to be committed
+    pub commit_index: LogIndex,
+    /// Index of highest log entry applied to state machine
+    pub last_applied: LogIndex,
+}
+
 /// Log entry - command to be replicated across the cluster
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LogEntry {
     /// Term when entry was received by leader
     pub term: Term,
     /// Position in the log
     pub index: LogIndex,
-    /// Command data (opaque to Raft)
+    /// Command data (opaque to Raft); encoded `MembershipChange` for
+    /// configuration entries
     pub data: Vec<u8>,
     /// Optional entry type for special entries
     pub entry_type: EntryType,
 }
 
 /// Entry type for distinguishing log entries
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum EntryType {
     /// Normal command entry
+    #[default]
     Command,
     /// Configuration change entry
     ConfigurationChange,
@@ -83,9 +84,40 @@ pub enum EntryType {
     NoOp,
 }
 
-impl Default for EntryType {
-    fn default() -> Self {
-        EntryType::Command
+impl From<consensus::LogEntry<Vec<u8>>> for LogEntry {
+    fn from(entry: consensus::LogEntry<Vec<u8>>) -> Self {
+        let (entry_type, data) = match entry.entry_type {
+            consensus::EntryType::Command => (EntryType::Command, entry.command),
+            consensus::EntryType::ConfigChange(change) => (EntryType::ConfigurationChange, change.encode()),
+            consensus::EntryType::NoOp => (EntryType::NoOp, entry.command),
+        };
+        LogEntry {
+            term: entry.term,
+            index: entry.index,
+            data,
+            entry_type,
+        }
+    }
+}
+
+impl From<LogEntry> for consensus::LogEntry<Vec<u8>> {
+    /// A configuration entry whose data does not decode becomes a no-op so
+    /// the log stays aligned with the leader's
+    fn from(entry: LogEntry) -> Self {
+        let (entry_type, command) = match entry.entry_type {
+            EntryType::Command => (consensus::EntryType::Command, entry.data),
+            EntryType::ConfigurationChange => match MembershipChange::decode(&entry.data) {
+                Some(change) => (consensus::EntryType::ConfigChange(change), Vec::new()),
+                None => (consensus::EntryType::NoOp, Vec::new()),
+            },
+            EntryType::NoOp => (consensus::EntryType::NoOp, entry.data),
+        };
+        consensus::LogEntry {
+            term: entry.term,
+            index: entry.index,
+            command,
+            entry_type,
+        }
     }
 }
 
@@ -131,117 +163,70 @@ impl RaftConfig {
             ..Default::default()
         }
     }
-    
+
     /// Get total cluster size
     pub fn cluster_size(&self) -> usize {
         self.peers.len() + 1
     }
-    
+
     /// Get quorum size (majority)
     pub fn quorum(&self) -> usize {
         (self.cluster_size() / 2) + 1
     }
-}
 
-/// Persistent state (must survive crashes)
-#[derive(Debug, Clone)]
-pub struct PersistentState {
-    /// Latest term server has seen
-    pub current_term: Term,
-    /// CandidateId that received vote in current term
-    pub voted_for: Option<NodeId>,
-    /// Log entries
-    pub log: Vec<LogEntry>,
-}
+    /// Core configuration, whose member list includes this node
+    fn to_core(&self) -> Config {
+        let mut members = Vec::with_capacity(self.cluster_size());
+        members.push(self.node_id);
+        members.extend(self.peers.iter().copied().filter(|&p| p != self.node_id));
 
-impl Default for PersistentState {
-    fn default() -> Self {
-        Self {
-            current_term: 0,
-            voted_for: None,
-            log: Vec::new(),
+        Config {
+            election_timeout_min: self.election_timeout_min,
+            election_timeout_max: self.election_timeout_max,
+            heartbeat_interval: self.heartbeat_interval,
+            max_entries_per_rpc: self.max_entries_per_append,
+            enable_pre_vote: self.enable_pre_vote,
+            ..Config::new(self.node_id, members)
         }
     }
 }
 
-impl PersistentState {
-    /// Get last log index
-    pub fn last_index(&self) -> LogIndex {
-        self.log.len() as LogIndex
-    }
-    
-    /// Get last log term
-    pub fn last_term(&self) -> Term {
-        if self.log.is_empty() {
-            0
-        } else {
-            self.log[self.log.len() - 1].term
-        }
-    }
-    
-    /// Get term at specific index
-    pub fn term_at(&self, index: LogIndex) -> Term {
-        if index == 0 || index > self.log.len() as u64 {
-            0
-        } else {
-            self.log[(index - 1) as usize].term
-        }
-    }
-    
-    /// Append entries to log
-    pub fn append_entries(&mut self, entries: &[LogEntry]) {
-        self.log.extend_from_slice(entries);
-    }
-    
-    /// Truncate log from index onwards
-    pub fn truncate_from(&mut self, index: LogIndex) {
-        if index <= self.log.len() as u64 && index > 0 {
-            self.log.truncate((index - 1) as usize);
-        }
-    }
-    
-    /// Get entries from start_index onwards
-    pub fn entries_from(&self, start_index: LogIndex) -> &[LogEntry] {
-        if start_index == 0 || start_index > self.log.len() as u64 {
-            &[]
-        } else {
-            &self.log[(start_index - 1) as usize..]
-        }
-    }
-}
-
-/// Volatile state
+/// Vote response RPC
 #[derive(Debug, Clone)]
-pub struct VolatileState {
-    /// Index of highest log entry known to be committed
-    pub commit_index: LogIndex,
-    /// Index of highest log entry applied to state machine
-    pub last_applied: LogIndex,
+pub struct RequestVoteResponse {
+    pub term: Term,
+    pub vote_granted: bool,
 }
 
-impl Default for VolatileState {
-    fn default() -> Self {
-        Self {
-            commit_index: 0,
-            last_applied: 0,
+/// Pre-vote response RPC
+pub type PreVoteResponse = RequestVoteResponse;
+
+impl From<RequestVoteReply> for RequestVoteResponse {
+    fn from(reply: RequestVoteReply) -> Self {
+        RequestVoteResponse {
+            term: reply.term,
+            vote_granted: reply.vote_granted,
         }
     }
 }
 
-/// Vote request RPC
-#[derive(Debug, Clone)]
-pub struct RequestVoteRequest {
-    pub term: Term,
-    pub candidate_id: NodeId,
-    pub last_log_index: LogIndex,
-    pub last_log_term: Term,
+impl From<RequestVoteResponse> for RequestVoteReply {
+    fn from(response: RequestVoteResponse) -> Self {
+        RequestVoteReply {
+            term: response.term,
+            vote_granted: response.vote_granted,
+            reason: None,
+        }
+    }
 }
 
-/// Vote response RPC
+/// Request produced by an election timeout
 #[derive(Debug, Clone)]
-pub struct RequestVoteResponse {
-    pub term: Term,
-    pub vote_granted: bool,
+pub enum ElectionRequest {
+    /// Pre-vote round (when `enable_pre_vote` is set)
+    PreVote(PreVoteRequest),
+    /// Real election
+    Vote(RequestVoteRequest),
 }
 
 /// AppendEntries request RPC
@@ -253,6 +238,22 @@ pub struct AppendEntriesRequest {
     pub prev_log_term: Term,
     pub entries: Vec<LogEntry>,
     pub leader_commit: LogIndex,
+    /// Leader's heartbeat round, echoed in the response
+    pub round: u64,
+}
+
+impl From<AppendEntriesRequest> for AppendEntriesArgs<Vec<u8>> {
+    fn from(request: AppendEntriesRequest) -> Self {
+        AppendEntriesArgs {
+            term: request.term,
+            leader_id: request.leader_id,
+            prev_log_index: request.prev_log_index,
+            prev_log_term: request.prev_log_term,
+            entries: request.entries.into_iter().map(Into::into).collect(),
+            leader_commit: request.leader_commit,
+            round: request.round,
+        }
+    }
 }
 
 /// AppendEntries response RPC
@@ -260,230 +261,306 @@ pub struct AppendEntriesRequest {
 pub struct AppendEntriesResponse {
     pub term: Term,
     pub success: bool,
+    /// On success, index of the last entry known to match the leader
+    pub match_index: LogIndex,
+    /// Heartbeat round of the request being answered
+    pub round: u64,
+}
+
+impl From<AppendEntriesReply> for AppendEntriesResponse {
+    fn from(reply: AppendEntriesReply) -> Self {
+        AppendEntriesResponse {
+            term: reply.term,
+            success: reply.success,
+            match_index: reply.match_index,
+            round: reply.round,
+        }
+    }
 }
 
 /// The main Raft consensus state machine
 pub struct RaftNode {
-    pub config: RaftConfig,
-    pub persistent: PersistentState,
-    pub volatile: VolatileState,
-    pub state: RaftState,
-    pub votes_received: Vec<NodeId>,
-    pub leader_id: NodeId,
+    core: Raft<Vec<u8>>,
+    config: RaftConfig,
 }
 
 impl RaftNode {
     /// Create a new Raft node
     pub fn new(config: RaftConfig) -> Self {
         Self {
+            core: Raft::new(config.to_core()),
             config,
-            persistent: PersistentState::default(),
-            volatile: VolatileState::default(),
-            state: RaftState::Follower,
-            votes_received: Vec::new(),
-            leader_id: 0,
         }
     }
-    
+
+    /// Generic core driving this node
+    pub fn core(&self) -> &Raft<Vec<u8>> {
+        &self.core
+    }
+
+    /// Mutable access to the core (for hosts that want its event stream)
+    pub fn core_mut(&mut self) -> &mut Raft<Vec<u8>> {
+        &mut self.core
+    }
+
+    /// Current configuration, reflecting committed membership changes
+    pub fn config(&self) -> RaftConfig {
+        RaftConfig {
+            peers: self.core.config.peers.iter()
+                .copied()
+                .filter(|&p| p != self.config.node_id)
+                .collect(),
+            ..self.config.clone()
+        }
+    }
+
+    /// Persistent state (term, vote, log)
+    pub fn persistent(&self) -> &PersistentState {
+        &self.core.persistent
+    }
+
+    /// Current server state
+    pub fn state(&self) -> RaftState {
+        self.core.state
+    }
+
+    /// Current term
+    pub fn term(&self) -> Term {
+        self.core.persistent.current_term
+    }
+
+    /// Candidate voted for in the current term
+    pub fn voted_for(&self) -> Option<NodeId> {
+        self.core.persistent.voted_for
+    }
+
+    /// Leader of the current term, if known
+    pub fn leader_id(&self) -> Option<NodeId> {
+        self.core.leader_id
+    }
+
+    /// Index of highest log entry known to be committed
+    pub fn commit_index(&self) -> LogIndex {
+        self.core.commit_index
+    }
+
+    /// Commit and apply progress
+    #[deprecated(note = "use `commit_index()`")]
+    pub fn volatile(&self) -> VolatileState {
+        VolatileState {
+            commit_index: self.core.commit_index,
+            last_applied: self.core.last_applied,
+        }
+    }
+
+    /// Votes received in the current election
+    pub fn votes_received(&self) -> &[NodeId] {
+        &self.core.votes_received
+    }
+
+    /// Latest snapshot, if any
+    pub fn snapshot(&self) -> Option<&Snapshot> {
+        self.core.snapshot.as_ref()
+    }
+
+    /// Peers that acknowledged the leader's current heartbeat round
+    pub fn heartbeat_acks(&self) -> &[NodeId] {
+        &self.core.heartbeat_acks
+    }
+
+    /// Get last log index
+    pub fn last_index(&self) -> LogIndex {
+        self.core.persistent.last_index()
+    }
+
+    /// Get term at specific index (0 if compacted or past the end)
+    pub fn term_at(&self, index: LogIndex) -> Term {
+        self.core.persistent.term_at(index)
+    }
+
+    /// Get entry at specific index
+    pub fn entry_at(&self, index: LogIndex) -> Option<LogEntry> {
+        self.core.persistent.entry_at(index).cloned().map(Into::into)
+    }
+
     /// Check if this node is the leader
     pub fn is_leader(&self) -> bool {
-        self.state == RaftState::Leader
+        self.core.state == RaftState::Leader
     }
-    
+
     /// Handle RequestVote RPC
     pub fn handle_request_vote(&mut self, request: RequestVoteRequest) -> RequestVoteResponse {
-        let mut response = RequestVoteResponse {
-            term: self.persistent.current_term,
-            vote_granted: false,
-        };
-        
-        if request.term > self.persistent.current_term {
-            self.become_follower(request.term);
-            response.term = self.persistent.current_term;
-        }
-        
-        if request.term < self.persistent.current_term {
-            return response;
-        }
-        
-        // Check if we can grant vote
-        let can_vote = match self.persistent.voted_for {
-            None => true,
-            Some(id) => id == request.candidate_id,
-        };
-        
-        let last_index = self.persistent.last_index();
-        let last_term = self.persistent.last_term();
-        
-        let log_up_to_date = 
-            request.last_log_term > last_term ||
-            (request.last_log_term == last_term && request.last_log_index >= last_index);
-        
-        if can_vote && log_up_to_date {
-            self.persistent.voted_for = Some(request.candidate_id);
-            response.vote_granted = true;
-        }
-        
-        response
+        let reply = self.core.handle_request_vote(request);
+        reply.into()
     }
-    
+
     /// Handle AppendEntries RPC
     pub fn handle_append_entries(&mut self, request: AppendEntriesRequest) -> AppendEntriesResponse {
-        let mut response = AppendEntriesResponse {
-            term: self.persistent.current_term,
-            success: false,
-        };
-        
-        if request.term < self.persistent.current_term {
-            return response;
-        }
-        
-        self.leader_id = request.leader_id;
-        
-        if request.term > self.persistent.current_term {
-            self.become_follower(request.term);
-            response.term = self.persistent.current_term;
-        } else if self.state != RaftState::Follower {
-            self.become_follower(self.persistent.current_term);
-        }
-        
-        // Check prev_log_index match
-        if request.prev_log_index > 0 {
-            if request.prev_log_index > self.persistent.last_index() {
-                return response;
-            }
-            
-            if self.persistent.term_at(request.prev_log_index) != request.prev_log_term {
-                self.persistent.truncate_from(request.prev_log_index);
-                return response;
-            }
-        }
-        
-        // Append new entries
-        if !request.entries.is_empty() {
-            let mut next_index = request.prev_log_index + 1;
-            for entry in &request.entries {
-                if next_index <= self.persistent.last_index() {
-                    if self.persistent.term_at(next_index) != entry.term {
-                        self.persistent.truncate_from(next_index);
-                        self.persistent.append_entries(&[entry.clone()]);
-                    }
-                } else {
-                    self.persistent.append_entries(&[entry.clone()]);
-                }
-                next_index += 1;
-            }
-        }
-        
-        // Update commit index
-        if request.leader_commit > self.volatile.commit_index {
-            let last_new = if request.entries.is_empty() {
-                request.prev_log_index
-            } else {
-                request.entries[request.entries.len() - 1].index
-            };
-            self.volatile.commit_index = core::cmp::min(request.leader_commit, last_new);
-        }
-        
-        response.success = true;
-        response
+        let reply = self.core.handle_append_entries(request.into());
+        reply.into()
     }
-    
+
     /// Handle election timeout
-    pub fn handle_election_timeout(&mut self) -> Option<RequestVoteRequest> {
-        if self.state == RaftState::Leader {
-            return None;
+    ///
+    /// With pre-vote enabled, a multi-node cluster first polls peers and
+    /// only bumps the term once `handle_pre_vote_response` sees a quorum.
+    pub fn handle_election_timeout(&mut self) -> Option<ElectionRequest> {
+        self.core.handle_election_timeout();
+        self.drop_returned(|e| matches!(e, Event::SendPreVote { .. } | Event::SendRequestVote { .. }));
+
+        match self.core.state {
+            RaftState::PreCandidate => {
+                let mut request = self.vote_request();
+                request.term += 1;
+                Some(ElectionRequest::PreVote(request))
+            }
+            RaftState::Candidate => Some(ElectionRequest::Vote(self.vote_request())),
+            _ => None,
         }
-        
-        self.become_candidate();
-        
-        // For single-node clusters, immediately become leader
-        if self.config.cluster_size() == 1 {
-            self.become_leader();
-            return None;
+    }
+
+    /// Handle PreVote RPC
+    ///
+    /// Granted only if the candidate's log is up to date and this node has
+    /// not heard from a live leader. Never changes term or vote.
+    pub fn handle_pre_vote(&self, request: PreVoteRequest) -> PreVoteResponse {
+        self.core.handle_pre_vote(request).into()
+    }
+
+    /// Handle pre-vote response; returns the real vote request once a
+    /// quorum of peers would support the election
+    pub fn handle_pre_vote_response(&mut self, from: NodeId, response: PreVoteResponse) -> Option<RequestVoteRequest> {
+        let was_pre_candidate = self.core.state == RaftState::PreCandidate;
+        self.core.handle_pre_vote_reply(from, response.into());
+        self.drop_returned(|e| matches!(e, Event::SendRequestVote { .. }));
+
+        if was_pre_candidate && self.core.state == RaftState::Candidate {
+            Some(self.vote_request())
+        } else {
+            None
         }
-        
-        Some(RequestVoteRequest {
-            term: self.persistent.current_term,
-            candidate_id: self.config.node_id,
-            last_log_index: self.persistent.last_index(),
-            last_log_term: self.persistent.last_term(),
-        })
     }
-    
+
     /// Handle vote response
     pub fn handle_request_vote_response(&mut self, from: NodeId, response: RequestVoteResponse) {
-        if response.term > self.persistent.current_term {
-            self.become_follower(response.term);
-            return;
-        }
-        
-        if self.state != RaftState::Candidate || response.term < self.persistent.current_term {
-            return;
-        }
-        
-        if response.vote_granted {
-            if !self.votes_received.contains(&from) {
-                self.votes_received.push(from);
-            }
-            
-            if self.votes_received.len() >= self.config.quorum() {
-                self.become_leader();
-            }
-        }
+        self.core.handle_request_vote_reply(from, response.into());
     }
-    
+
     /// Propose a command (must be leader)
     pub fn propose(&mut self, data: Vec<u8>) -> Option<LogEntry> {
-        if self.state != RaftState::Leader {
+        let index = self.core.propose(data).ok()?;
+        self.entry_at(index)
+    }
+
+    /// Start a new heartbeat round (leader only)
+    ///
+    /// Clears the acknowledgements used by `read_index` and returns the
+    /// empty AppendEntries to broadcast to every peer.
+    pub fn broadcast_heartbeat(&mut self) -> Option<AppendEntriesRequest> {
+        if !self.is_leader() {
             return None;
         }
-        
-        let entry = LogEntry {
-            term: self.persistent.current_term,
-            index: self.persistent.last_index() + 1,
-            data,
-            entry_type: EntryType::Command,
-        };
-        
-        self.persistent.append_entries(&[entry.clone()]);
-        Some(entry)
-    }
-    
-    /// Become follower
-    fn become_follower(&mut self, term: Term) {
-        self.state = RaftState::Follower;
-        self.persistent.current_term = term;
-        self.persistent.voted_for = None;
-        self.votes_received.clear();
-    }
-    
-    /// Become candidate
-    fn become_candidate(&mut self) {
-        self.state = RaftState::Candidate;
-        self.persistent.current_term += 1;
-        self.persistent.voted_for = Some(self.config.node_id);
-        self.votes_received.clear();
-        self.votes_received.push(self.config.node_id);
-    }
-    
-    /// Become leader
-    fn become_leader(&mut self) {
-        self.state = RaftState::Leader;
-        self.leader_id = self.config.node_id;
-    }
-    
+
+        self.core.send_heartbeats();
+        self.drop_returned(|e| matches!(e, Event::SendAppendEntries { args, .. } if args.entries.is_empty()));
+
+        let prev_log_index = self.last_index();
+        Some(AppendEntriesRequest {
+            term: self.term(),
+            leader_id: self.config.node_id,
+            prev_log_index,
+            prev_log_term: self.term_at(prev_log_index),
+            entries: Vec::new(),
+            leader_commit: self.commit_index(),
+            round: self.core.heartbeat_round,
+        })
+    }
+
+    /// Handle AppendEntries response (leader side)
+    pub fn handle_append_entries_response(&mut self, from: NodeId, response: AppendEntriesResponse) {
+        self.core.handle_append_entries_reply(from, AppendEntriesReply {
+            term: response.term,
+            success: response.success,
+            conflict_info: None,
+            match_index: response.match_index,
+            round: response.round,
+        });
+    }
+
+    /// Commit index that is safe to serve a linearizable read from
+    ///
+    /// See `Raft::read_index`.
+    pub fn read_index(&self) -> Option<LogIndex> {
+        self.core.read_index()
+    }
+
+    /// Add a voting member (leader only, one change at a time)
+    ///
+    /// The server joins the active configuration once the entry commits.
+    pub fn add_server(&mut self, node_id: NodeId) -> Option<LogEntry> {
+        let index = self.core.add_server(node_id).ok()?;
+        self.entry_at(index)
+    }
+
+    /// Remove a voting member (leader only, one change at a time)
+    ///
+    /// The server leaves the active configuration once the entry commits.
+    pub fn remove_server(&mut self, node_id: NodeId) -> Option<LogEntry> {
+        let index = self.core.remove_server(node_id).ok()?;
+        self.entry_at(index)
+    }
+
+    /// Whether a membership change is logged but not yet committed
+    pub fn config_change_pending(&self) -> bool {
+        self.core.config_change_pending()
+    }
+
+    /// Install a snapshot covering the log up to last_included_index
+    pub fn install_snapshot(&mut self, last_included_index: LogIndex, last_included_term: Term, state_bytes: Vec<u8>) {
+        self.core.install_snapshot(last_included_index, last_included_term, state_bytes);
+    }
+
+    /// Discard entries up to up_to_index that the snapshot covers
+    pub fn compact_log(&mut self, up_to_index: LogIndex) -> usize {
+        self.core.compact_log(up_to_index)
+    }
+
     /// Get node status
     pub fn get_status(&self) -> RaftStatus {
         RaftStatus {
             node_id: self.config.node_id,
-            state: self.state,
-            term: self.persistent.current_term,
-            leader_id: self.leader_id,
-            commit_index: self.volatile.commit_index,
-            log_size: self.persistent.log.len(),
+            state: self.core.state,
+            term: self.term(),
+            leader_id: self.leader_id().unwrap_or(0),
+            commit_index: self.commit_index(),
+            log_size: self.core.persistent.log.len(),
         }
     }
+
+    /// RequestVote for the current term and log
+    fn vote_request(&self) -> RequestVoteRequest {
+        RequestVoteRequest {
+            term: self.term(),
+            candidate_id: self.config.node_id,
+            last_log_index: self.last_index(),
+            last_log_term: self.core.persistent.last_term(),
+        }
+    }
+
+    /// Take everything the core queued that was not handed back directly
+    ///
+    /// This includes `PersistState` (flush before sending what a handler
+    /// returned), `Committed` entries to apply, and AppendEntries or
+    /// InstallSnapshot messages for replication and retries. Hosts should
+    /// drain it after every call.
+    pub fn take_events(&mut self) -> Vec<Event<Vec<u8>>> {
+        self.core.take_events()
+    }
+
+    /// Drop the queued RPCs this call returns to the caller instead
+    fn drop_returned(&mut self, returned: impl Fn(&Event<Vec<u8>>) -> bool) {
+        self.core.pending_events.retain(|e| !returned(e));
+    }
 }
 
 /// Node status for monitoring
@@ -499,7 +576,7 @@ pub struct RaftStatus {
 
 impl fmt::Display for RaftStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, 
+        write!(f,
             "Node {}: {} (Term: {}, Leader: {}, Log: {} entries, Committed: {})",
             self.node_id, self.state, self.term, self.leader_id,
             self.log_size, self.commit_index
@@ -510,42 +587,42 @@ impl fmt::Display for RaftStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     fn test_raft_single_node() {
         let config = RaftConfig::single_node(1);
         let mut node = RaftNode::new(config);
-        
+
         // Single node should become leader immediately on election timeout
         node.handle_election_timeout();
         assert!(node.is_leader());
-        
+
         // Should be able to propose entries
         let entry = node.propose(vec![1, 2, 3]);
         assert!(entry.is_some());
     }
-    
+
     #[test]
     fn test_vote_granting() {
         let config1 = RaftConfig { node_id: 1, peers: vec![2, 3], ..Default::default() };
         let mut node1 = RaftNode::new(config1);
-        
+
         let request = RequestVoteRequest {
             term: 1,
             candidate_id: 2,
             last_log_index: 0,
             last_log_term: 0,
         };
-        
+
         let response = node1.handle_request_vote(request);
         assert!(response.vote_granted);
     }
-    
+
     #[test]
     fn test_append_entries_rejection() {
         let config = RaftConfig { node_id: 1, peers: vec![2], ..Default::default() };
         let mut node = RaftNode::new(config);
-        
+
         let request = AppendEntriesRequest {
             term: 0,
             leader_id: 2,
@@ -553,9 +630,330 @@ mod tests {
             prev_log_term: 1,
             entries: vec![],
             leader_commit: 0,
+            round: 0,
         };
-        
+
         let response = node.handle_append_entries(request);
         assert!(!response.success);
     }
+
+    #[test]
+    fn test_compact_log_to_snapshot() {
+        let mut node = RaftNode::new(RaftConfig::single_node(1));
+        node.handle_election_timeout();
+
+        // Index 1 is the election no-op
+        for i in 2..=100u8 {
+            if i == 51 {
+                node.core_mut().persistent.current_term += 1;
+            }
+            node.propose(vec![i]).unwrap();
+        }
+
+        let term = node.term_at(80);
+        node.install_snapshot(80, term, vec![1, 2, 3]);
+
+        assert_eq!(node.persistent().log.len(), 20);
+        assert_eq!(node.last_index(), 100);
+        assert_eq!(node.term_at(80), 2);
+        assert_eq!(node.term_at(81), 2);
+        assert_eq!(node.entry_at(81).unwrap().data, vec![81]);
+        assert_eq!(node.compact_log(90), 0);
+        assert_eq!(node.propose(vec![101]).map(|e| e.index), Some(101));
+    }
+
+    fn ack(term: Term, match_index: LogIndex) -> AppendEntriesResponse {
+        AppendEntriesResponse { term, success: true, match_index, round: 0 }
+    }
+
+    #[test]
+    fn test_grow_cluster_three_to_five() {
+        let config = RaftConfig { node_id: 1, peers: vec![2, 3], enable_pre_vote: false, ..Default::default() };
+        let mut leader = RaftNode::new(config);
+        leader.handle_election_timeout();
+        leader.handle_request_vote_response(2, RequestVoteResponse { term: 1, vote_granted: true });
+        assert!(leader.is_leader());
+        assert_eq!(leader.config().quorum(), 2);
+
+        let entry = leader.add_server(4).unwrap();
+        assert_eq!(entry.entry_type, EntryType::ConfigurationChange);
+        assert!(leader.add_server(5).is_none());
+        assert_eq!(leader.config().quorum(), 2);
+        leader.handle_append_entries_response(2, ack(1, entry.index));
+        assert_eq!(leader.config().quorum(), 3);
+
+        let entry = leader.add_server(5).unwrap();
+        assert!(leader.add_server(5).is_none());
+        leader.handle_append_entries_response(2, ack(1, entry.index));
+        assert!(leader.config_change_pending());
+        leader.handle_append_entries_response(3, ack(1, entry.index));
+        assert_eq!(leader.config().peers, vec![2, 3, 4, 5]);
+        assert_eq!(leader.config().quorum(), 3);
+
+        // A follower learns the new membership through replication
+        let config = RaftConfig { node_id: 2, peers: vec![1, 3], enable_pre_vote: false, ..Default::default() };
+        let mut follower = RaftNode::new(config);
+        let request = AppendEntriesRequest {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: (1..=3).filter_map(|i| leader.entry_at(i)).collect(),
+            leader_commit: 3,
+            round: 0,
+        };
+        assert!(follower.handle_append_entries(request).success);
+        assert_eq!(follower.config().peers, vec![1, 3, 4, 5]);
+
+        // Two votes are no longer a majority of five
+        follower.handle_election_timeout();
+        let term = follower.term();
+        follower.handle_request_vote_response(3, RequestVoteResponse { term, vote_granted: true });
+        assert!(!follower.is_leader());
+        follower.handle_request_vote_response(4, RequestVoteResponse { term, vote_granted: true });
+        assert!(follower.is_leader());
+    }
+
+    fn heartbeat(term: Term) -> AppendEntriesRequest {
+        AppendEntriesRequest {
+            term,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+            round: 0,
+        }
+    }
+
+    fn three_nodes() -> Vec<RaftNode> {
+        (1..=3)
+            .map(|id| {
+                let peers = (1..=3).filter(|&p| p != id).collect();
+                RaftNode::new(RaftConfig { node_id: id, peers, ..Default::default() })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pre_vote_partitioned_follower_keeps_term() {
+        let mut nodes = three_nodes();
+
+        // Node 1 leads term 1 and nodes 2 and 3 have heard from it
+        nodes[0].core_mut().persistent.current_term = 1;
+        nodes[0].core_mut().become_leader();
+        for node in &mut nodes[1..] {
+            assert!(node.handle_append_entries(heartbeat(1)).success);
+        }
+
+        // Node 3 is partitioned and keeps timing out without replies
+        for _ in 0..5 {
+            match nodes[2].handle_election_timeout() {
+                Some(ElectionRequest::PreVote(request)) => assert_eq!(request.term, 2),
+                other => panic!("expected pre-vote, got {:?}", other),
+            }
+        }
+        assert_eq!(nodes[2].state(), RaftState::PreCandidate);
+        assert_eq!(nodes[2].term(), 1);
+
+        // On rejoin its pre-votes are refused, so nobody's term moves
+        let request = match nodes[2].handle_election_timeout() {
+            Some(ElectionRequest::PreVote(request)) => request,
+            other => panic!("expected pre-vote, got {:?}", other),
+        };
+        for from in 0..2 {
+            let response = nodes[from].handle_pre_vote(request.clone());
+            assert!(!response.vote_granted);
+            assert!(nodes[2].handle_pre_vote_response(from as NodeId + 1, response).is_none());
+        }
+        assert!(nodes.iter().all(|n| n.term() == 1));
+        assert!(nodes[0].is_leader());
+
+        // The next heartbeat brings it back as a follower
+        assert!(nodes[2].handle_append_entries(heartbeat(1)).success);
+        assert_eq!(nodes[2].state(), RaftState::Follower);
+    }
+
+    #[test]
+    fn test_pre_vote_then_election() {
+        let mut nodes = three_nodes();
+
+        // No leader yet: node 1 times out and polls node 2
+        let request = match nodes[0].handle_election_timeout() {
+            Some(ElectionRequest::PreVote(request)) => request,
+            other => panic!("expected pre-vote, got {:?}", other),
+        };
+        assert_eq!(nodes[0].term(), 0);
+
+        let response = nodes[1].handle_pre_vote(request);
+        assert!(response.vote_granted);
+        assert_eq!(nodes[1].voted_for(), None);
+
+        let vote_request = nodes[0].handle_pre_vote_response(2, response).unwrap();
+        assert_eq!(nodes[0].state(), RaftState::Candidate);
+        assert_eq!(vote_request.term, 1);
+
+        let response = nodes[1].handle_request_vote(vote_request);
+        assert!(response.vote_granted);
+        nodes[0].handle_request_vote_response(2, response);
+        assert!(nodes[0].is_leader());
+        assert_eq!(nodes[0].term(), 1);
+    }
+
+    #[test]
+    fn test_read_index() {
+        let mut node = RaftNode::new(RaftConfig::single_node(1));
+        assert_eq!(node.read_index(), None);
+
+        node.handle_election_timeout();
+        // The election no-op commits straight away on a single node
+        assert_eq!(node.read_index(), Some(1));
+        node.propose(vec![1]).unwrap();
+        assert_eq!(node.read_index(), Some(2));
+
+        let config = RaftConfig { node_id: 2, peers: vec![1, 3], ..Default::default() };
+        let mut follower = RaftNode::new(config);
+        assert!(follower.handle_append_entries(heartbeat(1)).success);
+        assert_eq!(follower.read_index(), None);
+    }
+
+    #[test]
+    fn test_read_index_needs_heartbeat_quorum() {
+        let config = RaftConfig { node_id: 1, peers: vec![2, 3, 4, 5], ..Default::default() };
+        let mut leader = RaftNode::new(config);
+        leader.core_mut().persistent.current_term = 1;
+        leader.core_mut().become_leader();
+        let entry = leader.propose(vec![9]).unwrap();
+        leader.handle_append_entries_response(2, ack(1, entry.index));
+        leader.handle_append_entries_response(3, ack(1, entry.index));
+        assert_eq!(leader.commit_index(), 2);
+
+        let round = leader.broadcast_heartbeat().unwrap().round;
+        assert_eq!(leader.read_index(), None);
+
+        let heartbeat_ack = AppendEntriesResponse { round, ..ack(1, 1) };
+        leader.handle_append_entries_response(2, heartbeat_ack.clone());
+        leader.handle_append_entries_response(2, heartbeat_ack.clone());
+        assert_eq!(leader.read_index(), None);
+        leader.handle_append_entries_response(3, heartbeat_ack.clone());
+        assert_eq!(leader.read_index(), Some(2));
+
+        // A new round must be confirmed again, and late acks from the last
+        // one do not count
+        leader.broadcast_heartbeat().unwrap();
+        assert_eq!(leader.read_index(), None);
+        for peer in 2..=4 {
+            leader.handle_append_entries_response(peer, heartbeat_ack.clone());
+        }
+        assert_eq!(leader.read_index(), None);
+
+        // Learning of a newer term ends the leadership
+        leader.handle_append_entries_response(4, AppendEntriesResponse { term: 2, success: false, match_index: 0, round: 0 });
+        assert!(!leader.is_leader());
+        assert_eq!(leader.read_index(), None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_legacy_state_accessors() {
+        let mut node = RaftNode::new(RaftConfig::single_node(1));
+        node.handle_election_timeout();
+        node.propose(vec![5]).unwrap();
+        assert_eq!(node.volatile().commit_index, 2);
+        assert_eq!(node.votes_received(), &[1]);
+        assert!(node.snapshot().is_none());
+
+        let mut persistent = PersistentState::default();
+        persistent.append_entries(&[node.entry_at(1).unwrap(), node.entry_at(2).unwrap()]);
+        assert_eq!(persistent.log, node.persistent().log);
+    }
+
+    #[test]
+    fn test_events_are_forwarded() {
+        let config = RaftConfig { node_id: 1, peers: vec![2, 3], enable_pre_vote: false, ..Default::default() };
+        let mut leader = RaftNode::new(config);
+
+        // Vote requests come back as the return value, not as events too
+        assert!(matches!(leader.handle_election_timeout(), Some(ElectionRequest::Vote(_))));
+        let events = leader.take_events();
+        assert!(events.iter().any(|e| matches!(e, Event::PersistState)));
+        assert!(!events.iter().any(|e| matches!(e, Event::SendRequestVote { .. })));
+
+        leader.handle_request_vote_response(2, RequestVoteResponse { term: 1, vote_granted: true });
+        assert!(leader.is_leader());
+        leader.take_events();
+
+        let entry = leader.propose(vec![7]).unwrap();
+        let events = leader.take_events();
+        assert!(events.iter().any(|e| matches!(e, Event::PersistState)));
+        let replicated_to: Vec<NodeId> = events.iter()
+            .filter_map(|e| match e {
+                Event::SendAppendEntries { peer, args } if args.entries.iter().any(|x| x.index == entry.index) => Some(*peer),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(replicated_to, vec![2, 3]);
+
+        leader.handle_append_entries_response(2, ack(1, entry.index));
+        let committed: Vec<LogIndex> = leader.take_events().into_iter()
+            .filter_map(|e| match e {
+                Event::Committed { entries } => Some(entries),
+                _ => None,
+            })
+            .flatten()
+            .map(|e| e.index)
+            .collect();
+        assert_eq!(committed, vec![1, entry.index]);
+    }
+
+    #[test]
+    fn test_wrapper_matches_core() {
+        // Same election and proposals, once through RaftNode and once
+        // through the generic core directly
+        let mut node = RaftNode::new(RaftConfig { node_id: 1, peers: vec![2, 3], ..Default::default() });
+        let mut core: Raft<Vec<u8>> = Raft::new(Config {
+            enable_pre_vote: true,
+            ..Config::new(1, vec![1, 2, 3])
+        });
+        let mut voter = RaftNode::new(RaftConfig { node_id: 2, peers: vec![1, 3], ..Default::default() });
+
+        let pre_vote = match node.handle_election_timeout() {
+            Some(ElectionRequest::PreVote(request)) => request,
+            other => panic!("expected pre-vote, got {:?}", other),
+        };
+        core.handle_election_timeout();
+        assert_eq!(node.state(), core.state);
+
+        let granted = voter.handle_pre_vote(pre_vote);
+        let vote = node.handle_pre_vote_response(2, granted.clone()).unwrap();
+        core.handle_pre_vote_reply(2, granted.into());
+        assert_eq!(node.state(), core.state);
+        assert_eq!(node.term(), core.persistent.current_term);
+
+        let granted = voter.handle_request_vote(vote);
+        node.handle_request_vote_response(2, granted.clone());
+        core.handle_request_vote_reply(2, granted.into());
+        assert!(node.is_leader());
+        assert_eq!(core.state, RaftState::Leader);
+
+        for data in [vec![1u8], vec![2, 2], vec![3, 3, 3]] {
+            let entry = node.propose(data.clone()).unwrap();
+            assert_eq!(core.propose(data.clone()), Ok(entry.index));
+            assert_eq!(core.persistent.entry_at(entry.index).map(|e| &e.command), Some(&data));
+        }
+        node.handle_append_entries_response(2, ack(1, 4));
+        core.handle_append_entries_reply(2, AppendEntriesReply {
+            term: 1,
+            success: true,
+            conflict_info: None,
+            match_index: 4,
+            round: 0,
+        });
+        assert_eq!(node.commit_index(), 4);
+        assert_eq!(node.commit_index(), core.commit_index);
+        assert_eq!(node.persistent().log, core.persistent.log);
+
+        let status = node.get_status();
+        assert_eq!((status.term, status.leader_id, status.log_size), (1, 1, 4));
+    }
 }
@@ -1,37 +1,154 @@
-//! Serial port output for debugging
-//! 
-//! Uses UART 16550 serial port for early boot output.
-//! The serial port is at COM1 (0x3F8).
-
-#![cfg(all(target_arch = "x86_64", not(feature = "std")))]
+//! Serial port input and output for debugging
+//!
+//! Uses UART 16550 serial port for early boot output and a minimal
+//! console. The serial port is at COM1 (0x3F8). Register access goes
+//! through `UartPort`, so the driver logic can run against a mock.
 
 use core::fmt::{self, Write};
+use spin::Mutex;
 
-/// Serial port writer
-pub struct SerialWriter;
+/// COM1 base I/O port
+pub const COM1_BASE: u16 = 0x3F8;
 
-impl SerialWriter {
-    pub const fn new() -> Self {
-        Self
-    }
-    
-    pub fn init(&mut self) {}
-    
-    pub fn write_byte(&mut self, _byte: u8) {
-        // On x86_64, this writes to port 0x3F8
-        #[cfg(all(target_arch = "x86_64", not(test)))]
+/// Data register (receive buffer / transmit holding)
+const REG_DATA: u16 = 0;
+/// Interrupt enable register (divisor high byte while DLAB is set)
+const REG_INTERRUPT_ENABLE: u16 = 1;
+/// FIFO control register
+const REG_FIFO_CONTROL: u16 = 2;
+/// Line control register
+const REG_LINE_CONTROL: u16 = 3;
+/// Modem control register
+const REG_MODEM_CONTROL: u16 = 4;
+/// Line status register
+const REG_LINE_STATUS: u16 = 5;
+
+/// Line status: a received byte is waiting
+const LSR_DATA_READY: u8 = 0x01;
+/// Line status: the transmit holding register is empty
+const LSR_TRANSMIT_EMPTY: u8 = 0x20;
+
+/// Access to a UART's registers
+pub trait UartPort {
+    /// Read the register at `offset` from the UART base
+    fn read_reg(&mut self, offset: u16) -> u8;
+    /// Write the register at `offset` from the UART base
+    fn write_reg(&mut self, offset: u16, value: u8);
+}
+
+/// COM1 through x86 port I/O
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Com1;
+
+impl UartPort for Com1 {
+    fn read_reg(&mut self, _offset: u16) -> u8 {
+        // On x86_64, this reads from port 0x3F8 + offset
+        #[cfg(all(target_arch = "x86_64", not(feature = "std"), not(test)))]
+        unsafe {
+            let value: u8;
+            core::arch::asm!(
+                "in al, dx",
+                in("dx") COM1_BASE + _offset,
+                out("al") value,
+                options(nomem, nostack)
+            );
+            value
+        }
+
+        // No UART hosted: nothing received, always ready to send
+        #[cfg(not(all(target_arch = "x86_64", not(feature = "std"), not(test))))]
+        match _offset {
+            REG_LINE_STATUS => LSR_TRANSMIT_EMPTY,
+            _ => 0,
+        }
+    }
+
+    fn write_reg(&mut self, _offset: u16, _value: u8) {
+        // On x86_64, this writes to port 0x3F8 + offset
+        #[cfg(all(target_arch = "x86_64", not(feature = "std"), not(test)))]
         unsafe {
             core::arch::asm!(
                 "out dx, al",
-                in("dx") 0x3F8u16,
-                in("al") _byte,
+                in("dx") COM1_BASE + _offset,
+                in("al") _value,
                 options(nomem, nostack)
             );
         }
     }
 }
 
-impl fmt::Write for SerialWriter {
+/// Serial port writer
+pub struct SerialWriter<P: UartPort = Com1> {
+    port: P,
+}
+
+impl SerialWriter<Com1> {
+    pub const fn new() -> Self {
+        Self { port: Com1 }
+    }
+}
+
+impl Default for SerialWriter<Com1> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: UartPort> SerialWriter<P> {
+    /// Driver over any UART register file
+    pub fn with_port(port: P) -> Self {
+        Self { port }
+    }
+
+    /// Program 38400 baud, 8N1, FIFOs enabled, interrupts off
+    pub fn init(&mut self) {
+        self.port.write_reg(REG_INTERRUPT_ENABLE, 0x00);
+        self.port.write_reg(REG_LINE_CONTROL, 0x80); // DLAB on
+        self.port.write_reg(REG_DATA, 0x03); // Divisor 3, low byte
+        self.port.write_reg(REG_INTERRUPT_ENABLE, 0x00); // Divisor high byte
+        self.port.write_reg(REG_LINE_CONTROL, 0x03); // 8 bits, no parity, one stop bit
+        self.port.write_reg(REG_FIFO_CONTROL, 0xC7); // Enable and clear, 14-byte threshold
+        self.port.write_reg(REG_MODEM_CONTROL, 0x0B); // DTR, RTS, OUT2
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        while self.port.read_reg(REG_LINE_STATUS) & LSR_TRANSMIT_EMPTY == 0 {
+            core::hint::spin_loop();
+        }
+        self.port.write_reg(REG_DATA, byte);
+    }
+
+    /// Return a received byte, if one is waiting
+    pub fn read_byte(&mut self) -> Option<u8> {
+        if self.port.read_reg(REG_LINE_STATUS) & LSR_DATA_READY != 0 {
+            Some(self.port.read_reg(REG_DATA))
+        } else {
+            None
+        }
+    }
+
+    /// Block until a full line is read into `buf`, returning its length
+    ///
+    /// The line ends at `\n`, which is not stored; `\r` is dropped so
+    /// terminals sending CR LF work too. Stops early if `buf` fills up.
+    pub fn read_line(&mut self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        while len < buf.len() {
+            match self.read_byte() {
+                Some(b'\n') => break,
+                Some(b'\r') => {}
+                Some(byte) => {
+                    buf[len] = byte;
+                    len += 1;
+                }
+                None => core::hint::spin_loop(),
+            }
+        }
+        len
+    }
+}
+
+impl<P: UartPort> fmt::Write for SerialWriter<P> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         for byte in s.bytes() {
             self.write_byte(byte);
@@ -40,9 +157,24 @@ impl fmt::Write for SerialWriter {
     }
 }
 
-/// Initialize the serial port for output
+/// Global COM1 writer, set up by `init`
+pub static SERIAL_WRITER: Mutex<Option<SerialWriter>> = Mutex::new(None);
+
+/// Initialize the serial port for input and output
 pub fn init() {
-    // UART initialization would go here on x86_64
+    let mut writer = SerialWriter::new();
+    writer.init();
+    *SERIAL_WRITER.lock() = Some(writer);
+}
+
+/// Return a byte received on COM1, if one is waiting
+pub fn serial_read_byte() -> Option<u8> {
+    SerialWriter::new().read_byte()
+}
+
+/// Block until a line arrives on COM1; see [`SerialWriter::read_line`]
+pub fn serial_read_line(buf: &mut [u8]) -> usize {
+    SerialWriter::new().read_line(buf)
 }
 
 /// Internal print function used by macros
@@ -53,6 +185,7 @@ pub fn _print(args: fmt::Arguments) {
 }
 
 /// Print to the serial port
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 #[macro_export]
 macro_rules! serial_print {
     ($($arg:tt)*) => {
@@ -61,6 +194,7 @@ macro_rules! serial_print {
 }
 
 /// Print with newline to the serial port
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 #[macro_export]
 macro_rules! serial_println {
     () => ($crate::serial_print!("\n"));
@@ -68,3 +202,79 @@ macro_rules! serial_println {
     ($fmt:expr, $($arg:tt)*) => ($crate::serial_print!(
         concat!($fmt, "\n"), $($arg)*));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Register file of a UART with a receive queue and a transmit log
+    #[derive(Default)]
+    struct MockUart {
+        rx: VecDeque<u8>,
+        tx: Vec<u8>,
+        regs: [u8; 8],
+    }
+
+    impl UartPort for MockUart {
+        fn read_reg(&mut self, offset: u16) -> u8 {
+            match offset {
+                REG_DATA => self.rx.pop_front().unwrap_or(0),
+                REG_LINE_STATUS => {
+                    let ready = if self.rx.is_empty() { 0 } else { LSR_DATA_READY };
+                    ready | LSR_TRANSMIT_EMPTY
+                }
+                _ => self.regs[offset as usize],
+            }
+        }
+
+        fn write_reg(&mut self, offset: u16, value: u8) {
+            if offset == REG_DATA && self.regs[REG_LINE_CONTROL as usize] & 0x80 == 0 {
+                self.tx.push(value);
+            } else {
+                self.regs[offset as usize] = value;
+            }
+        }
+    }
+
+    fn uart_with_input(input: &[u8]) -> SerialWriter<MockUart> {
+        SerialWriter::with_port(MockUart {
+            rx: input.iter().copied().collect(),
+            ..MockUart::default()
+        })
+    }
+
+    #[test]
+    fn test_read_byte() {
+        let mut serial = uart_with_input(b"ok");
+        assert_eq!(serial.read_byte(), Some(b'o'));
+        assert_eq!(serial.read_byte(), Some(b'k'));
+        assert_eq!(serial.read_byte(), None);
+    }
+
+    #[test]
+    fn test_read_line() {
+        let mut serial = uart_with_input(b"help\r\nls -l\nrest");
+        let mut buf = [0u8; 16];
+
+        let len = serial.read_line(&mut buf);
+        assert_eq!(&buf[..len], b"help");
+        let len = serial.read_line(&mut buf);
+        assert_eq!(&buf[..len], b"ls -l");
+
+        // A full buffer ends the read early
+        let mut small = [0u8; 2];
+        assert_eq!(serial.read_line(&mut small), 2);
+        assert_eq!(&small, b"re");
+        assert_eq!(serial.read_byte(), Some(b's'));
+    }
+
+    #[test]
+    fn test_init_and_write() {
+        let mut serial = uart_with_input(b"");
+        serial.init();
+        write!(serial, "hi {}", 7).unwrap();
+        assert_eq!(serial.port.tx, b"hi 7");
+        assert_eq!(serial.port.regs[REG_LINE_CONTROL as usize], 0x03);
+    }
+}
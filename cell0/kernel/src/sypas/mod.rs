@@ -24,6 +24,7 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use core::ops::Deref;
 use core::sync::atomic::{AtomicU64, Ordering};
 
 #[cfg(not(feature = "std"))]
@@ -31,10 +32,20 @@ use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use alloc::collections::BTreeMap;
 
-use crate::process::{Capabilities, Capability, ProcessError};
+use crate::crypto::hmac::{HmacSha256, HMAC_SHA256_SIZE};
+use crate::process::{Capabilities, Capability, ProcessError, PROCESS_TABLE};
+use spin::{Mutex, MutexGuard, Once};
 
 /// SYPAS version
 pub const SYPAS_VERSION: &str = "1.0.0";
+/// Default limit on the length of a delegation chain
+pub const DEFAULT_MAX_DELEGATION_DEPTH: u32 = 3;
+/// Serialized capability token format version
+pub const TOKEN_VERSION: u8 = 1;
+/// Length of a serialized capability token body (before the MAC)
+const TOKEN_BODY_SIZE: usize = 1 + 8 + 8 + 1 + 1 + 4 + 1 + 8 + 8;
+/// Length of a serialized capability token
+pub const TOKEN_SIZE: usize = TOKEN_BODY_SIZE + HMAC_SHA256_SIZE;
 
 /// Capability handle (unique identifier)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -58,8 +69,94 @@ pub struct CapabilityEntry {
     pub cap: Capability,
     pub delegated_from: Option<CapabilityHandle>,
     pub delegated_to: Vec<CapabilityHandle>,
+    /// Number of delegations between this entry and its original grant
+    pub depth: u32,
+    /// Rights this entry carries
+    pub rights: AccessRights,
     pub revoked: bool,
     pub created_at: u64,
+    /// Time after which the entry is treated as revoked
+    pub expires_at: Option<u64>,
+}
+
+impl CapabilityEntry {
+    /// Check whether the entry has lapsed at `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| now >= t)
+    }
+    
+    /// Check whether the entry currently grants its capability
+    pub fn is_live(&self, now: u64) -> bool {
+        !self.revoked && !self.is_expired(now)
+    }
+    
+    /// Serialize into an authenticated token for another node
+    ///
+    /// Layout (little-endian): version, handle, owner, cap, rights, depth,
+    /// expiry flag, expiry, nonce, then an HMAC-SHA3-256 tag over all of it.
+    /// The nonce lets the receiver reject replays.
+    pub fn to_bytes(&self, key: &[u8], nonce: u64) -> Vec<u8> {
+        let mut token = Vec::with_capacity(TOKEN_SIZE);
+        token.push(TOKEN_VERSION);
+        token.extend_from_slice(&self.handle.as_u64().to_le_bytes());
+        token.extend_from_slice(&self.owner.to_le_bytes());
+        token.push(self.cap as u8);
+        token.push(self.rights.to_bits());
+        token.extend_from_slice(&self.depth.to_le_bytes());
+        token.push(self.expires_at.is_some() as u8);
+        token.extend_from_slice(&self.expires_at.unwrap_or(0).to_le_bytes());
+        token.extend_from_slice(&nonce.to_le_bytes());
+        
+        let tag = HmacSha256::new(key).mac(&token);
+        token.extend_from_slice(&tag);
+        token
+    }
+    
+    /// Verify and decode a token produced by `to_bytes`
+    ///
+    /// Returns the entry and the token's nonce. Fails with
+    /// `InvalidCapability` if the token is malformed or its tag doesn't
+    /// verify under `key`.
+    pub fn from_bytes(bytes: &[u8], key: &[u8]) -> Result<(Self, u64), SypasError> {
+        if bytes.len() != TOKEN_SIZE {
+            return Err(SypasError::InvalidCapability);
+        }
+        
+        let (body, tag) = bytes.split_at(TOKEN_BODY_SIZE);
+        let mut expected = [0u8; HMAC_SHA256_SIZE];
+        expected.copy_from_slice(tag);
+        if !HmacSha256::new(key).verify(body, &expected) {
+            return Err(SypasError::InvalidCapability);
+        }
+        
+        let u64_at = |i: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&body[i..i + 8]);
+            u64::from_le_bytes(buf)
+        };
+        
+        if body[0] != TOKEN_VERSION {
+            return Err(SypasError::InvalidCapability);
+        }
+        let cap = Capability::from_u8(body[17]).ok_or(SypasError::InvalidCapability)?;
+        let mut depth = [0u8; 4];
+        depth.copy_from_slice(&body[19..23]);
+        
+        let entry = CapabilityEntry {
+            handle: CapabilityHandle(u64_at(1)),
+            owner: u64_at(9),
+            cap,
+            delegated_from: None,
+            delegated_to: Vec::new(),
+            depth: u32::from_le_bytes(depth),
+            rights: AccessRights::from_bits(body[18]),
+            revoked: false,
+            created_at: 0,
+            expires_at: if body[23] != 0 { Some(u64_at(24)) } else { None },
+        };
+        
+        Ok((entry, u64_at(32)))
+    }
 }
 
 /// Resource type that can be protected by capabilities
@@ -90,6 +187,25 @@ impl ResourceId {
             id: id.to_vec(),
         }
     }
+    
+    /// Match a concrete resource against this identifier used as a pattern
+    ///
+    /// The pattern is either an exact id or a prefix ending in `*`
+    /// (`/etc/*`, or `*` for everything). Returns a specificity score where
+    /// higher is more specific, or `None` if the resource doesn't match.
+    pub fn match_score(&self, resource: &ResourceId) -> Option<usize> {
+        if self.resource_type != resource.resource_type {
+            return None;
+        }
+        
+        match self.id.split_last() {
+            Some((b'*', prefix)) => {
+                resource.id.starts_with(prefix).then_some(prefix.len())
+            }
+            // An exact match outranks any glob over the same bytes
+            _ => (self.id == resource.id).then_some(usize::MAX),
+        }
+    }
 }
 
 /// Access rights for a resource
@@ -126,6 +242,31 @@ impl AccessRights {
         delete: true,
         delegate: true,
     };
+    
+    /// Pack into a bitmask (read = bit 0 ... delegate = bit 4)
+    pub fn to_bits(&self) -> u8 {
+        (self.read as u8)
+            | (self.write as u8) << 1
+            | (self.execute as u8) << 2
+            | (self.delete as u8) << 3
+            | (self.delegate as u8) << 4
+    }
+    
+    /// Whether every right in `other` is also in `self`
+    pub fn contains(&self, other: AccessRights) -> bool {
+        other.to_bits() & !self.to_bits() == 0
+    }
+    
+    /// Unpack from a bitmask produced by `to_bits`
+    pub fn from_bits(bits: u8) -> Self {
+        AccessRights {
+            read: bits & 1 != 0,
+            write: bits & (1 << 1) != 0,
+            execute: bits & (1 << 2) != 0,
+            delete: bits & (1 << 3) != 0,
+            delegate: bits & (1 << 4) != 0,
+        }
+    }
 }
 
 /// Security policy for a resource
@@ -165,6 +306,48 @@ pub struct SypasManager {
     policies: Vec<SecurityPolicy>,
     audit_log: Vec<AuditEntry>,
     enforcement_mode: EnforcementMode,
+    max_delegation_depth: u32,
+    /// Resolves a PID to its process capabilities
+    capability_lookup: fn(u64) -> Option<Capabilities>,
+}
+
+/// Look up a process's capabilities in the global process table
+fn process_capabilities(pid: u64) -> Option<Capabilities> {
+    PROCESS_TABLE.get_process(pid).map(|p| p.capabilities)
+}
+
+/// Capabilities implied by the rights requested on a resource
+fn rights_capabilities(resource_type: ResourceType, rights: AccessRights) -> Vec<Capability> {
+    let mut caps = Vec::new();
+    
+    match resource_type {
+        ResourceType::File | ResourceType::Directory => {
+            if rights.read {
+                caps.push(Capability::FileRead);
+            }
+            if rights.write {
+                caps.push(Capability::FileWrite);
+            }
+            if rights.delete {
+                caps.push(Capability::FileDelete);
+            }
+        }
+        ResourceType::Process => {
+            if rights.write || rights.delete {
+                caps.push(Capability::ProcessKill);
+            }
+        }
+        ResourceType::Device => caps.push(Capability::HardwareAccess),
+        ResourceType::NetworkEndpoint => caps.push(Capability::Network),
+        ResourceType::IpcChannel => caps.push(Capability::IpcJoin),
+        ResourceType::MemoryRegion | ResourceType::SystemCall => {}
+    }
+    
+    if rights.execute {
+        caps.push(Capability::Execute);
+    }
+    
+    caps
 }
 
 /// Security enforcement mode
@@ -187,9 +370,21 @@ impl SypasManager {
             policies: Vec::new(),
             audit_log: Vec::new(),
             enforcement_mode: EnforcementMode::Enforcing,
+            max_delegation_depth: DEFAULT_MAX_DELEGATION_DEPTH,
+            capability_lookup: process_capabilities,
         }
     }
     
+    /// Set the maximum delegation chain length
+    pub fn set_max_delegation_depth(&mut self, depth: u32) {
+        self.max_delegation_depth = depth;
+    }
+    
+    /// Replace the capability source (defaults to the global process table)
+    pub fn set_capability_lookup(&mut self, lookup: fn(u64) -> Option<Capabilities>) {
+        self.capability_lookup = lookup;
+    }
+    
     /// Initialize SYPAS
     pub fn init(&mut self) {
         // Set up default policies
@@ -202,22 +397,25 @@ impl SypasManager {
     }
     
     /// Check if process has capability for resource
+    ///
+    /// Granted capabilities that have expired by `now` are ignored.
     pub fn check_access(
         &mut self,
         process_id: u64,
         resource: &ResourceId,
         requested_rights: AccessRights,
+        now: u64,
     ) -> Result<(), SypasError> {
         let allowed = match self.enforcement_mode {
             EnforcementMode::Permissive => true,
             EnforcementMode::Auditing | EnforcementMode::Enforcing => {
-                self.verify_access(process_id, resource, requested_rights)
+                self.verify_access(process_id, resource, requested_rights, now)
             }
         };
         
         // Audit the access attempt
         self.audit_log.push(AuditEntry {
-            timestamp: 0, // TODO: Get real time
+            timestamp: now,
             process_id,
             action: AuditAction::ResourceAccess,
             resource: resource.clone(),
@@ -232,26 +430,59 @@ impl SypasManager {
         }
     }
     
-    /// Verify access internally
-    fn verify_access(&self, _process_id: u64, resource: &ResourceId, _rights: AccessRights) -> bool {
-        // Find applicable policy
-        for policy in &self.policies {
-            if policy.resource.resource_type == resource.resource_type {
-                // Check if process has required capabilities
-                // In real implementation, would check process capabilities
-                return true; // Simplified
+    /// Select the most specific policy covering a resource
+    fn find_policy(&self, resource: &ResourceId) -> Option<&SecurityPolicy> {
+        self.policies
+            .iter()
+            .filter_map(|p| p.resource.match_score(resource).map(|score| (score, p)))
+            .max_by_key(|&(score, _)| score)
+            .map(|(_, policy)| policy)
+    }
+    
+    /// Capabilities held by a process for `rights`: its own set plus live
+    /// granted tokens whose rights cover the request
+    fn effective_capabilities(&self, process_id: u64, rights: AccessRights, now: u64) -> Capabilities {
+        let mut caps = (self.capability_lookup)(process_id).unwrap_or_default();
+        
+        for entry in &self.capability_store {
+            if entry.owner == process_id && entry.is_live(now) && entry.rights.contains(rights) {
+                caps.set(entry.cap);
             }
         }
         
-        // No policy found - deny by default
-        false
+        caps
+    }
+    
+    /// Verify access internally
+    fn verify_access(
+        &self,
+        process_id: u64,
+        resource: &ResourceId,
+        rights: AccessRights,
+        now: u64,
+    ) -> bool {
+        // Find the most specific applicable policy - deny by default
+        let policy = match self.find_policy(resource) {
+            Some(policy) => policy,
+            None => return false,
+        };
+        
+        let caps = self.effective_capabilities(process_id, rights, now);
+        
+        // Every capability the policy requires, plus those implied by the rights
+        policy.required_caps
+            .iter()
+            .copied()
+            .chain(rights_capabilities(resource.resource_type, rights))
+            .all(|cap| caps.has(cap))
     }
     
-    /// Grant a capability to a process
+    /// Grant a capability to a process, optionally lapsing at `expires_at`
     pub fn grant_capability(
         &mut self,
         process_id: u64,
         cap: Capability,
+        expires_at: Option<u64>,
     ) -> Result<CapabilityHandle, SypasError> {
         let handle = CapabilityHandle(self.next_handle.fetch_add(1, Ordering::SeqCst));
         
@@ -261,14 +492,17 @@ impl SypasManager {
             cap,
             delegated_from: None,
             delegated_to: Vec::new(),
+            depth: 0,
+            rights: AccessRights::FULL,
             revoked: false,
-            created_at: 0, // TODO: Get real time
+            created_at: crate::time::now_ms(),
+            expires_at,
         };
         
         self.capability_store.push(entry);
         
         self.audit_log.push(AuditEntry {
-            timestamp: 0,
+            timestamp: crate::time::now_ms(),
             process_id,
             action: AuditAction::CapabilityDelegation,
             resource: ResourceId::new(ResourceType::Process, &process_id.to_le_bytes()),
@@ -297,7 +531,7 @@ impl SypasManager {
         }
         
         self.audit_log.push(AuditEntry {
-            timestamp: 0,
+            timestamp: crate::time::now_ms(),
             process_id: owner,
             action: AuditAction::CapabilityRevocation,
             resource: ResourceId::new(ResourceType::Process, &owner.to_le_bytes()),
@@ -308,17 +542,60 @@ impl SypasManager {
         Ok(())
     }
     
+    /// Remove expired capabilities from the store
+    ///
+    /// Each removal is recorded as a revocation in the audit log. Returns
+    /// the number of entries removed.
+    pub fn prune_expired(&mut self, now: u64) -> usize {
+        let before = self.capability_store.len();
+        let audit_log = &mut self.audit_log;
+        
+        self.capability_store.retain(|entry| {
+            if !entry.is_expired(now) {
+                return true;
+            }
+            
+            audit_log.push(AuditEntry {
+                timestamp: now,
+                process_id: entry.owner,
+                action: AuditAction::CapabilityRevocation,
+                resource: ResourceId::new(ResourceType::Process, &entry.owner.to_le_bytes()),
+                allowed: true,
+                reason: Some("Capability expired"),
+            });
+            false
+        });
+        
+        before - self.capability_store.len()
+    }
+    
     /// Delegate a capability to another process
+    ///
+    /// The parent must carry the `delegate` right and the resulting chain
+    /// may not exceed the configured maximum depth. `rights` narrows what
+    /// the delegate receives and must be a subset of the parent's rights;
+    /// `None` passes the parent's rights on unchanged.
     pub fn delegate_capability(
         &mut self,
         from_handle: CapabilityHandle,
         to_process: u64,
+        rights: Option<AccessRights>,
     ) -> Result<CapabilityHandle, SypasError> {
         // Find the original capability
-        let original = self.capability_store
+        let original_idx = self.capability_store
             .iter()
-            .find(|e| e.handle == from_handle && !e.revoked)
+            .position(|e| e.handle == from_handle && !e.revoked)
             .ok_or(SypasError::CapabilityNotFound)?;
+        let original = &self.capability_store[original_idx];
+        
+        let depth = original.depth + 1;
+        if depth > self.max_delegation_depth || !original.rights.delegate {
+            return Err(SypasError::DelegationNotAllowed);
+        }
+        let rights = rights.unwrap_or(original.rights);
+        if !original.rights.contains(rights) {
+            return Err(SypasError::DelegationNotAllowed);
+        }
         
         // Create delegated capability
         let new_handle = CapabilityHandle(self.next_handle.fetch_add(1, Ordering::SeqCst));
@@ -329,15 +606,46 @@ impl SypasManager {
             cap: original.cap,
             delegated_from: Some(from_handle),
             delegated_to: Vec::new(),
+            depth,
+            // Never more than the parent holds
+            rights,
             revoked: false,
-            created_at: 0,
+            created_at: crate::time::now_ms(),
+            // A delegated capability cannot outlive its parent
+            expires_at: original.expires_at,
         };
         
+        self.capability_store[original_idx].delegated_to.push(new_handle);
         self.capability_store.push(delegated);
         
         Ok(new_handle)
     }
     
+    /// Whether `process_id` owns the capability behind `handle`
+    pub fn owns_capability(&self, process_id: u64, handle: CapabilityHandle) -> bool {
+        self.capability_store
+            .iter()
+            .any(|e| e.handle == handle && e.owner == process_id)
+    }
+    
+    /// Delegate a capability on behalf of `from_process`, which must own it
+    pub fn delegate_owned_capability(
+        &mut self,
+        from_process: u64,
+        from_handle: CapabilityHandle,
+        to_process: u64,
+    ) -> Result<CapabilityHandle, SypasError> {
+        if !self.owns_capability(from_process, from_handle) {
+            return Err(SypasError::CapabilityNotFound);
+        }
+        self.delegate_capability(from_handle, to_process, None)
+    }
+    
+    /// Install a security policy
+    pub fn add_policy(&mut self, policy: SecurityPolicy) {
+        self.policies.push(policy);
+    }
+    
     /// Add default security policies
     fn add_default_policies(&mut self) {
         // File system policy
@@ -377,17 +685,39 @@ pub enum SypasError {
     AuditLogFull,
 }
 
-/// Global SYPAS manager
-static mut SYPAS_MANAGER: Option<SypasManager> = None;
+impl core::fmt::Display for SypasError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SypasError::AccessDenied => write!(f, "Access denied"),
+            SypasError::CapabilityNotFound => write!(f, "Capability not found"),
+            SypasError::InvalidCapability => write!(f, "Invalid capability"),
+            SypasError::DelegationNotAllowed => write!(f, "Capability delegation not allowed"),
+            SypasError::PolicyViolation => write!(f, "Security policy violation"),
+            SypasError::AuditLogFull => write!(f, "Audit log full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SypasError {}
+
+/// Global SYPAS manager, created once by `init`
+static SYPAS_MANAGER: Once<Mutex<SypasManager>> = Once::new();
 
 /// Initialize SYPAS
+///
+/// Only the first call creates the manager; later calls leave it intact.
 pub fn init() {
-    unsafe {
-        SYPAS_MANAGER = Some(SypasManager::new());
-        if let Some(ref mut manager) = SYPAS_MANAGER {
-            manager.init();
-        }
-    }
+    SYPAS_MANAGER.call_once(|| {
+        let mut manager = SypasManager::new();
+        manager.init();
+        Mutex::new(manager)
+    });
+}
+
+/// Run `f` with the global manager locked, or `None` before `init`
+fn with_manager<R>(f: impl FnOnce(&mut SypasManager) -> R) -> Option<R> {
+    SYPAS_MANAGER.get().map(|manager| f(&mut manager.lock()))
 }
 
 /// Check access to resource
@@ -395,55 +725,84 @@ pub fn check_access(
     process_id: u64,
     resource: &ResourceId,
     rights: AccessRights,
+    now: u64,
 ) -> Result<(), SypasError> {
-    unsafe {
-        if let Some(ref mut manager) = SYPAS_MANAGER {
-            manager.check_access(process_id, resource, rights)
-        } else {
-            Err(SypasError::AccessDenied)
-        }
-    }
+    with_manager(|manager| manager.check_access(process_id, resource, rights, now))
+        .unwrap_or(Err(SypasError::AccessDenied))
 }
 
 /// Grant capability to process
-pub fn grant_capability(process_id: u64, cap: Capability) -> Result<CapabilityHandle, SypasError> {
-    unsafe {
-        if let Some(ref mut manager) = SYPAS_MANAGER {
-            manager.grant_capability(process_id, cap)
-        } else {
-            Err(SypasError::AccessDenied)
-        }
-    }
+pub fn grant_capability(
+    process_id: u64,
+    cap: Capability,
+    expires_at: Option<u64>,
+) -> Result<CapabilityHandle, SypasError> {
+    with_manager(|manager| manager.grant_capability(process_id, cap, expires_at))
+        .unwrap_or(Err(SypasError::AccessDenied))
 }
 
 /// Revoke capability
 pub fn revoke_capability(handle: CapabilityHandle) -> Result<(), SypasError> {
-    unsafe {
-        if let Some(ref mut manager) = SYPAS_MANAGER {
-            manager.revoke_capability(handle)
-        } else {
-            Err(SypasError::CapabilityNotFound)
-        }
-    }
+    with_manager(|manager| manager.revoke_capability(handle))
+        .unwrap_or(Err(SypasError::CapabilityNotFound))
+}
+
+/// Delegate capability to another process, optionally with fewer rights
+pub fn delegate_capability(
+    from_handle: CapabilityHandle,
+    to_process: u64,
+    rights: Option<AccessRights>,
+) -> Result<CapabilityHandle, SypasError> {
+    with_manager(|manager| manager.delegate_capability(from_handle, to_process, rights))
+        .unwrap_or(Err(SypasError::CapabilityNotFound))
+}
+
+/// Whether `process_id` owns the capability behind `handle`
+pub fn owns_capability(process_id: u64, handle: CapabilityHandle) -> bool {
+    with_manager(|manager| manager.owns_capability(process_id, handle)).unwrap_or(false)
+}
+
+/// Delegate a capability owned by `from_process` to another process
+pub fn delegate_owned_capability(
+    from_process: u64,
+    from_handle: CapabilityHandle,
+    to_process: u64,
+) -> Result<CapabilityHandle, SypasError> {
+    with_manager(|manager| manager.delegate_owned_capability(from_process, from_handle, to_process))
+        .unwrap_or(Err(SypasError::CapabilityNotFound))
+}
+
+/// Remove expired capabilities
+pub fn prune_expired(now: u64) -> usize {
+    with_manager(|manager| manager.prune_expired(now)).unwrap_or(0)
 }
 
 /// Set enforcement mode
 pub fn set_enforcement_mode(mode: EnforcementMode) {
-    unsafe {
-        if let Some(ref mut manager) = SYPAS_MANAGER {
-            manager.set_enforcement_mode(mode);
-        }
-    }
+    with_manager(|manager| manager.set_enforcement_mode(mode));
 }
 
 /// Get audit log
-pub fn get_audit_log() -> &'static [AuditEntry] {
-    unsafe {
-        if let Some(ref manager) = SYPAS_MANAGER {
-            manager.get_audit_log()
-        } else {
-            &[]
-        }
+///
+/// The returned guard derefs to the entries and holds the manager's lock
+/// until dropped; other SYPAS calls spin while it is alive.
+pub fn get_audit_log() -> AuditLogGuard {
+    AuditLogGuard {
+        manager: SYPAS_MANAGER.get().map(|manager| manager.lock()),
+    }
+}
+
+/// Borrow of the global audit log, returned by [`get_audit_log`]
+pub struct AuditLogGuard {
+    /// `None` before `init`, which reads as an empty log
+    manager: Option<MutexGuard<'static, SypasManager>>,
+}
+
+impl Deref for AuditLogGuard {
+    type Target = [AuditEntry];
+
+    fn deref(&self) -> &[AuditEntry] {
+        self.manager.as_ref().map_or(&[], |manager| manager.get_audit_log())
     }
 }
 
@@ -474,13 +833,279 @@ mod tests {
         assert_eq!(res.id, b"/etc/passwd");
     }
 
+    /// pid 1: FileRead only, pid 2: Admin
+    fn test_caps(pid: u64) -> Option<Capabilities> {
+        let mut caps = Capabilities::new();
+        match pid {
+            1 => caps.set(Capability::FileRead),
+            2 => caps.set(Capability::Admin),
+            _ => return None,
+        }
+        Some(caps)
+    }
+
+    #[test]
+    fn test_verify_access_uses_capabilities() {
+        let mut manager = SypasManager::new();
+        manager.init();
+        manager.set_capability_lookup(test_caps);
+        
+        let file = ResourceId::new(ResourceType::File, b"/var/log/messages");
+        
+        assert!(manager.check_access(1, &file, AccessRights::READ, 0).is_ok());
+        assert_eq!(
+            manager.check_access(1, &file, AccessRights::READ_WRITE, 0),
+            Err(SypasError::AccessDenied)
+        );
+        
+        // Unknown processes hold nothing
+        assert_eq!(
+            manager.check_access(99, &file, AccessRights::READ, 0),
+            Err(SypasError::AccessDenied)
+        );
+        
+        // Admin satisfies every capability
+        assert!(manager.check_access(2, &file, AccessRights::FULL, 0).is_ok());
+        
+        // A granted token adds to the process's own set
+        manager.grant_capability(1, Capability::FileWrite, None).unwrap();
+        assert!(manager.check_access(1, &file, AccessRights::READ_WRITE, 0).is_ok());
+        
+        let last = manager.get_audit_log().last().unwrap();
+        assert_eq!(last.action, AuditAction::ResourceAccess);
+        assert!(last.allowed);
+    }
+
+    #[test]
+    fn test_expiring_capability() {
+        let mut manager = SypasManager::new();
+        manager.init();
+        manager.set_capability_lookup(|_| None);
+        
+        let endpoint = ResourceId::new(ResourceType::NetworkEndpoint, b"10.0.0.1:80");
+        manager.grant_capability(5, Capability::Network, Some(100)).unwrap();
+        
+        assert!(manager.check_access(5, &endpoint, AccessRights::READ, 50).is_ok());
+        assert_eq!(
+            manager.check_access(5, &endpoint, AccessRights::READ, 150),
+            Err(SypasError::AccessDenied)
+        );
+        
+        manager.clear_audit_log();
+        assert_eq!(manager.prune_expired(50), 0);
+        assert_eq!(manager.prune_expired(150), 1);
+        
+        let log = manager.get_audit_log();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].action, AuditAction::CapabilityRevocation);
+        assert_eq!(log[0].process_id, 5);
+    }
+
+    #[test]
+    fn test_delegation_depth_limit() {
+        let mut manager = SypasManager::new();
+        manager.init();
+        
+        let root = manager.grant_capability(1, Capability::FileRead, None).unwrap();
+        
+        // Three levels of delegation are within the default limit
+        let mut handle = root;
+        for pid in 2..5 {
+            handle = manager.delegate_capability(handle, pid, None).unwrap();
+        }
+        
+        let last = manager.capability_store.iter().find(|e| e.handle == handle).unwrap();
+        assert_eq!(last.depth, DEFAULT_MAX_DELEGATION_DEPTH);
+        assert!(last.delegated_from.is_some());
+        
+        // A fourth level is rejected
+        assert_eq!(
+            manager.delegate_capability(handle, 5, None),
+            Err(SypasError::DelegationNotAllowed)
+        );
+        
+        // Revoking the root cascades down the chain
+        manager.revoke_capability(root).unwrap();
+        assert!(manager.capability_store.iter().all(|e| e.revoked));
+    }
+
+    #[test]
+    fn test_delegation_downgrades_rights() {
+        let mut manager = SypasManager::new();
+        manager.init();
+        manager.set_capability_lookup(|_| None);
+        let file = ResourceId::new(ResourceType::File, b"/var/log/messages");
+        
+        // The parent holds read and write, and may delegate them
+        let read = manager.grant_capability(1, Capability::FileRead, None).unwrap();
+        let write = manager.grant_capability(1, Capability::FileWrite, None).unwrap();
+        assert!(manager.check_access(1, &file, AccessRights::READ_WRITE, 0).is_ok());
+        
+        // Handed on as read-only
+        for handle in [read, write] {
+            manager.delegate_capability(handle, 2, Some(AccessRights::READ)).unwrap();
+        }
+        assert!(manager.check_access(2, &file, AccessRights::READ, 0).is_ok());
+        assert_eq!(
+            manager.check_access(2, &file, AccessRights::READ_WRITE, 0),
+            Err(SypasError::AccessDenied)
+        );
+        
+        // The read-only copy cannot be passed on, nor widened
+        let narrowed = manager.capability_store.last().unwrap().handle;
+        assert_eq!(
+            manager.delegate_capability(narrowed, 3, Some(AccessRights::READ)),
+            Err(SypasError::DelegationNotAllowed)
+        );
+        let limited = AccessRights { delegate: true, ..AccessRights::READ };
+        let middle = manager.delegate_capability(read, 3, Some(limited)).unwrap();
+        assert_eq!(
+            manager.delegate_capability(middle, 4, Some(AccessRights::FULL)),
+            Err(SypasError::DelegationNotAllowed)
+        );
+        
+        // The parent keeps its full rights
+        assert!(manager.check_access(1, &file, AccessRights::READ_WRITE, 0).is_ok());
+    }
+
+    #[test]
+    fn test_policy_glob_matching() {
+        let etc = ResourceId::new(ResourceType::File, b"/etc/*");
+        let passwd = ResourceId::new(ResourceType::File, b"/etc/passwd");
+        assert_eq!(etc.match_score(&passwd), Some(5));
+        assert_eq!(etc.match_score(&ResourceId::new(ResourceType::File, b"/home/a")), None);
+        assert_eq!(passwd.match_score(&passwd), Some(usize::MAX));
+        assert_eq!(etc.match_score(&ResourceId::new(ResourceType::Directory, b"/etc/x")), None);
+        
+        let mut manager = SypasManager::new();
+        manager.init();
+        manager.set_capability_lookup(test_caps);
+        manager.add_policy(SecurityPolicy {
+            resource: etc,
+            required_caps: vec![Capability::FileRead],
+            default_rights: AccessRights::READ,
+        });
+        manager.add_policy(SecurityPolicy {
+            resource: ResourceId::new(ResourceType::File, b"/home/*"),
+            required_caps: vec![Capability::FileWrite],
+            default_rights: AccessRights::READ_WRITE,
+        });
+        
+        // pid 1 holds only FileRead
+        assert!(manager.check_access(1, &passwd, AccessRights::READ, 0).is_ok());
+        let notes = ResourceId::new(ResourceType::File, b"/home/user/notes");
+        assert_eq!(
+            manager.check_access(1, &notes, AccessRights::READ, 0),
+            Err(SypasError::AccessDenied)
+        );
+        
+        // Paths outside both fall back to the catch-all default policy
+        let tmp = ResourceId::new(ResourceType::File, b"/tmp/scratch");
+        assert!(manager.check_access(1, &tmp, AccessRights::READ, 0).is_ok());
+    }
+
+    #[test]
+    fn test_capability_token_round_trip() {
+        let key = b"cluster shared secret";
+        let mut manager = SypasManager::new();
+        let handle = manager.grant_capability(7, Capability::Network, Some(5000)).unwrap();
+        let entry = manager.capability_store.iter().find(|e| e.handle == handle).unwrap();
+        
+        let token = entry.to_bytes(key, 0xdead_beef);
+        assert_eq!(token.len(), TOKEN_SIZE);
+        
+        let (decoded, nonce) = CapabilityEntry::from_bytes(&token, key).unwrap();
+        assert_eq!(nonce, 0xdead_beef);
+        assert_eq!(decoded.handle, entry.handle);
+        assert_eq!(decoded.owner, 7);
+        assert_eq!(decoded.cap, Capability::Network);
+        assert_eq!(decoded.expires_at, Some(5000));
+        assert_eq!(decoded.rights.to_bits(), AccessRights::FULL.to_bits());
+        
+        let mut no_expiry = decoded.clone();
+        no_expiry.expires_at = None;
+        let (decoded, _) =
+            CapabilityEntry::from_bytes(&no_expiry.to_bytes(key, 1), key).unwrap();
+        assert_eq!(decoded.expires_at, None);
+    }
+
+    #[test]
+    fn test_capability_token_tamper() {
+        let key = b"cluster shared secret";
+        let mut manager = SypasManager::new();
+        let handle = manager.grant_capability(7, Capability::FileRead, None).unwrap();
+        let token = manager.capability_store
+            .iter()
+            .find(|e| e.handle == handle)
+            .unwrap()
+            .to_bytes(key, 42);
+        
+        // Escalating the capability byte must break the tag
+        let mut forged = token.clone();
+        forged[17] = Capability::Admin as u8;
+        assert_eq!(
+            CapabilityEntry::from_bytes(&forged, key).map(|_| ()),
+            Err(SypasError::InvalidCapability)
+        );
+        
+        // So must any flipped bit, and a different key
+        for i in 0..token.len() {
+            let mut flipped = token.clone();
+            flipped[i] ^= 0x01;
+            assert!(CapabilityEntry::from_bytes(&flipped, key).is_err());
+        }
+        assert!(CapabilityEntry::from_bytes(&token, b"wrong key").is_err());
+        assert!(CapabilityEntry::from_bytes(&token[1..], key).is_err());
+    }
+
     #[test]
     fn test_sypas_manager() {
         let mut manager = SypasManager::new();
         manager.init();
         
         // Grant capability
-        let handle = manager.grant_capability(1, Capability::FileRead);
+        let handle = manager.grant_capability(1, Capability::FileRead, None);
         assert!(handle.is_ok());
     }
+
+    #[test]
+    fn test_global_manager_across_threads() {
+        init();
+        let first = grant_capability(500, Capability::FileRead, None).unwrap();
+        
+        // A second init keeps the existing manager, so handles keep counting up
+        init();
+        let second = grant_capability(500, Capability::FileRead, None).unwrap();
+        assert!(second.as_u64() > first.as_u64());
+        
+        let workers: Vec<_> = (0..2u64)
+            .map(|worker| {
+                std::thread::spawn(move || {
+                    let pid = 600 + worker;
+                    (0..200)
+                        .map(|_| {
+                            let handle = grant_capability(pid, Capability::FileWrite, None).unwrap();
+                            revoke_capability(handle).unwrap();
+                            handle.as_u64()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut handles: Vec<u64> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        
+        // Every grant got its own handle
+        handles.sort_unstable();
+        handles.dedup();
+        assert_eq!(handles.len(), 400);
+        
+        let granted = get_audit_log()
+            .iter()
+            .filter(|entry| entry.process_id == 600 || entry.process_id == 601)
+            .count();
+        assert!(granted >= 400);
+    }
 }
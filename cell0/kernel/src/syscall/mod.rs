@@ -1,11 +1,424 @@
 //! System call interface
+//!
+//! Syscalls are dispatched by number through a `SyscallTable`. Handlers
+//! take the raw argument registers and return a non-negative result on
+//! success or a negated errno on failure, as on Linux.
+//!
+//! Descriptors passed to `Read` and `Write` are IPC channel IDs.
+//!
+//! Each syscall may list SYPAS capabilities the calling process must hold;
+//! `dispatch` checks them before the handler runs and fails with `-EPERM`.
 
 // Note: no_std is set at the crate root (lib.rs), not here
 
+use crate::error::{KernelError, KernelResult};
+use crate::ipc::{self, ChannelId, IpcError, Message, MAX_MESSAGE_SIZE};
+use crate::memory::MemoryError;
+use crate::process::{self, Capability, Priority, ProcessError, KERNEL_PID, PROCESS_TABLE};
+use spin::{Mutex, Once};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// Operation not permitted
+pub const EPERM: i64 = 1;
+/// No such process
+pub const ESRCH: i64 = 3;
+/// Bad file descriptor
+pub const EBADF: i64 = 9;
+/// Try again
+pub const EAGAIN: i64 = 11;
+/// Out of memory
+pub const ENOMEM: i64 = 12;
+/// Bad address
+pub const EFAULT: i64 = 14;
+/// Invalid argument
+pub const EINVAL: i64 = 22;
+/// Broken pipe
+pub const EPIPE: i64 = 32;
+/// Function not implemented
+pub const ENOSYS: i64 = 38;
+/// Message too long
+pub const EMSGSIZE: i64 = 90;
+
 /// Syscall numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u64)]
 pub enum Syscall {
     Exit = 0,
     Write = 1,
     Read = 2,
+    Spawn = 3,
+    Sleep = 4,
+}
+
+/// Syscall handler: raw arguments in, result or `-errno` out
+pub type SyscallHandler = fn(&[u64]) -> i64;
+
+/// Maps syscall numbers to handlers
+pub struct SyscallTable {
+    handlers: BTreeMap<u64, SyscallHandler>,
+    /// Capabilities the caller must hold, per syscall
+    required_caps: BTreeMap<u64, Vec<Capability>>,
+    /// Checks the calling process for a capability
+    capability_check: fn(Capability) -> Result<(), ProcessError>,
+}
+
+impl SyscallTable {
+    /// Create an empty table
+    pub const fn new() -> Self {
+        SyscallTable {
+            handlers: BTreeMap::new(),
+            required_caps: BTreeMap::new(),
+            capability_check: process::require_capability,
+        }
+    }
+
+    /// Create a table with the kernel's built-in syscalls registered
+    pub fn with_defaults() -> Self {
+        let mut table = Self::new();
+        table.register(Syscall::Exit as u64, |args| syscall_return(sys_exit(args)));
+        table.register(Syscall::Write as u64, |args| syscall_return(sys_write(args)));
+        table.register(Syscall::Read as u64, |args| syscall_return(sys_read(args)));
+        table.register(Syscall::Spawn as u64, |args| syscall_return(sys_spawn(args)));
+        table.register(Syscall::Sleep as u64, |args| syscall_return(sys_sleep(args)));
+        table.require(Syscall::Write as u64, Capability::FileWrite);
+        table.require(Syscall::Read as u64, Capability::FileRead);
+        table.require(Syscall::Spawn as u64, Capability::ProcessSpawn);
+        table
+    }
+
+    /// Install a handler, replacing any existing one for `num`
+    pub fn register(&mut self, num: u64, handler: SyscallHandler) {
+        self.handlers.insert(num, handler);
+    }
+
+    /// Require callers of `num` to hold `cap`
+    pub fn require(&mut self, num: u64, cap: Capability) {
+        let caps = self.required_caps.entry(num).or_default();
+        if !caps.contains(&cap) {
+            caps.push(cap);
+        }
+    }
+
+    /// Capabilities required to invoke `num`
+    pub fn required_caps(&self, num: u64) -> &[Capability] {
+        self.required_caps.get(&num).map_or(&[], |caps| caps.as_slice())
+    }
+
+    /// Replace the capability check (defaults to the current process in
+    /// the global process table)
+    pub fn set_capability_check(&mut self, check: fn(Capability) -> Result<(), ProcessError>) {
+        self.capability_check = check;
+    }
+
+    /// Invoke the handler for `num`, or return `-ENOSYS`
+    ///
+    /// Fails with `-EPERM` if the caller lacks a required capability.
+    pub fn dispatch(&self, num: u64, args: &[u64]) -> i64 {
+        match self.resolve(num) {
+            Ok(handler) => handler(args),
+            Err(errno) => errno,
+        }
+    }
+
+    /// Look up the handler for `num` and check the caller's capabilities,
+    /// returning the negated errno on failure
+    fn resolve(&self, num: u64) -> Result<SyscallHandler, i64> {
+        let handler = *self.handlers.get(&num).ok_or(-ENOSYS)?;
+
+        for &cap in self.required_caps(num) {
+            if (self.capability_check)(cap).is_err() {
+                return Err(-EPERM);
+            }
+        }
+
+        Ok(handler)
+    }
+}
+
+impl Default for SyscallTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Errno for a kernel error
+fn errno(error: KernelError) -> i64 {
+    match error {
+        KernelError::Ipc(e) => match e {
+            IpcError::ChannelNotFound | IpcError::ResourceNotFound => EBADF,
+            IpcError::ChannelClosed => EPIPE,
+            IpcError::MessageTooLarge => EMSGSIZE,
+            IpcError::WouldBlock | IpcError::NoMessage | IpcError::ResourceLimit => EAGAIN,
+            IpcError::PermissionDenied => EPERM,
+            IpcError::InvalidState => EINVAL,
+        },
+        KernelError::Process(e) => match e {
+            ProcessError::ProcessNotFound | ProcessError::ParentNotFound => ESRCH,
+            ProcessError::PermissionDenied => EPERM,
+            ProcessError::ResourceLimit | ProcessError::TableFull => EAGAIN,
+            ProcessError::InvalidState => EINVAL,
+        },
+        KernelError::Memory(MemoryError::OutOfMemory) => ENOMEM,
+        KernelError::Sypas(_) => EPERM,
+        KernelError::BadAddress => EFAULT,
+        KernelError::Memory(_) | KernelError::Crypto(_) | KernelError::InvalidArgument => EINVAL,
+    }
+}
+
+/// Turn a handler result into a syscall return value
+fn syscall_return(result: KernelResult<i64>) -> i64 {
+    match result {
+        Ok(value) => value,
+        Err(e) => -errno(e),
+    }
+}
+
+/// Split `(fd, buf, len)` out of the argument registers
+fn buffer_args(args: &[u64]) -> KernelResult<(ChannelId, u64, usize)> {
+    match *args {
+        [fd, buf, len, ..] => {
+            let len = usize::try_from(len).map_err(|_| KernelError::InvalidArgument)?;
+            if buf == 0 && len > 0 {
+                return Err(KernelError::BadAddress);
+            }
+            Ok((ChannelId::new(fd), buf, len))
+        }
+        _ => Err(KernelError::InvalidArgument),
+    }
+}
+
+/// `exit(code)`: terminate the calling process and release its channels
+fn sys_exit(args: &[u64]) -> KernelResult<i64> {
+    let code = args.first().copied().unwrap_or(0) as i32;
+    let pid = process::current_pid().ok_or(ProcessError::ProcessNotFound)?;
+
+    PROCESS_TABLE.terminate(pid, code)?;
+    ipc::cleanup_process(pid);
+    Ok(0)
+}
+
+/// `write(fd, buf, len)`: send `len` bytes as one message on channel `fd`
+///
+/// Returns the number of bytes sent. A full channel gives `-EAGAIN` and
+/// blocks the caller until a read frees a slot.
+fn sys_write(args: &[u64]) -> KernelResult<i64> {
+    let (channel, buf, len) = buffer_args(args)?;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(IpcError::MessageTooLarge.into());
+    }
+
+    let payload: &[u8] = if len == 0 {
+        &[]
+    } else {
+        // Caller guarantees `buf` points at `len` readable bytes
+        unsafe { core::slice::from_raw_parts(buf as *const u8, len) }
+    };
+
+    let pid = process::current_pid().unwrap_or(KERNEL_PID);
+    let result = ipc::send_from(pid, channel, Message::new(pid, 0, 0, payload));
+    if result == Err(IpcError::WouldBlock) && pid != KERNEL_PID {
+        // Sleep until a read frees a slot; the channel wakes us to retry
+        PROCESS_TABLE.block(pid)?;
+    }
+    result?;
+    Ok(len as i64)
+}
+
+/// `read(fd, buf, len)`: receive the next message on channel `fd`
+///
+/// Copies at most `len` bytes, dropping the rest of a longer message, and
+/// returns the number copied. An empty queue gives `-EAGAIN`.
+fn sys_read(args: &[u64]) -> KernelResult<i64> {
+    let (channel, buf, len) = buffer_args(args)?;
+    let message = ipc::recv(channel)?;
+
+    let count = message.payload.len().min(len);
+    // Caller guarantees `buf` points at `len` writable bytes
+    unsafe {
+        core::ptr::copy_nonoverlapping(message.payload.as_ptr(), buf as *mut u8, count);
+    }
+    Ok(count as i64)
+}
+
+/// `spawn(priority)`: start a child of the calling process
+///
+/// Returns the child's PID. Unknown priorities fall back to `Normal`.
+fn sys_spawn(args: &[u64]) -> KernelResult<i64> {
+    let priority = match args.first().copied().unwrap_or(Priority::Normal as u64) {
+        0 => Priority::Realtime,
+        1 => Priority::High,
+        2 => Priority::AboveNormal,
+        4 => Priority::BelowNormal,
+        5 => Priority::Low,
+        6 => Priority::Idle,
+        _ => Priority::Normal,
+    };
+    let parent = process::current_pid().unwrap_or(KERNEL_PID);
+
+    let pid = process::spawn(parent, priority)?;
+    Ok(pid as i64)
+}
+
+/// `sleep(ms)`: suspend the calling process for at least `ms` milliseconds
+fn sys_sleep(args: &[u64]) -> KernelResult<i64> {
+    let duration_ms = *args.first().ok_or(KernelError::InvalidArgument)?;
+
+    process::sleep(duration_ms)?;
+    Ok(0)
+}
+
+/// Global syscall table, created once by `init`
+static SYSCALL_TABLE: Once<Mutex<SyscallTable>> = Once::new();
+
+/// Initialize the syscall table with the built-in handlers
+///
+/// Only the first call creates the table; later calls leave it intact.
+pub fn init() {
+    SYSCALL_TABLE.call_once(|| Mutex::new(SyscallTable::with_defaults()));
+}
+
+/// Run `f` with the global table locked, or `None` before `init`
+fn with_table<R>(f: impl FnOnce(&mut SyscallTable) -> R) -> Option<R> {
+    SYSCALL_TABLE.get().map(|table| f(&mut table.lock()))
+}
+
+/// Install a handler in the global table
+pub fn register(num: u64, handler: SyscallHandler) {
+    with_table(|table| table.register(num, handler));
+}
+
+/// Dispatch a syscall through the global table
+///
+/// The handler runs after the table lock is released: `exit` and `sleep`
+/// may switch away and never return to this frame.
+pub fn dispatch(num: u64, args: &[u64]) -> i64 {
+    match with_table(|table| table.resolve(num)) {
+        Some(Ok(handler)) => handler(args),
+        Some(Err(errno)) => errno,
+        None => -ENOSYS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::ChannelType;
+    use std::sync::{Mutex, MutexGuard, Once};
+
+    /// The handlers work on the global process table, which is not thread
+    /// safe; tests using it hold this lock
+    fn init_globals() -> MutexGuard<'static, ()> {
+        static INIT: Once = Once::new();
+        static LOCK: Mutex<()> = Mutex::new(());
+        INIT.call_once(|| {
+            process::init();
+            ipc::init();
+        });
+        LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[test]
+    fn test_write_and_read_ipc_channel() {
+        let _globals = init_globals();
+        let table = SyscallTable::with_defaults();
+
+        let channel = ipc::create_channel(KERNEL_PID, ChannelType::Unidirectional).unwrap();
+        ipc::connect(channel, KERNEL_PID).unwrap();
+        let fd = channel.as_u64();
+
+        let data = b"hello, channel";
+        let args = [fd, data.as_ptr() as u64, data.len() as u64];
+        assert_eq!(table.dispatch(Syscall::Write as u64, &args), data.len() as i64);
+
+        // A short buffer takes the head of the message
+        let mut buf = [0u8; 5];
+        let args = [fd, buf.as_mut_ptr() as u64, buf.len() as u64];
+        assert_eq!(table.dispatch(Syscall::Read as u64, &args), 5);
+        assert_eq!(&buf, b"hello");
+
+        // Nothing left to read
+        assert_eq!(table.dispatch(Syscall::Read as u64, &args), -EAGAIN);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let _globals = init_globals();
+        let table = SyscallTable::with_defaults();
+        let data = [0u8; 4];
+
+        assert_eq!(table.dispatch(Syscall::Write as u64, &[u64::MAX, data.as_ptr() as u64, 4]), -EBADF);
+        assert_eq!(table.dispatch(Syscall::Write as u64, &[1, 0, 4]), -EFAULT);
+        assert_eq!(table.dispatch(Syscall::Write as u64, &[1]), -EINVAL);
+    }
+
+    /// A caller holding everything but `FileWrite`
+    fn without_file_write(cap: Capability) -> Result<(), ProcessError> {
+        if cap == Capability::FileWrite {
+            Err(ProcessError::PermissionDenied)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A caller holding only `FileWrite`
+    fn only_file_write(cap: Capability) -> Result<(), ProcessError> {
+        if cap == Capability::FileWrite {
+            Ok(())
+        } else {
+            Err(ProcessError::PermissionDenied)
+        }
+    }
+
+    #[test]
+    fn test_write_requires_file_write() {
+        let _globals = init_globals();
+        let mut table = SyscallTable::with_defaults();
+        assert_eq!(table.required_caps(Syscall::Write as u64), &[Capability::FileWrite]);
+        assert_eq!(table.required_caps(Syscall::Spawn as u64), &[Capability::ProcessSpawn]);
+        assert!(table.required_caps(Syscall::Exit as u64).is_empty());
+
+        let channel = ipc::create_channel(KERNEL_PID, ChannelType::Unidirectional).unwrap();
+        ipc::connect(channel, KERNEL_PID).unwrap();
+        let data = b"denied";
+        let args = [channel.as_u64(), data.as_ptr() as u64, data.len() as u64];
+
+        table.set_capability_check(without_file_write);
+        assert_eq!(table.dispatch(Syscall::Write as u64, &args), -EPERM);
+        // The handler never ran
+        assert_eq!(ipc::recv(channel).unwrap_err(), IpcError::WouldBlock);
+
+        table.set_capability_check(only_file_write);
+        assert_eq!(table.dispatch(Syscall::Write as u64, &args), data.len() as i64);
+        assert_eq!(table.dispatch(Syscall::Spawn as u64, &[]), -EPERM);
+        assert_eq!(ipc::recv(channel).unwrap().payload, data);
+    }
+
+    #[test]
+    fn test_unknown_syscall() {
+        let mut table = SyscallTable::new();
+        assert_eq!(table.dispatch(Syscall::Write as u64, &[]), -ENOSYS);
+
+        table.register(99, |args| args.iter().sum::<u64>() as i64);
+        assert_eq!(table.dispatch(99, &[1, 2, 3]), 6);
+        assert_eq!(table.dispatch(100, &[1, 2, 3]), -ENOSYS);
+    }
+
+    #[test]
+    fn test_global_handler_runs_unlocked() {
+        init();
+        register(200, |args| args.iter().sum::<u64>() as i64);
+        // A handler that re-enters dispatch would deadlock if the table
+        // stayed locked while it ran
+        register(201, |args| dispatch(200, args) + 1);
+
+        assert_eq!(dispatch(201, &[1, 2, 3]), 7);
+        assert_eq!(dispatch(202, &[]), -ENOSYS);
+    }
 }
@@ -0,0 +1,63 @@
+//! Kernel Time Module
+//!
+//! Central monotonic time source. The timer interrupt calls `tick` at
+//! `TIMER_HZ`; everything else reads the tick counter through `now_ms`
+//! and friends instead of keeping its own clock. Hosted builds have no
+//! timer interrupt, so time only moves when something calls `tick`.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Timer interrupt frequency
+pub const TIMER_HZ: u32 = 100;
+/// Milliseconds per timer tick
+pub const MS_PER_TICK: u64 = 1000 / TIMER_HZ as u64;
+
+/// Timer ticks since boot
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Advance the clock by one tick, returning the new tick count
+pub fn tick() -> u64 {
+    TICKS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// Timer ticks since boot
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Milliseconds since boot
+pub fn now_ms() -> u64 {
+    ticks() * MS_PER_TICK
+}
+
+/// Whole seconds since boot
+pub fn now_secs() -> u64 {
+    now_ms() / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{Priority, ProcessState, ProcessTable, KERNEL_PID};
+
+    #[test]
+    fn test_ticks_drive_sleep() {
+        let table = ProcessTable::new();
+        table.init();
+        table.set_clock(now_ms);
+
+        let pid = table.spawn(KERNEL_PID, Priority::Normal).unwrap();
+        table.sleep(pid, table.now() + 3 * MS_PER_TICK).unwrap();
+
+        for _ in 0..2 {
+            tick();
+            table.wake_sleepers(table.now());
+        }
+        assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Sleeping);
+
+        tick();
+        table.wake_sleepers(table.now());
+        assert_eq!(table.get_process(pid).unwrap().state, ProcessState::Ready);
+        assert_eq!(now_ms(), ticks() * MS_PER_TICK);
+    }
+}
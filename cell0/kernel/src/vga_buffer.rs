@@ -3,21 +3,41 @@
 //! The VGA text buffer is located at physical address 0xb8000.
 //! It supports 25 lines of 80 columns with 16 colors.
 
-#![cfg(all(target_arch = "x86_64", not(feature = "std")))]
-
-use volatile::Volatile;
 use core::fmt;
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 use lazy_static::lazy_static;
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 use spin::Mutex;
 
 /// VGA buffer dimensions
-const BUFFER_HEIGHT: usize = 25;
-const BUFFER_WIDTH: usize = 80;
+pub const BUFFER_HEIGHT: usize = 25;
+pub const BUFFER_WIDTH: usize = 80;
 
-/// VGA text buffer located at 0xb8000
+/// VGA text buffer, as laid out at 0xb8000
+///
+/// All access goes through volatile reads and writes so stores to the
+/// memory-mapped hardware buffer are never elided.
 #[repr(transparent)]
-struct Buffer {
-    chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; BUFFER_HEIGHT],
+pub struct Buffer {
+    chars: [[ScreenChar; BUFFER_WIDTH]; BUFFER_HEIGHT],
+}
+
+impl Buffer {
+    /// An all-blank buffer in ordinary memory (for hosted use and tests)
+    pub const fn blank() -> Self {
+        Buffer {
+            chars: [[ScreenChar::BLANK; BUFFER_WIDTH]; BUFFER_HEIGHT],
+        }
+    }
+
+    fn read(&self, row: usize, col: usize) -> ScreenChar {
+        unsafe { core::ptr::read_volatile(&self.chars[row][col]) }
+    }
+
+    fn write(&mut self, row: usize, col: usize, character: ScreenChar) {
+        unsafe { core::ptr::write_volatile(&mut self.chars[row][col], character) }
+    }
 }
 
 /// A single character on screen
@@ -28,6 +48,13 @@ struct ScreenChar {
     color_code: ColorCode,
 }
 
+impl ScreenChar {
+    const BLANK: ScreenChar = ScreenChar {
+        ascii_character: b' ',
+        color_code: ColorCode(0),
+    };
+}
+
 /// VGA color palette
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -57,19 +84,52 @@ pub enum Color {
 struct ColorCode(u8);
 
 impl ColorCode {
-    fn new(foreground: Color, background: Color) -> ColorCode {
+    const fn new(foreground: Color, background: Color) -> ColorCode {
         ColorCode((background as u8) << 4 | (foreground as u8))
     }
 }
 
 /// VGA writer that handles printing to the screen
-pub struct Writer {
+///
+/// Text is written at a cursor that moves down one row per line; once
+/// the bottom row is full, the whole screen scrolls up by one line.
+pub struct Writer<'a> {
+    row_position: usize,
     column_position: usize,
     color_code: ColorCode,
-    buffer: &'static mut Buffer,
+    buffer: &'a mut Buffer,
 }
 
-impl Writer {
+impl<'a> Writer<'a> {
+    /// Writer over `buffer` with the cursor in the top-left corner
+    pub fn new(buffer: &'a mut Buffer) -> Self {
+        Writer {
+            row_position: 0,
+            column_position: 0,
+            color_code: ColorCode::new(Color::Yellow, Color::Black),
+            buffer,
+        }
+    }
+
+    /// Set the colors used for subsequent output
+    pub fn set_color(&mut self, foreground: Color, background: Color) {
+        self.color_code = ColorCode::new(foreground, background);
+    }
+
+    /// Cursor position as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    /// Blank the whole screen in the current colors and home the cursor
+    pub fn clear_screen(&mut self) {
+        for row in 0..BUFFER_HEIGHT {
+            self.clear_row(row);
+        }
+        self.row_position = 0;
+        self.column_position = 0;
+    }
+
     pub fn write_byte(&mut self, byte: u8) {
         match byte {
             b'\n' => self.new_line(),
@@ -78,28 +138,34 @@ impl Writer {
                     self.new_line();
                 }
 
-                let row = BUFFER_HEIGHT - 1;
-                let col = self.column_position;
-
-                let color_code = self.color_code;
-                self.buffer.chars[row][col].write(ScreenChar {
+                let character = ScreenChar {
                     ascii_character: byte,
-                    color_code,
-                });
+                    color_code: self.color_code,
+                };
+                self.buffer.write(self.row_position, self.column_position, character);
                 self.column_position += 1;
             }
         }
     }
 
     fn new_line(&mut self) {
+        if self.row_position + 1 < BUFFER_HEIGHT {
+            self.row_position += 1;
+        } else {
+            self.scroll_up();
+        }
+        self.column_position = 0;
+    }
+
+    /// Move every row up by one, blanking the bottom row
+    fn scroll_up(&mut self) {
         for row in 1..BUFFER_HEIGHT {
             for col in 0..BUFFER_WIDTH {
-                let character = self.buffer.chars[row][col].read();
-                self.buffer.chars[row - 1][col].write(character);
+                let character = self.buffer.read(row, col);
+                self.buffer.write(row - 1, col, character);
             }
         }
         self.clear_row(BUFFER_HEIGHT - 1);
-        self.column_position = 0;
     }
 
     fn clear_row(&mut self, row: usize) {
@@ -108,7 +174,7 @@ impl Writer {
             color_code: self.color_code,
         };
         for col in 0..BUFFER_WIDTH {
-            self.buffer.chars[row][col].write(blank);
+            self.buffer.write(row, col, blank);
         }
     }
 
@@ -124,29 +190,32 @@ impl Writer {
     }
 }
 
-impl fmt::Write for Writer {
+impl fmt::Write for Writer<'_> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         self.write_string(s);
         Ok(())
     }
 }
 
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 lazy_static! {
-    /// Global VGA writer
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
-        column_position: 0,
-        color_code: ColorCode::new(Color::Yellow, Color::Black),
-        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
-    });
+    /// Global VGA writer over the hardware text buffer
+    pub static ref WRITER: Mutex<Writer<'static>> = {
+        let mut writer = Writer::new(unsafe { &mut *(0xb8000 as *mut Buffer) });
+        writer.clear_screen();
+        Mutex::new(writer)
+    };
 }
 
 /// Print to the VGA buffer
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => ($crate::vga_buffer::_print(format_args!($($arg)*)));
 }
 
 /// Print with newline to the VGA buffer
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 #[macro_export]
 macro_rules! println {
     () => ($crate::print!("\n"));
@@ -154,6 +223,7 @@ macro_rules! println {
 }
 
 /// Internal print function
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments) {
     use core::fmt::Write;
@@ -161,8 +231,72 @@ pub fn _print(args: fmt::Arguments) {
 }
 
 /// Clear the screen
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
 pub fn clear_screen() {
-    for _ in 0..BUFFER_HEIGHT {
-        println!();
+    WRITER.lock().clear_screen();
+}
+
+/// Set the colors of subsequent screen output
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+pub fn set_color(foreground: Color, background: Color) {
+    WRITER.lock().set_color(foreground, background);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    fn row_text(buffer: &Buffer, row: usize) -> String {
+        (0..BUFFER_WIDTH)
+            .map(|col| buffer.read(row, col).ascii_character as char)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_scrolls_past_bottom_row() {
+        let mut buffer = Buffer::blank();
+        let mut writer = Writer::new(&mut buffer);
+        for line in 0..30 {
+            writeln!(writer, "line {}", line).unwrap();
+        }
+        assert_eq!(writer.cursor(), (BUFFER_HEIGHT - 1, 0));
+
+        // Lines 0..=5 scrolled off; the cursor sits on a fresh bottom row
+        assert_eq!(row_text(&buffer, 0), "line 6");
+        assert_eq!(row_text(&buffer, BUFFER_HEIGHT - 2), "line 29");
+        assert_eq!(row_text(&buffer, BUFFER_HEIGHT - 1), "");
+    }
+
+    #[test]
+    fn test_long_line_wraps() {
+        let mut buffer = Buffer::blank();
+        let mut writer = Writer::new(&mut buffer);
+        let long = "x".repeat(BUFFER_WIDTH + 5);
+        writer.write_string(&long);
+        assert_eq!(writer.cursor(), (1, 5));
+        assert_eq!(row_text(&buffer, 1), "xxxxx");
+    }
+
+    #[test]
+    fn test_color_bytes() {
+        let mut buffer = Buffer::blank();
+        let mut writer = Writer::new(&mut buffer);
+        writer.set_color(Color::LightGreen, Color::Blue);
+        writer.write_string("ok");
+        writer.set_color(Color::White, Color::Red);
+        writer.write_string("!");
+
+        assert_eq!(buffer.read(0, 0).color_code, ColorCode(0x1A));
+        assert_eq!(buffer.read(0, 1).color_code, ColorCode(0x1A));
+        assert_eq!(buffer.read(0, 2).color_code, ColorCode(0x4F));
+
+        let mut writer = Writer::new(&mut buffer);
+        writer.set_color(Color::Black, Color::LightGray);
+        writer.clear_screen();
+        assert_eq!(writer.cursor(), (0, 0));
+        assert_eq!(buffer.read(24, 79), ScreenChar { ascii_character: b' ', color_code: ColorCode(0x70) });
     }
 }
@@ -0,0 +1,120 @@
+//! Multi-node Raft tests over the in-memory transport
+
+#[cfg(test)]
+mod tests {
+    use cell0_kernel::consensus::{
+        storage::MemoryStorage,
+        transport::{MemoryNetwork, MemoryTransport},
+        Config, EntryType, Event, NodeState, Raft,
+    };
+
+    struct Node {
+        raft: Raft<u64>,
+        transport: MemoryTransport<u64>,
+        storage: MemoryStorage<u64>,
+        committed: Vec<u64>,
+    }
+
+    fn cluster(network: &MemoryNetwork<u64>, ids: &[u64]) -> Vec<Node> {
+        ids.iter()
+            .map(|&id| Node {
+                raft: Raft::new(Config::new(id, ids.to_vec())),
+                transport: network.connect(id, ids.iter().copied().filter(|&p| p != id).collect()),
+                storage: MemoryStorage::new(),
+                committed: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Pump events and messages until the network is quiet
+    fn run(network: &MemoryNetwork<u64>, nodes: &mut [Node]) {
+        for _ in 0..100 {
+            for node in nodes.iter_mut() {
+                for event in node.raft.send_events(&mut node.transport).unwrap() {
+                    if let Event::Committed { entries } = event {
+                        // Only commands reach the state machine, not the
+                        // leader's no-op or membership changes
+                        node.committed.extend(
+                            entries.iter()
+                                .filter(|e| e.entry_type == EntryType::Command)
+                                .map(|e| e.command),
+                        );
+                    }
+                }
+            }
+            if network.in_flight() == 0 {
+                return;
+            }
+            for node in nodes.iter_mut() {
+                node.raft.poll(&mut node.transport, &mut node.storage).unwrap();
+            }
+        }
+        panic!("cluster did not settle");
+    }
+
+    fn leaders(nodes: &[Node]) -> Vec<u64> {
+        nodes
+            .iter()
+            .filter(|n| n.raft.state == NodeState::Leader)
+            .map(|n| n.raft.config.node_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_three_node_election() {
+        let network = MemoryNetwork::new();
+        let mut nodes = cluster(&network, &[1, 2, 3]);
+
+        nodes[1].raft.start_election();
+        run(&network, &mut nodes);
+
+        assert_eq!(leaders(&nodes), vec![2]);
+        for node in &nodes {
+            assert_eq!(node.raft.persistent.current_term, 1);
+        }
+        assert_eq!(nodes[0].raft.persistent.voted_for, Some(2));
+    }
+
+    #[test]
+    fn test_replication_over_transport() {
+        let network = MemoryNetwork::new();
+        let mut nodes = cluster(&network, &[1, 2, 3]);
+
+        nodes[0].raft.start_election();
+        run(&network, &mut nodes);
+        assert_eq!(leaders(&nodes), vec![1]);
+
+        for value in [10, 20, 30] {
+            nodes[0].raft.propose(value).unwrap();
+        }
+        run(&network, &mut nodes);
+        assert_eq!(nodes[0].committed, vec![10, 20, 30]);
+
+        // Followers learn the commit index from the next heartbeat
+        nodes[0].raft.send_heartbeats();
+        run(&network, &mut nodes);
+        for node in &nodes {
+            assert_eq!(node.raft.commit_index, 4);
+            assert_eq!(node.committed, vec![10, 20, 30]);
+        }
+    }
+
+    #[test]
+    fn test_election_after_leader_partitioned() {
+        let network = MemoryNetwork::new();
+        let mut nodes = cluster(&network, &[1, 2, 3]);
+
+        nodes[0].raft.start_election();
+        run(&network, &mut nodes);
+        assert_eq!(leaders(&nodes), vec![1]);
+
+        // Cut the leader off; the remaining pair still forms a majority
+        network.disconnect(1);
+        nodes[2].raft.start_election();
+        run(&network, &mut nodes);
+
+        assert_eq!(nodes[2].raft.state, NodeState::Leader);
+        assert_eq!(nodes[2].raft.persistent.current_term, 2);
+        assert_eq!(nodes[1].raft.persistent.voted_for, Some(3));
+    }
+}